@@ -0,0 +1,118 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use factoryfactory::{factory::FactoryType, *};
+use std::hint::black_box;
+
+// A world of `tiles` belts laid out as two-row loops, every other belt carrying an item, so each
+// tick moves items all over the base.
+fn looped_world(tiles: usize) -> WorldRes {
+    const LOOP_WIDTH: i32 = 50;
+    let mut world = WorldRes::new(Difficulty::Normal);
+    world.tiles.clear();
+
+    let loops = (tiles / (LOOP_WIDTH as usize * 2)).max(1) as i32;
+    for index in 0..loops {
+        let y = index * 3;
+        for x in 0..LOOP_WIDTH {
+            let bottom = if x == LOOP_WIDTH - 1 {
+                Direction::Up
+            } else {
+                Direction::Right
+            };
+            let top = if x == 0 {
+                Direction::Down
+            } else {
+                Direction::Left
+            };
+            for (pos, direction) in [
+                (Position::new(x, y), bottom),
+                (Position::new(x, y + 1), top),
+            ] {
+                let (mut tile, id) = get_new_tile((2, 1), pos, direction);
+                if x % 2 == 0 {
+                    tile.set_item(Some(Item::Circuit));
+                }
+                world.tiles.insert(pos, (tile, id));
+            }
+        }
+    }
+
+    // Settle into the steady state first, once the first tick has scheduled every belt.
+    for _ in 0..3 {
+        simulate_tick(&mut world);
+    }
+    world
+}
+
+fn tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulate_tick");
+    group.sample_size(20);
+    for tiles in [1_000, 10_000, 100_000] {
+        let mut world = looped_world(tiles);
+        group.bench_function(BenchmarkId::from_parameter(tiles), |b| {
+            b.iter(|| simulate_tick(&mut world))
+        });
+    }
+    group.finish();
+}
+
+fn sort_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sort_moves_topologically");
+    for tiles in [1_000, 10_000, 100_000] {
+        let world = looped_world(tiles);
+        let actions = world.actions.clone();
+        group.bench_function(BenchmarkId::from_parameter(tiles), |b| {
+            b.iter(|| sort_moves_topologically(black_box(actions.clone()), &world))
+        });
+    }
+    group.finish();
+}
+
+fn save_load(c: &mut Criterion) {
+    let path = std::env::temp_dir().join("factoryfactory_bench.ffs");
+    let key = path.to_str().unwrap();
+    let hotkeys = Hotkeys::default();
+
+    let mut group = c.benchmark_group("save_load");
+    group.sample_size(10);
+    for tiles in [1_000, 10_000, 100_000] {
+        let mut world = looped_world(tiles);
+        // Every chunk is re-encoded, as on the first save after loading a changed base.
+        group.bench_function(BenchmarkId::new("save_all_chunks", tiles), |b| {
+            b.iter(|| {
+                world.chunk_saves.stale = true;
+                world.save(key, &hotkeys).unwrap();
+            })
+        });
+        // Only the chunks a tick touched are re-encoded.
+        group.bench_function(BenchmarkId::new("save_after_tick", tiles), |b| {
+            b.iter(|| {
+                simulate_tick(&mut world);
+                world.save(key, &hotkeys).unwrap();
+            })
+        });
+        group.bench_function(BenchmarkId::new("load", tiles), |b| {
+            b.iter(|| WorldRes::load_game(key).unwrap())
+        });
+    }
+    group.finish();
+    let _ = std::fs::remove_file(&path);
+}
+
+// Factories look up their recipe and input capacity every tick.
+fn recipe_lookup(c: &mut Criterion) {
+    c.bench_function("recipe_lookup", |b| {
+        b.iter(|| {
+            for factory_type in FactoryType::ALL {
+                for recipe_index in 0..factory_type.recipes().len() {
+                    black_box((
+                        factory_type.recipe(recipe_index),
+                        factory_type.capacity(recipe_index),
+                    ));
+                }
+            }
+        })
+    });
+}
+
+criterion_group!(benches, tick, sort_moves, save_load, recipe_lookup);
+criterion_main!(benches);
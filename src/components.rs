@@ -1,5 +1,9 @@
-use crate::Position;
+use crate::{
+    Difficulty, Item, Position, StarterKit, WorldSize, factory::FactorySide,
+    router::RouterOutputIndex,
+};
 use bevy::prelude::*;
+use std::collections::HashMap;
 
 #[derive(Component)]
 pub struct TerrainChunk {
@@ -11,13 +15,57 @@ pub struct TileSprite {
     pub pos: Position,
 }
 
+#[derive(Component)]
+pub struct PlacementLabel;
+
+// Painted zone cells, zone names and label markers, rebuilt whenever they change.
+#[derive(Component)]
+pub struct ZoneOverlay;
+
+#[derive(Component)]
+pub struct MarkerSprite {
+    pub pos: Position,
+}
+
+#[derive(Component)]
+pub struct AltModeIcon {
+    pub pos: Position,
+    pub item: Item,
+}
+
+#[derive(Component)]
+pub struct GhostSprite {
+    pub pos: Position,
+    pub queued: bool,
+}
+
+#[derive(Component)]
+pub struct ItemDisplayLabel {
+    pub pos: Position,
+}
+
 #[derive(Component)]
 pub struct ItemAnimation {
+    pub item: Item,
     pub start_pos: Vec3,
     pub end_pos: Vec3,
     pub timer: Timer,
 }
 
+#[derive(Component)]
+pub struct Particle {
+    pub velocity: Vec2,
+    pub growth: f32,
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct CameraPan {
+    pub start_pos: Vec2,
+    pub end_pos: Vec2,
+    pub timer: Timer,
+}
+
 #[derive(Component)]
 pub struct Inventory {
     pub selected_category: u8,
@@ -47,6 +95,39 @@ pub struct HotkeyOption {
 #[derive(Component)]
 pub struct SellOption {
     pub tile_type: (u8, u8),
+    pub quantity: TradeQuantity,
+}
+
+#[derive(Component)]
+pub struct AutoSellOption {
+    pub tile_type: (u8, u8),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TradeQuantity {
+    One,
+    Ten,
+    Hundred,
+    Max,
+}
+
+impl TradeQuantity {
+    pub const ALL: [TradeQuantity; 4] = [
+        TradeQuantity::One,
+        TradeQuantity::Ten,
+        TradeQuantity::Hundred,
+        TradeQuantity::Max,
+    ];
+
+    /// How many units this option trades when at most `max` are possible.
+    pub fn amount(&self, max: u32) -> u32 {
+        match self {
+            TradeQuantity::One => 1,
+            TradeQuantity::Ten => 10,
+            TradeQuantity::Hundred => 100,
+            TradeQuantity::Max => max,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -55,6 +136,9 @@ pub struct HotkeyButton {
     pub tile_type: (u8, u8),
 }
 
+#[derive(Component)]
+pub struct HotkeyModeButton;
+
 #[derive(Component)]
 pub struct CoreMenu {
     pub position: Position,
@@ -80,7 +164,340 @@ pub struct CoreContextMenu;
 #[derive(Component)]
 pub struct BuyOption {
     pub tile_type: (u8, u8),
+    pub quantity: TradeQuantity,
+}
+
+// Buys however many units were typed while the Core's buy menu is open.
+#[derive(Component)]
+pub struct BuyAmountField {
+    pub tile_type: (u8, u8),
+    pub amount: u32,
 }
 
 #[derive(Component)]
 pub struct MoneyWidget;
+
+#[derive(Component)]
+pub struct MoneyWidgetButton;
+
+#[derive(Component)]
+pub struct WorldEventBanner;
+
+#[derive(Component)]
+pub struct LedgerPanel;
+
+#[derive(Component)]
+pub struct LedgerText;
+
+#[derive(Component)]
+pub struct LedgerBar {
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct BankruptcyMenu;
+
+#[derive(Clone, Copy)]
+pub enum BankruptcyAction {
+    TakeLoan,
+    GiveUp,
+    NewWorld,
+}
+
+#[derive(Component)]
+pub struct BankruptcyButton {
+    pub action: BankruptcyAction,
+}
+
+#[derive(Component)]
+pub struct VictoryScreen;
+
+#[derive(Clone, Copy)]
+pub enum VictoryAction {
+    Freeplay,
+    NewWorld,
+}
+
+#[derive(Component)]
+pub struct VictoryButton {
+    pub action: VictoryAction,
+}
+
+#[derive(Component)]
+pub struct FactoryMenu {
+    pub position: Position,
+}
+
+#[derive(Component)]
+pub struct FactoryInventoryText {
+    pub item: Item,
+}
+
+#[derive(Component)]
+pub struct FactoryProgressBar;
+
+#[derive(Component)]
+pub struct FactoryRecipeOption {
+    pub recipe_index: usize,
+}
+
+#[derive(Component)]
+pub struct FactorySideButton {
+    pub side: FactorySide,
+    pub output: bool,
+}
+
+#[derive(Component)]
+pub struct PhotoCamera {
+    pub image: Handle<Image>,
+    pub path: String,
+    pub frames: u32,
+}
+
+#[derive(Component)]
+pub struct PhotoTerrain;
+
+#[derive(Component)]
+pub struct DebugOverlay;
+
+#[derive(Component)]
+pub struct DebugOverlayText;
+
+#[derive(Component)]
+pub struct Dialog;
+
+#[derive(Component)]
+pub struct BlueprintMenu;
+
+#[derive(Component)]
+pub struct BlueprintPreview;
+
+#[derive(Clone, Copy)]
+pub enum BlueprintAction {
+    Place(usize),
+    Export(usize),
+    Delete(usize),
+    Import,
+}
+
+#[derive(Component)]
+pub struct BlueprintButton {
+    pub action: BlueprintAction,
+}
+
+#[derive(Component)]
+pub struct ConditionMenu {
+    pub position: Position,
+}
+
+#[derive(Component)]
+pub struct ConditionText;
+
+#[derive(Component)]
+pub struct RouterMenu {
+    pub position: Position,
+}
+
+#[derive(Component)]
+pub struct RouterFilterText {
+    pub output: RouterOutputIndex,
+}
+
+#[derive(Component)]
+pub struct RouterPriorityText;
+
+#[derive(Clone, Copy)]
+pub enum RouterAction {
+    PreviousFilter(RouterOutputIndex),
+    NextFilter(RouterOutputIndex),
+    CyclePriority,
+    Close,
+}
+
+#[derive(Component)]
+pub struct RouterButton {
+    pub action: RouterAction,
+}
+
+#[derive(Component)]
+pub struct ConsolePanel;
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+// Only spawned in builds with the `inspector` feature.
+#[derive(Component)]
+pub struct InspectorPanel;
+
+pub type OverlayMenuFilter = Or<(
+    With<BlueprintMenu>,
+    With<ConditionMenu>,
+    With<DifficultyMenu>,
+    With<ScenarioMenu>,
+    With<StatsScreen>,
+    With<ShopMenu>,
+    With<LedgerPanel>,
+    With<BankruptcyMenu>,
+    With<VictoryScreen>,
+    With<RouterMenu>,
+    With<InspectorPanel>,
+    With<ConsolePanel>,
+)>;
+
+// Full-screen panels, of which only one is open at a time.
+pub type PanelMenuFilter = Or<(With<Inventory>, With<CoreMenu>, With<FactoryMenu>)>;
+
+// Menus that Escape closes, one at a time through `MenuStack`.
+pub type ClosableMenuFilter = Or<(
+    With<Inventory>,
+    With<InventoryContextMenu>,
+    With<CoreMenu>,
+    With<CoreContextMenu>,
+    With<FactoryMenu>,
+    With<BlueprintMenu>,
+    With<ConditionMenu>,
+    With<ScenarioMenu>,
+    With<StatsScreen>,
+    With<ShopMenu>,
+    With<LedgerPanel>,
+    With<RouterMenu>,
+)>;
+
+#[derive(Clone, Copy)]
+pub enum ConditionAction {
+    PreviousItem,
+    NextItem,
+    Adjust(i32),
+    Channel(i32),
+    Remove,
+    Close,
+}
+
+#[derive(Component)]
+pub struct ConditionButton {
+    pub action: ConditionAction,
+}
+
+#[derive(Component)]
+pub struct AlertFeed;
+
+#[derive(Component)]
+pub struct AlertEntry {
+    pub position: Position,
+}
+
+#[derive(Component)]
+pub struct AlertMuteButton {
+    pub category: crate::resources::AlertCategory,
+}
+
+#[derive(Component)]
+pub struct GoalText;
+
+#[derive(Component)]
+pub struct GoalProgressBar;
+
+#[derive(Clone, Copy)]
+pub enum GoalAction {
+    PreviousItem,
+    NextItem,
+    DecreaseTarget,
+    IncreaseTarget,
+    Clear,
+}
+
+#[derive(Component)]
+pub struct GoalButton {
+    pub action: GoalAction,
+}
+
+#[derive(Component)]
+pub struct DifficultyMenu;
+
+#[derive(Component)]
+pub struct DifficultyButton {
+    pub difficulty: Difficulty,
+}
+
+#[derive(Component)]
+pub struct StarterKitButton {
+    pub difficulty: Difficulty,
+    pub kit: StarterKit,
+    pub world_size: WorldSize,
+}
+
+// Cycles the size of the world about to be created.
+#[derive(Component)]
+pub struct WorldSizeButton {
+    pub difficulty: Difficulty,
+    pub world_size: WorldSize,
+}
+
+#[derive(Component)]
+pub struct ScenarioText;
+
+#[derive(Component)]
+pub struct TileBar;
+
+#[derive(Component)]
+pub struct TileBarSlot {
+    pub tile_type: (u8, u8),
+}
+
+#[derive(Component)]
+pub struct FavoriteOption {
+    pub tile_type: (u8, u8),
+}
+
+#[derive(Component)]
+pub struct ScenarioMenu;
+
+#[derive(Component)]
+pub struct ScenarioButton {
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub enum ChallengeButton {
+    Daily,
+    TimeTrial { seed: u32 },
+}
+
+#[derive(Component)]
+pub struct OpenScenarioMenuButton;
+
+#[derive(Component)]
+pub struct StatsScreen;
+
+#[derive(Component)]
+pub struct StatsScreenText;
+
+#[derive(Component, Default)]
+pub struct ShopMenu {
+    pub basket: HashMap<(u8, u8), u32>,
+}
+
+#[derive(Component)]
+pub struct ShopQuantityText {
+    pub tile_type: (u8, u8),
+}
+
+#[derive(Component)]
+pub struct ShopBasketText;
+
+#[derive(Component)]
+pub struct ShopStepButton {
+    pub tile_type: (u8, u8),
+    pub delta: i32,
+}
+
+#[derive(Clone, Copy)]
+pub enum ShopAction {
+    Clear,
+    Checkout,
+}
+
+#[derive(Component)]
+pub struct ShopButton {
+    pub action: ShopAction,
+}
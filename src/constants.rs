@@ -1,8 +1,41 @@
+use crate::Item;
+use bevy::color::Color;
+
 pub const TILE_SIZE: f32 = 64.0;
 pub const ITEM_SIZE: f32 = 32.0;
 pub const IMAGE_SIZE: f32 = 128.0;
 pub const TICK_LENGTH: f32 = 1.0;
+// Actions applied per frame while a tick resolves; larger ticks spread over several frames.
+pub const TICK_BUDGET_ACTIONS: u32 = 5000;
+pub const OFFLINE_MAX_TICKS: u32 = 3600;
+pub const SAVE_BACKUP_COUNT: usize = 5;
+pub const SAVE_BACKUP_INTERVAL_TICKS: i32 = 60;
+// Marks saves that start with an uncompressed `SaveHeader`.
+pub const SAVE_HEADER_MAGIC: &[u8; 4] = b"FFSH";
+pub const SAVE_HEADER_VERSION: u32 = 2;
+// Width and height of the save thumbnail in cells, one pixel per cell around the origin.
+pub const SAVE_THUMBNAIL_SIZE: i32 = 16;
+pub const WORLD_EVENT_INTERVAL_TICKS: i32 = 300;
+pub const WORLD_EVENT_CHANCE_PERCENT: u64 = 40;
+pub const WORLD_EVENT_DURATION_TICKS: i32 = 60;
+pub const DECONSTRUCTION_PER_TICK: usize = 4;
+pub const CONVEYOR_STACK_SIZE: u8 = 4;
+
+pub const ALERT_MAX_ENTRIES: usize = 6;
+pub const ALERT_LIFETIME_TICKS: i32 = 120;
+pub const ALERT_STARVATION_TICKS: i32 = 60;
+pub const DEADLOCK_CHECK_TICKS: i32 = 20;
+pub const SIGNAL_CHANNELS: u8 = 9;
+
+pub const BLUEPRINT_PREFIX: &str = "ffbp1:";
+pub const BLUEPRINT_MAX_TILES: usize = 4096;
+pub const BLUEPRINT_MAX_BYTES: usize = 1 << 20;
+// Offsets are measured from the captured region's corner, so none can span more than the world.
+pub const BLUEPRINT_MAX_EXTENT: i32 = 2 * TERRAIN_GEN_RANGE;
+
 pub const CAMERA_SPEED: f32 = 10.0;
+pub const CAMERA_PAN_DURATION: f32 = 0.5;
+pub const PLACE_REPEAT_DELAY: f32 = 0.25;
 
 pub const TERRAIN_GEN_RANGE: i32 = 200;
 pub const TERRAIN_BASE_THRESHOLD: f64 = 0.4;
@@ -10,13 +43,125 @@ pub const TERRAIN_BASE_THRESHOLD: f64 = 0.4;
 pub const RIGTORIUM_NOISE_SCALE: f64 = 0.15;
 pub const FLEXTORIUM_NOISE_SCALE: f64 = 0.15;
 pub const ELECTRINE_NOISE_SCALE: f64 = 0.4;
+pub const OIL_NOISE_SCALE: f64 = 0.3;
 
 pub const RIGTORIUM_DENSITY: f64 = -0.2;
 pub const FLEXTORIUM_DENSITY: f64 = -0.3;
 pub const ELECTRINE_DENSITY: f64 = -0.4;
+// Seeps only form on bare stone, where the oil noise peaks above this.
+pub const OIL_SEEP_THRESHOLD: f64 = 0.6;
+// Deposit tiles an extractor's 3x3 area needs to run at its base speed.
+pub const EXTRACTOR_FULL_COVERAGE: i32 = 5;
+pub const BEACON_RADIUS: i32 = 3;
+pub const BEACON_BOOST_PERCENT: u32 = 20;
+pub const BEACON_MAX_BOOST_PERCENT: u32 = 50;
+pub const BEACON_CHARGE_PER_ELECTRINE: u32 = 20;
+pub const BEACON_MAX_CHARGE: u32 = 40;
+// Tiles larger than 1x1, as (category, index, width, height).
+pub const TILE_FOOTPRINTS: &[(u8, u8, i32, i32)] = &[(3, 10, 3, 3)];
+// Tiles that can't be removed, replaced or copied; clicking one opens its menu instead. The
+// starting Core is stored as (6, 1).
+pub const PROTECTED_TILES: &[(u8, u8)] = &[(1, 1), (6, 1)];
+// Tiles that work the same whichever way they face, so they're always placed facing up.
+pub const NON_ROTATABLE_TILES: &[(u8, u8)] = &[(1, 1), (1, 2), (1, 3), (2, 3), (2, 5), (6, 1)];
+// Ticks per item for a quarry sitting entirely on deposits.
+pub const QUARRY_INTERVAL: i32 = 2;
 
 pub const CHUNK_SIZE: i32 = 16;
+pub const TERRAIN_BAKE_TEXELS: u32 = 32;
+// Baked chunk images kept after their chunk leaves the view.
+pub const CHUNK_IMAGE_CACHE_SIZE: usize = 64;
 
 pub const MIN_ZOOM: f32 = 0.1;
 pub const MAX_ZOOM: f32 = 2.0;
 pub const ZOOM_SPEED: f32 = 0.0001;
+pub const ZOOM_SMOOTHING: f32 = 12.0;
+pub const LOD_ZOOM_THRESHOLD: f32 = 0.35;
+pub const CULL_MARGIN: f32 = 128.0;
+pub const PARTICLE_BURST_SIZE: usize = 8;
+pub const PARTICLE_LIFETIME: f32 = 0.6;
+pub const LANE_OFFSET: f32 = 0.25;
+pub const BELT_STRIPE_COUNT: usize = 2;
+pub const BELT_STRIPE_COLOR: Color = Color::srgba(0.9, 0.9, 0.9, 0.35);
+
+pub const BUZZ_FREQUENCY: f32 = 110.0;
+pub const BUZZ_DURATION_MS: u64 = 150;
+pub const PLACE_FREQUENCY: f32 = 660.0;
+pub const REMOVE_FREQUENCY: f32 = 330.0;
+pub const TILE_SOUND_DURATION_MS: u64 = 40;
+
+pub const PHOTO_MAX_SIZE: f32 = 8192.0;
+pub const PHOTO_WARMUP_FRAMES: u32 = 5;
+pub const PHOTO_RENDER_LAYER: usize = 1;
+
+pub const STATS_WINDOW_TICKS: i32 = 60;
+pub const STATS_MAX_WINDOWS: usize = 1000;
+pub const LEDGER_MAX_ENTRIES: usize = 500;
+pub const LEDGER_VISIBLE_ENTRIES: usize = 10;
+pub const LEDGER_GRAPH_BUCKETS: usize = 30;
+pub const LEDGER_BUCKET_TICKS: i32 = 10;
+pub const LOAN_AMOUNT: u32 = 200;
+pub const LOAN_INTEREST_PERCENT: u32 = 25;
+pub const LOAN_REPAYMENT_PERCENT: u32 = 50;
+
+pub const SCENARIO_FILES: [&str; 3] = [
+    include_str!("../assets/scenarios/first_steps.ron"),
+    include_str!("../assets/scenarios/rods_and_routers.ron"),
+    include_str!("../assets/scenarios/circuit_board.ron"),
+];
+
+pub const TEXTURE_PACK_DIR: &str = "texture_packs";
+
+pub const CONSOLE_MAX_LINES: usize = 100;
+pub const CONSOLE_VISIBLE_LINES: usize = 14;
+pub const CONSOLE_MAX_TICKS: u32 = 10_000;
+
+pub const GOAL_DEFAULT_TARGET: u32 = 100;
+pub const GOAL_TARGET_STEP: u32 = 10;
+
+// Amounts the inventory's auto-sell option cycles through before switching off again.
+pub const AUTO_SELL_THRESHOLDS: [u32; 4] = [0, 10, 50, 100];
+pub const BUY_AMOUNT_MAX: u32 = 9999;
+pub const RECENT_TILES_MAX: usize = 6;
+
+// Items a daily challenge can ask for, with the range its amount is picked from.
+pub const DAILY_CONTRACT_ITEMS: &[(Item, u64, u64)] = &[
+    (Item::Rigtorium, 20, 60),
+    (Item::Flextorium, 20, 60),
+    (Item::Electrine, 20, 60),
+    (Item::RigtoriumRod, 10, 30),
+    (Item::Circuit, 5, 20),
+    (Item::Motor, 3, 10),
+    (Item::Plastic, 5, 15),
+];
+pub const DAILY_CONTRACT_COUNT: usize = 3;
+
+pub const TIME_TRIAL_ITEM: Item = Item::Router;
+pub const TIME_TRIAL_AMOUNT: u32 = 100;
+// Runs kept per seed on the time trial leaderboard.
+pub const LEADERBOARD_SIZE: usize = 5;
+
+// Colours zones can be painted in, drawn translucently over the tiles.
+pub const ZONE_COLORS: [Color; 6] = [
+    Color::srgba(0.9, 0.3, 0.3, 0.2),
+    Color::srgba(0.9, 0.6, 0.2, 0.2),
+    Color::srgba(0.9, 0.9, 0.3, 0.2),
+    Color::srgba(0.3, 0.8, 0.4, 0.2),
+    Color::srgba(0.3, 0.5, 0.9, 0.2),
+    Color::srgba(0.7, 0.4, 0.9, 0.2),
+];
+
+// Icons map markers cycle through.
+pub const MARKER_ICONS: [Item; 6] = [
+    Item::Rigtorium,
+    Item::Flextorium,
+    Item::Electrine,
+    Item::Circuit,
+    Item::Motor,
+    Item::OrbitalBeacon,
+];
+// On-screen size of a marker icon, and how far from the screen edge off-screen markers are kept.
+pub const MARKER_SIZE: f32 = 32.0;
+pub const MARKER_EDGE_MARGIN: f32 = 40.0;
+// Longest path the item flow tracer follows before giving up.
+pub const FLOW_TRACE_MAX_STEPS: usize = 256;
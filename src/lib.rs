@@ -0,0 +1,19 @@
+// The simulation, its Bevy systems and the UI, shared by the game binary and the benchmarks.
+// `simulate_tick` advances a `WorldRes` by one tick without a running app.
+
+mod components;
+mod constants;
+mod persistence;
+mod resources;
+pub mod systems;
+pub mod tiles;
+mod types;
+mod utils;
+
+pub use components::*;
+pub use constants::*;
+pub use resources::*;
+pub use systems::*;
+pub use tiles::*;
+pub use types::*;
+pub use utils::*;
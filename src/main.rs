@@ -1,123 +1,301 @@
-mod components;
-mod constants;
-mod resources;
-mod systems;
-mod tiles;
-mod types;
-mod utils;
-
-use std::collections::{HashMap, HashSet};
-
-use bevy::prelude::*;
+use bevy::{
+    diagnostic::{Diagnostic, FrameTimeDiagnosticsPlugin, RegisterDiagnostic},
+    input::InputSystem,
+    prelude::*,
+};
 use bevy_embedded_assets::{EmbeddedAssetPlugin, PluginMode};
-
-pub use components::*;
-pub use constants::*;
-use rand::{Rng, rng};
-pub use resources::*;
-pub use systems::*;
-pub use tiles::*;
-pub use types::*;
-pub use utils::*;
+use factoryfactory::*;
 
 fn main() {
-    App::new()
-        .add_plugins((
-            DefaultPlugins.set(WindowPlugin {
-                primary_window: Some(Window {
-                    title: "Factory Factory".into(),
-                    name: Some("factoyfactory.app".into()),
-                    resolution: (1280.0, 720.0).into(),
-                    fit_canvas_to_parent: true,
-                    prevent_default_event_handling: false,
-                    ..default()
-                }),
-                ..default()
-            }),
-            EmbeddedAssetPlugin {
-                mode: PluginMode::AutoLoad,
-            },
-        ))
-        .insert_resource(Placer::default())
-        .add_systems(Startup, (setup_resources, setup.after(setup_resources)))
-        .add_systems(
-            Update,
+    let settings = Settings::load("settings.ron").unwrap_or_default();
+    if let Err(err) = settings.save("settings.ron") {
+        eprintln!("Error saving settings: {}", err);
+    }
+
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(settings.window()),
+            ..default()
+        }),
+        EmbeddedAssetPlugin {
+            mode: PluginMode::AutoLoad,
+        },
+    ))
+    .add_plugins(FrameTimeDiagnosticsPlugin::default())
+    .register_diagnostic(Diagnostic::new(TICK_DURATION).with_suffix("ms"))
+    .register_diagnostic(Diagnostic::new(TICK_TILES_TIME).with_suffix("ms"))
+    .register_diagnostic(Diagnostic::new(TILE_VISUALS_TIME).with_suffix("ms"))
+    .insert_resource(settings)
+    .insert_resource(Placer::default())
+    .insert_resource(GameSpeed::default())
+    .insert_resource(OfflineProgress::default())
+    .insert_resource(CopiedSettings::default())
+    .insert_resource(UpgradePlanner::default())
+    .insert_resource(DeconstructionPlanner::default())
+    .insert_resource(ClearItemsTool::default())
+    .insert_resource(ZonePainter::default())
+    .insert_resource(FlowTrace::default())
+    .insert_resource(Alerts::default())
+    .insert_resource(ProductionGoal::default())
+    .insert_resource(DepositOverlay::default())
+    .insert_resource(AltMode::default())
+    .insert_resource(RecentTiles::default())
+    .insert_resource(MenuStack::default())
+    .insert_resource(UiState::default())
+    .insert_resource(Console::default())
+    .insert_resource(ConsoleCommands::default())
+    .add_event::<TileChanged>()
+    .add_event::<TilePlaced>()
+    .add_event::<TileRemoved>()
+    .add_event::<ItemMoved>()
+    .add_event::<ItemProduced>()
+    .add_event::<TickFinished>()
+    .add_systems(
+        Startup,
+        (
+            setup_resources,
+            systems::apply_texture_pack.after(setup_resources),
+            setup.after(systems::apply_texture_pack),
+            systems::simulate_offline_progress.after(setup_resources),
+            systems::register_console_commands,
+        ),
+    )
+    .add_systems(PreUpdate, systems::handle_console_input.after(InputSystem))
+    .add_systems(PreUpdate, systems::update_ui_state)
+    .add_systems(
+        Update,
+        (
             (
+                (systems::manage_terrain_chunks, systems::stream_tile_sprites).chain(),
+                systems::manage_game_speed,
+                systems::tick_tiles.run_if(systems::offline_progress_caught_up),
+                systems::build_ghosts.after(tick_tiles),
+                systems::emit_tile_events.after(build_ghosts),
+                systems::spawn_animations.after(tick_tiles),
+                systems::update_tile_visuals.after(spawn_animations),
+                systems::animate_items.after(update_tile_visuals),
+                systems::cull_tile_sprites.after(animate_items),
+                systems::apply_tile_lod.after(cull_tile_sprites),
+                systems::draw_router_priorities.after(apply_tile_lod),
+                systems::draw_disabled_tiles.after(apply_tile_lod),
+                systems::draw_deconstruction_marks.after(apply_tile_lod),
                 (
-                    systems::manage_terrain_chunks,
-                    systems::tick_tiles,
-                    systems::spawn_animations.after(tick_tiles),
-                    systems::update_tile_visuals.after(spawn_animations),
-                    systems::animate_items.after(update_tile_visuals),
+                    systems::draw_deposit_overlay,
+                    systems::draw_beacon_ranges,
+                    systems::draw_deadlocks,
+                    systems::update_zone_overlays,
+                    systems::update_markers,
+                    systems::draw_flow_trace,
+                    systems::draw_world_border,
                 )
-                    .chain(),
-                (systems::manage_tiles, systems::move_camera).chain(),
+                    .after(apply_tile_lod),
+                systems::update_alt_mode_icons.after(apply_tile_lod),
+                systems::draw_belt_motion.after(apply_tile_lod),
+                systems::spawn_production_particles.after(tick_tiles),
+                systems::update_particles.after(spawn_production_particles),
+                systems::update_item_display_labels.after(apply_tile_lod),
+                systems::detect_alerts.after(tick_tiles),
+            )
+                .chain(),
+            (
+                systems::manage_tiles,
+                systems::move_camera,
+                systems::pan_camera,
+                systems::edge_scroll_camera,
+                systems::jump_to_core,
+                systems::animate_camera_pan,
+                (systems::smooth_zoom, systems::clamp_camera_to_border).chain(),
+                systems::capture_blueprint,
+                systems::place_blueprint,
+                systems::copy_paste_tile_settings,
+                systems::upgrade_planner,
+                systems::upgrade_tile_in_place,
+                systems::deconstruction_planner,
+                systems::clear_items_tool,
+                (
+                    systems::paint_zones,
+                    systems::place_markers,
+                    systems::select_flow_trace,
+                ),
+                systems::toggle_planning_mode,
+                systems::toggle_deposit_overlay,
+                systems::toggle_alt_mode,
+                systems::place_ghosts,
+                systems::update_placement_feedback,
+            )
+                .chain(),
+            (systems::photograph_base, systems::capture_base_photo).chain(),
+            systems::export_production_stats,
+            (
+                systems::advance_offline_progress.before(tick_tiles),
+                systems::record_production_stats.after(tick_tiles),
+            ),
+            systems::spawn_tile_sprites
+                .after(emit_tile_events)
+                .before(update_tile_visuals),
+            systems::play_tile_sounds.after(emit_tile_events),
+            (systems::toggle_debug_overlay, systems::update_debug_overlay).chain(),
+            (systems::run_console_commands, systems::update_console).chain(),
+            (systems::toggle_fullscreen, systems::remember_window).chain(),
+            (
+                (systems::exit_menu, systems::toggle_core_menu).chain(),
+                systems::spawn_inventory,
+                systems::update_inventory,
+                systems::handle_inventory_interaction,
+                systems::handle_inventory_context_menu,
                 (
-                    systems::exit_menu,
-                    systems::spawn_inventory,
-                    systems::update_inventory,
-                    systems::handle_inventory_interaction,
-                    systems::handle_inventory_context_menu,
                     systems::handle_hotkey_assignment,
-                    systems::update_core_menu,
-                    systems::handle_core_menu_interaction,
-                    systems::handle_core_context_menu,
-                    systems::update_money_widget,
+                    systems::toggle_hotkey_mode,
+                ),
+                systems::update_core_menu,
+                systems::handle_core_menu_interaction,
+                systems::handle_core_context_menu,
+                systems::update_factory_menu,
+                systems::handle_factory_menu_interaction,
+                systems::toggle_blueprint_menu,
+                systems::handle_blueprint_menu_interaction,
+                systems::open_condition_menu,
+                systems::handle_condition_menu_interaction,
+                systems::handle_router_menu_interaction,
+                systems::update_alert_feed,
+                systems::handle_alert_interaction,
+                systems::handle_dialogs,
+                systems::update_money_widget,
+            )
+                .chain(),
+            (
+                systems::update_world_event_banner,
+                (
+                    systems::track_recent_tiles,
+                    systems::handle_favorite_option,
+                    systems::handle_tile_bar_interaction,
+                    systems::update_tile_bar,
                 )
                     .chain(),
             ),
-        )
-        .run();
+            (
+                systems::handle_goal_interaction,
+                systems::update_goal_widget,
+            )
+                .chain(),
+            (
+                systems::handle_difficulty_menu_interaction,
+                systems::handle_world_size_interaction,
+            ),
+            (
+                systems::advance_scenario.after(tick_tiles),
+                systems::advance_daily_challenge.after(tick_tiles),
+                systems::advance_time_trial.after(tick_tiles),
+                systems::update_scenario_widget,
+                systems::toggle_scenario_menu,
+                systems::handle_scenario_menu_interaction,
+            )
+                .chain(),
+            (systems::toggle_stats_screen, systems::update_stats_screen).chain(),
+            (
+                systems::toggle_shop,
+                systems::handle_shop_interaction,
+                systems::update_shop,
+            )
+                .chain(),
+            (systems::toggle_ledger, systems::update_ledger).chain(),
+            (
+                systems::check_bankruptcy.after(tick_tiles),
+                systems::handle_bankruptcy_interaction,
+            )
+                .chain(),
+            (
+                systems::check_victory.after(tick_tiles),
+                systems::handle_victory_interaction,
+            )
+                .chain(),
+        ),
+    );
+
+    #[cfg(feature = "inspector")]
+    app.insert_resource(systems::Inspector::default())
+        .add_systems(
+            Update,
+            (
+                systems::toggle_inspector,
+                systems::select_inspected_tile,
+                systems::handle_inspector_interaction,
+                systems::update_inspector,
+            )
+                .chain(),
+        );
+
+    #[cfg(feature = "stats_feed")]
+    app.insert_resource(systems::StatsFeed::default())
+        .add_systems(
+            Update,
+            systems::write_stats_feed.after(systems::record_production_stats),
+        );
+
+    app.run();
 }
-fn setup_resources(mut commands: Commands) {
-    match WorldRes::load_game("savegame.ffs") {
-        Ok((world, hotkeys_map)) => {
+fn setup_resources(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    commands.insert_resource(BlueprintLibrary::load("blueprints.ffs").unwrap_or_default());
+    commands.insert_resource(Leaderboard::load("leaderboard.ffs").unwrap_or_default());
+
+    match WorldRes::load_game_with_backups("savegame.ffs") {
+        Ok((world, hotkeys_map, backup)) => {
+            if let Some(index) = backup {
+                let key = WorldRes::backup_key("savegame.ffs", index);
+                let mut lines = vec![
+                    "savegame.ffs could not be read.".to_string(),
+                    format!("Loaded backup {} instead.", key),
+                ];
+                if let Ok(header) = WorldRes::read_save_header(&key) {
+                    lines.push(format!(
+                        "It has ${} and {} tiles after {}:{:02} played.",
+                        header.money,
+                        header.tile_count,
+                        header.playtime / 3600,
+                        header.playtime / 60 % 60
+                    ));
+                }
+                let moved = WorldRes::set_aside_unreadable("savegame.ffs", index - 1);
+                if !moved.is_empty() {
+                    lines.push(format!(
+                        "The unreadable saves were kept as {}.",
+                        moved.join(", ")
+                    ));
+                }
+                systems::spawn_dialog(&mut commands, "Save file corrupted", &lines);
+            }
+            commands.insert_resource(ProductionStats::starting_at(world.tick_count));
             commands.insert_resource(world);
-            commands.insert_resource(Hotkeys {
-                mappings: hotkeys_map,
-            });
+            commands.insert_resource(Hotkeys::new(hotkeys_map, &settings));
         }
-        Err(_) => {
-            let mut resources = HashMap::new();
-            resources.insert((2, 1), 20);
-            resources.insert((2, 2), 5);
-            resources.insert((2, 3), 5);
-            resources.insert((3, 1), 1);
-            resources.insert((3, 3), 1);
-            resources.insert((4, 1), 1);
+        Err(err) => {
+            let moved = WorldRes::set_aside_unreadable("savegame.ffs", SAVE_BACKUP_COUNT);
+            if !moved.is_empty() {
+                let lines = [
+                    format!("savegame.ffs and its backups could not be read: {}", err),
+                    format!("They were kept as {}.", moved.join(", ")),
+                    "Started a new world.".to_string(),
+                ];
+                systems::spawn_dialog(&mut commands, "Save file corrupted", &lines);
+            }
 
-            let mut tiles: HashMap<Position, (Box<dyn Tile + 'static>, (u8, u8))> = HashMap::new();
-            tiles.insert(
-                Position::new(0, 0),
-                (
-                    Box::new(Core {
-                        position: Position::new(0, 0),
-                        interval: 10,
-                        ticks: 0,
-                        tile_id: (6, 1),
-                    }),
-                    (6, 1),
-                ),
-            );
-
-            commands.insert_resource(WorldRes {
-                tiles,
-                terrain: HashMap::new(),
-                loaded_chunks: HashSet::new(),
-                resources,
-                world_seed: rng().random_range(u32::MIN..u32::MAX),
-                tick_timer: Timer::from_seconds(TICK_LENGTH, TimerMode::Repeating),
-                tick_count: 0,
-                actions: Vec::new(),
-                money: 100,
-            });
-            commands.insert_resource(Hotkeys::default());
+            let world = WorldRes::new(Difficulty::default());
+            commands.insert_resource(ProductionStats::starting_at(world.tick_count));
+            commands.insert_resource(world);
+            systems::spawn_difficulty_menu(&mut commands, &mut images);
+            commands.insert_resource(Hotkeys::new(HotkeyMap::new(), &settings));
         }
     }
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>, world: Res<WorldRes>) {
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn(Camera2d);
+    commands.insert_resource(TerrainTextures::new(&asset_server));
     commands.spawn((
         Node {
             position_type: PositionType::Absolute,
@@ -132,6 +310,8 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>, world: Res<Worl
         },
         BorderRadius::all(Val::Px(10.0)),
         BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+        Interaction::default(),
+        MoneyWidgetButton,
         children![(
             Text::new(""),
             TextFont {
@@ -142,25 +322,78 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>, world: Res<Worl
             MoneyWidget,
         )],
     ));
-
-    for (pos, _) in world.tiles.iter() {
-        commands
-            .spawn((
-                Sprite::from_image(
-                    asset_server.load("embedded://textures/tiles/conveyors/back.png"),
-                ),
-                Transform {
-                    translation: Vec3::new(pos.x as f32 * TILE_SIZE, pos.y as f32 * TILE_SIZE, 0.0),
-                    scale: Vec3::splat(TILE_SIZE / IMAGE_SIZE),
-                    ..Default::default()
-                },
-                TileSprite { pos: *pos },
-            ))
-            .with_children(|parent| {
-                parent.spawn((
-                    Sprite::from_image(asset_server.load("embedded://textures/items/none.png")),
-                    Transform::from_scale(Vec3::splat(0.5)),
-                ));
-            });
-    }
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(5.0),
+            top: Val::Px(5.0),
+            max_width: Val::Vw(30.0),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::End,
+            row_gap: Val::Px(5.0),
+            ..default()
+        },
+        AlertFeed,
+    ));
+    systems::spawn_goal_widget(&mut commands);
+    systems::spawn_tile_bar(&mut commands);
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(5.0),
+            bottom: Val::Px(5.0),
+            max_width: Val::Vw(30.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            display: Display::None,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(10.0)),
+        BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        ScenarioText,
+    ));
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Vw(35.0),
+            top: Val::Px(5.0),
+            width: Val::Vw(30.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            display: Display::None,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(10.0)),
+        BackgroundColor(Color::srgb(0.45, 0.3, 0.1)),
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout {
+            justify: JustifyText::Center,
+            ..default()
+        },
+        WorldEventBanner,
+    ));
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            display: Display::None,
+            ..default()
+        },
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        PlacementLabel,
+    ));
 }
@@ -0,0 +1,110 @@
+use std::io;
+
+// Where settings, blueprints and saves are kept. Keys are file names such as `savegame.ffs`.
+pub trait SaveBackend {
+    fn read(&self, key: &str) -> io::Result<Vec<u8>>;
+    fn write(&self, key: &str, data: &[u8]) -> io::Result<()>;
+    fn exists(&self, key: &str) -> bool;
+    fn rename(&self, from: &str, to: &str) -> io::Result<()>;
+
+    fn copy(&self, from: &str, to: &str) -> io::Result<()> {
+        let data = self.read(from)?;
+        self.write(to, &data)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileBackend;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveBackend for FileBackend {
+    fn read(&self, key: &str) -> io::Result<Vec<u8>> {
+        std::fs::read(key)
+    }
+
+    fn write(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        std::fs::write(key, data)
+    }
+
+    fn exists(&self, key: &str) -> bool {
+        std::path::Path::new(key).exists()
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn copy(&self, from: &str, to: &str) -> io::Result<()> {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+// Browsers have no file system, so the web build keeps everything in localStorage as base64.
+#[cfg(target_arch = "wasm32")]
+pub struct BrowserBackend;
+
+#[cfg(target_arch = "wasm32")]
+impl BrowserBackend {
+    const PREFIX: &'static str = "factoryfactory/";
+
+    fn local_storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "localStorage unavailable"))
+    }
+
+    fn js_error(err: web_sys::wasm_bindgen::JsValue) -> io::Error {
+        io::Error::other(format!("{:?}", err))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl SaveBackend for BrowserBackend {
+    fn read(&self, key: &str) -> io::Result<Vec<u8>> {
+        use base64::{Engine, engine::general_purpose::STANDARD};
+
+        let value = Self::local_storage()?
+            .get_item(&format!("{}{}", Self::PREFIX, key))
+            .map_err(Self::js_error)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, key.to_string()))?;
+        STANDARD
+            .decode(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn write(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        use base64::{Engine, engine::general_purpose::STANDARD};
+
+        Self::local_storage()?
+            .set_item(&format!("{}{}", Self::PREFIX, key), &STANDARD.encode(data))
+            .map_err(Self::js_error)
+    }
+
+    fn exists(&self, key: &str) -> bool {
+        Self::local_storage()
+            .ok()
+            .and_then(|storage| {
+                storage
+                    .get_item(&format!("{}{}", Self::PREFIX, key))
+                    .ok()
+                    .flatten()
+            })
+            .is_some()
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        self.copy(from, to)?;
+        Self::local_storage()?
+            .remove_item(&format!("{}{}", Self::PREFIX, from))
+            .map_err(Self::js_error)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+static BACKEND: FileBackend = FileBackend;
+#[cfg(target_arch = "wasm32")]
+static BACKEND: BrowserBackend = BrowserBackend;
+
+pub fn save_backend() -> &'static dyn SaveBackend {
+    &BACKEND
+}
@@ -1,75 +1,1429 @@
-use bevy::prelude::*;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use bevy::{
+    prelude::*,
+    window::{MonitorSelection, WindowMode, WindowPosition},
+};
 use bincode::{Decode, Encode, config};
 use flate2::{Compression, read::DeflateDecoder, write::DeflateEncoder};
-use noise::{NoiseFn, Perlin};
+use rand::{Rng, SeedableRng, rng, rngs::StdRng, seq::SliceRandom};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::io::{self, Read, Write};
-use std::path::Path;
+use std::sync::LazyLock;
+use web_time::{SystemTime, UNIX_EPOCH};
 
-use crate::extractor::ExtractorType;
+use crate::extractor::{ExtractorTier, ExtractorType};
 use crate::factory::FactoryType;
 use crate::router::RouterOutputIndex;
 use crate::storage::StorageType;
 use crate::tiles::Tile;
-use crate::{Conveyor, Extractor, Factory, Junction, Portal, Router, Storage, types::*};
-use crate::{Core, constants::*};
+use crate::{
+    Beacon, Conveyor, Extractor, Factory, Inserter, ItemDisplay, Junction, Portal, Quarry, Router,
+    Sensor, Storage, Wire, types::*,
+};
+use crate::{
+    Core,
+    constants::*,
+    persistence::save_backend,
+    utils::{
+        TerrainNoise, footprint_cells, get_conveyor_tier, get_new_tile, get_terrain_texture,
+        get_tile_core_interval, get_tile_direction, get_tile_lod_color, get_tile_name,
+        get_tile_price, is_known_tile, is_protected_tile,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkPosition {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn from_world_position(world_pos: Vec2) -> Self {
+        Self {
+            x: (world_pos.x / (CHUNK_SIZE as f32 * TILE_SIZE)).floor() as i32,
+            y: (world_pos.y / (CHUNK_SIZE as f32 * TILE_SIZE)).floor() as i32,
+        }
+    }
+
+    pub fn of(pos: Position) -> Self {
+        Self {
+            x: pos.x.div_euclid(CHUNK_SIZE),
+            y: pos.y.div_euclid(CHUNK_SIZE),
+        }
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Position> {
+        let (min_x, min_y) = (self.x * CHUNK_SIZE, self.y * CHUNK_SIZE);
+        (0..CHUNK_SIZE)
+            .flat_map(move |x| (0..CHUNK_SIZE).map(move |y| Position::new(min_x + x, min_y + y)))
+    }
+}
+
+#[derive(Resource)]
+pub struct Placer {
+    pub direction: Direction,
+    pub tile_type: (u8, u8),
+    pub preview_entity: Option<Entity>,
+    pub zoom_level: f32,
+    pub planning: bool,
+    // A different tile that was clicked once and is replaced if clicked again.
+    pub pending_replace: Option<Position>,
+    // The cell the held left mouse last placed on, so holding still doesn't place every frame.
+    pub last_placed: Option<Position>,
+    pub repeat_timer: f32,
+}
+
+impl Default for Placer {
+    fn default() -> Self {
+        Self {
+            direction: Direction::Up,
+            tile_type: (0, 1),
+            preview_entity: None,
+            zoom_level: 1.0,
+            planning: false,
+            pending_replace: None,
+            last_placed: None,
+            repeat_timer: 0.0,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Hotkeys {
+    // What the number keys place: the global mappings with this world's own on top.
+    pub mappings: HotkeyMap,
+    // Mappings saved with this world. Without global hotkeys these are all of them.
+    pub world: HotkeyMap,
+}
+
+impl Hotkeys {
+    pub fn new(world: HotkeyMap, settings: &Settings) -> Self {
+        let mut hotkeys = Self {
+            mappings: HashMap::new(),
+            world,
+        };
+        hotkeys.merge(settings);
+        hotkeys
+    }
+
+    pub fn merge(&mut self, settings: &Settings) {
+        self.mappings = if settings.global_hotkeys {
+            settings.hotkeys.iter().copied().collect()
+        } else {
+            HashMap::new()
+        };
+        self.mappings.extend(&self.world);
+    }
+
+    // Returns whether the settings changed and need saving. A global assignment drops this
+    // world's mapping for the key so the new one shows through.
+    pub fn assign(
+        &mut self,
+        key: u8,
+        tile_type: (u8, u8),
+        settings: &mut Settings,
+        world_only: bool,
+    ) -> bool {
+        let global = settings.global_hotkeys && !world_only;
+        if global {
+            settings.hotkeys.retain(|(mapped, _)| *mapped != key);
+            settings.hotkeys.push((key, tile_type));
+            self.world.remove(&key);
+        } else {
+            self.world.insert(key, tile_type);
+        }
+        self.merge(settings);
+        global
+    }
+
+    // Switching keeps every key placing what it did: going global moves this world's mappings
+    // into the settings, going per-world copies the merged ones into the world.
+    pub fn set_global(&mut self, settings: &mut Settings, global: bool) {
+        if global {
+            let mut mappings: Vec<_> = self.mappings.iter().map(|(k, v)| (*k, *v)).collect();
+            mappings.sort();
+            settings.hotkeys = mappings;
+            self.world.clear();
+        } else {
+            self.world = self.mappings.clone();
+        }
+        settings.global_hotkeys = global;
+        self.merge(settings);
+    }
+}
+
+#[derive(Resource)]
+pub struct GameSpeed {
+    pub paused: bool,
+    pub multiplier: u32,
+}
+
+impl Default for GameSpeed {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            multiplier: 1,
+        }
+    }
+}
+
+// Ticks still to catch up on after loading a save, run a budget of actions per frame so a large
+// base doesn't hold up the first frame.
+#[derive(Resource, Default)]
+pub struct OfflineProgress {
+    pub remaining: u32,
+    pub ticks: u32,
+    pub elapsed: u64,
+    pub resources_before: HashMap<(u8, u8), u32>,
+    pub money_before: u32,
+}
+
+// Randomness for simulation that must replay the same way, like world events. It is seeded from
+// the world seed and a tick, so the same world and tick always roll the same. The world seed is
+// the only gameplay draw from `rand::rng()`, and it's saved; cosmetic effects use `rand::rng()` too.
+pub fn tick_rng(world_seed: u32, tick: i32) -> StdRng {
+    StdRng::seed_from_u64((world_seed as u64) << 32 | tick as u32 as u64)
+}
+
+#[derive(Resource)]
+pub struct TerrainTextures {
+    pub handles: Vec<(TerrainTileType, Handle<Image>)>,
+    pub pixels: HashMap<TerrainTileType, Vec<[u8; 4]>>,
+    // Keyed by the seed and difficulty the images were baked for.
+    chunk_world: Option<(u32, Difficulty)>,
+    chunk_images: HashMap<ChunkPosition, Handle<Image>>,
+    chunk_order: VecDeque<ChunkPosition>,
+}
+
+impl TerrainTextures {
+    pub fn new(asset_server: &AssetServer) -> Self {
+        Self {
+            handles: [
+                TerrainTileType::Stone,
+                TerrainTileType::RawFlextoriumDeposit,
+                TerrainTileType::RawRigtoriumDeposit,
+                TerrainTileType::ElectrineDeposit,
+                TerrainTileType::OilSeep,
+            ]
+            .into_iter()
+            .map(|terrain_type| {
+                (
+                    terrain_type,
+                    asset_server.load(get_terrain_texture(terrain_type)),
+                )
+            })
+            .collect(),
+            pixels: HashMap::new(),
+            chunk_world: None,
+            chunk_images: HashMap::new(),
+            chunk_order: VecDeque::new(),
+        }
+    }
+
+    pub fn cached_chunk(&mut self, world: &WorldRes, pos: ChunkPosition) -> Option<Handle<Image>> {
+        let key = (world.world_seed, world.difficulty);
+        if self.chunk_world != Some(key) {
+            self.chunk_world = Some(key);
+            self.chunk_images.clear();
+            self.chunk_order.clear();
+        }
+        // A new world with the same seed still needs its terrain generated.
+        if !world
+            .terrain
+            .contains_key(&Position::new(pos.x * CHUNK_SIZE, pos.y * CHUNK_SIZE))
+        {
+            return None;
+        }
+        self.chunk_images.get(&pos).cloned()
+    }
+
+    pub fn cache_chunk(&mut self, pos: ChunkPosition, image: Handle<Image>) {
+        if self.chunk_images.insert(pos, image).is_none() {
+            self.chunk_order.push_back(pos);
+        }
+        while self.chunk_order.len() > CHUNK_IMAGE_CACHE_SIZE {
+            if let Some(oldest) = self.chunk_order.pop_front() {
+                self.chunk_images.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn is_ready(&mut self, images: &Assets<Image>) -> bool {
+        for (terrain_type, handle) in self.handles.iter() {
+            if self.pixels.contains_key(terrain_type) {
+                continue;
+            }
+            let Some(image) = images.get(handle) else {
+                return false;
+            };
+
+            let step = (image.width() / TERRAIN_BAKE_TEXELS).max(1);
+            let mut pixels =
+                Vec::with_capacity((TERRAIN_BAKE_TEXELS * TERRAIN_BAKE_TEXELS) as usize);
+            for y in 0..TERRAIN_BAKE_TEXELS {
+                for x in 0..TERRAIN_BAKE_TEXELS {
+                    let mut sum = Vec4::ZERO;
+                    for dy in 0..step {
+                        for dx in 0..step {
+                            if let Ok(color) = image.get_color_at(x * step + dx, y * step + dy) {
+                                sum += color.to_srgba().to_vec4();
+                            }
+                        }
+                    }
+                    pixels.push(Srgba::from_vec4(sum / (step * step) as f32).to_u8_array());
+                }
+            }
+            self.pixels.insert(*terrain_type, pixels);
+        }
+        true
+    }
+}
+
+// Stored as RON so hand edits are easy and fields added later fall back to their defaults
+// instead of discarding the whole file.
+#[derive(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub pan_sensitivity: f32,
+    pub edge_scroll: bool,
+    pub edge_scroll_speed: f32,
+    pub edge_scroll_margin: f32,
+    pub offline_progress: bool,
+    pub muted_alerts: Vec<AlertCategory>,
+    pub completed_scenarios: Vec<String>,
+    pub bankruptcy: bool,
+    // 0 resolves every tick in a single frame.
+    pub tick_budget: u32,
+    // Name of a directory in `texture_packs/` whose files override the embedded `textures/`.
+    pub texture_pack: Option<String>,
+    // Windowed size and position, kept while fullscreen so leaving it restores them.
+    pub window_size: (f32, f32),
+    pub window_position: Option<(i32, i32)>,
+    pub fullscreen: bool,
+    pub favorite_tiles: Vec<(u8, u8)>,
+    // Fewest ticks taken to finish each day's daily challenge, by day.
+    pub daily_results: Vec<(u64, i32)>,
+    // Shares `hotkeys` across worlds, with each world's own mappings taking precedence.
+    pub global_hotkeys: bool,
+    pub hotkeys: Vec<(u8, (u8, u8))>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            pan_sensitivity: 1.0,
+            edge_scroll: false,
+            edge_scroll_speed: CAMERA_SPEED,
+            edge_scroll_margin: 20.0,
+            offline_progress: true,
+            muted_alerts: Vec::new(),
+            completed_scenarios: Vec::new(),
+            bankruptcy: true,
+            tick_budget: TICK_BUDGET_ACTIONS,
+            texture_pack: None,
+            window_size: (1280.0, 720.0),
+            window_position: None,
+            fullscreen: false,
+            favorite_tiles: Vec::new(),
+            daily_results: Vec::new(),
+            global_hotkeys: false,
+            hotkeys: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn window(&self) -> Window {
+        Window {
+            title: "Factory Factory".into(),
+            name: Some("factoyfactory.app".into()),
+            resolution: self.window_size.into(),
+            position: match self.window_position {
+                Some((x, y)) => WindowPosition::At(IVec2::new(x, y)),
+                None => WindowPosition::Automatic,
+            },
+            mode: if self.fullscreen {
+                WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+            } else {
+                WindowMode::Windowed
+            },
+            fit_canvas_to_parent: true,
+            prevent_default_event_handling: false,
+            ..default()
+        }
+    }
+
+    pub fn save(&self, key: &str) -> Result<(), io::Error> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+
+        save_backend().write(key, serialized.as_bytes())
+    }
+
+    pub fn load(key: &str) -> io::Result<Settings> {
+        let buffer = save_backend().read(key)?;
+
+        ron::de::from_bytes(&buffer).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[derive(Clone, Encode, Decode)]
+pub struct BlueprintTile {
+    pub offset: (i32, i32),
+    pub tile_type: (u8, u8),
+    pub direction: Direction,
+}
+
+#[derive(Clone, Encode, Decode)]
+pub struct Blueprint {
+    pub name: String,
+    pub tiles: Vec<BlueprintTile>,
+}
+
+impl Blueprint {
+    pub fn capture(world: &WorldRes, name: String, start: Position, end: Position) -> Blueprint {
+        let region = GridRegion::new(start, end);
+
+        let mut tiles = Vec::new();
+        for pos in region.cells() {
+            if let Some((tile, tile_type)) = world.tiles.get(&pos)
+                && !is_protected_tile(*tile_type)
+            {
+                tiles.push(BlueprintTile {
+                    offset: (pos.x - region.min.x, pos.y - region.min.y),
+                    tile_type: *tile_type,
+                    direction: get_tile_direction(tile.as_ref()),
+                });
+            }
+        }
+
+        Blueprint { name, tiles }
+    }
+
+    pub fn export(&self) -> io::Result<String> {
+        let config = config::standard().with_fixed_int_encoding().with_no_limit();
+
+        let serialized = bincode::encode_to_vec(self, config).map_err(io::Error::other)?;
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&serialized)?;
+
+        Ok(format!(
+            "{}{}",
+            BLUEPRINT_PREFIX,
+            URL_SAFE_NO_PAD.encode(encoder.finish()?)
+        ))
+    }
+
+    pub fn import(string: &str) -> io::Result<Blueprint> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let encoded = string
+            .trim()
+            .strip_prefix(BLUEPRINT_PREFIX)
+            .ok_or_else(|| invalid("not a blueprint string"))?;
+        let compressed = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| invalid("blueprint string is not valid base64"))?;
+
+        let mut buffer = Vec::new();
+        DeflateDecoder::new(compressed.as_slice())
+            .take(BLUEPRINT_MAX_BYTES as u64)
+            .read_to_end(&mut buffer)?;
+
+        let config = config::standard()
+            .with_fixed_int_encoding()
+            .with_limit::<BLUEPRINT_MAX_BYTES>();
+
+        let (blueprint, _): (Blueprint, _) = bincode::decode_from_slice(&buffer, config)
+            .map_err(|_| invalid("blueprint data is corrupted"))?;
+
+        if blueprint.tiles.is_empty() {
+            return Err(invalid("blueprint is empty"));
+        }
+        if blueprint.tiles.len() > BLUEPRINT_MAX_TILES {
+            return Err(invalid("blueprint is too large"));
+        }
+        for tile in blueprint.tiles.iter() {
+            let (x, y) = tile.offset;
+            if !(0..=BLUEPRINT_MAX_EXTENT).contains(&x) || !(0..=BLUEPRINT_MAX_EXTENT).contains(&y)
+            {
+                return Err(invalid("blueprint is too large"));
+            }
+            if is_protected_tile(tile.tile_type) || !is_known_tile(tile.tile_type) {
+                return Err(invalid("blueprint contains unknown tiles"));
+            }
+        }
+
+        Ok(blueprint)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct BlueprintLibrary {
+    pub blueprints: Vec<Blueprint>,
+    pub active: Option<usize>,
+    pub selection_start: Option<Position>,
+}
+
+impl BlueprintLibrary {
+    pub fn save(&self, key: &str) -> Result<(), io::Error> {
+        let config = config::standard().with_fixed_int_encoding().with_no_limit();
+
+        let serialized =
+            bincode::encode_to_vec(&self.blueprints, config).map_err(io::Error::other)?;
+
+        save_backend().write(key, &serialized)
+    }
+
+    pub fn load(key: &str) -> io::Result<BlueprintLibrary> {
+        let buffer = save_backend().read(key)?;
+
+        let config = config::standard().with_fixed_int_encoding().with_no_limit();
+
+        let (blueprints, _) =
+            bincode::decode_from_slice(&buffer, config).map_err(io::Error::other)?;
+
+        Ok(BlueprintLibrary {
+            blueprints,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum TileSettings {
+    Core {
+        tile_id: (u8, u8),
+    },
+    Factory {
+        tile_type: (u8, u8),
+        recipe_index: usize,
+        outputs: [bool; 4],
+        inputs: [bool; 4],
+    },
+    PriorityRouter {
+        priority: RouterOutputIndex,
+    },
+}
+
+impl TileSettings {
+    pub fn copy_from(tile: &dyn Tile, tile_type: (u8, u8)) -> Option<TileSettings> {
+        if let Some(core) = tile.as_any().downcast_ref::<Core>() {
+            return Some(TileSettings::Core {
+                tile_id: core.tile_id,
+            });
+        }
+        if let Some(router) = tile.as_any().downcast_ref::<Router>() {
+            return router
+                .priority
+                .map(|priority| TileSettings::PriorityRouter { priority });
+        }
+
+        tile.as_any()
+            .downcast_ref::<Factory>()
+            .map(|factory| TileSettings::Factory {
+                tile_type,
+                recipe_index: factory.recipe_index,
+                outputs: factory.outputs,
+                inputs: factory.inputs,
+            })
+    }
+
+    // Returns whether the tile changed; settings it can't take leave it as it was.
+    pub fn apply(
+        &self,
+        tile: &mut dyn Tile,
+        target_type: (u8, u8),
+        difficulty: Difficulty,
+    ) -> bool {
+        match *self {
+            TileSettings::Core { tile_id } => {
+                if let Some(core) = tile.as_any_mut().downcast_mut::<Core>()
+                    && core.tile_id != tile_id
+                {
+                    core.tile_id = tile_id;
+                    core.interval = get_tile_core_interval(tile_id, difficulty);
+                    core.ticks = 0;
+                    return true;
+                }
+            }
+            TileSettings::Factory {
+                tile_type,
+                recipe_index,
+                outputs,
+                inputs,
+            } => {
+                if tile_type == target_type
+                    && let Some(factory) = tile.as_any_mut().downcast_mut::<Factory>()
+                    && (factory.recipe_index, factory.outputs, factory.inputs)
+                        != (recipe_index, outputs, inputs)
+                {
+                    // A recipe the inventory won't fit leaves the factory as it was.
+                    if !factory.select_recipe(recipe_index) {
+                        return false;
+                    }
+                    factory.outputs = outputs;
+                    factory.inputs = inputs;
+                    return true;
+                }
+            }
+            TileSettings::PriorityRouter { priority } => {
+                if let Some(router) = tile.as_any_mut().downcast_mut::<Router>()
+                    && router.priority.is_some_and(|current| current != priority)
+                {
+                    router.priority = Some(priority);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct CopiedSettings {
+    pub settings: Option<TileSettings>,
+}
+
+#[derive(Resource, Default)]
+pub struct UpgradePlanner {
+    pub selection_start: Option<Position>,
+}
+
+#[derive(Resource, Default)]
+pub struct DeconstructionPlanner {
+    pub selection_start: Option<Position>,
+}
+
+#[derive(Resource, Default)]
+pub struct ClearItemsTool {
+    pub selection_start: Option<Position>,
+}
+
+// The tile the item flow tracer starts from and the item it follows.
+#[derive(Resource, Default)]
+pub struct FlowTrace {
+    pub start: Option<Position>,
+    pub item: Option<Item>,
+}
+
+#[derive(Resource, Default)]
+pub struct ZonePainter {
+    pub color: u8,
+    pub selection_start: Option<Position>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encode, Decode, Serialize, Deserialize)]
+pub enum AlertCategory {
+    Starvation,
+    StorageFull,
+    CoreProduction,
+    Deadlock,
+}
+
+impl AlertCategory {
+    pub const ALL: [AlertCategory; 4] = [
+        AlertCategory::Starvation,
+        AlertCategory::StorageFull,
+        AlertCategory::CoreProduction,
+        AlertCategory::Deadlock,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertCategory::Starvation => "Starvation",
+            AlertCategory::StorageFull => "Storage full",
+            AlertCategory::CoreProduction => "Core output",
+            AlertCategory::Deadlock => "Deadlock",
+        }
+    }
+}
+
+pub struct Alert {
+    pub category: AlertCategory,
+    pub message: String,
+    pub position: Position,
+    pub tick: i32,
+}
+
+// A tile was placed, removed, reconfigured or had its items change.
+#[derive(Event, Clone, Copy)]
+pub struct TileChanged {
+    pub pos: Position,
+}
+
+#[derive(Event, Clone, Copy)]
+pub struct TilePlaced {
+    pub pos: Position,
+    pub tile_type: (u8, u8),
+}
+
+#[derive(Event, Clone, Copy)]
+pub struct TileRemoved {
+    pub pos: Position,
+    pub tile_type: (u8, u8),
+}
+
+// An item left `from` for `to` this tick. Lanes are `None` for tiles without lanes.
+#[derive(Event, Clone, Copy)]
+pub struct ItemMoved {
+    pub from: Position,
+    pub to: Position,
+    pub item: Item,
+    pub from_lane: Option<Lane>,
+    pub to_lane: Option<Lane>,
+}
+
+#[derive(Event, Clone, Copy)]
+pub struct TickFinished {
+    pub tick: i32,
+}
+
+#[derive(Event, Clone, Copy)]
+pub struct ItemProduced {
+    pub pos: Position,
+    pub kind: ProductionEventKind,
+}
+
+#[derive(Clone, Copy)]
+pub enum ProductionEventKind {
+    Factory(&'static Recipe),
+    Extractor(Item),
+    Core((u8, u8)),
+}
+
+// What a single simulation tick did, for the systems that react to it.
+#[derive(Default)]
+pub struct TickEvents {
+    pub moved: Vec<ItemMoved>,
+    pub produced: Vec<ItemProduced>,
+}
+
+// Placements and removals queued until the next frame's events are written.
+#[derive(Default)]
+pub struct TileMutations {
+    pub placed: Vec<TilePlaced>,
+    pub removed: Vec<TileRemoved>,
+}
+
+// Handlers get the whole ECS world so that commands added by mods can reach any resource.
+pub type ConsoleHandler = fn(&mut World, &[&str]) -> Result<String, String>;
+
+pub struct ConsoleCommand {
+    pub usage: &'static str,
+    pub run: ConsoleHandler,
+}
+
+#[derive(Resource, Default)]
+pub struct ConsoleCommands {
+    pub commands: BTreeMap<&'static str, ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    pub fn register(&mut self, name: &'static str, usage: &'static str, run: ConsoleHandler) {
+        self.commands.insert(name, ConsoleCommand { usage, run });
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Console {
+    pub input: String,
+    pub lines: VecDeque<String>,
+    pub submitted: Vec<String>,
+}
+
+impl Console {
+    pub fn print(&mut self, text: &str) {
+        for line in text.lines() {
+            self.lines.push_back(line.to_string());
+        }
+        while self.lines.len() > CONSOLE_MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct AltMode {
+    pub active: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UiPanel {
+    Inventory,
+    Core,
+    Factory,
+}
+
+// Which menus are open, refreshed once per frame before any input is read. Systems ask this
+// instead of querying every menu themselves.
+#[derive(Resource, Default)]
+pub struct UiState {
+    // The full-screen panel that is open; only one can be at a time.
+    pub panel: Option<UiPanel>,
+    // Any smaller menu or overlay that takes clicks away from the world.
+    pub overlay: bool,
+}
+
+impl UiState {
+    pub fn blocks_gameplay(&self) -> bool {
+        self.panel.is_some() || self.overlay
+    }
+
+    pub fn blocks_camera(&self) -> bool {
+        self.panel.is_some()
+    }
+
+    pub fn can_open(&self, panel: UiPanel) -> bool {
+        self.panel.is_none_or(|open| open == panel)
+    }
+
+    // Overlays stack on each other but never open over a panel.
+    pub fn can_open_overlay(&self) -> bool {
+        self.panel.is_none()
+    }
+}
+
+// Open menus, oldest first, so Escape closes the most recently opened one.
+#[derive(Resource, Default)]
+pub struct MenuStack {
+    pub menus: Vec<Entity>,
+}
+
+// The tiles most recently picked for placing, newest first.
+#[derive(Resource, Default)]
+pub struct RecentTiles {
+    pub tiles: VecDeque<(u8, u8)>,
+}
+
+#[derive(Resource, Default)]
+pub struct DepositOverlay {
+    pub active: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct Alerts {
+    pub entries: VecDeque<Alert>,
+    pub last_tick: i32,
+    // When each starving factory started starving, and whether it has been alerted about yet.
+    pub starved_since: HashMap<Position, (i32, bool)>,
+    pub full_storages: HashSet<Position>,
+    pub core_ticks: HashMap<Position, u32>,
+    pub deadlocked: HashSet<Position>,
+    pub dirty: bool,
+}
+
+impl Alerts {
+    pub fn push(&mut self, settings: &Settings, alert: Alert) {
+        if settings.muted_alerts.contains(&alert.category) {
+            return;
+        }
+        self.entries.push_front(alert);
+        self.entries.truncate(ALERT_MAX_ENTRIES);
+        self.dirty = true;
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Scenario {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub difficulty: Difficulty,
+    pub money: u32,
+    pub resources: Vec<((u8, u8), u32)>,
+    pub tiles: Vec<ScenarioTile>,
+    pub objectives: Vec<Objective>,
+    pub locked: Vec<(u8, u8)>,
+}
+
+#[derive(Deserialize)]
+pub struct ScenarioTile {
+    pub position: (i32, i32),
+    pub tile_type: (u8, u8),
+    pub direction: Direction,
+}
+
+#[derive(Deserialize)]
+pub struct Objective {
+    pub description: String,
+    pub goal: ObjectiveGoal,
+    pub unlocks: Vec<(u8, u8)>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum ObjectiveGoal {
+    Produce { item: Item, amount: u64 },
+    Money(u32),
+    Place { tile_type: (u8, u8), count: u32 },
+}
+
+pub static SCENARIOS: LazyLock<Vec<Scenario>> = LazyLock::new(|| {
+    SCENARIO_FILES
+        .iter()
+        .filter_map(|source| match ron::from_str(source) {
+            Ok(scenario) => Some(scenario),
+            Err(err) => {
+                eprintln!("Error loading scenario: {}", err);
+                None
+            }
+        })
+        .collect()
+});
+
+impl Scenario {
+    pub fn find(id: &str) -> Option<&'static Scenario> {
+        SCENARIOS.iter().find(|scenario| scenario.id == id)
+    }
+
+    pub fn create_world(&self) -> WorldRes {
+        let mut world = WorldRes::new(self.difficulty);
+        world.money = self.money;
+        world.resources = self.resources.iter().copied().collect();
+        for tile in self.tiles.iter() {
+            let pos = Position::new(tile.position.0, tile.position.1);
+            world
+                .tiles
+                .insert(pos, get_new_tile(tile.tile_type, pos, tile.direction));
+            world.occupy(pos, tile.tile_type);
+        }
+        world.scenario = Some(ScenarioProgress {
+            id: self.id.clone(),
+            objective: 0,
+            produced: 0,
+        });
+        world
+    }
+}
+
+// A world everyone gets on the same day: the seed and the contracts come from the date.
+#[derive(Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct DailyChallenge {
+    // Days since the Unix epoch, in UTC.
+    pub day: u64,
+    pub contracts: Vec<(Item, u64)>,
+    pub completed_at: Option<i32>,
+}
+
+impl DailyChallenge {
+    pub fn today() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self::for_day(now / 86400)
+    }
+
+    pub fn for_day(day: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(day);
+        let mut pool = DAILY_CONTRACT_ITEMS.to_vec();
+        pool.shuffle(&mut rng);
+        let contracts = pool
+            .into_iter()
+            .take(DAILY_CONTRACT_COUNT)
+            .map(|(item, min, max)| (item, rng.random_range(min..=max)))
+            .collect();
+
+        DailyChallenge {
+            day,
+            contracts,
+            completed_at: None,
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        (self.day as u32).wrapping_mul(2_654_435_761)
+    }
+
+    // The day as YYYY-MM-DD.
+    pub fn date(&self) -> String {
+        let days = self.day as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        format!("{}-{:02}-{:02}", year, month, day)
+    }
+
+    pub fn contract_progress(&self, lifetime: &LifetimeStats) -> Vec<(Item, u64, u64)> {
+        self.contracts
+            .iter()
+            .map(|(item, amount)| {
+                let produced = lifetime.produced.get(item).copied().unwrap_or(0);
+                (*item, produced.min(*amount), *amount)
+            })
+            .collect()
+    }
+
+    // Daily challenges always start on Normal with the balanced kit, so runs are comparable.
+    pub fn create_world(&self) -> WorldRes {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        world.world_seed = self.seed();
+        world.daily = Some(self.clone());
+        world
+    }
+}
+
+// Race to deliver a fixed number of one item through Portals; the time is kept per seed.
+#[derive(Clone, Copy, Serialize, Deserialize, Encode, Decode)]
+pub struct TimeTrial {
+    pub item: Item,
+    pub amount: u32,
+    pub delivered: u32,
+    pub completed_at: Option<i32>,
+}
+
+impl TimeTrial {
+    pub fn create_world(seed: u32) -> WorldRes {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        world.world_seed = seed;
+        *world.resources.entry((1, 2)).or_insert(0) += 1;
+        world.time_trial = Some(TimeTrial {
+            item: TIME_TRIAL_ITEM,
+            amount: TIME_TRIAL_AMOUNT,
+            delivered: 0,
+            completed_at: None,
+        });
+        world
+    }
+}
+
+#[derive(Clone, Copy, Encode, Decode)]
+pub struct LeaderboardEntry {
+    pub seed: u32,
+    pub ticks: i32,
+    pub finished_at: u64,
+}
+
+// Best time trial runs, kept per seed in their own file.
+#[derive(Resource, Default)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    pub fn save(&self, key: &str) -> Result<(), io::Error> {
+        let config = config::standard().with_fixed_int_encoding().with_no_limit();
+
+        let serialized = bincode::encode_to_vec(&self.entries, config).map_err(io::Error::other)?;
+
+        save_backend().write(key, &serialized)
+    }
+
+    pub fn load(key: &str) -> io::Result<Leaderboard> {
+        let buffer = save_backend().read(key)?;
+
+        let config = config::standard().with_fixed_int_encoding().with_no_limit();
+
+        let (entries, _) = bincode::decode_from_slice(&buffer, config).map_err(io::Error::other)?;
+
+        Ok(Leaderboard { entries })
+    }
+
+    // Fastest runs on a seed first.
+    pub fn times(&self, seed: u32) -> Vec<LeaderboardEntry> {
+        let mut times: Vec<LeaderboardEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.seed == seed)
+            .copied()
+            .collect();
+        times.sort_by_key(|entry| entry.ticks);
+        times
+    }
+
+    // Adds a run and returns its place on the seed's board, or `None` if it didn't make the cut.
+    pub fn record(&mut self, seed: u32, ticks: i32) -> Option<usize> {
+        let mut times = self.times(seed);
+        let place = times
+            .iter()
+            .position(|entry| entry.ticks > ticks)
+            .unwrap_or(times.len());
+        if place >= LEADERBOARD_SIZE {
+            return None;
+        }
+
+        times.insert(
+            place,
+            LeaderboardEntry {
+                seed,
+                ticks,
+                finished_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or_default(),
+            },
+        );
+        times.truncate(LEADERBOARD_SIZE);
+        self.entries.retain(|entry| entry.seed != seed);
+        self.entries.extend(times);
+        Some(place + 1)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct ScenarioProgress {
+    pub id: String,
+    pub objective: usize,
+    pub produced: u64,
+}
+
+impl ScenarioProgress {
+    pub fn is_tile_locked(&self, tile_type: (u8, u8)) -> bool {
+        let Some(scenario) = Scenario::find(&self.id) else {
+            return false;
+        };
+        scenario.locked.contains(&tile_type)
+            && !scenario
+                .objectives
+                .iter()
+                .take(self.objective)
+                .any(|objective| objective.unlocks.contains(&tile_type))
+    }
+}
+
+#[derive(Resource)]
+pub struct ProductionGoal {
+    pub item: Option<Item>,
+    pub target: u32,
+    pub baseline: u64,
+}
+
+impl Default for ProductionGoal {
+    fn default() -> Self {
+        Self {
+            item: None,
+            target: GOAL_DEFAULT_TARGET,
+            baseline: 0,
+        }
+    }
+}
+
+impl ProductionGoal {
+    pub fn progress(&self, stats: &ProductionStats) -> u64 {
+        match self.item {
+            Some(item) => stats
+                .totals
+                .get(&item)
+                .unwrap_or(&0)
+                .saturating_sub(self.baseline),
+            None => 0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct StatsWindow {
+    pub start_tick: i32,
+    pub end_tick: i32,
+    pub produced: HashMap<Item, u32>,
+    pub consumed: HashMap<Item, u32>,
+}
+
+#[derive(Resource, Default)]
+pub struct ProductionStats {
+    pub windows: VecDeque<StatsWindow>,
+    pub current: StatsWindow,
+    pub totals: HashMap<Item, u64>,
+}
+
+impl ProductionStats {
+    // Stats for a world loaded or started at `tick_count`, so the first window doesn't reach
+    // back to tick 0.
+    pub fn starting_at(tick_count: i32) -> Self {
+        Self {
+            current: StatsWindow {
+                start_tick: tick_count,
+                end_tick: tick_count,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn advance(&mut self, tick_count: i32) {
+        self.current.end_tick = tick_count;
+        if tick_count - self.current.start_tick >= STATS_WINDOW_TICKS {
+            let finished = std::mem::replace(
+                &mut self.current,
+                StatsWindow {
+                    start_tick: tick_count,
+                    end_tick: tick_count,
+                    ..Default::default()
+                },
+            );
+            self.windows.push_back(finished);
+            if self.windows.len() > STATS_MAX_WINDOWS {
+                self.windows.pop_front();
+            }
+        }
+    }
+
+    pub fn record_production(&mut self, item: Item, qty: u32) {
+        *self.current.produced.entry(item).or_insert(0) += qty;
+        *self.totals.entry(item).or_insert(0) += qty as u64;
+    }
+
+    pub fn record_consumption(&mut self, item: Item, qty: u32) {
+        *self.current.consumed.entry(item).or_insert(0) += qty;
+    }
+
+    pub fn record(&mut self, kind: ProductionEventKind) {
+        match kind {
+            ProductionEventKind::Factory(recipe) => {
+                self.record_production(recipe.output, 1);
+                for item in recipe.byproducts.iter() {
+                    self.record_production(*item, 1);
+                }
+                for (item, qty) in recipe.inputs.iter() {
+                    self.record_consumption(*item, *qty);
+                }
+            }
+            ProductionEventKind::Extractor(item) => self.record_production(item, 1),
+            ProductionEventKind::Core(_) => {}
+        }
+    }
+
+    pub fn export_csv(&self, key: &str) -> io::Result<()> {
+        let mut csv = Vec::new();
+        writeln!(
+            csv,
+            "window_start_tick,window_end_tick,item,produced,consumed"
+        )?;
+
+        for window in self.windows.iter().chain(std::iter::once(&self.current)) {
+            let mut items: Vec<Item> = window
+                .produced
+                .keys()
+                .chain(window.consumed.keys())
+                .copied()
+                .collect();
+            items.sort_by_key(|item| item.name());
+            items.dedup();
+
+            for item in items {
+                writeln!(
+                    csv,
+                    "{},{},{},{},{}",
+                    window.start_tick,
+                    window.end_tick,
+                    item.name(),
+                    window.produced.get(&item).unwrap_or(&0),
+                    window.consumed.get(&item).unwrap_or(&0)
+                )?;
+            }
+        }
+
+        save_backend().write(key, &csv)
+    }
+}
+
+pub type HotkeyMap = HashMap<u8, (u8, u8)>;
+pub type TileMap = HashMap<Position, (Box<dyn Tile>, (u8, u8))>;
+
+#[derive(Resource)]
+pub struct WorldRes {
+    pub tiles: TileMap,
+    pub terrain: HashMap<Position, TerrainTileType>,
+    pub loaded_chunks: HashSet<ChunkPosition>,
+    // Chunks whose tiles have sprites. Tiles anywhere else get theirs once their chunk is loaded.
+    pub sprite_chunks: HashSet<ChunkPosition>,
+    pub resources: HashMap<(u8, u8), u32>,
+    pub world_seed: u32,
+    // Half the side of the square the world is limited to, in tiles. None for an endless world.
+    pub world_radius: Option<i32>,
+    pub tick_timer: Timer,
+    pub tick_count: i32,
+    pub actions: Vec<Action>,
+    pub money: u32,
+    pub saved_at: u64,
+    pub conditions: HashMap<Position, SignalCondition>,
+    // Wireless channel of each sensor, portal and display that has one.
+    pub channels: HashMap<Position, u8>,
+    // Painted zone colour of each cell, as an index into `ZONE_COLORS`, and the zones' names.
+    pub zones: HashMap<Position, u8>,
+    pub zone_names: HashMap<u8, String>,
+    pub labels: HashMap<Position, String>,
+    // Bumped whenever zones, their names or labels change, so the overlay knows to redraw.
+    pub zone_revision: u32,
+    // Map pins, as an index into `MARKER_ICONS`.
+    pub markers: HashMap<Position, u8>,
+    pub disabled: HashSet<Position>,
+    pub last_input: HashMap<Position, Position>,
+    // Tick each factory last finished a recipe on, since the world was loaded.
+    pub last_produced: HashMap<Position, i32>,
+    pub flowing: HashSet<(Position, Lane)>,
+    pub schedule: ActionSchedule,
+    pub changed: HashSet<Position>,
+    pub mutations: TileMutations,
+    pub resolving: Option<TickProgress>,
+    pub ghosts: HashMap<Position, Ghost>,
+    pub difficulty: Difficulty,
+    pub scenario: Option<ScenarioProgress>,
+    pub daily: Option<DailyChallenge>,
+    pub time_trial: Option<TimeTrial>,
+    pub lifetime: LifetimeStats,
+    pub ledger: VecDeque<LedgerEntry>,
+    pub loan_owed: u32,
+    pub victory: VictoryGoal,
+    pub world_event: Option<ActiveWorldEvent>,
+    pub deconstruction: VecDeque<Position>,
+    // How many of a tile type to keep; anything the Core makes beyond that is sold right away.
+    pub auto_sell: HashMap<(u8, u8), u32>,
+    pub footprints: HashMap<Position, Position>,
+    pub storage_clusters: StorageClusters,
+    pub chunk_saves: ChunkSaves,
+}
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub struct ChunkPosition {
-    pub x: i32,
-    pub y: i32,
+// Which tiles to tick next. Tiles that acted last tick, extractors (which run on the clock) and
+// the neighbourhood of anything that changed are ticked; everything else is known to be idle.
+pub struct ActionSchedule {
+    pub active: HashSet<Position>,
+    pub clocked: HashSet<Position>,
+    pub dirty: HashSet<Position>,
+    pub stale: bool,
 }
 
-impl ChunkPosition {
-    pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
+impl Default for ActionSchedule {
+    fn default() -> Self {
+        Self {
+            active: HashSet::new(),
+            clocked: HashSet::new(),
+            dirty: HashSet::new(),
+            stale: true,
+        }
     }
+}
 
-    pub fn from_world_position(world_pos: Vec2) -> Self {
+// Each chunk's tiles as last saved. Only chunks marked dirty since then are encoded again; a
+// stale cache is rebuilt from every tile.
+pub struct ChunkSaves {
+    pub encoded: HashMap<ChunkPosition, Vec<u8>>,
+    pub dirty: HashSet<ChunkPosition>,
+    pub stale: bool,
+}
+
+impl Default for ChunkSaves {
+    fn default() -> Self {
         Self {
-            x: (world_pos.x / (CHUNK_SIZE as f32 * TILE_SIZE)).floor() as i32,
-            y: (world_pos.y / (CHUNK_SIZE as f32 * TILE_SIZE)).floor() as i32,
+            encoded: HashMap::new(),
+            dirty: HashSet::new(),
+            stale: true,
         }
     }
 }
 
-#[derive(Resource)]
-pub struct Placer {
-    pub direction: Direction,
-    pub tile_type: (u8, u8),
-    pub preview_entity: Option<Entity>,
-    pub zoom_level: f32,
+// Identical storages touching each other pool their contents and capacity. Only clusters of two
+// or more are kept, and they are rebuilt on the next tick after a storage is placed or removed.
+pub struct StorageClusters {
+    pub clusters: Vec<Vec<Position>>,
+    pub cluster_of: HashMap<Position, usize>,
+    pub stale: bool,
 }
 
-impl Default for Placer {
+impl Default for StorageClusters {
     fn default() -> Self {
         Self {
-            direction: Direction::Up,
-            tile_type: (0, 1),
-            preview_entity: None,
-            zoom_level: 1.0,
+            clusters: Vec::new(),
+            cluster_of: HashMap::new(),
+            stale: true,
         }
     }
 }
 
-#[derive(Resource, Default)]
-pub struct Hotkeys {
-    pub mappings: HashMap<u8, (u8, u8)>,
+// A tick whose actions are still being applied, possibly across several frames.
+#[derive(Default)]
+pub struct TickProgress {
+    pub actions: VecDeque<Action>,
+    pub moved_from: Vec<Position>,
+    pub events: TickEvents,
 }
 
-#[derive(Resource)]
-pub struct WorldRes {
-    pub tiles: HashMap<Position, (Box<dyn Tile>, (u8, u8))>,
-    pub terrain: HashMap<Position, TerrainTileType>,
-    pub loaded_chunks: HashSet<ChunkPosition>,
-    pub resources: HashMap<(u8, u8), u32>,
-    pub world_seed: u32,
-    pub tick_timer: Timer,
-    pub tick_count: i32,
-    pub actions: Vec<Action>,
-    pub money: u32,
+#[derive(Clone, Copy, Serialize, Deserialize, Encode, Decode)]
+pub struct ActiveWorldEvent {
+    pub kind: WorldEventKind,
+    pub ends_at: i32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Encode, Decode)]
+pub struct VictoryGoal {
+    pub item: Item,
+    pub target: u64,
+    pub won_at: Option<i32>,
+}
+
+impl VictoryGoal {
+    pub fn new(difficulty: Difficulty) -> Self {
+        VictoryGoal {
+            item: Item::OrbitalBeacon,
+            target: difficulty.victory_target(),
+            won_at: None,
+        }
+    }
+
+    pub fn progress(&self, lifetime: &LifetimeStats) -> u64 {
+        lifetime.produced.get(&self.item).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub enum LedgerReason {
+    Sale,
+    Purchase,
+    Loan,
+    Repayment,
+}
+
+impl LedgerReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LedgerReason::Sale => "Sale",
+            LedgerReason::Purchase => "Purchase",
+            LedgerReason::Loan => "Loan",
+            LedgerReason::Repayment => "Repayment",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct LedgerEntry {
+    pub tick: i32,
+    pub amount: i64,
+    pub reason: LedgerReason,
+    pub detail: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, Encode, Decode)]
+pub struct LifetimeStats {
+    pub produced: HashMap<Item, u64>,
+    pub tiles_placed: u64,
+    pub tiles_removed: u64,
+    pub money_earned: u64,
+    pub money_spent: u64,
+    // Seconds the world has been open, paused or not.
+    pub playtime: f64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Encode, Decode)]
+pub struct Ghost {
+    pub tile_type: (u8, u8),
+    pub direction: Direction,
+    pub queued: bool,
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode)]
@@ -77,18 +1431,20 @@ pub enum SerializableTile {
     Conveyor {
         position: Position,
         direction: Direction,
-        item: Option<Item>,
+        lanes: [Option<ItemStack>; 2],
     },
     Router {
         position: Position,
         direction: Direction,
         item: Option<Item>,
         last_output: RouterOutputIndex,
+        filters: [Option<Item>; 3],
     },
     Extractor {
         position: Position,
         direction: Direction,
         extractor_type: ExtractorType,
+        tier: ExtractorTier,
         item: Option<Item>,
     },
     Factory {
@@ -97,8 +1453,12 @@ pub enum SerializableTile {
         factory_type: FactoryType,
         inventory: HashMap<Item, u32>,
         item: Option<Item>,
+        byproducts: Vec<Item>,
         interval: u32,
         ticks: u32,
+        recipe_index: usize,
+        outputs: [bool; 4],
+        inputs: [bool; 4],
     },
 
     Storage {
@@ -110,158 +1470,1136 @@ pub enum SerializableTile {
     Portal {
         position: Position,
         item: Option<Item>,
+        teleported: HashMap<Item, u32>,
     },
     Junction {
         position: Position,
-        horizontal_item: Option<(Item, Direction)>,
-        vertical_item: Option<(Item, Direction)>,
+        horizontal_item: Option<(Item, Direction, Lane)>,
+        vertical_item: Option<(Item, Direction, Lane)>,
     },
     Core {
         position: Position,
         interval: u32,
         ticks: u32,
         tile_id: (u8, u8),
+        eject: bool,
+        item: Option<Item>,
+    },
+    PriorityRouter {
+        position: Position,
+        direction: Direction,
+        item: Option<Item>,
+        last_output: RouterOutputIndex,
+        priority: RouterOutputIndex,
+        filters: [Option<Item>; 3],
+    },
+    Wire {
+        position: Position,
+    },
+    Sensor {
+        position: Position,
+        direction: Direction,
+    },
+    ItemDisplay {
+        position: Position,
+        direction: Direction,
+        count: u32,
+    },
+    Inserter {
+        position: Position,
+        direction: Direction,
     },
+    Beacon {
+        position: Position,
+        item: Option<Item>,
+        charge: u32,
+    },
+    Quarry {
+        position: Position,
+        direction: Direction,
+        item: Option<Item>,
+        mined: u32,
+    },
+}
+
+// Stored uncompressed in front of the save so it can be shown without decoding the world.
+#[derive(Clone, Encode, Decode)]
+pub struct SaveHeader {
+    pub version: u32,
+    pub playtime: u64,
+    pub money: u32,
+    pub tile_count: u32,
+    // RGB pixels, `SAVE_THUMBNAIL_SIZE` squared, rows from the top.
+    pub thumbnail: Vec<u8>,
+}
+
+impl SaveHeader {
+    // Splits a save into its header and the checksummed payload. Saves written before the
+    // header existed have none.
+    pub fn split(contents: &[u8]) -> io::Result<(Option<SaveHeader>, &[u8])> {
+        let corrupted = || io::Error::new(io::ErrorKind::InvalidData, "save header corrupted");
+
+        let Some(rest) = contents.strip_prefix(SAVE_HEADER_MAGIC.as_slice()) else {
+            return Ok((None, contents));
+        };
+        if rest.len() < 4 {
+            return Err(corrupted());
+        }
+        let (length, rest) = rest.split_at(4);
+        let length = u32::from_le_bytes(length.try_into().map_err(|_| corrupted())?) as usize;
+        if rest.len() < length {
+            return Err(corrupted());
+        }
+        let (header, payload) = rest.split_at(length);
+
+        let config = config::standard().with_fixed_int_encoding().with_no_limit();
+        let (header, _): (SaveHeader, _) =
+            bincode::decode_from_slice(header, config).map_err(|_| corrupted())?;
+        Ok((Some(header), payload))
+    }
+}
+
+pub type ChunkTiles = Vec<(u64, (SerializableTile, (u8, u8)))>;
+
+// The tiles of one chunk, encoded and compressed on their own so a save can reuse the chunks
+// that didn't change and a load can decode them one at a time.
+#[derive(Serialize, Deserialize, Encode, Decode)]
+pub struct SerializableChunk {
+    pub x: i32,
+    pub y: i32,
+    pub tiles: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Encode, Decode)]
+pub struct SerializableWorld {
+    pub tile_chunks: Vec<SerializableChunk>,
+    pub resources: HashMap<(u8, u8), u32>,
+    pub world_seed: u32,
+    pub world_radius: Option<i32>,
+    pub tick_count: i32,
+    pub hotkey_mappings: HashMap<u8, (u8, u8)>,
+    pub money: u32,
+    pub saved_at: u64,
+    pub conditions: HashMap<u64, SignalCondition>,
+    pub channels: HashMap<u64, u8>,
+    pub zones: HashMap<u64, u8>,
+    pub zone_names: HashMap<u8, String>,
+    pub labels: HashMap<u64, String>,
+    pub markers: HashMap<u64, u8>,
+    pub ghosts: HashMap<u64, Ghost>,
+    pub difficulty: Difficulty,
+    pub scenario: Option<ScenarioProgress>,
+    pub daily: Option<DailyChallenge>,
+    pub time_trial: Option<TimeTrial>,
+    pub lifetime: LifetimeStats,
+    pub ledger: VecDeque<LedgerEntry>,
+    pub loan_owed: u32,
+    pub victory: VictoryGoal,
+    pub world_event: Option<ActiveWorldEvent>,
+    pub deconstruction: Vec<u64>,
+    pub auto_sell: HashMap<(u8, u8), u32>,
 }
 
-#[derive(Serialize, Deserialize, Encode, Decode)]
-pub struct SerializableWorld {
-    pub tiles: HashMap<u64, (SerializableTile, (u8, u8))>,
-    pub resources: HashMap<(u8, u8), u32>,
-    pub world_seed: u32,
-    pub tick_count: i32,
-    pub hotkey_mappings: HashMap<u8, (u8, u8)>,
-    pub money: u32,
-}
+impl WorldRes {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self::with_starter_kit(difficulty, StarterKit::default())
+    }
+
+    pub fn with_starter_kit(difficulty: Difficulty, kit: StarterKit) -> Self {
+        let mut resources = HashMap::new();
+        for &(tile_type, count) in kit.resources() {
+            resources.insert(tile_type, difficulty.scale_resources(count));
+        }
+
+        let mut tiles: TileMap = HashMap::new();
+        tiles.insert(
+            Position::new(0, 0),
+            (
+                Box::new(Core {
+                    position: Position::new(0, 0),
+                    interval: difficulty.scale_core_interval(10),
+                    ticks: 0,
+                    tile_id: (6, 1),
+                    eject: false,
+                    item: None,
+                }),
+                (6, 1),
+            ),
+        );
+
+        WorldRes {
+            tiles,
+            terrain: HashMap::new(),
+            loaded_chunks: HashSet::new(),
+            sprite_chunks: HashSet::new(),
+            resources,
+            // The seed is the one roll that isn't reproducible; everything else derives from it.
+            world_seed: rng().random_range(u32::MIN..u32::MAX),
+            world_radius: None,
+            tick_timer: Timer::from_seconds(TICK_LENGTH, TimerMode::Repeating),
+            tick_count: 0,
+            actions: Vec::new(),
+            money: difficulty.scale_resources(kit.money()),
+            saved_at: 0,
+            conditions: HashMap::new(),
+            channels: HashMap::new(),
+            zones: HashMap::new(),
+            zone_names: HashMap::new(),
+            labels: HashMap::new(),
+            zone_revision: 0,
+            markers: HashMap::new(),
+            disabled: HashSet::new(),
+            last_input: HashMap::new(),
+            last_produced: HashMap::new(),
+            flowing: HashSet::new(),
+            schedule: ActionSchedule::default(),
+            changed: HashSet::new(),
+            mutations: TileMutations::default(),
+            resolving: None,
+            ghosts: HashMap::new(),
+            difficulty,
+            scenario: None,
+            daily: None,
+            time_trial: None,
+            lifetime: LifetimeStats::default(),
+            ledger: VecDeque::new(),
+            loan_owed: 0,
+            victory: VictoryGoal::new(difficulty),
+            world_event: None,
+            deconstruction: VecDeque::new(),
+            auto_sell: HashMap::new(),
+            footprints: HashMap::new(),
+            storage_clusters: StorageClusters::default(),
+            chunk_saves: ChunkSaves::default(),
+        }
+    }
+
+    pub fn earn(&mut self, amount: u32, reason: LedgerReason, detail: String) {
+        self.money = self.money.saturating_add(amount);
+        self.lifetime.money_earned += amount as u64;
+        self.record_ledger(amount as i64, reason, detail);
+
+        let repayment = if reason == LedgerReason::Sale {
+            (amount as u64 * LOAN_REPAYMENT_PERCENT as u64 / 100).min(self.loan_owed as u64) as u32
+        } else {
+            0
+        };
+        if repayment > 0 {
+            self.money -= repayment;
+            self.loan_owed -= repayment;
+            self.record_ledger(
+                -(repayment as i64),
+                LedgerReason::Repayment,
+                format!("${} still owed", self.loan_owed),
+            );
+        }
+    }
+
+    pub fn take_loan(&mut self) {
+        self.money += LOAN_AMOUNT;
+        self.loan_owed += LOAN_AMOUNT * (100 + LOAN_INTEREST_PERCENT) / 100;
+        self.record_ledger(
+            LOAN_AMOUNT as i64,
+            LedgerReason::Loan,
+            format!("repay ${}", self.loan_owed),
+        );
+    }
+
+    pub fn has_world_event(&self, kind: WorldEventKind) -> bool {
+        self.world_event.is_some_and(|event| event.kind == kind)
+    }
+
+    pub fn sell_price(&self, tile_type: (u8, u8)) -> u32 {
+        let price = get_tile_price(tile_type, self.difficulty);
+        if self.has_world_event(WorldEventKind::MarketCrash) {
+            (price / 2).max(1)
+        } else {
+            price
+        }
+    }
+
+    // Sells whatever is above the tile type's auto-sell threshold.
+    pub fn apply_auto_sell(&mut self, tile_type: (u8, u8)) {
+        let Some(&keep) = self.auto_sell.get(&tile_type) else {
+            return;
+        };
+        let available = *self.resources.get(&tile_type).unwrap_or(&0);
+        let amount = available.saturating_sub(keep);
+        if amount == 0 {
+            return;
+        }
+        self.resources.insert(tile_type, keep);
+        self.earn(
+            self.sell_price(tile_type).saturating_mul(amount),
+            LedgerReason::Sale,
+            format!("{} x{} (auto-sell)", get_tile_name(tile_type), amount),
+        );
+    }
+
+    pub fn advance_world_event(&mut self) {
+        if self
+            .world_event
+            .is_some_and(|event| self.tick_count >= event.ends_at)
+        {
+            self.world_event = None;
+        }
+
+        if self.world_event.is_some() || self.tick_count % WORLD_EVENT_INTERVAL_TICKS != 0 {
+            return;
+        }
+
+        // Rolled from the seed and event window so every world gets the same schedule.
+        let window = self.tick_count / WORLD_EVENT_INTERVAL_TICKS;
+        let mut rng = tick_rng(self.world_seed, window);
+        if rng.random_range(0..100) < WORLD_EVENT_CHANCE_PERCENT {
+            let kinds = WorldEventKind::ALL;
+            self.world_event = Some(ActiveWorldEvent {
+                kind: kinds[rng.random_range(0..kinds.len())],
+                ends_at: self.tick_count + WORLD_EVENT_DURATION_TICKS,
+            });
+        }
+    }
+
+    // Tiles changed outside of action resolution must be marked so they and their neighbours are
+    // ticked and redrawn again.
+    pub fn mark_dirty(&mut self, pos: Position) {
+        self.schedule.dirty.insert(pos);
+        self.changed.insert(pos);
+        self.mark_chunk_dirty(pos);
+    }
+
+    // For changes that need saving but don't affect what ticks, like a display's count.
+    pub fn mark_chunk_dirty(&mut self, pos: Position) {
+        self.chunk_saves
+            .dirty
+            .insert(ChunkPosition::of(self.anchor_of(pos)));
+    }
+
+    pub fn in_bounds(&self, pos: Position) -> bool {
+        self.world_radius
+            .is_none_or(|radius| pos.x.abs() <= radius && pos.y.abs() <= radius)
+    }
+
+    // Whether any cell of the chunk lies inside the world border.
+    pub fn chunk_in_bounds(&self, chunk: ChunkPosition) -> bool {
+        self.world_radius.is_none_or(|radius| {
+            let (min_x, min_y) = (chunk.x * CHUNK_SIZE, chunk.y * CHUNK_SIZE);
+            min_x <= radius
+                && min_x + CHUNK_SIZE > -radius
+                && min_y <= radius
+                && min_y + CHUNK_SIZE > -radius
+        })
+    }
+
+    // The cell holding the tile that covers `pos`, which is `pos` itself unless it is one of the
+    // extra cells of a multi-tile building.
+    pub fn anchor_of(&self, pos: Position) -> Position {
+        self.footprints.get(&pos).copied().unwrap_or(pos)
+    }
+
+    pub fn is_protected(&self, pos: Position) -> bool {
+        self.tiles
+            .get(&self.anchor_of(pos))
+            .is_some_and(|(_, tile_type)| is_protected_tile(*tile_type))
+    }
+
+    pub fn is_occupied(&self, pos: Position) -> bool {
+        self.tiles.contains_key(&pos) || self.footprints.contains_key(&pos)
+    }
+
+    pub fn occupy(&mut self, anchor: Position, tile_type: (u8, u8)) {
+        for cell in footprint_cells(tile_type, anchor) {
+            if cell != anchor {
+                self.footprints.insert(cell, anchor);
+            }
+            self.mark_dirty(cell);
+        }
+    }
+
+    pub fn vacate(&mut self, anchor: Position, tile_type: (u8, u8)) {
+        for cell in footprint_cells(tile_type, anchor) {
+            if cell != anchor {
+                self.footprints.remove(&cell);
+            }
+            self.mark_dirty(cell);
+        }
+    }
+
+    // Drops what a tile scheduled once it is edited or removed, including the actions of a tick
+    // that is still resolving over several frames.
+    pub fn forget_actions(&mut self, pos: Position) {
+        self.actions.retain(|action| action.position() != pos);
+        if let Some(progress) = &mut self.resolving {
+            progress.actions.retain(|action| action.position() != pos);
+        }
+    }
+
+    pub fn tile_placed(&mut self, pos: Position, tile_type: (u8, u8)) {
+        self.forget_actions(pos);
+        self.storage_clusters.stale |= tile_type.0 == 5;
+        self.mutations.placed.push(TilePlaced { pos, tile_type });
+        self.mark_chunk_dirty(pos);
+    }
+
+    pub fn tile_removed(&mut self, pos: Position, tile_type: (u8, u8)) {
+        self.forget_actions(pos);
+        self.last_produced.remove(&pos);
+        self.mark_chunk_dirty(pos);
+        self.storage_clusters.stale |= tile_type.0 == 5;
+        self.mutations.removed.push(TileRemoved { pos, tile_type });
+    }
+
+    // Takes the tile at `pos` off the map and back into the inventory, along with its wiring.
+    pub fn remove_tile(&mut self, pos: Position) {
+        let Some((_, tile_type)) = self.tiles.remove(&pos) else {
+            return;
+        };
+        *self.resources.entry(tile_type).or_insert(0) += 1;
+        self.conditions.remove(&pos);
+        self.channels.remove(&pos);
+        self.lifetime.tiles_removed += 1;
+        self.vacate(pos, tile_type);
+        self.tile_removed(pos, tile_type);
+    }
+
+    pub fn rebuild_storage_clusters(&mut self) {
+        let storages: HashMap<Position, (u8, u8)> = self
+            .tiles
+            .iter()
+            .filter(|(_, (tile, _))| tile.as_any().is::<Storage>())
+            .map(|(pos, (_, tile_type))| (*pos, *tile_type))
+            .collect();
+
+        let mut clusters = Vec::new();
+        let mut seen = HashSet::new();
+        for (&start, &tile_type) in storages.iter() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut members = vec![start];
+            let mut frontier = vec![start];
+            while let Some(pos) = frontier.pop() {
+                for direction in Direction::ALL {
+                    let next = pos.shift(direction);
+                    if storages.get(&next) == Some(&tile_type) && seen.insert(next) {
+                        members.push(next);
+                        frontier.push(next);
+                    }
+                }
+            }
+            if members.len() > 1 {
+                members.sort();
+                clusters.push(members);
+            }
+        }
+
+        self.storage_clusters.cluster_of = clusters
+            .iter()
+            .enumerate()
+            .flat_map(|(index, members)| members.iter().map(move |pos| (*pos, index)))
+            .collect();
+        self.storage_clusters.clusters = clusters;
+        self.storage_clusters.stale = false;
+        self.schedule.stale = true;
+    }
+
+    // The other storages `pos` is pooled with, itself included.
+    pub fn storage_cluster(&self, pos: Position) -> Option<&[Position]> {
+        self.storage_clusters
+            .cluster_of
+            .get(&pos)
+            .map(|&index| self.storage_clusters.clusters[index].as_slice())
+    }
+
+    // Cells whose state may have changed along with `pos`: every cell of a multi-tile building,
+    // and every member of a storage cluster, which share their contents.
+    pub fn changed_cells(&self, pos: Position) -> Vec<Position> {
+        let mut cells = self
+            .tiles
+            .get(&pos)
+            .map_or_else(|| vec![pos], |(_, id)| footprint_cells(*id, pos));
+        if let Some(members) = self.storage_cluster(pos) {
+            cells.extend_from_slice(members);
+        }
+        cells
+    }
+
+    // Items stored and total capacity of the cluster `pos` belongs to, or of `pos` alone.
+    pub fn storage_fill(&self, pos: Position) -> (u32, u32) {
+        let fill = |member: &Position| {
+            self.storage_at(*member).map_or((0, 0), |storage| {
+                (storage.inventory, storage.storage_type.capacity())
+            })
+        };
+        match self.storage_cluster(pos) {
+            Some(members) => members
+                .iter()
+                .map(fill)
+                .fold((0, 0), |total, fill| (total.0 + fill.0, total.1 + fill.1)),
+            None => fill(&pos),
+        }
+    }
+
+    fn storage_at(&self, pos: Position) -> Option<&Storage> {
+        self.tiles
+            .get(&pos)
+            .and_then(|(tile, _)| tile.as_any().downcast_ref::<Storage>())
+    }
+
+    fn storage_at_mut(&mut self, pos: Position) -> Option<&mut Storage> {
+        self.tiles
+            .get_mut(&pos)
+            .and_then(|(tile, _)| tile.as_any_mut().downcast_mut::<Storage>())
+    }
+
+    // Moves one item into `pos` from another member of its cluster, or out of it into one, and
+    // returns the (source, target) pair so the shift can be settled.
+    fn shift_storage_item(&mut self, pos: Position, into: bool) -> Option<(Position, Position)> {
+        let members = self.storage_cluster(pos)?;
+        let other = members.iter().copied().find(|member| {
+            *member != pos
+                && self.storage_at(*member).is_some_and(|storage| {
+                    if into {
+                        storage.inventory > 0
+                    } else {
+                        storage.inventory < storage.storage_type.capacity()
+                    }
+                })
+        })?;
+
+        let (source, target) = if into { (other, pos) } else { (pos, other) };
+        self.move_storage_item(source, target);
+        Some((source, target))
+    }
+
+    fn move_storage_item(&mut self, source: Position, target: Position) {
+        if let Some(storage) = self.storage_at_mut(source) {
+            storage.inventory -= 1;
+        }
+        if let Some(storage) = self.storage_at_mut(target) {
+            storage.inventory += 1;
+        }
+    }
+
+    // Before a clustered storage sends an item out, an empty one borrows it from a neighbour.
+    pub fn fill_storage_from_cluster(&mut self, pos: Position) -> Option<(Position, Position)> {
+        self.storage_at(pos)
+            .is_some_and(|storage| storage.inventory == 0)
+            .then(|| self.shift_storage_item(pos, true))
+            .flatten()
+    }
+
+    // Before a clustered storage takes an item in, a full one passes one on to a neighbour.
+    pub fn spill_storage_into_cluster(&mut self, pos: Position) -> Option<(Position, Position)> {
+        self.storage_at(pos)
+            .is_some_and(|storage| storage.inventory >= storage.storage_type.capacity())
+            .then(|| self.shift_storage_item(pos, false))
+            .flatten()
+    }
+
+    // Keeps a shift made for a move that went through, or puts the item back if it didn't.
+    pub fn settle_storage_shift(&mut self, (source, target): (Position, Position), kept: bool) {
+        if kept {
+            self.mark_dirty(source);
+            self.mark_dirty(target);
+        } else {
+            self.move_storage_item(target, source);
+        }
+    }
+
+    pub fn advance_deconstruction(&mut self) {
+        for _ in 0..DECONSTRUCTION_PER_TICK {
+            let Some(pos) = self.deconstruction.pop_front() else {
+                return;
+            };
+            self.remove_tile(pos);
+        }
+    }
+
+    pub fn is_bankrupt(&self) -> bool {
+        let sellable = self.resources.iter().any(|(tile_type, count)| {
+            *count > 0 && get_tile_price(*tile_type, self.difficulty) > 0
+        });
+        let can_produce = self.tiles.values().any(|(tile, _)| {
+            tile.as_any().is::<Extractor>()
+                || tile.as_any().is::<Quarry>()
+                || tile.as_any().is::<Factory>()
+                || tile.as_any().is::<Portal>()
+        });
+
+        self.money == 0 && !sellable && !can_produce
+    }
+
+    pub fn spend(&mut self, amount: u32, reason: LedgerReason, detail: String) {
+        self.money -= amount;
+        self.lifetime.money_spent += amount as u64;
+        self.record_ledger(-(amount as i64), reason, detail);
+    }
+
+    fn record_ledger(&mut self, amount: i64, reason: LedgerReason, detail: String) {
+        self.ledger.push_back(LedgerEntry {
+            tick: self.tick_count,
+            amount,
+            reason,
+            detail,
+        });
+        while self.ledger.len() > LEDGER_MAX_ENTRIES {
+            self.ledger.pop_front();
+        }
+    }
+
+    pub fn is_tile_locked(&self, tile_type: (u8, u8)) -> bool {
+        self.scenario
+            .as_ref()
+            .is_some_and(|progress| progress.is_tile_locked(tile_type))
+    }
+
+    pub fn save_header(&self) -> SaveHeader {
+        let half = SAVE_THUMBNAIL_SIZE / 2;
+        let mut thumbnail = Vec::new();
+        for y in (-half..SAVE_THUMBNAIL_SIZE - half).rev() {
+            for x in -half..SAVE_THUMBNAIL_SIZE - half {
+                let color = self
+                    .tiles
+                    .get(&self.anchor_of(Position::new(x, y)))
+                    .map_or(Color::srgb(0.1, 0.1, 0.1), |(_, id)| {
+                        get_tile_lod_color(*id)
+                    });
+                thumbnail.extend_from_slice(&color.to_srgba().to_u8_array_no_alpha());
+            }
+        }
+
+        SaveHeader {
+            version: SAVE_HEADER_VERSION,
+            playtime: self.lifetime.playtime as u64,
+            money: self.money,
+            tile_count: self.tiles.len() as u32,
+            thumbnail,
+        }
+    }
+
+    pub fn read_save_header(key: &str) -> io::Result<SaveHeader> {
+        let contents = save_backend().read(key)?;
+        SaveHeader::split(&contents)?
+            .0
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "save has no header"))
+    }
+
+    // Re-encodes the tiles of every chunk that changed since the last save.
+    fn encode_dirty_chunks(&mut self) -> io::Result<()> {
+        let dirty: HashSet<ChunkPosition> = if self.chunk_saves.stale {
+            self.chunk_saves.encoded.clear();
+            self.chunk_saves.dirty.clear();
+            self.tiles
+                .keys()
+                .map(|pos| ChunkPosition::of(*pos))
+                .collect()
+        } else {
+            std::mem::take(&mut self.chunk_saves.dirty)
+        };
+        self.chunk_saves.stale = false;
+
+        for chunk in dirty {
+            let mut chunk_tiles: ChunkTiles = Vec::new();
+            for pos in chunk.cells() {
+                if let Some((tile, id)) = self.tiles.get(&pos) {
+                    chunk_tiles.push((pos.get_as_key(), (serialize_tile(pos, tile.as_ref()), *id)));
+                }
+            }
+            if chunk_tiles.is_empty() {
+                self.chunk_saves.encoded.remove(&chunk);
+                continue;
+            }
+
+            self.chunk_saves
+                .encoded
+                .insert(chunk, encode_chunk(&chunk_tiles)?);
+        }
+        Ok(())
+    }
+
+    pub fn save(&mut self, key: &str, hotkeys: &Hotkeys) -> Result<(), io::Error> {
+        if let Err(err) = self.encode_dirty_chunks() {
+            self.chunk_saves.stale = true;
+            return Err(err);
+        }
 
-impl WorldRes {
-    pub fn save(&self, path: impl AsRef<Path>, hotkeys: &Hotkeys) -> Result<(), io::Error> {
         let serializable_world = SerializableWorld {
-            tiles: self
-                .tiles
+            tile_chunks: self
+                .chunk_saves
+                .encoded
                 .iter()
-                .map(|(pos, (tile, id))| {
-                    let serializable_tile =
-                        if let Some(conveyor) = tile.as_any().downcast_ref::<Conveyor>() {
-                            SerializableTile::Conveyor {
-                                position: conveyor.position,
-                                direction: conveyor.direction,
-                                item: conveyor.item,
-                            }
-
-                        } else if let Some(extractor) = tile.as_any().downcast_ref::<Extractor>() {
-                            SerializableTile::Extractor {
-                                position: extractor.position,
-                                direction: extractor.direction,
-                                extractor_type: extractor.extractor_type,
-                                item: extractor.item,
-                            }
-                        } else if let Some(factory) = tile.as_any().downcast_ref::<Factory>() {
-                            SerializableTile::Factory {
-                                position: factory.position,
-                                direction: factory.direction,
-                                factory_type: factory.factory_type,
-                                inventory: factory.inventory.clone(),
-                                item: factory.item,
-                                interval: factory.interval,
-                                ticks: factory.ticks,
-                            }
-                        } else if let Some(portal) = tile.as_any().downcast_ref::<Portal>() {
-                            SerializableTile::Portal {
-                                position: portal.position,
-                                item: portal.item,
-                            }
-                        } else if let Some(junction) = tile.as_any().downcast_ref::<Junction>() {
-                            SerializableTile::Junction {
-                                position: junction.position,
-                                horizontal_item: junction.horizontal_item,
-                                vertical_item: junction.vertical_item,
-                            }
-                        } else if let Some(core) = tile.as_any().downcast_ref::<Core>() {
-                            SerializableTile::Core {
-                                position: core.position,
-                                interval: core.interval,
-                                ticks: core.ticks,
-                                tile_id: core.tile_id,
-                            }
-                        } else if let Some(router) = tile.as_any().downcast_ref::<Router>() {
-                            SerializableTile::Router {
-                                position: router.position,
-                                direction: router.direction,
-                                item: router.item,
-                                last_output: router.last_output,
-                            }
-                        } else {
-                            SerializableTile::Conveyor {
-                                position: *pos,
-                                direction: Direction::Up,
-                                item: None,
-                            }
-                        };
-                    (pos.get_as_key(), (serializable_tile, *id))
+                .map(|(chunk, tiles)| SerializableChunk {
+                    x: chunk.x,
+                    y: chunk.y,
+                    tiles: tiles.clone(),
                 })
                 .collect(),
             resources: self.resources.clone(),
             world_seed: self.world_seed,
+            world_radius: self.world_radius,
             tick_count: self.tick_count,
-            hotkey_mappings: hotkeys.mappings.clone(),
+            hotkey_mappings: hotkeys.world.clone(),
             money: self.money,
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            conditions: self
+                .conditions
+                .iter()
+                .map(|(pos, condition)| (pos.get_as_key(), *condition))
+                .collect(),
+            channels: self
+                .channels
+                .iter()
+                .map(|(pos, channel)| (pos.get_as_key(), *channel))
+                .collect(),
+            zones: self
+                .zones
+                .iter()
+                .map(|(pos, zone)| (pos.get_as_key(), *zone))
+                .collect(),
+            zone_names: self.zone_names.clone(),
+            labels: self
+                .labels
+                .iter()
+                .map(|(pos, label)| (pos.get_as_key(), label.clone()))
+                .collect(),
+            markers: self
+                .markers
+                .iter()
+                .map(|(pos, icon)| (pos.get_as_key(), *icon))
+                .collect(),
+            ghosts: self
+                .ghosts
+                .iter()
+                .map(|(pos, ghost)| (pos.get_as_key(), *ghost))
+                .collect(),
+            difficulty: self.difficulty,
+            scenario: self.scenario.clone(),
+            daily: self.daily.clone(),
+            time_trial: self.time_trial,
+            lifetime: self.lifetime.clone(),
+            ledger: self.ledger.clone(),
+            loan_owed: self.loan_owed,
+            victory: self.victory,
+            world_event: self.world_event,
+            deconstruction: self
+                .deconstruction
+                .iter()
+                .map(|pos| pos.get_as_key())
+                .collect(),
+            auto_sell: self.auto_sell.clone(),
         };
 
         let config = config::standard().with_fixed_int_encoding().with_no_limit();
 
-        let serialized = bincode::encode_to_vec(&serializable_world, config)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let payload =
+            bincode::encode_to_vec(&serializable_world, config).map_err(io::Error::other)?;
+        let checksum = crc32fast::hash(&payload);
 
-        let file = File::create(path)?;
-        let mut encoder = DeflateEncoder::new(file, Compression::best());
-        encoder.write_all(&serialized)?;
-        encoder.finish()?;
+        let header =
+            bincode::encode_to_vec(self.save_header(), config).map_err(io::Error::other)?;
+        let mut contents = SAVE_HEADER_MAGIC.to_vec();
+        contents.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        contents.extend_from_slice(&header);
+        contents.extend_from_slice(&payload);
+        contents.extend_from_slice(&checksum.to_le_bytes());
 
-        Ok(())
+        save_backend().write(key, &contents)
     }
 
-    pub fn load_game(path: impl AsRef<Path>) -> io::Result<(WorldRes, HashMap<u8, (u8, u8)>)> {
-        let file = File::open(path)?;
+    pub fn backup_key(key: &str, index: usize) -> String {
+        format!("{}.{}", key, index)
+    }
 
-        let mut decoder = DeflateDecoder::new(file);
-        let mut buffer = Vec::new();
-        decoder.read_to_end(&mut buffer)?;
+    pub fn rotate_backups(key: &str) -> io::Result<()> {
+        let backend = save_backend();
+        if !backend.exists(key) {
+            return Ok(());
+        }
 
-        let config = config::standard().with_fixed_int_encoding().with_no_limit();
+        for index in (1..SAVE_BACKUP_COUNT).rev() {
+            let from = Self::backup_key(key, index);
+            if backend.exists(&from) {
+                backend.rename(&from, &Self::backup_key(key, index + 1))?;
+            }
+        }
+        backend.copy(key, &Self::backup_key(key, 1))
+    }
+
+    // Moves a save and its first `backups` backups, which couldn't be loaded, out of the way so
+    // later autosaves don't overwrite them. Returns the names they were moved to.
+    pub fn set_aside_unreadable(key: &str, backups: usize) -> Vec<String> {
+        let backend = save_backend();
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        let mut moved = Vec::new();
+        let keys = std::iter::once(key.to_string())
+            .chain((1..=backups).map(|index| Self::backup_key(key, index)));
+        for from in keys {
+            if !backend.exists(&from) {
+                continue;
+            }
+            let to = format!("{}.corrupt-{}", from, stamp);
+            match backend.rename(&from, &to) {
+                Ok(()) => moved.push(to),
+                Err(err) => eprintln!("Error moving {} aside: {}", from, err),
+            }
+        }
+        moved
+    }
+
+    pub fn load_game_with_backups(key: &str) -> io::Result<(WorldRes, HotkeyMap, Option<usize>)> {
+        let newest_err = match Self::load_game(key) {
+            Ok((world, hotkeys)) => return Ok((world, hotkeys, None)),
+            Err(err) => err,
+        };
+
+        for index in 1..=SAVE_BACKUP_COUNT {
+            if let Ok((world, hotkeys)) = Self::load_game(&Self::backup_key(key, index)) {
+                return Ok((world, hotkeys, Some(index)));
+            }
+        }
+
+        Err(newest_err)
+    }
+
+    pub fn load_game(key: &str) -> io::Result<(WorldRes, HotkeyMap)> {
+        let contents = save_backend().read(key)?;
+        let (header, contents) = SaveHeader::split(&contents)?;
+        let corrupted = || io::Error::new(io::ErrorKind::InvalidData, "save corrupted");
+
+        // Saves from before the header existed are version 1, which had no checksum either.
+        let Some(header) = header else {
+            return Self::from_serializable(migrate_v1_payload(contents)?);
+        };
+        if header.version > SAVE_HEADER_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "save format {} can't be read by this version",
+                    header.version
+                ),
+            ));
+        }
+
+        if contents.len() < 4 {
+            return Err(corrupted());
+        }
+        let (payload, checksum) = contents.split_at(contents.len() - 4);
+        let checksum = u32::from_le_bytes(checksum.try_into().map_err(|_| corrupted())?);
+        if crc32fast::hash(payload) != checksum {
+            return Err(corrupted());
+        }
 
+        let config = config::standard().with_fixed_int_encoding().with_no_limit();
         let (serializable_world, _): (SerializableWorld, _) =
-            bincode::decode_from_slice(&buffer, config)
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            bincode::decode_from_slice(payload, config).map_err(io::Error::other)?;
+        Self::from_serializable(serializable_world)
+    }
 
+    fn from_serializable(
+        serializable_world: SerializableWorld,
+    ) -> io::Result<(WorldRes, HotkeyMap)> {
+        let config = config::standard().with_fixed_int_encoding().with_no_limit();
         let mut tiles = HashMap::new();
         let mut terrain = HashMap::new();
         let loaded_chunks = HashSet::new();
 
-        for (pos_key, (tile, id)) in serializable_world.tiles {
-            let pos = Position::from_key(pos_key);
-            let boxed_tile: Box<dyn Tile> = match tile {
+        let mut chunk_saves = ChunkSaves {
+            stale: false,
+            ..default()
+        };
+        for chunk in serializable_world.tile_chunks {
+            let mut decoder = DeflateDecoder::new(chunk.tiles.as_slice());
+            let mut buffer = Vec::new();
+            decoder.read_to_end(&mut buffer)?;
+            let (chunk_tiles, _): (ChunkTiles, _) =
+                bincode::decode_from_slice(&buffer, config).map_err(io::Error::other)?;
+            for (pos_key, (tile, id)) in chunk_tiles {
+                tiles.insert(
+                    Position::from_key(pos_key),
+                    (deserialize_tile(tile, id), id),
+                );
+            }
+            chunk_saves
+                .encoded
+                .insert(ChunkPosition::new(chunk.x, chunk.y), chunk.tiles);
+        }
+
+        // A bordered world has no terrain to generate past its border.
+        let range = serializable_world
+            .world_radius
+            .map_or(TERRAIN_GEN_RANGE, |radius| radius.min(TERRAIN_GEN_RANGE));
+        let noise = TerrainNoise::new(serializable_world.world_seed);
+        for x in -range..=range {
+            for y in -range..=range {
+                let pos = Position::new(x, y);
+                terrain.insert(pos, noise.terrain_at(serializable_world.difficulty, pos));
+            }
+        }
+
+        let footprints = tiles
+            .iter()
+            .flat_map(|(pos, (_, tile_type))| {
+                footprint_cells(*tile_type, *pos)
+                    .into_iter()
+                    .filter(move |cell| cell != pos)
+                    .map(move |cell| (cell, *pos))
+            })
+            .collect();
+
+        let world_res = WorldRes {
+            tiles,
+            terrain,
+            loaded_chunks,
+            sprite_chunks: HashSet::new(),
+            resources: serializable_world.resources,
+            world_seed: serializable_world.world_seed,
+            world_radius: serializable_world.world_radius,
+            tick_timer: Timer::from_seconds(TICK_LENGTH, TimerMode::Repeating),
+            tick_count: serializable_world.tick_count,
+            actions: Vec::new(),
+            money: serializable_world.money,
+            saved_at: serializable_world.saved_at,
+            conditions: serializable_world
+                .conditions
+                .into_iter()
+                .map(|(key, condition)| (Position::from_key(key), condition))
+                .collect(),
+            channels: serializable_world
+                .channels
+                .into_iter()
+                .map(|(key, channel)| (Position::from_key(key), channel))
+                .collect(),
+            zones: serializable_world
+                .zones
+                .into_iter()
+                .map(|(key, zone)| (Position::from_key(key), zone))
+                .collect(),
+            zone_names: serializable_world.zone_names,
+            labels: serializable_world
+                .labels
+                .into_iter()
+                .map(|(key, label)| (Position::from_key(key), label))
+                .collect(),
+            zone_revision: 0,
+            markers: serializable_world
+                .markers
+                .into_iter()
+                .map(|(key, icon)| (Position::from_key(key), icon))
+                .collect(),
+            disabled: HashSet::new(),
+            last_input: HashMap::new(),
+            last_produced: HashMap::new(),
+            flowing: HashSet::new(),
+            schedule: ActionSchedule::default(),
+            changed: HashSet::new(),
+            mutations: TileMutations::default(),
+            resolving: None,
+            ghosts: serializable_world
+                .ghosts
+                .into_iter()
+                .map(|(key, ghost)| (Position::from_key(key), ghost))
+                .collect(),
+            difficulty: serializable_world.difficulty,
+            scenario: serializable_world.scenario,
+            daily: serializable_world.daily,
+            time_trial: serializable_world.time_trial,
+            lifetime: serializable_world.lifetime,
+            ledger: serializable_world.ledger,
+            loan_owed: serializable_world.loan_owed,
+            victory: serializable_world.victory,
+            world_event: serializable_world.world_event,
+            deconstruction: serializable_world
+                .deconstruction
+                .into_iter()
+                .map(Position::from_key)
+                .collect(),
+            auto_sell: serializable_world.auto_sell,
+            footprints,
+            storage_clusters: StorageClusters::default(),
+            chunk_saves,
+        };
+
+        Ok((world_res, serializable_world.hotkey_mappings))
+    }
+}
+
+fn encode_chunk(chunk_tiles: &ChunkTiles) -> io::Result<Vec<u8>> {
+    let config = config::standard().with_fixed_int_encoding().with_no_limit();
+    let serialized = bincode::encode_to_vec(chunk_tiles, config).map_err(io::Error::other)?;
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&serialized)?;
+    encoder.finish()
+}
+
+// Version 1 deflated the whole world, with every tile in one map, and had no header or
+// checksum. Its tiles are regrouped into chunks and the fields it didn't have start out empty.
+fn migrate_v1_payload(compressed: &[u8]) -> io::Result<SerializableWorld> {
+    let mut decoder = DeflateDecoder::new(compressed);
+    let mut buffer = Vec::new();
+    decoder.read_to_end(&mut buffer)?;
+
+    let config = config::standard().with_fixed_int_encoding().with_no_limit();
+    let (world, _): (v1::SerializableWorld, _) =
+        bincode::decode_from_slice(&buffer, config).map_err(io::Error::other)?;
+
+    let mut chunks: HashMap<ChunkPosition, ChunkTiles> = HashMap::new();
+    for (pos_key, (tile, id)) in world.tiles {
+        chunks
+            .entry(ChunkPosition::of(Position::from_key(pos_key)))
+            .or_default()
+            .push((pos_key, (tile.into_current(), id)));
+    }
+    let tile_chunks = chunks
+        .into_iter()
+        .map(|(chunk, chunk_tiles)| {
+            Ok(SerializableChunk {
+                x: chunk.x,
+                y: chunk.y,
+                tiles: encode_chunk(&chunk_tiles)?,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    Ok(SerializableWorld {
+        tile_chunks,
+        resources: world.resources,
+        world_seed: world.world_seed,
+        world_radius: None,
+        tick_count: world.tick_count,
+        hotkey_mappings: world.hotkey_mappings,
+        money: world.money,
+        saved_at: 0,
+        conditions: HashMap::new(),
+        channels: HashMap::new(),
+        zones: HashMap::new(),
+        zone_names: HashMap::new(),
+        labels: HashMap::new(),
+        markers: HashMap::new(),
+        ghosts: HashMap::new(),
+        difficulty: Difficulty::Normal,
+        scenario: None,
+        daily: None,
+        time_trial: None,
+        lifetime: LifetimeStats::default(),
+        ledger: VecDeque::new(),
+        loan_owed: 0,
+        victory: VictoryGoal::new(Difficulty::Normal),
+        world_event: None,
+        deconstruction: Vec::new(),
+        auto_sell: HashMap::new(),
+    })
+}
+
+// The save layout as it was before saves had a header. These must not change: bincode stores
+// enum variants by index and fields by position, so they are kept apart from the current types.
+mod v1 {
+    use super::*;
+
+    #[derive(Encode, Decode)]
+    pub enum SerializableTile {
+        Conveyor {
+            position: Position,
+            direction: Direction,
+            item: Option<Item>,
+        },
+        Router {
+            position: Position,
+            direction: Direction,
+            item: Option<Item>,
+            last_output: RouterOutputIndex,
+        },
+        Extractor {
+            position: Position,
+            direction: Direction,
+            extractor_type: ExtractorType,
+            item: Option<Item>,
+        },
+        Factory {
+            position: Position,
+            direction: Direction,
+            factory_type: FactoryType,
+            inventory: HashMap<Item, u32>,
+            item: Option<Item>,
+            interval: u32,
+            ticks: u32,
+        },
+        Storage {
+            position: Position,
+            direction: Direction,
+            inventory: u32,
+            storage_type: StorageType,
+        },
+        Portal {
+            position: Position,
+            item: Option<Item>,
+        },
+        Junction {
+            position: Position,
+            horizontal_item: Option<(Item, Direction)>,
+            vertical_item: Option<(Item, Direction)>,
+        },
+        Core {
+            position: Position,
+            interval: u32,
+            ticks: u32,
+            tile_id: (u8, u8),
+        },
+    }
+
+    #[derive(Encode, Decode)]
+    pub struct SerializableWorld {
+        pub tiles: HashMap<u64, (SerializableTile, (u8, u8))>,
+        pub resources: HashMap<(u8, u8), u32>,
+        pub world_seed: u32,
+        pub tick_count: i32,
+        pub hotkey_mappings: HashMap<u8, (u8, u8)>,
+        pub money: u32,
+    }
+
+    impl SerializableTile {
+        pub fn into_current(self) -> super::SerializableTile {
+            match self {
                 SerializableTile::Conveyor {
                     position,
                     direction,
                     item,
-                } => Box::new(Conveyor {
+                } => super::SerializableTile::Conveyor {
+                    position,
+                    direction,
+                    lanes: [item.map(ItemStack::new), None],
+                },
+                SerializableTile::Router {
                     position,
                     direction,
                     item,
-                }),
-
+                    last_output,
+                } => super::SerializableTile::Router {
+                    position,
+                    direction,
+                    item,
+                    last_output,
+                    filters: [None; 3],
+                },
                 SerializableTile::Extractor {
                     position,
                     direction,
                     extractor_type,
                     item,
-                } => Box::new(Extractor {
+                } => super::SerializableTile::Extractor {
                     position,
                     direction,
                     extractor_type,
+                    tier: ExtractorTier::Mk1,
                     item,
-                }),
+                },
                 SerializableTile::Factory {
                     position,
                     direction,
@@ -270,122 +2608,814 @@ impl WorldRes {
                     item,
                     interval,
                     ticks,
-                } => Box::new(Factory {
+                } => super::SerializableTile::Factory {
                     position,
                     direction,
                     factory_type,
                     inventory,
                     item,
+                    byproducts: Vec::new(),
                     interval,
                     ticks,
-                }),
+                    recipe_index: 0,
+                    outputs: [true, false, false, false],
+                    inputs: [true; 4],
+                },
                 SerializableTile::Storage {
                     position,
                     direction,
-                    storage_type,
                     inventory,
-                } => Box::new(Storage {
-                    position,
-                    direction,
                     storage_type,
-                    inventory,
-                }),
-                SerializableTile::Portal { position, item } => Box::new(Portal { position, item }),
-                SerializableTile::Router {
+                } => super::SerializableTile::Storage {
                     position,
                     direction,
-                    item,
-                    last_output,
-                } => Box::new(Router {
+                    inventory,
+                    storage_type,
+                },
+                SerializableTile::Portal { position, item } => super::SerializableTile::Portal {
                     position,
-                    direction,
                     item,
-                    last_output,
-                }),
+                    teleported: HashMap::new(),
+                },
                 SerializableTile::Junction {
                     position,
                     horizontal_item,
                     vertical_item,
-                } => Box::new(Junction {
+                } => super::SerializableTile::Junction {
                     position,
-                    horizontal_item,
-                    vertical_item,
-                }),
+                    horizontal_item: horizontal_item
+                        .map(|(item, direction)| (item, direction, Lane::Left)),
+                    vertical_item: vertical_item
+                        .map(|(item, direction)| (item, direction, Lane::Left)),
+                },
                 SerializableTile::Core {
                     position,
                     interval,
                     ticks,
                     tile_id,
-                } => Box::new(Core {
+                } => super::SerializableTile::Core {
                     position,
                     interval,
                     ticks,
                     tile_id,
-                }),
-            };
+                    eject: false,
+                    item: None,
+                },
+            }
+        }
+    }
+}
 
-            tiles.insert(pos, (boxed_tile, id));
-        }
-
-        if terrain.is_empty() {
-            let seed = serializable_world.world_seed;
-            let rigtorium_noise = Perlin::new(seed);
-            let flextorium_noise = Perlin::new(seed.wrapping_add(1));
-            let electrine_noise = Perlin::new(seed.wrapping_add(2));
-
-            for x in -TERRAIN_GEN_RANGE..=TERRAIN_GEN_RANGE {
-                for y in -TERRAIN_GEN_RANGE..=TERRAIN_GEN_RANGE {
-                    let rigtorium_val = rigtorium_noise.get([
-                        x as f64 * RIGTORIUM_NOISE_SCALE,
-                        y as f64 * RIGTORIUM_NOISE_SCALE,
-                    ]) + RIGTORIUM_DENSITY;
-
-                    let flextorium_val = flextorium_noise.get([
-                        x as f64 * FLEXTORIUM_NOISE_SCALE,
-                        y as f64 * FLEXTORIUM_NOISE_SCALE,
-                    ]) + FLEXTORIUM_DENSITY;
-
-                    let electrine_val = electrine_noise.get([
-                        x as f64 * ELECTRINE_NOISE_SCALE,
-                        y as f64 * ELECTRINE_NOISE_SCALE,
-                    ]) + ELECTRINE_DENSITY;
-
-                    let terrain_type = if rigtorium_val > TERRAIN_BASE_THRESHOLD
-                        && rigtorium_val > flextorium_val
-                        && rigtorium_val > electrine_val
-                    {
-                        TerrainTileType::RawRigtoriumDeposit
-                    } else if flextorium_val > TERRAIN_BASE_THRESHOLD
-                        && flextorium_val > rigtorium_val
-                        && flextorium_val > electrine_val
-                    {
-                        TerrainTileType::RawFlextoriumDeposit
-                    } else if electrine_val > TERRAIN_BASE_THRESHOLD
-                        && electrine_val > rigtorium_val
-                        && electrine_val > flextorium_val
-                    {
-                        TerrainTileType::ElectrineDeposit
-                    } else {
-                        TerrainTileType::Stone
-                    };
+fn serialize_tile(pos: Position, tile: &dyn Tile) -> SerializableTile {
+    if let Some(conveyor) = tile.as_any().downcast_ref::<Conveyor>() {
+        SerializableTile::Conveyor {
+            position: conveyor.position,
+            direction: conveyor.direction,
+            lanes: conveyor.lanes,
+        }
+    } else if let Some(extractor) = tile.as_any().downcast_ref::<Extractor>() {
+        SerializableTile::Extractor {
+            position: extractor.position,
+            direction: extractor.direction,
+            extractor_type: extractor.extractor_type,
+            tier: extractor.tier,
+            item: extractor.item,
+        }
+    } else if let Some(factory) = tile.as_any().downcast_ref::<Factory>() {
+        SerializableTile::Factory {
+            position: factory.position,
+            direction: factory.direction,
+            factory_type: factory.factory_type,
+            inventory: factory.inventory.clone(),
+            item: factory.item,
+            byproducts: factory.byproducts.clone(),
+            interval: factory.interval,
+            ticks: factory.ticks,
+            recipe_index: factory.recipe_index,
+            outputs: factory.outputs,
+            inputs: factory.inputs,
+        }
+    } else if let Some(storage) = tile.as_any().downcast_ref::<Storage>() {
+        SerializableTile::Storage {
+            position: storage.position,
+            direction: storage.direction,
+            inventory: storage.inventory,
+            storage_type: storage.storage_type,
+        }
+    } else if let Some(portal) = tile.as_any().downcast_ref::<Portal>() {
+        SerializableTile::Portal {
+            position: portal.position,
+            item: portal.item,
+            teleported: portal.teleported.clone(),
+        }
+    } else if let Some(junction) = tile.as_any().downcast_ref::<Junction>() {
+        SerializableTile::Junction {
+            position: junction.position,
+            horizontal_item: junction.horizontal_item,
+            vertical_item: junction.vertical_item,
+        }
+    } else if let Some(core) = tile.as_any().downcast_ref::<Core>() {
+        SerializableTile::Core {
+            position: core.position,
+            interval: core.interval,
+            ticks: core.ticks,
+            tile_id: core.tile_id,
+            eject: core.eject,
+            item: core.item,
+        }
+    } else if let Some(wire) = tile.as_any().downcast_ref::<Wire>() {
+        SerializableTile::Wire {
+            position: wire.position,
+        }
+    } else if let Some(sensor) = tile.as_any().downcast_ref::<Sensor>() {
+        SerializableTile::Sensor {
+            position: sensor.position,
+            direction: sensor.direction,
+        }
+    } else if let Some(display) = tile.as_any().downcast_ref::<ItemDisplay>() {
+        SerializableTile::ItemDisplay {
+            position: display.position,
+            direction: display.direction,
+            count: display.count,
+        }
+    } else if let Some(router) = tile.as_any().downcast_ref::<Router>() {
+        if let Some(priority) = router.priority {
+            SerializableTile::PriorityRouter {
+                position: router.position,
+                direction: router.direction,
+                item: router.item,
+                last_output: router.last_output,
+                priority,
+                filters: router.filters,
+            }
+        } else {
+            SerializableTile::Router {
+                position: router.position,
+                direction: router.direction,
+                item: router.item,
+                last_output: router.last_output,
+                filters: router.filters,
+            }
+        }
+    } else if let Some(inserter) = tile.as_any().downcast_ref::<Inserter>() {
+        SerializableTile::Inserter {
+            position: inserter.position,
+            direction: inserter.direction,
+        }
+    } else if let Some(beacon) = tile.as_any().downcast_ref::<Beacon>() {
+        SerializableTile::Beacon {
+            position: beacon.position,
+            item: beacon.item,
+            charge: beacon.charge,
+        }
+    } else if let Some(quarry) = tile.as_any().downcast_ref::<Quarry>() {
+        SerializableTile::Quarry {
+            position: quarry.position,
+            direction: quarry.direction,
+            item: quarry.item,
+            mined: quarry.mined,
+        }
+    } else {
+        SerializableTile::Conveyor {
+            position: pos,
+            direction: Direction::Up,
+            lanes: [None, None],
+        }
+    }
+}
+
+fn deserialize_tile(tile: SerializableTile, tile_type: (u8, u8)) -> Box<dyn Tile> {
+    match tile {
+        SerializableTile::Conveyor {
+            position,
+            direction,
+            lanes,
+        } => Box::new(Conveyor {
+            position,
+            direction,
+            lanes,
+            tier: get_conveyor_tier(tile_type),
+        }),
+
+        SerializableTile::Extractor {
+            position,
+            direction,
+            extractor_type,
+            tier,
+            item,
+        } => Box::new(Extractor {
+            position,
+            direction,
+            extractor_type,
+            tier,
+            item,
+        }),
+        SerializableTile::Factory {
+            position,
+            direction,
+            factory_type,
+            inventory,
+            item,
+            byproducts,
+            interval,
+            ticks,
+            recipe_index,
+            outputs,
+            inputs,
+        } => Box::new(Factory {
+            position,
+            direction,
+            factory_type,
+            inventory,
+            item,
+            byproducts,
+            interval,
+            ticks,
+            recipe_index,
+            outputs,
+            inputs,
+        }),
+        SerializableTile::Storage {
+            position,
+            direction,
+            storage_type,
+            inventory,
+        } => Box::new(Storage {
+            position,
+            direction,
+            storage_type,
+            inventory,
+        }),
+        SerializableTile::Portal {
+            position,
+            item,
+            teleported,
+        } => Box::new(Portal {
+            position,
+            item,
+            teleported,
+        }),
+        SerializableTile::Router {
+            position,
+            direction,
+            item,
+            last_output,
+            filters,
+        } => Box::new(Router {
+            position,
+            direction,
+            item,
+            last_output,
+            priority: None,
+            filters,
+        }),
+        SerializableTile::PriorityRouter {
+            position,
+            direction,
+            item,
+            last_output,
+            priority,
+            filters,
+        } => Box::new(Router {
+            position,
+            direction,
+            item,
+            last_output,
+            priority: Some(priority),
+            filters,
+        }),
+        SerializableTile::Wire { position } => Box::new(Wire { position }),
+        SerializableTile::Sensor {
+            position,
+            direction,
+        } => Box::new(Sensor {
+            position,
+            direction,
+        }),
+        SerializableTile::ItemDisplay {
+            position,
+            direction,
+            count,
+        } => Box::new(ItemDisplay {
+            position,
+            direction,
+            count,
+        }),
+        SerializableTile::Inserter {
+            position,
+            direction,
+        } => Box::new(Inserter {
+            position,
+            direction,
+        }),
+        SerializableTile::Beacon {
+            position,
+            item,
+            charge,
+        } => Box::new(Beacon {
+            position,
+            item,
+            charge,
+        }),
+        SerializableTile::Quarry {
+            position,
+            direction,
+            item,
+            mined,
+        } => Box::new(Quarry {
+            position,
+            direction,
+            item,
+            mined,
+        }),
+        SerializableTile::Junction {
+            position,
+            horizontal_item,
+            vertical_item,
+        } => Box::new(Junction {
+            position,
+            horizontal_item,
+            vertical_item,
+        }),
+        SerializableTile::Core {
+            position,
+            interval,
+            ticks,
+            tile_id,
+            eject,
+            item,
+        } => Box::new(Core {
+            position,
+            interval,
+            ticks,
+            tile_id,
+            eject,
+            item,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{get_new_tile, is_known_tile};
 
-                    terrain.insert(Position::new(x, y), terrain_type);
+    #[test]
+    fn blueprints_reaching_past_the_world_are_refused() {
+        let blueprint = |offset| Blueprint {
+            name: "Belt".to_string(),
+            tiles: vec![BlueprintTile {
+                offset,
+                tile_type: (2, 1),
+                direction: Direction::Up,
+            }],
+        };
+
+        let fits = blueprint((BLUEPRINT_MAX_EXTENT, 0)).export().unwrap();
+        assert!(Blueprint::import(&fits).is_ok());
+
+        for offset in [(i32::MAX, 0), (0, -1), (BLUEPRINT_MAX_EXTENT + 1, 0)] {
+            let string = blueprint(offset).export().unwrap();
+            assert!(Blueprint::import(&string).is_err());
+        }
+    }
+
+    #[test]
+    fn a_large_sale_repays_the_loan_without_overflowing() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        world.money = 0;
+        world.take_loan();
+        let owed = world.loan_owed;
+
+        world.earn(u32::MAX - LOAN_AMOUNT, LedgerReason::Sale, String::new());
+
+        assert_eq!(world.loan_owed, 0);
+        assert_eq!(world.money, u32::MAX - owed);
+    }
+
+    #[test]
+    fn save_round_trips_every_tile_type() {
+        let mut tiles = HashMap::new();
+        let mut x = 0;
+        for category in 1..=5 {
+            for index in 1..=20 {
+                let tile_type = (category, index);
+                if !is_known_tile(tile_type) {
+                    continue;
+                }
+                let position = Position::new(x, 0);
+                let (mut tile, id) = get_new_tile(tile_type, position, Direction::Left);
+                if let Some(storage) = tile.as_any_mut().downcast_mut::<Storage>() {
+                    storage.inventory = 7;
                 }
+                if let Some(display) = tile.as_any_mut().downcast_mut::<ItemDisplay>() {
+                    display.count = 42;
+                }
+                if let Some(conveyor) = tile.as_any_mut().downcast_mut::<Conveyor>() {
+                    conveyor.lanes = [
+                        None,
+                        Some(ItemStack {
+                            item: Item::Circuit,
+                            count: 3,
+                        }),
+                    ];
+                }
+                tiles.insert(position, (tile, id));
+                x += 1;
             }
         }
 
-        let world_res = WorldRes {
+        let mut world = WorldRes {
             tiles,
-            terrain,
-            loaded_chunks,
-            resources: serializable_world.resources,
-            world_seed: serializable_world.world_seed,
+            terrain: HashMap::new(),
+            loaded_chunks: HashSet::new(),
+            sprite_chunks: HashSet::new(),
+            resources: HashMap::new(),
+            world_seed: 0,
+            world_radius: None,
             tick_timer: Timer::from_seconds(TICK_LENGTH, TimerMode::Repeating),
-            tick_count: serializable_world.tick_count,
+            tick_count: 0,
             actions: Vec::new(),
-            money: serializable_world.money,
+            money: 0,
+            saved_at: 0,
+            conditions: HashMap::new(),
+            channels: HashMap::new(),
+            zones: HashMap::new(),
+            zone_names: HashMap::new(),
+            labels: HashMap::new(),
+            zone_revision: 0,
+            markers: HashMap::new(),
+            disabled: HashSet::new(),
+            last_input: HashMap::new(),
+            last_produced: HashMap::new(),
+            flowing: HashSet::new(),
+            schedule: ActionSchedule::default(),
+            changed: HashSet::new(),
+            mutations: TileMutations::default(),
+            resolving: None,
+            ghosts: HashMap::new(),
+            difficulty: Difficulty::Normal,
+            scenario: None,
+            daily: None,
+            time_trial: None,
+            lifetime: LifetimeStats::default(),
+            ledger: VecDeque::new(),
+            loan_owed: 0,
+            victory: VictoryGoal::new(Difficulty::Normal),
+            world_event: None,
+            deconstruction: VecDeque::new(),
+            auto_sell: HashMap::new(),
+            footprints: HashMap::new(),
+            storage_clusters: StorageClusters::default(),
+            chunk_saves: ChunkSaves::default(),
         };
 
-        Ok((world_res, serializable_world.hotkey_mappings))
+        let path = std::env::temp_dir().join("factoryfactory_round_trip.ffs");
+        let key = path.to_str().unwrap();
+        world.save(key, &Hotkeys::default()).unwrap();
+        let (loaded, _) = WorldRes::load_game(key).unwrap();
+        let header = WorldRes::read_save_header(key).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(header.tile_count as usize, world.tiles.len());
+        assert_eq!(header.version, SAVE_HEADER_VERSION);
+        assert_eq!(
+            header.thumbnail.len(),
+            (SAVE_THUMBNAIL_SIZE * SAVE_THUMBNAIL_SIZE * 3) as usize
+        );
+
+        assert_eq!(loaded.tiles.len(), world.tiles.len());
+        for (position, (tile, id)) in world.tiles.iter() {
+            let (loaded_tile, loaded_id) = &loaded.tiles[position];
+            assert_eq!(loaded_id, id);
+            assert_eq!(
+                format!("{:?}", loaded_tile),
+                format!("{:?}", tile),
+                "tile {:?} did not survive a save round trip",
+                id
+            );
+        }
+    }
+
+    #[test]
+    fn saving_reencodes_only_changed_chunks() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        let belt = Position::new(CHUNK_SIZE * 3, CHUNK_SIZE * 3);
+        world
+            .tiles
+            .insert(belt, get_new_tile((2, 1), belt, Direction::Right));
+        world.tile_placed(belt, (2, 1));
+
+        let path = std::env::temp_dir().join("factoryfactory_chunk_saves.ffs");
+        let key = path.to_str().unwrap();
+        world.save(key, &Hotkeys::default()).unwrap();
+        let core_chunk = world.chunk_saves.encoded[&ChunkPosition::new(0, 0)].clone();
+
+        if let Some((tile, _)) = world.tiles.get_mut(&belt) {
+            tile.set_item(Some(Item::Circuit));
+        }
+        world.mark_dirty(belt);
+        assert_eq!(
+            world.chunk_saves.dirty,
+            HashSet::from([ChunkPosition::of(belt)])
+        );
+        world.save(key, &Hotkeys::default()).unwrap();
+        let (loaded, _) = WorldRes::load_game(key).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(world.chunk_saves.dirty.is_empty());
+        assert_eq!(
+            world.chunk_saves.encoded[&ChunkPosition::new(0, 0)],
+            core_chunk
+        );
+        assert_eq!(loaded.tiles.len(), 2);
+        assert_eq!(loaded.tiles[&belt].0.get_item(), Some(Item::Circuit));
+    }
+
+    // Written by `WorldRes::save` before saves had a header, from a world with one tile of each
+    // kind that existed then. The Storage at (-40, 7) went in as a Small Battery.
+    const V1_SAVE: &[u8] = include_bytes!("../tests/fixtures/v1_save.ffs");
+
+    fn assert_v1_world(world: &WorldRes, hotkeys: &HotkeyMap) {
+        assert_eq!(world.world_seed, 1234);
+        assert_eq!(world.tick_count, 4321);
+        assert_eq!(world.money, 567);
+        assert_eq!(world.resources, HashMap::from([((2, 1), 12), ((4, 1), 1)]));
+        assert_eq!(*hotkeys, HashMap::from([(1, (2, 1)), (2, (2, 2))]));
+        assert_eq!(world.tiles.len(), 8);
+
+        let tile = |x, y| world.tiles[&Position::new(x, y)].0.as_any();
+        let conveyor = tile(1, 0).downcast_ref::<Conveyor>().unwrap();
+        assert_eq!(conveyor.direction, Direction::Right);
+        assert_eq!(conveyor.lanes, [Some(ItemStack::new(Item::Router)), None]);
+        let router = tile(2, 0).downcast_ref::<Router>().unwrap();
+        assert_eq!(router.item, Some(Item::Conveyor));
+        assert!(matches!(router.last_output, RouterOutputIndex::Left));
+        let extractor = tile(3, 0).downcast_ref::<Extractor>().unwrap();
+        assert!(matches!(extractor.extractor_type, ExtractorType::Electrine));
+        assert_eq!(extractor.item, Some(Item::Electrine));
+        let factory = tile(0, 5).downcast_ref::<Factory>().unwrap();
+        assert!(matches!(
+            factory.factory_type,
+            FactoryType::RigtoriumSmelter
+        ));
+        assert_eq!(
+            factory.inventory,
+            HashMap::from([(Item::RawRigtorium, 2), (Item::Electrine, 1)])
+        );
+        assert_eq!(factory.item, Some(Item::Rigtorium));
+        assert_eq!((factory.interval, factory.ticks), (4, 2));
+        // That version wrote storages as empty conveyors.
+        let storage = tile(-40, 7).downcast_ref::<Conveyor>().unwrap();
+        assert_eq!(storage.lanes, [None, None]);
+        let portal = tile(0, -3).downcast_ref::<Portal>().unwrap();
+        assert_eq!(portal.item, Some(Item::Flextorium));
+        let junction = tile(5, 5).downcast_ref::<Junction>().unwrap();
+        assert_eq!(
+            junction.horizontal_item,
+            Some((Item::RigtoriumRod, Direction::Left, Lane::Left))
+        );
+        assert_eq!(junction.vertical_item, None);
+        let core = tile(0, 0).downcast_ref::<Core>().unwrap();
+        assert_eq!((core.interval, core.ticks, core.tile_id), (10, 3, (6, 1)));
+    }
+
+    #[test]
+    fn version_1_saves_are_migrated_to_chunks() {
+        let (mut world, hotkeys) =
+            WorldRes::from_serializable(migrate_v1_payload(V1_SAVE).unwrap()).unwrap();
+        assert_v1_world(&world, &hotkeys);
+        assert_eq!(
+            world
+                .chunk_saves
+                .encoded
+                .keys()
+                .copied()
+                .collect::<HashSet<_>>(),
+            HashSet::from([
+                ChunkPosition::new(0, 0),
+                ChunkPosition::of(Position::new(0, -3)),
+                ChunkPosition::of(Position::new(-40, 7))
+            ])
+        );
+
+        // The next save is written in the current format and reads back the same.
+        let path = std::env::temp_dir().join("factoryfactory_v1_migration.ffs");
+        let key = path.to_str().unwrap();
+        world
+            .save(key, &Hotkeys::new(hotkeys, &Settings::default()))
+            .unwrap();
+        let header = WorldRes::read_save_header(key).unwrap();
+        let (reloaded, hotkeys) = WorldRes::load_game(key).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(header.version, SAVE_HEADER_VERSION);
+        assert_v1_world(&reloaded, &hotkeys);
+    }
+
+    #[test]
+    fn saves_without_a_header_load_without_a_checksum() {
+        let path = std::env::temp_dir().join("factoryfactory_v1_load.ffs");
+        let key = path.to_str().unwrap();
+        save_backend().write(key, V1_SAVE).unwrap();
+        let loaded = WorldRes::load_game_with_backups(key);
+        let _ = std::fs::remove_file(&path);
+
+        let (world, hotkeys, backup) = loaded.unwrap();
+        assert_eq!(backup, None);
+        assert_v1_world(&world, &hotkeys);
+    }
+
+    #[test]
+    fn saves_from_a_newer_version_are_rejected() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        let path = std::env::temp_dir().join("factoryfactory_newer_version.ffs");
+        let key = path.to_str().unwrap();
+        world.save(key, &Hotkeys::default()).unwrap();
+
+        let contents = save_backend().read(key).unwrap();
+        let (_, payload) = SaveHeader::split(&contents).unwrap();
+        let config = config::standard().with_fixed_int_encoding().with_no_limit();
+        let header = SaveHeader {
+            version: SAVE_HEADER_VERSION + 1,
+            ..world.save_header()
+        };
+        let header = bincode::encode_to_vec(header, config).unwrap();
+        let mut contents = SAVE_HEADER_MAGIC.to_vec();
+        contents.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        contents.extend_from_slice(&header);
+        contents.extend_from_slice(payload);
+        save_backend().write(key, &contents).unwrap();
+
+        let err = WorldRes::load_game(key).err().unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn unreadable_saves_are_moved_aside_instead_of_overwritten() {
+        let path = std::env::temp_dir().join("factoryfactory_unreadable.ffs");
+        let key = path.to_str().unwrap();
+        let backup = WorldRes::backup_key(key, 1);
+        save_backend().write(key, b"not a save").unwrap();
+        save_backend().write(&backup, b"nor this").unwrap();
+        assert!(WorldRes::load_game_with_backups(key).is_err());
+
+        let moved = WorldRes::set_aside_unreadable(key, SAVE_BACKUP_COUNT);
+        assert_eq!(moved.len(), 2);
+        assert!(!save_backend().exists(key) && !save_backend().exists(&backup));
+        assert_eq!(save_backend().read(&moved[0]).unwrap(), b"not a save");
+        for moved in moved {
+            let _ = std::fs::remove_file(moved);
+        }
+    }
+
+    #[test]
+    fn stats_windows_start_at_the_loaded_tick() {
+        let mut stats = ProductionStats::starting_at(5000);
+        stats.advance(5001);
+        stats.record_production(Item::Circuit, 1);
+        assert!(stats.windows.is_empty());
+
+        stats.advance(5000 + STATS_WINDOW_TICKS);
+        let window = stats.windows.back().unwrap();
+        assert_eq!(
+            (window.start_tick, window.end_tick),
+            (5000, 5000 + STATS_WINDOW_TICKS)
+        );
+        assert_eq!(window.produced[&Item::Circuit], 1);
+    }
+
+    #[test]
+    fn daily_challenge_depends_only_on_the_day() {
+        assert_eq!(DailyChallenge::for_day(0).date(), "1970-01-01");
+        assert_eq!(DailyChallenge::for_day(19_782).date(), "2024-02-29");
+
+        let first = DailyChallenge::for_day(20_743);
+        let second = DailyChallenge::for_day(20_743);
+        assert_eq!(first.date(), "2026-10-17");
+        assert_eq!(first.contracts, second.contracts);
+        assert_eq!(first.contracts.len(), DAILY_CONTRACT_COUNT);
+        assert_eq!(
+            first.create_world().world_seed,
+            second.create_world().world_seed
+        );
+    }
+
+    #[test]
+    fn leaderboard_keeps_the_fastest_runs_per_seed() {
+        let mut leaderboard = Leaderboard::default();
+        for ticks in [300, 100, 200, 400, 500] {
+            leaderboard.record(1, ticks);
+        }
+        leaderboard.record(2, 50);
+
+        assert_eq!(leaderboard.record(1, 150), Some(2));
+        assert_eq!(leaderboard.record(1, 1000), None);
+        let times: Vec<i32> = leaderboard
+            .times(1)
+            .iter()
+            .map(|entry| entry.ticks)
+            .collect();
+        assert_eq!(times, vec![100, 150, 200, 300, 400]);
+        assert_eq!(leaderboard.times(2).len(), 1);
+    }
+
+    #[test]
+    fn tick_rng_replays_for_the_same_seed_and_tick() {
+        let draws = |seed: u32, tick: i32| {
+            let mut rng = tick_rng(seed, tick);
+            (0..4).map(|_| rng.random()).collect::<Vec<u32>>()
+        };
+        assert_eq!(draws(1234, 50), draws(1234, 50));
+        assert_ne!(draws(1234, 50), draws(1234, 51));
+        assert_ne!(draws(1234, 50), draws(1235, 50));
+    }
+
+    #[test]
+    fn world_events_roll_the_same_for_the_same_seed() {
+        let schedule = |seed: u32| {
+            let mut world = WorldRes::new(Difficulty::Normal);
+            world.world_seed = seed;
+            (0..WORLD_EVENT_INTERVAL_TICKS * 20)
+                .filter_map(|tick| {
+                    world.tick_count = tick;
+                    world.advance_world_event();
+                    world.world_event.map(|event| (tick, event.kind))
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = schedule(1234);
+        assert!(!first.is_empty());
+        assert_eq!(first, schedule(1234));
+    }
+
+    #[test]
+    fn world_hotkeys_override_global_ones() {
+        let mut settings = Settings::default();
+        let mut hotkeys = Hotkeys::new(HashMap::from([(1, (2, 1))]), &settings);
+        hotkeys.set_global(&mut settings, true);
+        assert_eq!(settings.hotkeys, vec![(1, (2, 1))]);
+        assert!(hotkeys.world.is_empty());
+
+        assert!(!hotkeys.assign(1, (2, 2), &mut settings, true));
+        assert!(hotkeys.assign(2, (2, 3), &mut settings, false));
+        assert_eq!(hotkeys.mappings[&1], (2, 2));
+        assert_eq!(hotkeys.mappings[&2], (2, 3));
+
+        // Another world only sees the global mappings.
+        let other = Hotkeys::new(HotkeyMap::new(), &settings);
+        assert_eq!(other.mappings[&1], (2, 1));
+
+        assert!(hotkeys.assign(1, (2, 4), &mut settings, false));
+        assert_eq!(hotkeys.mappings[&1], (2, 4));
+        assert!(!hotkeys.world.contains_key(&1));
+    }
+
+    #[test]
+    fn settings_files_missing_fields_keep_their_defaults() {
+        let settings: Settings = ron::from_str("(edge_scroll: true, tick_budget: 40)").unwrap();
+        assert!(settings.edge_scroll);
+        assert_eq!(settings.tick_budget, 40);
+        assert_eq!(
+            settings.pan_sensitivity,
+            Settings::default().pan_sensitivity
+        );
+        assert!(settings.offline_progress);
+    }
+
+    #[test]
+    fn pasting_a_recipe_that_doesnt_fit_changes_nothing() {
+        let position = Position::new(0, 0);
+        let (mut tile, tile_type) = get_new_tile((4, 12), position, Direction::Up);
+        let factory = tile.as_any_mut().downcast_mut::<Factory>().unwrap();
+        let recipes = factory.factory_type.recipes();
+        let (&first_input, _) = recipes[0].inputs.iter().next().unwrap();
+        let other = recipes
+            .iter()
+            .position(|recipe| !recipe.inputs.contains_key(&first_input))
+            .unwrap();
+        factory.inventory.insert(first_input, 1);
+        let inputs = factory.inputs;
+        let settings = TileSettings::Factory {
+            tile_type,
+            recipe_index: other,
+            outputs: [false, true, false, false],
+            inputs: [true, false, false, false],
+        };
+
+        assert!(!settings.apply(tile.as_mut(), tile_type, Difficulty::Normal));
+        let factory = tile.as_any().downcast_ref::<Factory>().unwrap();
+        assert_eq!(factory.recipe_index, 0);
+        assert_eq!(factory.outputs, [true, false, false, false]);
+        assert_eq!(factory.inputs, inputs);
+
+        tile.as_any_mut()
+            .downcast_mut::<Factory>()
+            .unwrap()
+            .inventory
+            .clear();
+        assert!(settings.apply(tile.as_mut(), tile_type, Difficulty::Normal));
+        // Pasting the same settings again, as holding the button does, changes nothing.
+        assert!(!settings.apply(tile.as_mut(), tile_type, Difficulty::Normal));
     }
 }
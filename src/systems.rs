@@ -1,11 +1,45 @@
+pub mod alerts;
+pub mod blueprints;
 pub mod camera;
+pub mod console;
+pub mod debug;
+pub mod flow;
+#[cfg(feature = "inspector")]
+pub mod inspector;
 pub mod items;
+pub mod markers;
+pub mod particles;
+pub mod photo;
+pub mod scenario;
+pub mod stats;
+#[cfg(feature = "stats_feed")]
+pub mod stats_feed;
+pub mod texture_pack;
 pub mod tiles;
 pub mod ui;
+pub mod window;
 pub mod world;
+pub mod zones;
 
+pub use alerts::*;
+pub use blueprints::*;
 pub use camera::*;
+pub use console::*;
+pub use debug::*;
+pub use flow::*;
+#[cfg(feature = "inspector")]
+pub use inspector::*;
 pub use items::*;
+pub use markers::*;
+pub use particles::*;
+pub use photo::*;
+pub use scenario::*;
+pub use stats::*;
+#[cfg(feature = "stats_feed")]
+pub use stats_feed::*;
+pub use texture_pack::*;
 pub use tiles::*;
 pub use ui::*;
+pub use window::*;
 pub use world::*;
+pub use zones::*;
@@ -0,0 +1,274 @@
+use crate::{
+    ALERT_LIFETIME_TICKS, ALERT_STARVATION_TICKS, Alert, AlertCategory, AlertEntry, AlertFeed,
+    AlertMuteButton, Alerts, CAMERA_PAN_DURATION, CameraPan, DEADLOCK_CHECK_TICKS, PhotoCamera,
+    Position, Settings, TILE_SIZE, WorldRes, tiles::*, utils::*,
+};
+use bevy::prelude::*;
+
+pub fn detect_alerts(world: Res<WorldRes>, settings: Res<Settings>, mut alerts: ResMut<Alerts>) {
+    if world.tick_count == alerts.last_tick {
+        return;
+    }
+    // Several ticks can finish in one frame, so thresholds are checked with `>=` and deadlocks
+    // whenever a check interval was crossed.
+    let previous_tick = alerts.last_tick;
+    alerts.last_tick = world.tick_count;
+    let tick = world.tick_count;
+
+    let mut new_alerts = Vec::new();
+    for (pos, (tile, _)) in world.tiles.iter() {
+        if let Some(factory) = tile.as_any().downcast_ref::<Factory>() {
+            let missing = factory
+                .recipe()
+                .inputs
+                .iter()
+                .filter(|(item, required)| factory.inventory.get(item).unwrap_or(&0) < required)
+                .map(|(item, _)| *item)
+                .min_by_key(|item| item.name());
+
+            match missing {
+                Some(item) if factory.item.is_none() => {
+                    let (since, alerted) =
+                        alerts.starved_since.entry(*pos).or_insert((tick, false));
+                    if !*alerted && tick - *since >= ALERT_STARVATION_TICKS {
+                        *alerted = true;
+                        new_alerts.push(Alert {
+                            category: AlertCategory::Starvation,
+                            message: format!(
+                                "Factory at ({},{}) starved for {} for {} ticks",
+                                pos.x,
+                                pos.y,
+                                item.name(),
+                                ALERT_STARVATION_TICKS
+                            ),
+                            position: *pos,
+                            tick,
+                        });
+                    }
+                }
+                _ => {
+                    alerts.starved_since.remove(pos);
+                }
+            }
+        } else if tile.as_any().is::<Storage>() {
+            // A cluster raises one alert, from its first member.
+            let cluster = world.storage_cluster(*pos);
+            let (stored, capacity) = world.storage_fill(*pos);
+            if stored >= capacity && cluster.is_none_or(|members| members[0] == *pos) {
+                if alerts.full_storages.insert(*pos) {
+                    new_alerts.push(Alert {
+                        category: AlertCategory::StorageFull,
+                        message: match cluster {
+                            Some(members) => format!(
+                                "Storage cluster of {} at ({},{}) is full",
+                                members.len(),
+                                pos.x,
+                                pos.y
+                            ),
+                            None => format!("Storage at ({},{}) is full", pos.x, pos.y),
+                        },
+                        position: *pos,
+                        tick,
+                    });
+                }
+            } else {
+                alerts.full_storages.remove(pos);
+            }
+        } else if let Some(core) = tile.as_any().downcast_ref::<Core>() {
+            let previous = alerts.core_ticks.insert(*pos, core.ticks).unwrap_or(0);
+            if core.ticks < previous {
+                new_alerts.push(Alert {
+                    category: AlertCategory::CoreProduction,
+                    message: format!("Core finished producing {}", get_tile_name(core.tile_id)),
+                    position: *pos,
+                    tick,
+                });
+            }
+        }
+    }
+
+    if tick.div_euclid(DEADLOCK_CHECK_TICKS) != previous_tick.div_euclid(DEADLOCK_CHECK_TICKS) {
+        let deadlocked = find_deadlocks(&world);
+        let fresh: Vec<Position> = deadlocked.difference(&alerts.deadlocked).copied().collect();
+        if let Some(pos) = fresh.iter().min() {
+            new_alerts.push(Alert {
+                category: AlertCategory::Deadlock,
+                message: format!(
+                    "Belts deadlocked near ({},{}), {} tiles stuck in a loop",
+                    pos.x,
+                    pos.y,
+                    fresh.len()
+                ),
+                position: *pos,
+                tick,
+            });
+        }
+        alerts.deadlocked = deadlocked;
+    }
+
+    alerts
+        .starved_since
+        .retain(|pos, _| world.tiles.contains_key(pos));
+    alerts
+        .full_storages
+        .retain(|pos| world.tiles.contains_key(pos));
+    alerts
+        .core_ticks
+        .retain(|pos, _| world.tiles.contains_key(pos));
+
+    let before = alerts.entries.len();
+    alerts
+        .entries
+        .retain(|alert| tick - alert.tick < ALERT_LIFETIME_TICKS);
+    if alerts.entries.len() != before {
+        alerts.dirty = true;
+    }
+
+    for alert in new_alerts {
+        alerts.push(&settings, alert);
+    }
+}
+
+pub fn update_alert_feed(
+    mut commands: Commands,
+    mut alerts: ResMut<Alerts>,
+    settings: Res<Settings>,
+    feed_query: Query<Entity, With<AlertFeed>>,
+) {
+    if !alerts.dirty && !settings.is_changed() {
+        return;
+    }
+    alerts.dirty = false;
+
+    let Ok(feed) = feed_query.single() else {
+        return;
+    };
+    commands.entity(feed).despawn_related::<Children>();
+
+    commands.entity(feed).with_children(|parent| {
+        parent
+            .spawn(Node {
+                display: Display::Flex,
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(5.0),
+                ..default()
+            })
+            .with_children(|row| {
+                for category in AlertCategory::ALL {
+                    let muted = settings.muted_alerts.contains(&category);
+                    row.spawn((
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(3.0)),
+                            ..default()
+                        },
+                        BackgroundColor(if muted {
+                            Color::srgb(0.3, 0.3, 0.3)
+                        } else {
+                            Color::srgb(0.3, 0.5, 0.7)
+                        }),
+                        BorderRadius::all(Val::Px(5.0)),
+                        Interaction::default(),
+                        AlertMuteButton { category },
+                        children![(
+                            Text::new(category.name()),
+                            TextFont {
+                                font_size: 12.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        )],
+                    ));
+                }
+            });
+
+        for alert in alerts.entries.iter() {
+            parent.spawn((
+                Button,
+                Node {
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.18, 0.2, 0.23, 0.9)),
+                BorderRadius::all(Val::Px(5.0)),
+                Interaction::default(),
+                AlertEntry {
+                    position: alert.position,
+                },
+                children![(
+                    Text::new(alert.message.clone()),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                )],
+            ));
+        }
+    });
+}
+
+pub fn handle_alert_interaction(
+    mut commands: Commands,
+    entry_query: Query<(&Interaction, &AlertEntry), Changed<Interaction>>,
+    mute_query: Query<(&Interaction, &AlertMuteButton), Changed<Interaction>>,
+    camera: Query<(Entity, &Camera, &Transform), Without<PhotoCamera>>,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, entry) in entry_query.iter() {
+        if matches!(interaction, Interaction::Pressed)
+            && let Ok((entity, _, transform)) = camera.single()
+        {
+            commands.entity(entity).insert(CameraPan {
+                start_pos: transform.translation.truncate(),
+                end_pos: Vec2::new(
+                    entry.position.x as f32 * TILE_SIZE,
+                    entry.position.y as f32 * TILE_SIZE,
+                ),
+                timer: Timer::from_seconds(CAMERA_PAN_DURATION, TimerMode::Once),
+            });
+        }
+    }
+
+    for (interaction, button) in mute_query.iter() {
+        if matches!(interaction, Interaction::Pressed) {
+            if let Some(index) = settings
+                .muted_alerts
+                .iter()
+                .position(|category| *category == button.category)
+            {
+                settings.muted_alerts.remove(index);
+            } else {
+                settings.muted_alerts.push(button.category);
+            }
+
+            if let Err(err) = settings.save("settings.ron") {
+                eprintln!("Error saving settings: {}", err);
+            }
+        }
+    }
+}
+
+// A warning triangle over every tile the last deadlock check found stuck.
+pub fn draw_deadlocks(alerts: Res<Alerts>, mut gizmos: Gizmos) {
+    let color = Color::srgb(1.0, 0.75, 0.1);
+    let half = TILE_SIZE * 0.3;
+    for pos in alerts.deadlocked.iter() {
+        let center = Vec2::new(pos.x as f32 * TILE_SIZE, pos.y as f32 * TILE_SIZE);
+        gizmos.linestrip_2d(
+            [
+                center + Vec2::new(-half, -half),
+                center + Vec2::new(half, -half),
+                center + Vec2::new(0.0, half),
+                center + Vec2::new(-half, -half),
+            ],
+            color,
+        );
+        gizmos.line_2d(
+            center + Vec2::new(0.0, half * 0.5),
+            center + Vec2::new(0.0, -half * 0.3),
+            color,
+        );
+        gizmos.circle_2d(center + Vec2::new(0.0, -half * 0.65), 1.0, color);
+    }
+}
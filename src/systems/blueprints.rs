@@ -0,0 +1,168 @@
+use crate::{
+    Blueprint, BlueprintLibrary, BlueprintPreview, Direction, GridRegion, IMAGE_SIZE, Placer,
+    Position, TILE_SIZE, UiState, WorldRes, systems::ui::spawn_dialog, utils::*,
+};
+use bevy::prelude::*;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+pub fn capture_blueprint(
+    mut commands: Commands,
+    cursor: CursorInput,
+    placer: Res<Placer>,
+    world: Res<WorldRes>,
+    mut library: ResMut<BlueprintLibrary>,
+    mut gizmos: Gizmos,
+    ui_state: Res<UiState>,
+) {
+    if placer.tile_type != (0, 1) || library.active.is_some() || ui_state.blocks_gameplay() {
+        library.selection_start = None;
+        return;
+    }
+
+    let Some(pos) = cursor.grid_position() else {
+        return;
+    };
+
+    if cursor.mouse_button_input.just_pressed(MouseButton::Left)
+        && cursor
+            .keyboard_input
+            .any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
+        library.selection_start = Some(pos);
+    }
+
+    let Some(start) = library.selection_start else {
+        return;
+    };
+
+    draw_grid_region(
+        &mut gizmos,
+        GridRegion::new(start, pos),
+        Color::srgb(0.45, 0.67, 0.9),
+    );
+
+    if cursor.mouse_button_input.just_released(MouseButton::Left) {
+        library.selection_start = None;
+
+        let name = format!("Blueprint {}", library.blueprints.len() + 1);
+        let blueprint = Blueprint::capture(&world, name, start, pos);
+        if blueprint.tiles.is_empty() {
+            return;
+        }
+
+        spawn_dialog(
+            &mut commands,
+            "Blueprint saved",
+            &[
+                format!(
+                    "Saved \"{}\" with {} tiles.",
+                    blueprint.name,
+                    blueprint.tiles.len()
+                ),
+                "Press L to open the blueprint library.".to_string(),
+            ],
+        );
+
+        library.blueprints.push(blueprint);
+        if let Err(err) = library.save("blueprints.ffs") {
+            eprintln!("Error saving blueprints: {}", err);
+        }
+    }
+}
+
+pub fn place_blueprint(
+    mut commands: Commands,
+    cursor: CursorInput,
+    asset_server: Res<AssetServer>,
+    mut world: ResMut<WorldRes>,
+    mut library: ResMut<BlueprintLibrary>,
+    preview_query: Query<Entity, With<BlueprintPreview>>,
+    ui_state: Res<UiState>,
+) {
+    for entity in preview_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let Some(index) = library.active else {
+        return;
+    };
+    if cursor.keyboard_input.just_pressed(KeyCode::Escape) || index >= library.blueprints.len() {
+        library.active = None;
+        return;
+    }
+    if ui_state.blocks_gameplay() {
+        return;
+    }
+
+    let Some(origin) = cursor.grid_position() else {
+        return;
+    };
+
+    let blueprint = &library.blueprints[index];
+    let place = cursor.mouse_button_input.just_pressed(MouseButton::Left);
+    let mut missing = 0;
+
+    for tile in blueprint.tiles.iter() {
+        let (Some(x), Some(y)) = (
+            origin.x.checked_add(tile.offset.0),
+            origin.y.checked_add(tile.offset.1),
+        ) else {
+            continue;
+        };
+        let pos = Position::new(x, y);
+        let blocked = footprint_cells(tile.tile_type, pos)
+            .into_iter()
+            .any(|cell| world.is_occupied(cell))
+            || !is_valid_placement(&world, tile.tile_type, pos, tile.direction);
+        let rotation = match tile.direction {
+            Direction::Up => Quat::IDENTITY,
+            Direction::Down => Quat::from_rotation_z(PI),
+            Direction::Left => Quat::from_rotation_z(FRAC_PI_2),
+            Direction::Right => Quat::from_rotation_z(-FRAC_PI_2),
+        };
+
+        if place && !blocked {
+            if *world.resources.get(&tile.tile_type).unwrap_or(&0) >= 1 {
+                *world.resources.entry(tile.tile_type).or_insert(0) -= 1;
+                world.lifetime.tiles_placed += 1;
+                world
+                    .tiles
+                    .insert(pos, get_new_tile(tile.tile_type, pos, tile.direction));
+                world.occupy(pos, tile.tile_type);
+                world.tile_placed(pos, tile.tile_type);
+            } else {
+                missing += 1;
+            }
+            continue;
+        }
+
+        commands.spawn((
+            Sprite {
+                image: asset_server.load(get_tile_texture(tile.tile_type)),
+                color: if blocked {
+                    Color::srgba(1.0, 0.4, 0.4, 0.5)
+                } else {
+                    Color::srgba(1.0, 1.0, 1.0, 0.5)
+                },
+                ..Default::default()
+            },
+            Transform {
+                translation: Vec3::new(pos.x as f32 * TILE_SIZE, pos.y as f32 * TILE_SIZE, 5.0),
+                scale: Vec3::splat(TILE_SIZE / IMAGE_SIZE),
+                rotation,
+            },
+            BlueprintPreview,
+        ));
+    }
+
+    if missing > 0 {
+        spawn_dialog(
+            &mut commands,
+            "Not enough tiles",
+            &[format!(
+                "{} tiles of the blueprint could not be placed.",
+                missing
+            )],
+        );
+    }
+}
@@ -1,14 +1,16 @@
-use crate::{CAMERA_SPEED, CoreMenu, Inventory, Placer};
-use bevy::prelude::*;
+use crate::{
+    CAMERA_PAN_DURATION, CAMERA_SPEED, CameraPan, Core, PhotoCamera, Placer, Settings, TILE_SIZE,
+    UiState, WorldRes, ZOOM_SMOOTHING,
+};
+use bevy::{input::mouse::AccumulatedMouseMotion, prelude::*, window::PrimaryWindow};
 
 pub fn move_camera(
-    mut camera: Query<&mut Transform, With<Camera2d>>,
+    mut camera: Query<&mut Transform, (With<Camera2d>, Without<PhotoCamera>)>,
     placer: Res<Placer>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    inventory_query: Query<(), With<Inventory>>,
-    core_menu_query: Query<(), With<CoreMenu>>,
+    ui_state: Res<UiState>,
 ) {
-    if inventory_query.is_empty() && core_menu_query.is_empty() {
+    if !ui_state.blocks_camera() {
         let mut direction = Vec2::ZERO;
         if keyboard_input.pressed(KeyCode::KeyW) {
             direction.y = 1.0;
@@ -28,3 +30,170 @@ pub fn move_camera(
         }
     }
 }
+
+pub fn pan_camera(
+    mut camera: Query<&mut Transform, (With<Camera2d>, Without<PhotoCamera>)>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    settings: Res<Settings>,
+    ui_state: Res<UiState>,
+) {
+    if mouse_button_input.pressed(MouseButton::Middle)
+        && !ui_state.blocks_camera()
+        && let Ok(mut camera) = camera.single_mut()
+    {
+        let delta = Vec2::new(-mouse_motion.delta.x, mouse_motion.delta.y);
+        let scale = camera.scale.x;
+        camera.translation += (delta * scale * settings.pan_sensitivity).extend(0.0);
+    }
+}
+
+pub fn edge_scroll_camera(
+    mut camera: Query<&mut Transform, (With<Camera2d>, Without<PhotoCamera>)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    placer: Res<Placer>,
+    settings: Res<Settings>,
+    ui_state: Res<UiState>,
+) {
+    if !settings.edge_scroll || ui_state.blocks_camera() {
+        return;
+    }
+
+    if let Ok(window) = windows.single()
+        && let Some(cursor) = window.cursor_position()
+        && let Ok(mut camera) = camera.single_mut()
+    {
+        let mut direction = Vec2::ZERO;
+        if cursor.x < settings.edge_scroll_margin {
+            direction.x = -1.0;
+        }
+        if cursor.x > window.width() - settings.edge_scroll_margin {
+            direction.x = 1.0;
+        }
+        if cursor.y < settings.edge_scroll_margin {
+            direction.y = 1.0;
+        }
+        if cursor.y > window.height() - settings.edge_scroll_margin {
+            direction.y = -1.0;
+        }
+        camera.translation += direction.normalize_or_zero().extend(0.0)
+            * settings.edge_scroll_speed
+            / placer.zoom_level;
+    }
+}
+
+pub fn jump_to_core(
+    mut commands: Commands,
+    camera: Query<(Entity, &Camera, &Transform), Without<PhotoCamera>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    world: Res<WorldRes>,
+    ui_state: Res<UiState>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Home)
+        && !ui_state.blocks_camera()
+        && let Ok((entity, _, transform)) = camera.single()
+    {
+        let start_pos = transform.translation.truncate();
+        let end_pos = world
+            .tiles
+            .iter()
+            .filter(|(_, (tile, _))| tile.as_any().is::<Core>())
+            .map(|(pos, _)| Vec2::new(pos.x as f32 * TILE_SIZE, pos.y as f32 * TILE_SIZE))
+            .min_by(|a, b| {
+                a.distance_squared(start_pos)
+                    .total_cmp(&b.distance_squared(start_pos))
+            })
+            .unwrap_or(Vec2::ZERO);
+
+        commands.entity(entity).insert(CameraPan {
+            start_pos,
+            end_pos,
+            timer: Timer::from_seconds(CAMERA_PAN_DURATION, TimerMode::Once),
+        });
+    }
+}
+
+pub fn animate_camera_pan(
+    mut commands: Commands,
+    mut camera: Query<(Entity, &mut Transform, &mut CameraPan), Without<PhotoCamera>>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut pan) in camera.iter_mut() {
+        pan.timer.tick(time.delta());
+        let t = pan.timer.fraction();
+        let eased = t * t * (3.0 - 2.0 * t);
+        let pos = pan.start_pos.lerp(pan.end_pos, eased);
+        transform.translation.x = pos.x;
+        transform.translation.y = pos.y;
+
+        if pan.timer.finished() {
+            commands.entity(entity).remove::<CameraPan>();
+        }
+    }
+}
+
+pub fn smooth_zoom(
+    mut camera: Query<&mut Transform, (With<Camera2d>, Without<PhotoCamera>)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    placer: Res<Placer>,
+    time: Res<Time>,
+) {
+    let Ok(mut transform) = camera.single_mut() else {
+        return;
+    };
+
+    let current_scale = transform.scale.x;
+    let target_scale = 1.0 / placer.zoom_level;
+    if current_scale == target_scale {
+        return;
+    }
+
+    let t = 1.0 - (-ZOOM_SMOOTHING * time.delta_secs()).exp();
+    let mut new_scale = current_scale + (target_scale - current_scale) * t;
+    if (target_scale - new_scale).abs() < target_scale * 0.001 {
+        new_scale = target_scale;
+    }
+
+    if let Ok(window) = windows.single()
+        && let Some(cursor) = window.cursor_position()
+    {
+        let mut offset = cursor - Vec2::new(window.width(), window.height()) / 2.0;
+        offset.y *= -1.0;
+        transform.translation += (offset * (current_scale - new_scale)).extend(0.0);
+    }
+
+    transform.scale = Vec3::new(new_scale, new_scale, transform.scale.z);
+}
+
+// Keeps the camera centre inside the world border, after everything else has moved it.
+pub fn clamp_camera_to_border(
+    mut camera: Query<&mut Transform, (With<Camera2d>, Without<PhotoCamera>)>,
+    world: Res<WorldRes>,
+) {
+    let (Some(radius), Ok(mut transform)) = (world.world_radius, camera.single_mut()) else {
+        return;
+    };
+    let limit = radius as f32 * TILE_SIZE;
+    let clamped = transform
+        .translation
+        .truncate()
+        .clamp(Vec2::splat(-limit), Vec2::splat(limit));
+    if clamped != transform.translation.truncate() {
+        transform.translation.x = clamped.x;
+        transform.translation.y = clamped.y;
+    }
+}
+
+pub fn draw_world_border(world: Res<WorldRes>, mut gizmos: Gizmos) {
+    let Some(radius) = world.world_radius else {
+        return;
+    };
+    // Cells are centred on their position, so the edge sits half a tile past the last one.
+    let side = (radius * 2 + 1) as f32 * TILE_SIZE;
+    gizmos.rect_2d(Vec2::ZERO, Vec2::splat(side), Color::srgb(0.9, 0.3, 0.2));
+    gizmos.rect_2d(
+        Vec2::ZERO,
+        Vec2::splat(side + TILE_SIZE * 0.5),
+        Color::srgba(0.9, 0.3, 0.2, 0.35),
+    );
+}
@@ -0,0 +1,426 @@
+use crate::{
+    Position,
+    components::*,
+    constants::*,
+    resources::*,
+    systems::{list_texture_packs, simulate_tick},
+    utils::*,
+};
+use bevy::{
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+fn parse_tile_type(text: &str) -> Option<(u8, u8)> {
+    let (category, index) = text
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split_once(',')?;
+    let tile_type = (category.trim().parse().ok()?, index.trim().parse().ok()?);
+    is_known_tile(tile_type).then_some(tile_type)
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: Option<&&str>, usage: &str) -> Result<T, String> {
+    arg.and_then(|arg| arg.parse().ok())
+        .ok_or_else(|| format!("Usage: {}", usage))
+}
+
+// Commands that change the world are off during a daily challenge or time trial so runs stay
+// comparable.
+fn check_cheats_allowed(ecs: &World) -> Result<(), String> {
+    let world = ecs.resource::<WorldRes>();
+    if world.daily.is_some() || world.time_trial.is_some() {
+        return Err("Not available during a daily challenge or time trial".to_string());
+    }
+    Ok(())
+}
+
+fn give(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    check_cheats_allowed(ecs)?;
+    let usage = "give (category,index) count";
+    let (count, tile_type) = args
+        .split_last()
+        .ok_or_else(|| format!("Usage: {}", usage))?;
+    let count: u32 = parse_arg(Some(count), usage)?;
+    let tile_type = parse_tile_type(&tile_type.concat())
+        .ok_or_else(|| format!("Unknown tile type, usage: {}", usage))?;
+
+    let mut world = ecs.resource_mut::<WorldRes>();
+    *world.resources.entry(tile_type).or_insert(0) += count;
+    Ok(format!("Gave {} x{}", get_tile_name(tile_type), count))
+}
+
+fn money(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    check_cheats_allowed(ecs)?;
+    let money = parse_arg(args.first(), "money amount")?;
+    ecs.resource_mut::<WorldRes>().money = money;
+    Ok(format!("Money set to ${}", money))
+}
+
+fn teleport(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    let usage = "tp x y";
+    let x: i32 = parse_arg(args.first(), usage)?;
+    let y: i32 = parse_arg(args.get(1), usage)?;
+
+    let mut camera_query =
+        ecs.query_filtered::<&mut Transform, (With<Camera2d>, Without<PhotoCamera>)>();
+    let mut transform = camera_query
+        .single_mut(ecs)
+        .map_err(|_| "No camera to move".to_string())?;
+    transform.translation.x = x as f32 * TILE_SIZE;
+    transform.translation.y = y as f32 * TILE_SIZE;
+    Ok(format!("Moved to ({}, {})", x, y))
+}
+
+fn tick(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    check_cheats_allowed(ecs)?;
+    let ticks: u32 = parse_arg(args.first(), "tick count")?;
+    let ticks = ticks.min(CONSOLE_MAX_TICKS);
+
+    ecs.resource_scope(|ecs, mut world: Mut<WorldRes>| {
+        let mut stats = ecs.resource_mut::<ProductionStats>();
+        for _ in 0..ticks {
+            let events = simulate_tick(&mut world);
+            stats.advance(world.tick_count);
+            for event in events.produced {
+                stats.record(event.kind);
+            }
+        }
+        Ok(format!(
+            "Simulated {} ticks, now at tick {}",
+            ticks, world.tick_count
+        ))
+    })
+}
+
+fn seed(ecs: &mut World, _args: &[&str]) -> Result<String, String> {
+    Ok(format!(
+        "World seed: {}",
+        ecs.resource::<WorldRes>().world_seed
+    ))
+}
+
+fn texture_pack(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    let mut settings = ecs.resource_mut::<Settings>();
+    let Some(name) = args.first() else {
+        let packs = list_texture_packs();
+        return Ok(format!(
+            "Current: {}\nAvailable: {}",
+            settings.texture_pack.as_deref().unwrap_or("none"),
+            if packs.is_empty() {
+                format!("none, add directories to {}/", TEXTURE_PACK_DIR)
+            } else {
+                packs.join(", ")
+            }
+        ));
+    };
+
+    settings.texture_pack = match *name {
+        "none" => None,
+        name if list_texture_packs().iter().any(|pack| pack == name) => Some(name.to_string()),
+        name => return Err(format!("No texture pack named \"{}\"", name)),
+    };
+    settings
+        .save("settings.ron")
+        .map_err(|err| format!("Error saving settings: {}", err))?;
+    Ok("Texture pack changed, restart the game to apply it".to_string())
+}
+
+fn hotkeys(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    let global = match args.first().copied() {
+        None => {
+            let global = ecs.resource::<Settings>().global_hotkeys;
+            return Ok(format!(
+                "Hotkeys are {}",
+                if global { "global" } else { "saved per world" }
+            ));
+        }
+        Some("global") => true,
+        Some("world") => false,
+        Some(mode) => return Err(format!("Unknown hotkey mode \"{}\"", mode)),
+    };
+
+    ecs.resource_scope(|ecs, mut settings: Mut<Settings>| {
+        ecs.resource_mut::<Hotkeys>()
+            .set_global(&mut settings, global);
+        settings
+            .save("settings.ron")
+            .map_err(|err| format!("Error saving settings: {}", err))
+    })?;
+    Ok(if global {
+        "Hotkeys are now shared across worlds".to_string()
+    } else {
+        "Hotkeys are now saved per world".to_string()
+    })
+}
+
+fn camera(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    let usage = "camera edge on|off | camera sensitivity amount";
+    let mut settings = ecs.resource_mut::<Settings>();
+    match args {
+        [] => {
+            return Ok(format!(
+                "Edge scrolling {}, pan sensitivity {}",
+                if settings.edge_scroll { "on" } else { "off" },
+                settings.pan_sensitivity
+            ));
+        }
+        ["edge", "on"] => settings.edge_scroll = true,
+        ["edge", "off"] => settings.edge_scroll = false,
+        ["sensitivity", amount] => {
+            let amount: f32 = parse_arg(Some(amount), usage)?;
+            if !amount.is_finite() || amount <= 0.0 {
+                return Err("Sensitivity must be above 0".to_string());
+            }
+            settings.pan_sensitivity = amount;
+        }
+        _ => return Err(format!("Usage: {}", usage)),
+    }
+    settings
+        .save("settings.ron")
+        .map_err(|err| format!("Error saving settings: {}", err))?;
+    Ok(format!(
+        "Edge scrolling {}, pan sensitivity {}",
+        if settings.edge_scroll { "on" } else { "off" },
+        settings.pan_sensitivity
+    ))
+}
+
+// Shares blueprints as text, which is the only way to do so in the web build.
+fn blueprint(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    let mut library = ecs.resource_mut::<BlueprintLibrary>();
+    match args {
+        ["export", number] => {
+            let blueprint = number
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| library.blueprints.get(number.wrapping_sub(1)))
+                .ok_or_else(|| format!("No blueprint numbered {}", number))?;
+            blueprint.export().map_err(|err| err.to_string())
+        }
+        ["import", text] => {
+            let blueprint = Blueprint::import(text).map_err(|err| err.to_string())?;
+            let name = blueprint.name.clone();
+            library.blueprints.push(blueprint);
+            library
+                .save("blueprints.ffs")
+                .map_err(|err| format!("Error saving blueprints: {}", err))?;
+            Ok(format!("Imported \"{}\"", name))
+        }
+        _ => Err("Usage: blueprint export <number> | blueprint import <text>".to_string()),
+    }
+}
+
+fn deadlocks(ecs: &mut World, _args: &[&str]) -> Result<String, String> {
+    let mut deadlocked: Vec<Position> = find_deadlocks(ecs.resource::<WorldRes>())
+        .into_iter()
+        .collect();
+    if deadlocked.is_empty() {
+        return Ok("No deadlocks found".to_string());
+    }
+
+    deadlocked.sort();
+    let positions: Vec<String> = deadlocked
+        .iter()
+        .map(|pos| format!("({}, {})", pos.x, pos.y))
+        .collect();
+    ecs.resource_mut::<Alerts>().deadlocked = deadlocked.into_iter().collect();
+    Ok(format!("Deadlocked tiles: {}", positions.join(", ")))
+}
+
+fn cursor_cell(ecs: &mut World) -> Result<Position, String> {
+    let mut window_query = ecs.query_filtered::<&Window, With<PrimaryWindow>>();
+    let mut camera_query =
+        ecs.query_filtered::<(&Camera, &Transform), (With<Camera2d>, Without<PhotoCamera>)>();
+    let window = window_query
+        .single(ecs)
+        .map_err(|_| "No window".to_string())?;
+    let (camera, camera_transform) = camera_query
+        .single(ecs)
+        .map_err(|_| "No camera".to_string())?;
+    cursor_grid_position(window, camera, camera_transform)
+        .ok_or_else(|| "Point the cursor at a tile first".to_string())
+}
+
+fn zone(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    let usage = format!("zone 1-{} [name]", ZONE_COLORS.len());
+    let index: usize = parse_arg(args.first(), &usage)?;
+    if !(1..=ZONE_COLORS.len()).contains(&index) {
+        return Err(format!("Usage: {}", usage));
+    }
+    let zone = (index - 1) as u8;
+
+    let mut world = ecs.resource_mut::<WorldRes>();
+    let name = args[1..].join(" ");
+    world.zone_revision += 1;
+    if name.is_empty() {
+        world.zone_names.remove(&zone);
+        Ok(format!("Zone {} is unnamed", index))
+    } else {
+        world.zone_names.insert(zone, name.clone());
+        Ok(format!("Zone {} is now \"{}\"", index, name))
+    }
+}
+
+fn label(ecs: &mut World, args: &[&str]) -> Result<String, String> {
+    let pos = cursor_cell(ecs)?;
+    let mut world = ecs.resource_mut::<WorldRes>();
+    let text = args.join(" ");
+    world.zone_revision += 1;
+    if text.is_empty() {
+        world.labels.remove(&pos);
+        Ok(format!("Removed the label at ({}, {})", pos.x, pos.y))
+    } else {
+        world.labels.insert(pos, text.clone());
+        Ok(format!("Labelled ({}, {}) \"{}\"", pos.x, pos.y, text))
+    }
+}
+
+pub fn register_console_commands(mut registry: ResMut<ConsoleCommands>) {
+    registry.register("give", "give (category,index) count", give);
+    registry.register("money", "money amount", money);
+    registry.register("tp", "tp x y", teleport);
+    registry.register("tick", "tick count", tick);
+    registry.register("seed", "seed", seed);
+    registry.register("deadlocks", "deadlocks", deadlocks);
+    registry.register("texture_pack", "texture_pack [name|none]", texture_pack);
+    registry.register("hotkeys", "hotkeys [global|world]", hotkeys);
+    registry.register(
+        "camera",
+        "camera [edge on|off | sensitivity amount]",
+        camera,
+    );
+    registry.register(
+        "blueprint",
+        "blueprint export number | import text",
+        blueprint,
+    );
+    registry.register("zone", "zone number [name]", zone);
+    registry.register("label", "label [text]", label);
+}
+
+fn spawn_console(commands: &mut Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(0.0),
+            top: Val::Px(0.0),
+            width: Val::Vw(100.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.1, 0.11, 0.13, 0.92)),
+        Interaction::default(),
+        ZIndex(300),
+        ConsolePanel,
+        children![(
+            Text::new(""),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            ConsoleText,
+        )],
+    ));
+}
+
+// Runs before the rest of the game sees the keyboard, which is cleared while the console is open
+// so typing does not also trigger hotkeys.
+pub fn handle_console_input(
+    mut commands: Commands,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut key_events: EventReader<KeyboardInput>,
+    panel_query: Query<Entity, With<ConsolePanel>>,
+    mut console: ResMut<Console>,
+) {
+    let toggle = keyboard_input.just_pressed(KeyCode::Backquote);
+    let Ok(panel) = panel_query.single() else {
+        if toggle {
+            spawn_console(&mut commands);
+            keyboard_input.reset_all();
+            key_events.clear();
+        }
+        return;
+    };
+
+    if toggle || keyboard_input.just_pressed(KeyCode::Escape) {
+        commands.entity(panel).despawn();
+        console.input.clear();
+        keyboard_input.reset_all();
+        key_events.clear();
+        return;
+    }
+
+    for event in key_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input).trim().to_string();
+                if !line.is_empty() {
+                    console.submitted.push(line);
+                }
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Space => console.input.push(' '),
+            Key::Character(text) => console.input.push_str(text),
+            _ => {}
+        }
+    }
+    keyboard_input.reset_all();
+}
+
+pub fn run_console_commands(ecs: &mut World) {
+    let submitted = std::mem::take(&mut ecs.resource_mut::<Console>().submitted);
+    if submitted.is_empty() {
+        return;
+    }
+
+    ecs.resource_scope(|ecs, registry: Mut<ConsoleCommands>| {
+        for line in submitted {
+            let args: Vec<&str> = line.split_whitespace().collect();
+            let Some((name, args)) = args.split_first() else {
+                continue;
+            };
+            let result = match registry.commands.get(name) {
+                Some(command) => (command.run)(ecs, args),
+                None if *name == "help" => Ok(registry
+                    .commands
+                    .values()
+                    .map(|command| command.usage)
+                    .collect::<Vec<_>>()
+                    .join("\n")),
+                None => Err(format!("Unknown command \"{}\", try help", name)),
+            };
+
+            let mut console = ecs.resource_mut::<Console>();
+            console.print(&format!("> {}", line));
+            match result {
+                Ok(output) => console.print(&output),
+                Err(err) => console.print(&format!("Error: {}", err)),
+            }
+        }
+    });
+}
+
+pub fn update_console(console: Res<Console>, mut text_query: Query<&mut Text, With<ConsoleText>>) {
+    let Ok(mut text) = text_query.single_mut() else {
+        return;
+    };
+
+    let skip = console.lines.len().saturating_sub(CONSOLE_VISIBLE_LINES);
+    let mut lines: Vec<&str> = console
+        .lines
+        .iter()
+        .skip(skip)
+        .map(String::as_str)
+        .collect();
+    let prompt = format!("> {}_", console.input);
+    lines.push(&prompt);
+    text.0 = lines.join("\n");
+}
@@ -0,0 +1,71 @@
+use crate::{DebugOverlay, DebugOverlayText, ItemAnimation, WorldRes};
+use bevy::{
+    diagnostic::{DiagnosticPath, DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+pub const TICK_DURATION: DiagnosticPath = DiagnosticPath::const_new("tick_duration");
+pub const TICK_TILES_TIME: DiagnosticPath = DiagnosticPath::const_new("tick_tiles_time");
+pub const TILE_VISUALS_TIME: DiagnosticPath = DiagnosticPath::const_new("tile_visuals_time");
+
+pub fn toggle_debug_overlay(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    debug_overlay_query: Query<Entity, With<DebugOverlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        if let Ok(entity) = debug_overlay_query.single() {
+            commands.entity(entity).despawn();
+        } else {
+            commands.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(5.0),
+                    top: Val::Px(5.0),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                BorderRadius::all(Val::Px(10.0)),
+                BackgroundColor(Color::srgba(0.18, 0.2, 0.23, 0.85)),
+                DebugOverlay,
+                children![(
+                    Text::new(""),
+                    TextFont {
+                        font_size: 14.0,
+                        ..Default::default()
+                    },
+                    TextColor(Color::WHITE),
+                    DebugOverlayText,
+                )],
+            ));
+        }
+    }
+}
+
+pub fn update_debug_overlay(
+    mut debug_overlay_query: Query<&mut Text, With<DebugOverlayText>>,
+    diagnostics: Res<DiagnosticsStore>,
+    world: Res<WorldRes>,
+    item_animation_query: Query<(), With<ItemAnimation>>,
+) {
+    if let Ok(mut text) = debug_overlay_query.single_mut() {
+        let smoothed = |path: &DiagnosticPath| {
+            diagnostics
+                .get(path)
+                .and_then(|diagnostic| diagnostic.smoothed())
+                .unwrap_or(0.0)
+        };
+
+        text.0 = format!(
+            "FPS: {:.0}\nTick duration: {:.2} ms\ntick_tiles: {:.2} ms\nupdate_tile_visuals: {:.2} ms\nTiles: {}\nPending actions: {}\nLoaded chunks: {}\nItem animations: {}",
+            smoothed(&FrameTimeDiagnosticsPlugin::FPS),
+            smoothed(&TICK_DURATION),
+            smoothed(&TICK_TILES_TIME),
+            smoothed(&TILE_VISUALS_TIME),
+            world.tiles.len(),
+            world.actions.len(),
+            world.loaded_chunks.len(),
+            item_animation_query.iter().count(),
+        );
+    }
+}
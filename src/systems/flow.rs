@@ -0,0 +1,81 @@
+use crate::{FlowTrace, PhotoCamera, TILE_SIZE, UiState, WorldRes, utils::*};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+pub fn select_flow_trace(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &Transform), Without<PhotoCamera>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    world: Res<WorldRes>,
+    ui_state: Res<UiState>,
+    mut trace: ResMut<FlowTrace>,
+) {
+    if !keyboard_input.pressed(KeyCode::KeyT) || ui_state.blocks_gameplay() {
+        return;
+    }
+    if mouse_button_input.just_pressed(MouseButton::Right) {
+        *trace = FlowTrace::default();
+        return;
+    }
+    if !mouse_button_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Some(pos) = cursor_grid_position(window, camera, camera_transform) else {
+        return;
+    };
+
+    let pos = world.anchor_of(pos);
+    // Clicking the traced tile again, or an empty cell, turns the trace off.
+    if trace.start == Some(pos) || !world.tiles.contains_key(&pos) {
+        *trace = FlowTrace::default();
+        return;
+    }
+    trace.start = Some(pos);
+    trace.item = world.tiles.get(&pos).and_then(|(tile, _)| tile.get_item());
+}
+
+// The path is recomputed every frame so it follows routers and belts as they are rebuilt.
+pub fn draw_flow_trace(
+    trace: Res<FlowTrace>,
+    world: Res<WorldRes>,
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+) {
+    let Some(start) = trace.start else {
+        return;
+    };
+    if !world.tiles.contains_key(&start) {
+        return;
+    }
+
+    let points: Vec<Vec2> = trace_item_flow(&world, start, trace.item)
+        .into_iter()
+        .map(|pos| Vec2::new(pos.x as f32, pos.y as f32) * TILE_SIZE)
+        .collect();
+
+    let t = time.elapsed_secs();
+    let glow = 0.6 + 0.4 * (t * 4.0).sin();
+    let color = Color::srgba(0.3, 1.0, 0.9, glow);
+    let halo = Color::srgba(0.3, 1.0, 0.9, glow * 0.3);
+
+    gizmos.linestrip_2d(points.iter().copied(), color);
+    for point in points.iter() {
+        gizmos.circle_2d(*point, TILE_SIZE * 0.15, color);
+        gizmos.circle_2d(*point, TILE_SIZE * 0.3, halo);
+    }
+    gizmos.rect_2d(points[0], Vec2::splat(TILE_SIZE), color);
+
+    // A dot running along the path shows which way items travel.
+    if points.len() > 1 {
+        let progress = (t * 3.0) % (points.len() - 1) as f32;
+        let i = progress as usize;
+        let head = points[i].lerp(points[i + 1], progress.fract());
+        gizmos.circle_2d(head, TILE_SIZE * 0.2, Color::WHITE);
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{components::*, resources::*, tiles::*, types::*, utils::*};
+use bevy::{prelude::*, window::PrimaryWindow};
+use std::collections::HashMap;
+
+#[derive(Resource, Default)]
+pub struct Inspector {
+    pub selected: Option<Position>,
+}
+
+#[derive(Component)]
+pub struct InspectorText;
+
+#[derive(Component)]
+pub struct InspectorButton {
+    pub action: InspectorAction,
+}
+
+#[derive(Clone, Copy)]
+pub enum InspectorAction {
+    Rotate,
+    NextItem,
+    ClearItems,
+    AddTicks,
+    ResetTicks,
+    FillInventory,
+    EmptyInventory,
+}
+
+fn inspector_button(label: &str, action: InspectorAction) -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+        BorderRadius::all(Val::Px(5.0)),
+        Interaction::default(),
+        InspectorButton { action },
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+fn direction_mut(tile: &mut dyn Tile) -> Option<&mut Direction> {
+    let tile = tile.as_any_mut();
+    if tile.is::<Conveyor>() {
+        tile.downcast_mut::<Conveyor>()
+            .map(|tile| &mut tile.direction)
+    } else if tile.is::<Router>() {
+        tile.downcast_mut::<Router>()
+            .map(|tile| &mut tile.direction)
+    } else if tile.is::<Extractor>() {
+        tile.downcast_mut::<Extractor>()
+            .map(|tile| &mut tile.direction)
+    } else if tile.is::<Factory>() {
+        tile.downcast_mut::<Factory>()
+            .map(|tile| &mut tile.direction)
+    } else if tile.is::<Storage>() {
+        tile.downcast_mut::<Storage>()
+            .map(|tile| &mut tile.direction)
+    } else if tile.is::<Sensor>() {
+        tile.downcast_mut::<Sensor>()
+            .map(|tile| &mut tile.direction)
+    } else if tile.is::<ItemDisplay>() {
+        tile.downcast_mut::<ItemDisplay>()
+            .map(|tile| &mut tile.direction)
+    } else {
+        tile.downcast_mut::<Inserter>()
+            .map(|tile| &mut tile.direction)
+    }
+}
+
+fn apply_action(tile: &mut dyn Tile, action: InspectorAction) {
+    match action {
+        InspectorAction::Rotate => {
+            if let Some(direction) = direction_mut(tile) {
+                *direction = direction.shift(1);
+            }
+        }
+        InspectorAction::NextItem => {
+            let next = tile
+                .get_item()
+                .and_then(|item| Item::ALL.iter().position(|other| *other == item))
+                .map_or(0, |index| (index + 1) % Item::ALL.len());
+            tile.set_item(Some(Item::ALL[next]));
+        }
+        InspectorAction::ClearItems => {
+            tile.clear_items();
+        }
+        InspectorAction::AddTicks | InspectorAction::ResetTicks => {
+            let tile = tile.as_any_mut();
+            let ticks = if tile.is::<Factory>() {
+                tile.downcast_mut::<Factory>()
+                    .map(|factory| &mut factory.ticks)
+            } else {
+                tile.downcast_mut::<Core>().map(|core| &mut core.ticks)
+            };
+            let Some(ticks) = ticks else {
+                return;
+            };
+            *ticks = match action {
+                InspectorAction::AddTicks => *ticks + 10,
+                _ => 0,
+            };
+        }
+        InspectorAction::FillInventory => {
+            if let Some(factory) = tile.as_any_mut().downcast_mut::<Factory>() {
+                factory.inventory = factory.capacity().clone();
+            } else if let Some(storage) = tile.as_any_mut().downcast_mut::<Storage>() {
+                storage.inventory = storage.storage_type.capacity();
+            }
+        }
+        InspectorAction::EmptyInventory => {
+            if let Some(factory) = tile.as_any_mut().downcast_mut::<Factory>() {
+                factory.inventory.clear();
+            } else if let Some(storage) = tile.as_any_mut().downcast_mut::<Storage>() {
+                storage.inventory = 0;
+            }
+        }
+    }
+}
+
+// Why a factory isn't producing: the input holding it back, a full output slot, and how long
+// it has gone without finishing a recipe.
+fn factory_diagnostics(world: &WorldRes, pos: Position, factory: &Factory) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some((item, held, required)) = factory.limiting_input() {
+        lines.push(format!(
+            "Limiting input: {} ({}/{}){}",
+            item.name(),
+            held,
+            required,
+            if held >= required { ", satisfied" } else { "" }
+        ));
+    }
+    if factory.item.is_some() || !factory.byproducts.is_empty() {
+        lines.push("Output blocked: waiting for room downstream".to_string());
+    }
+    lines.push(match world.last_produced.get(&pos) {
+        Some(tick) => format!("Last produced {} ticks ago", world.tick_count - tick),
+        None => "Has not produced since the world was loaded".to_string(),
+    });
+    lines
+}
+
+pub fn toggle_inspector(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    panel_query: Query<Entity, With<InspectorPanel>>,
+    mut inspector: ResMut<Inspector>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    inspector.selected = None;
+    if let Ok(entity) = panel_query.single() {
+        commands.entity(entity).despawn();
+        return;
+    }
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(5.0),
+            top: Val::Px(60.0),
+            width: Val::Vw(28.0),
+            max_height: Val::Vh(85.0),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(10.0)),
+            row_gap: Val::Px(8.0),
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        BorderRadius::all(Val::Px(10.0)),
+        BackgroundColor(Color::srgba(0.18, 0.2, 0.23, 0.92)),
+        Interaction::default(),
+        ZIndex(150),
+        InspectorPanel,
+        children![
+            (
+                Node {
+                    display: Display::Flex,
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(6.0),
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                children![
+                    inspector_button("Rotate", InspectorAction::Rotate),
+                    inspector_button("Next item", InspectorAction::NextItem),
+                    inspector_button("Clear items", InspectorAction::ClearItems),
+                    inspector_button("Ticks +10", InspectorAction::AddTicks),
+                    inspector_button("Reset ticks", InspectorAction::ResetTicks),
+                    inspector_button("Fill", InspectorAction::FillInventory),
+                    inspector_button("Empty", InspectorAction::EmptyInventory),
+                ],
+            ),
+            (
+                Text::new(""),
+                TextFont {
+                    font_size: 13.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                InspectorText,
+            ),
+        ],
+    ));
+}
+
+pub fn select_inspected_tile(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &Transform), Without<PhotoCamera>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    panel_query: Query<&Interaction, With<InspectorPanel>>,
+    button_query: Query<&Interaction, With<InspectorButton>>,
+    mut inspector: ResMut<Inspector>,
+) {
+    let Ok(panel_interaction) = panel_query.single() else {
+        return;
+    };
+    if !mouse_button_input.just_pressed(MouseButton::Left)
+        || *panel_interaction != Interaction::None
+        || button_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.single(), camera_query.single())
+    else {
+        return;
+    };
+    inspector.selected = cursor_grid_position(window, camera, camera_transform);
+}
+
+pub fn handle_inspector_interaction(
+    button_query: Query<(&Interaction, &InspectorButton), Changed<Interaction>>,
+    inspector: Res<Inspector>,
+    mut world: ResMut<WorldRes>,
+) {
+    let Some(pos) = inspector.selected else {
+        return;
+    };
+
+    for (interaction, button) in button_query.iter() {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        if let Some((tile, _)) = world.tiles.get_mut(&pos) {
+            apply_action(tile.as_mut(), button.action);
+            world.forget_actions(pos);
+            world.mark_dirty(pos);
+        }
+    }
+}
+
+pub fn update_inspector(
+    world: Res<WorldRes>,
+    inspector: Res<Inspector>,
+    mut text_query: Query<&mut Text, With<InspectorText>>,
+) {
+    let Ok(mut text) = text_query.single_mut() else {
+        return;
+    };
+
+    let mut counts: HashMap<(u8, u8), usize> = HashMap::new();
+    for (_, tile_type) in world.tiles.values() {
+        *counts.entry(*tile_type).or_insert(0) += 1;
+    }
+    let mut counts: Vec<((u8, u8), usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut lines = vec![
+        "Inspector (F9 to close, click a tile to select)".to_string(),
+        format!(
+            "Tick {}, money ${}, seed {}",
+            world.tick_count, world.money, world.world_seed
+        ),
+        format!(
+            "Tiles {}, active {}, clocked {}, disabled {}",
+            world.tiles.len(),
+            world.schedule.active.len(),
+            world.schedule.clocked.len(),
+            world.disabled.len()
+        ),
+        format!(
+            "Pending actions {}, resolving {}, flowing lanes {}",
+            world.actions.len(),
+            world
+                .resolving
+                .as_ref()
+                .map_or(0, |progress| progress.actions.len()),
+            world.flowing.len()
+        ),
+        format!(
+            "Ghosts {}, deconstruction queue {}, chunks {}",
+            world.ghosts.len(),
+            world.deconstruction.len(),
+            world.loaded_chunks.len()
+        ),
+    ];
+    for (tile_type, count) in counts.iter().take(6) {
+        lines.push(format!("  {} x{}", get_tile_name(*tile_type), count));
+    }
+
+    match inspector.selected {
+        Some(pos) => {
+            lines.push(String::new());
+            match world.tiles.get(&pos) {
+                Some((tile, tile_type)) => {
+                    lines.push(format!(
+                        "{} {:?} at ({}, {})",
+                        get_tile_name(*tile_type),
+                        tile_type,
+                        pos.x,
+                        pos.y
+                    ));
+                    lines.push(format!(
+                        "Scheduled: {}, disabled: {}",
+                        world.schedule.active.contains(&pos)
+                            || world.schedule.clocked.contains(&pos),
+                        world.disabled.contains(&pos)
+                    ));
+                    if let Some(factory) = tile.as_any().downcast_ref::<Factory>() {
+                        lines.extend(factory_diagnostics(&world, pos, factory));
+                    }
+                    lines.push(format!("{:#?}", tile));
+                }
+                None => lines.push(format!("No tile at ({}, {})", pos.x, pos.y)),
+            }
+        }
+        None => lines.push("No tile selected".to_string()),
+    }
+
+    text.0 = lines.join("\n");
+}
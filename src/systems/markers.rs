@@ -0,0 +1,109 @@
+use crate::{
+    MARKER_EDGE_MARGIN, MARKER_ICONS, MARKER_SIZE, MarkerSprite, PhotoCamera, TILE_SIZE, UiState,
+    WorldRes, utils::*,
+};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+pub fn place_markers(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &Transform), Without<PhotoCamera>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut world: ResMut<WorldRes>,
+    ui_state: Res<UiState>,
+) {
+    if !keyboard_input.pressed(KeyCode::KeyM) || ui_state.blocks_gameplay() {
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Some(pos) = cursor_grid_position(window, camera, camera_transform) else {
+        return;
+    };
+
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        // Clicking an existing marker moves it on to the next icon.
+        let icon = world
+            .markers
+            .get(&pos)
+            .map_or(0, |icon| (*icon as usize + 1) % MARKER_ICONS.len());
+        world.markers.insert(pos, icon as u8);
+    } else if mouse_button_input.just_pressed(MouseButton::Right) {
+        world.markers.remove(&pos);
+    }
+}
+
+// Markers are drawn at a fixed on-screen size. Ones outside the view are pinned to the edge of
+// the screen with an arrow pointing towards them.
+pub fn update_markers(
+    mut commands: Commands,
+    world: Res<WorldRes>,
+    asset_server: Res<AssetServer>,
+    camera_query: Query<&Transform, (With<Camera2d>, Without<PhotoCamera>)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut marker_query: Query<
+        (Entity, &MarkerSprite, &mut Sprite, &mut Transform),
+        Without<Camera2d>,
+    >,
+    mut gizmos: Gizmos,
+) {
+    let (Ok(camera_transform), Ok(window)) = (camera_query.single(), windows.single()) else {
+        return;
+    };
+
+    let scale = camera_transform.scale.x;
+    let center = camera_transform.translation.truncate();
+    let half_size = (Vec2::new(window.width(), window.height()) / 2.0 - MARKER_EDGE_MARGIN)
+        .max(Vec2::ZERO)
+        * scale;
+    let view = Rect::from_center_half_size(center, half_size);
+
+    let mut drawn = Vec::new();
+    for (entity, marker, mut sprite, mut transform) in marker_query.iter_mut() {
+        let Some(icon) = world.markers.get(&marker.pos) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        drawn.push(marker.pos);
+
+        let image = asset_server.load(MARKER_ICONS[*icon as usize % MARKER_ICONS.len()].sprite());
+        if sprite.image != image {
+            sprite.image = image;
+        }
+        sprite.custom_size = Some(Vec2::splat(MARKER_SIZE * scale));
+
+        let target = Vec2::new(marker.pos.x as f32, marker.pos.y as f32) * TILE_SIZE;
+        let shown = target.clamp(view.min, view.max);
+        transform.translation = shown.extend(7.0);
+        if shown != target {
+            let direction = (target - shown).normalize_or_zero();
+            gizmos.arrow_2d(
+                shown + direction * MARKER_SIZE * 0.6 * scale,
+                shown + direction * MARKER_SIZE * 1.2 * scale,
+                Color::WHITE,
+            );
+        }
+    }
+
+    for (pos, icon) in world.markers.iter() {
+        if drawn.contains(pos) {
+            continue;
+        }
+        commands.spawn((
+            Sprite {
+                image: asset_server
+                    .load(MARKER_ICONS[*icon as usize % MARKER_ICONS.len()].sprite()),
+                custom_size: Some(Vec2::splat(MARKER_SIZE * scale)),
+                ..default()
+            },
+            Transform::from_translation(
+                (Vec2::new(pos.x as f32, pos.y as f32) * TILE_SIZE).extend(7.0),
+            ),
+            MarkerSprite { pos: *pos },
+        ));
+    }
+}
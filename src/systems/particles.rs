@@ -0,0 +1,104 @@
+use crate::{
+    ItemProduced, LOD_ZOOM_THRESHOLD, PARTICLE_BURST_SIZE, PARTICLE_LIFETIME, Placer,
+    ProductionEventKind, TILE_SIZE, components::*,
+};
+use bevy::{prelude::*, window::PrimaryWindow};
+use rand::{Rng, rng};
+
+pub fn spawn_production_particles(
+    mut commands: Commands,
+    mut production_events: EventReader<ItemProduced>,
+    placer: Res<Placer>,
+    camera_query: Query<&Transform, (With<Camera2d>, Without<PhotoCamera>)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let (Ok(camera_transform), Ok(window)) = (camera_query.single(), windows.single()) else {
+        production_events.clear();
+        return;
+    };
+    if placer.zoom_level < LOD_ZOOM_THRESHOLD {
+        production_events.clear();
+        return;
+    }
+
+    let half_size = Vec2::new(window.width(), window.height()) * camera_transform.scale.x / 2.0;
+    let view = Rect::from_center_half_size(
+        camera_transform.translation.truncate(),
+        half_size + Vec2::splat(TILE_SIZE),
+    );
+    let mut rng = rng();
+
+    for event in production_events.read() {
+        let center = Vec2::new(
+            event.pos.x as f32 * TILE_SIZE,
+            event.pos.y as f32 * TILE_SIZE,
+        );
+        if !view.contains(center) {
+            continue;
+        }
+
+        match event.kind {
+            ProductionEventKind::Factory(_) => {
+                for _ in 0..PARTICLE_BURST_SIZE {
+                    let spark = rng.random_bool(0.4);
+                    let angle = rng.random_range(0.0..std::f32::consts::TAU);
+                    let speed = rng.random_range(20.0..60.0);
+                    let color = if spark {
+                        Color::srgb(1.0, 0.8, 0.3)
+                    } else {
+                        Color::srgba(0.6, 0.6, 0.6, 0.8)
+                    };
+                    commands.spawn((
+                        Sprite {
+                            color,
+                            custom_size: Some(Vec2::splat(if spark { 3.0 } else { 6.0 })),
+                            ..Default::default()
+                        },
+                        Transform::from_translation(center.extend(5.0)),
+                        Particle {
+                            velocity: Vec2::from_angle(angle) * speed
+                                + if spark { Vec2::ZERO } else { Vec2::Y * 20.0 },
+                            growth: if spark { 0.0 } else { 1.5 },
+                            timer: Timer::from_seconds(PARTICLE_LIFETIME, TimerMode::Once),
+                        },
+                    ));
+                }
+            }
+            ProductionEventKind::Core(_) => {
+                commands.spawn((
+                    Sprite {
+                        color: Color::srgba(0.5, 0.8, 1.0, 0.6),
+                        custom_size: Some(Vec2::splat(TILE_SIZE)),
+                        ..Default::default()
+                    },
+                    Transform::from_translation(center.extend(5.0)),
+                    Particle {
+                        velocity: Vec2::ZERO,
+                        growth: 1.0,
+                        timer: Timer::from_seconds(PARTICLE_LIFETIME, TimerMode::Once),
+                    },
+                ));
+            }
+            ProductionEventKind::Extractor(_) => {}
+        }
+    }
+}
+
+pub fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particle_query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in particle_query.iter_mut() {
+        particle.timer.tick(time.delta());
+        if particle.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let t = particle.timer.fraction();
+        transform.translation += (particle.velocity * time.delta_secs()).extend(0.0);
+        transform.scale = Vec3::splat(1.0 + particle.growth * t);
+        sprite.color.set_alpha((1.0 - t) * 0.8);
+    }
+}
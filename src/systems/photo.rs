@@ -0,0 +1,186 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use crate::{
+    CHUNK_SIZE, ChunkPosition, Direction, IMAGE_SIZE, PHOTO_MAX_SIZE, PHOTO_RENDER_LAYER,
+    PHOTO_WARMUP_FRAMES, PhotoCamera, PhotoTerrain, Position, TILE_SIZE, TerrainTextures, WorldRes,
+    systems::{terrain_chunk_image, terrain_chunk_translation},
+    utils::*,
+};
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        view::{
+            RenderLayers,
+            screenshot::{Screenshot, save_to_disk},
+        },
+    },
+};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+pub fn photograph_base(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut world: ResMut<WorldRes>,
+    mut images: ResMut<Assets<Image>>,
+    mut terrain_textures: ResMut<TerrainTextures>,
+    asset_server: Res<AssetServer>,
+    photo_query: Query<(), With<PhotoCamera>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F2)
+        || !photo_query.is_empty()
+        || !terrain_textures.is_ready(&images)
+    {
+        return;
+    }
+
+    let Some((min, max)) = world.tiles.keys().fold(None, |bounds, pos| match bounds {
+        None => Some((*pos, *pos)),
+        Some((min, max)) => Some((
+            Position::new(min.x.min(pos.x), min.y.min(pos.y)),
+            Position::new(max.x.max(pos.x), max.y.max(pos.y)),
+        )),
+    }) else {
+        return;
+    };
+    let min = Position::new(min.x - 1, min.y - 1);
+    let max = Position::new(max.x + 1, max.y + 1);
+
+    let world_width = (max.x - min.x + 1) as f32 * TILE_SIZE;
+    let world_height = (max.y - min.y + 1) as f32 * TILE_SIZE;
+    let scale = (world_width.max(world_height) / PHOTO_MAX_SIZE).max(1.0);
+
+    let size = Extent3d {
+        width: (world_width / scale).ceil() as u32,
+        height: (world_height / scale).ceil() as u32,
+        ..default()
+    };
+    let mut image = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_DST
+        | TextureUsages::COPY_SRC
+        | TextureUsages::RENDER_ATTACHMENT;
+    let image = images.add(image);
+
+    let center = Vec3::new(
+        (min.x + max.x) as f32 * TILE_SIZE / 2.0,
+        (min.y + max.y) as f32 * TILE_SIZE / 2.0,
+        0.0,
+    );
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    // The photo renders its own copies on a separate layer, so sprites the main camera culls or
+    // streams out do not leave holes in it, and the main view never draws the copies.
+    let layer = RenderLayers::layer(PHOTO_RENDER_LAYER);
+
+    // Terrain is drawn from the same baked chunk images as the main view, generating any chunk
+    // that was never loaded, so the photo needs one sprite per chunk rather than per cell.
+    let (min_chunk, max_chunk) = (ChunkPosition::of(min), ChunkPosition::of(max));
+    let mut chunk_images = Vec::new();
+    for x in min_chunk.x..=max_chunk.x {
+        for y in min_chunk.y..=max_chunk.y {
+            let chunk_pos = ChunkPosition::new(x, y);
+            if world.chunk_in_bounds(chunk_pos) {
+                let image =
+                    terrain_chunk_image(&mut world, chunk_pos, &mut images, &mut terrain_textures);
+                chunk_images.push((chunk_pos, image));
+            }
+        }
+    }
+
+    commands.spawn((
+        Camera2d,
+        Camera {
+            target: RenderTarget::Image(image.clone().into()),
+            order: -1,
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..default()
+        },
+        Transform::from_translation(center).with_scale(Vec3::splat(scale)),
+        PhotoCamera {
+            image,
+            path: format!("base_photo_{}.png", timestamp),
+            frames: PHOTO_WARMUP_FRAMES,
+        },
+        layer.clone(),
+    ));
+
+    commands
+        .spawn((
+            PhotoTerrain,
+            Transform::default(),
+            Visibility::Visible,
+            layer.clone(),
+        ))
+        .with_children(|parent| {
+            for (chunk_pos, image) in chunk_images {
+                parent.spawn((
+                    Sprite {
+                        image,
+                        custom_size: Some(Vec2::splat(CHUNK_SIZE as f32 * TILE_SIZE)),
+                        ..default()
+                    },
+                    Transform::from_translation(terrain_chunk_translation(chunk_pos)),
+                    layer.clone(),
+                ));
+            }
+
+            // Tile sprites only exist for the chunks around the main camera and are hidden when
+            // off-screen, so the photo spawns its own copies for everything inside its bounds.
+            for (pos, (tile, tile_type)) in world.tiles.iter() {
+                let mut sprite =
+                    Sprite::from_image(asset_server.load(get_tile_texture(*tile_type)));
+                sprite.custom_size = footprint_sprite_size(*tile_type);
+                let rotation = match get_tile_direction(tile.as_ref()) {
+                    Direction::Up => Quat::IDENTITY,
+                    Direction::Down => Quat::from_rotation_z(PI),
+                    Direction::Left => Quat::from_rotation_z(FRAC_PI_2),
+                    Direction::Right => Quat::from_rotation_z(-FRAC_PI_2),
+                };
+                parent.spawn((
+                    sprite,
+                    Transform {
+                        translation: (Vec2::new(pos.x as f32, pos.y as f32) * TILE_SIZE
+                            + footprint_offset(*tile_type))
+                        .extend(0.0),
+                        rotation,
+                        scale: Vec3::splat(TILE_SIZE / IMAGE_SIZE),
+                    },
+                    layer.clone(),
+                ));
+            }
+        });
+}
+
+pub fn capture_base_photo(
+    mut commands: Commands,
+    mut photo_query: Query<(Entity, &mut PhotoCamera)>,
+    terrain_query: Query<Entity, With<PhotoTerrain>>,
+) {
+    for (entity, mut photo) in photo_query.iter_mut() {
+        if photo.frames > 0 {
+            photo.frames -= 1;
+            continue;
+        }
+
+        commands
+            .spawn(Screenshot::image(photo.image.clone()))
+            .observe(save_to_disk(photo.path.clone()));
+        commands.entity(entity).despawn();
+        for terrain in terrain_query.iter() {
+            commands.entity(terrain).despawn();
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{
+    Leaderboard, ObjectiveGoal, ProductionStats, Scenario, ScenarioText, Settings, TICK_LENGTH,
+    WorldRes, systems::ui::spawn_dialog, types::*, utils::*,
+};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+pub fn advance_scenario(
+    mut commands: Commands,
+    mut world: ResMut<WorldRes>,
+    stats: Res<ProductionStats>,
+    mut settings: ResMut<Settings>,
+    mut last_totals: Local<HashMap<Item, u64>>,
+) {
+    let produced: HashMap<Item, u64> = stats
+        .totals
+        .iter()
+        .map(|(item, total)| (*item, total - last_totals.get(item).unwrap_or(&0)))
+        .collect();
+    last_totals.clone_from(&stats.totals);
+
+    let Some(progress) = world.scenario.as_ref() else {
+        return;
+    };
+    let Some(scenario) = Scenario::find(&progress.id) else {
+        return;
+    };
+    let Some(objective) = scenario.objectives.get(progress.objective) else {
+        return;
+    };
+
+    let complete = match objective.goal {
+        ObjectiveGoal::Produce { item, amount } => {
+            let gained = *produced.get(&item).unwrap_or(&0);
+            let Some(progress) = world.scenario.as_mut() else {
+                return;
+            };
+            progress.produced += gained;
+            progress.produced >= amount
+        }
+        ObjectiveGoal::Money(amount) => world.money >= amount,
+        ObjectiveGoal::Place { tile_type, count } => {
+            world
+                .tiles
+                .values()
+                .filter(|(_, id)| *id == tile_type)
+                .count()
+                >= count as usize
+        }
+    };
+
+    if !complete {
+        return;
+    }
+
+    let Some(progress) = world.scenario.as_mut() else {
+        return;
+    };
+    progress.objective += 1;
+    progress.produced = 0;
+
+    if progress.objective < scenario.objectives.len() {
+        let mut lines = vec![objective.description.clone()];
+        for tile_type in objective.unlocks.iter() {
+            lines.push(format!("Unlocked {}", get_tile_name(*tile_type)));
+        }
+        spawn_dialog(&mut commands, "Objective complete", &lines);
+    } else {
+        if !settings.completed_scenarios.contains(&scenario.id) {
+            settings.completed_scenarios.push(scenario.id.clone());
+            if let Err(err) = settings.save("settings.ron") {
+                eprintln!("Error saving settings: {}", err);
+            }
+        }
+        spawn_dialog(
+            &mut commands,
+            "Scenario complete",
+            &[format!("You completed {}.", scenario.name)],
+        );
+    }
+}
+
+pub fn advance_daily_challenge(
+    mut commands: Commands,
+    mut world: ResMut<WorldRes>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(daily) = world.daily.as_ref() else {
+        return;
+    };
+    if daily.completed_at.is_some()
+        || daily
+            .contract_progress(&world.lifetime)
+            .iter()
+            .any(|(_, produced, amount)| produced < amount)
+    {
+        return;
+    }
+
+    let ticks = world.tick_count;
+    let day = daily.day;
+    let date = daily.date();
+    if let Some(daily) = world.daily.as_mut() {
+        daily.completed_at = Some(ticks);
+    }
+
+    let best = match settings.daily_results.iter_mut().find(|(d, _)| *d == day) {
+        Some((_, best)) => {
+            *best = (*best).min(ticks);
+            *best
+        }
+        None => {
+            settings.daily_results.push((day, ticks));
+            ticks
+        }
+    };
+    if let Err(err) = settings.save("settings.ron") {
+        eprintln!("Error saving settings: {}", err);
+    }
+
+    spawn_dialog(
+        &mut commands,
+        "Daily challenge complete",
+        &[
+            format!("You finished the {} challenge in {} ticks.", date, ticks),
+            format!("Your best for the day is {} ticks.", best),
+        ],
+    );
+}
+
+pub fn advance_time_trial(
+    mut commands: Commands,
+    mut world: ResMut<WorldRes>,
+    mut leaderboard: ResMut<Leaderboard>,
+) {
+    let ticks = world.tick_count;
+    let seed = world.world_seed;
+    let Some(trial) = world.time_trial.as_mut() else {
+        return;
+    };
+    if trial.completed_at.is_some() || trial.delivered < trial.amount {
+        return;
+    }
+    trial.completed_at = Some(ticks);
+
+    let place = leaderboard.record(seed, ticks);
+    if let Err(err) = leaderboard.save("leaderboard.ffs") {
+        eprintln!("Error saving leaderboard: {}", err);
+    }
+
+    let mut lines = vec![format!("Finished in {} ticks on seed {}.", ticks, seed)];
+    if let Some(place) = place {
+        lines.push(format!("That's #{} on this seed.", place));
+    }
+    for (index, entry) in leaderboard.times(seed).iter().enumerate() {
+        lines.push(format!("{}. {} ticks", index + 1, entry.ticks));
+    }
+    spawn_dialog(&mut commands, "Time trial complete", &lines);
+}
+
+pub fn update_scenario_widget(
+    world: Res<WorldRes>,
+    mut text_query: Query<(&mut Text, &mut Node), With<ScenarioText>>,
+) {
+    let Ok((mut text, mut node)) = text_query.single_mut() else {
+        return;
+    };
+
+    if let Some(daily) = world.daily.as_ref() {
+        node.display = Display::Flex;
+        let mut lines = vec![format!("Daily challenge {}", daily.date())];
+        match daily.completed_at {
+            Some(ticks) => lines.push(format!("Complete in {} ticks", ticks)),
+            None => {
+                let seconds = (world.tick_count.max(0) as f32 * TICK_LENGTH) as u64;
+                lines.push(format!("Time: {}:{:02}", seconds / 60, seconds % 60));
+                for (item, produced, amount) in daily.contract_progress(&world.lifetime) {
+                    lines.push(format!("{}: {}/{}", item.name(), produced, amount));
+                }
+            }
+        }
+        text.0 = lines.join("\n");
+        return;
+    }
+
+    if let Some(trial) = world.time_trial.as_ref() {
+        node.display = Display::Flex;
+        let ticks = trial.completed_at.unwrap_or(world.tick_count).max(0);
+        let seconds = (ticks as f32 * TICK_LENGTH) as u64;
+        text.0 = format!(
+            "Time trial (seed {}){}\nTime: {}:{:02}\n{} delivered to Portals: {}/{}",
+            world.world_seed,
+            if trial.completed_at.is_some() {
+                ", finished"
+            } else {
+                ""
+            },
+            seconds / 60,
+            seconds % 60,
+            trial.item.name(),
+            trial.delivered.min(trial.amount),
+            trial.amount
+        );
+        return;
+    }
+
+    let Some(progress) = world.scenario.as_ref() else {
+        node.display = Display::None;
+        return;
+    };
+    let Some(scenario) = Scenario::find(&progress.id) else {
+        node.display = Display::None;
+        return;
+    };
+    node.display = Display::Flex;
+
+    text.0 = match scenario.objectives.get(progress.objective) {
+        Some(objective) => {
+            let status = match objective.goal {
+                ObjectiveGoal::Produce { amount, .. } => {
+                    format!(" ({}/{})", progress.produced.min(amount), amount)
+                }
+                ObjectiveGoal::Money(amount) => {
+                    format!(" (${}/${})", world.money.min(amount), amount)
+                }
+                ObjectiveGoal::Place { tile_type, count } => {
+                    let placed = world
+                        .tiles
+                        .values()
+                        .filter(|(_, id)| *id == tile_type)
+                        .count() as u32;
+                    format!(" ({}/{})", placed.min(count), count)
+                }
+            };
+            format!(
+                "{}\nObjective {}/{}: {}{}",
+                scenario.name,
+                progress.objective + 1,
+                scenario.objectives.len(),
+                objective.description,
+                status
+            )
+        }
+        None => format!("{}\nAll objectives complete", scenario.name),
+    };
+}
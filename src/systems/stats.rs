@@ -0,0 +1,21 @@
+use crate::ProductionStats;
+use bevy::prelude::*;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+pub fn export_production_stats(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    stats: Res<ProductionStats>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let path = format!("production_stats_{}.csv", timestamp);
+
+        match stats.export_csv(&path) {
+            Ok(()) => info!("Production stats exported to {}", path),
+            Err(err) => eprintln!("Error exporting production stats: {}", err),
+        }
+    }
+}
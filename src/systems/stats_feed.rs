@@ -0,0 +1,50 @@
+use crate::{ProductionStats, TickFinished, WorldRes};
+use bevy::prelude::*;
+use std::fs::File;
+use std::io::{self, Write};
+
+// Only used in builds with the `stats_feed` feature. Each finished tick appends one JSON line to
+// stats_feed.jsonl for external dashboards to follow.
+#[derive(Resource, Default)]
+pub struct StatsFeed {
+    file: Option<File>,
+}
+
+fn stats_feed_line(tick: i32, world: &WorldRes, stats: &ProductionStats) -> String {
+    let mut produced: Vec<String> = stats
+        .totals
+        .iter()
+        .map(|(item, count)| format!("\"{}\":{}", item.name(), count))
+        .collect();
+    produced.sort();
+
+    format!(
+        "{{\"tick\":{},\"money\":{},\"tiles\":{},\"produced\":{{{}}}}}",
+        tick,
+        world.money,
+        world.tiles.len(),
+        produced.join(",")
+    )
+}
+
+pub fn write_stats_feed(
+    mut ticks_finished: EventReader<TickFinished>,
+    world: Res<WorldRes>,
+    stats: Res<ProductionStats>,
+    mut feed: ResMut<StatsFeed>,
+) {
+    for finished in ticks_finished.read() {
+        let line = stats_feed_line(finished.tick, &world, &stats);
+        let result = match feed.file.as_mut() {
+            Some(file) => writeln!(file, "{}", line),
+            None => File::create("stats_feed.jsonl").and_then(|mut file| {
+                writeln!(file, "{}", line)?;
+                feed.file = Some(file);
+                Ok::<(), io::Error>(())
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("Error writing stats feed: {}", err);
+        }
+    }
+}
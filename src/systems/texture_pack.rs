@@ -0,0 +1,68 @@
+use crate::{constants::*, resources::*, systems::ui::spawn_dialog};
+use bevy::{asset::io::embedded::EmbeddedAssetRegistry, prelude::*};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub fn list_texture_packs() -> Vec<String> {
+    let mut packs: Vec<String> = fs::read_dir(TEXTURE_PACK_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    packs.sort();
+    packs
+}
+
+// Overwrites embedded textures with the selected pack's files. Has to run before anything loads a
+// texture, since the asset server keeps whatever it loaded first.
+pub fn apply_texture_pack(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    registry: Res<EmbeddedAssetRegistry>,
+) {
+    let Some(name) = &settings.texture_pack else {
+        return;
+    };
+
+    let root = Path::new(TEXTURE_PACK_DIR).join(name);
+    let mut files = Vec::new();
+    if let Err(err) = collect_files(&root, &mut files) {
+        spawn_dialog(
+            &mut commands,
+            "Texture pack not loaded",
+            &[
+                format!("{} could not be read: {}", root.display(), err),
+                "Using the built-in textures.".to_string(),
+            ],
+        );
+        return;
+    }
+
+    for path in files {
+        let Ok(relative) = path.strip_prefix(&root) else {
+            continue;
+        };
+        match fs::read(&path) {
+            Ok(bytes) => {
+                registry.insert_asset(path.clone(), &Path::new("textures").join(relative), bytes)
+            }
+            Err(err) => eprintln!("Error reading {}: {}", path.display(), err),
+        }
+    }
+}
@@ -1,27 +1,74 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::f32::consts::{FRAC_PI_2, PI};
+use std::time::Duration;
 
-use crate::{components::*, constants::*, resources::*, tiles::*, types::*, utils::*};
+use crate::{
+    components::*,
+    constants::*,
+    resources::*,
+    systems::TILE_VISUALS_TIME,
+    systems::ui::{spawn_core_menu, spawn_dialog, spawn_factory_menu, spawn_router_menu},
+    tiles::*,
+    types::*,
+    utils::*,
+};
+use bevy::audio::Volume;
 use bevy::color::palettes::css;
+use bevy::diagnostic::Diagnostics;
+use bevy::ecs::system::SystemParam;
 use bevy::input::mouse::MouseWheel;
+use bevy::platform::time::Instant;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+// Held for the upgrade, deconstruction, clear items, zone, marker and flow trace tools, which take
+// the mouse over from placing and removing tiles.
+fn tool_modifier_held(keyboard_input: &ButtonInput<KeyCode>) -> bool {
+    keyboard_input.any_pressed([
+        KeyCode::KeyU,
+        KeyCode::KeyX,
+        KeyCode::KeyK,
+        KeyCode::KeyZ,
+        KeyCode::KeyM,
+        KeyCode::KeyT,
+    ])
+}
+
+// What placing tiles reads besides the cursor: the hotkeys, the frame time, and the menus and
+// tools that take the mouse over.
+#[derive(SystemParam)]
+pub struct PlacerContext<'w> {
+    hotkeys: Res<'w, Hotkeys>,
+    ui_state: Res<'w, UiState>,
+    library: Res<'w, BlueprintLibrary>,
+    upgrade_planner: Res<'w, UpgradePlanner>,
+    time: Res<'w, Time>,
+}
+
 pub fn manage_tiles(
-    windows: Query<&mut Window, With<PrimaryWindow>>,
-    mut camera_query: Query<(&Camera, &mut Transform)>,
+    input: CursorInput,
     mut mouse_wheel_events: EventReader<MouseWheel>,
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
     mut placer: ResMut<Placer>,
     mut world: ResMut<WorldRes>,
     asset_server: Res<AssetServer>,
     mut commands: Commands,
-    hotkeys: Res<Hotkeys>,
-    core_menu_query: Query<(), With<CoreMenu>>,
-    inventory_query: Query<Entity, With<Inventory>>,
+    context: PlacerContext,
 ) {
-    if inventory_query.is_empty() && core_menu_query.is_empty() {
+    let PlacerContext {
+        hotkeys,
+        ui_state,
+        library,
+        upgrade_planner,
+        time,
+    } = context;
+    let CursorInput {
+        windows,
+        camera_query,
+        mouse_button_input,
+        keyboard_input,
+    } = input;
+
+    if !ui_state.blocks_gameplay() {
         if keyboard_input.just_pressed(KeyCode::Digit0) {
             if let Some(&tile_type) = hotkeys.mappings.get(&0) {
                 placer.tile_type = tile_type;
@@ -66,13 +113,13 @@ pub fn manage_tiles(
     }
 
     for event in mouse_wheel_events.read() {
-        if placer.tile_type == (0, 1) && inventory_query.is_empty() && core_menu_query.is_empty() {
+        // Z + Scroll picks the zone colour instead.
+        if keyboard_input.pressed(KeyCode::KeyZ) {
+            continue;
+        }
+        if placer.tile_type == (0, 1) && !ui_state.blocks_gameplay() {
             let zoom_delta = event.y * ZOOM_SPEED;
             placer.zoom_level = (placer.zoom_level + zoom_delta).clamp(MIN_ZOOM, MAX_ZOOM);
-
-            if let Ok((_, mut transform)) = camera_query.single_mut() {
-                transform.scale = Vec3::splat(1.0 / placer.zoom_level);
-            }
         } else {
             placer.direction = match (placer.direction, event.y.partial_cmp(&0.0)) {
                 (Direction::Up, Some(std::cmp::Ordering::Less)) => Direction::Right,
@@ -92,24 +139,13 @@ pub fn manage_tiles(
     let Ok(window) = windows.single() else {
         return;
     };
-    if let Some(screen_pos) = window.cursor_position() {
-        if let Ok((camera, camera_transform)) = camera_query.single() {
+    if let Ok((camera, camera_transform)) = camera_query.single() {
+        if let Some(cursor_pos) = cursor_grid_position(window, camera, camera_transform) {
             if let Some(preview_entity) = placer.preview_entity {
                 commands.entity(preview_entity).despawn();
             }
-            if inventory_query.is_empty() && core_menu_query.is_empty() {
-                let window_size = Vec2::new(window.width(), window.height());
-
-                let mut ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
-                ndc.y *= -1.0;
-                let ndc_to_world =
-                    camera_transform.compute_matrix() * camera.clip_from_view().inverse();
-                let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
-                let world_pos: Vec2 = world_pos.truncate();
-
-                let grid_x = (world_pos.x / TILE_SIZE).round() as i32;
-                let grid_y = (world_pos.y / TILE_SIZE).round() as i32;
-                let pos = Position::new(grid_x, grid_y);
+            if !ui_state.blocks_gameplay() {
+                let pos = world.anchor_of(cursor_pos);
 
                 let texture_path = get_tile_texture(placer.tile_type);
 
@@ -117,7 +153,22 @@ pub fn manage_tiles(
                     .spawn((
                         Sprite {
                             image: asset_server.load(texture_path),
-                            color: Color::srgba(1.0, 1.0, 1.0, 0.5),
+                            color: if placer.tile_type == (0, 1)
+                                || is_valid_placement(
+                                    &world,
+                                    placer.tile_type,
+                                    pos,
+                                    placer.direction,
+                                ) {
+                                if can_afford_placement(&world, placer.tile_type, pos) {
+                                    Color::srgba(1.0, 1.0, 1.0, 0.5)
+                                } else {
+                                    Color::srgba(0.5, 0.5, 0.5, 0.5)
+                                }
+                            } else {
+                                Color::srgba(1.0, 0.4, 0.4, 0.5)
+                            },
+                            custom_size: footprint_sprite_size(placer.tile_type),
                             ..Default::default()
                         },
                         Transform {
@@ -125,9 +176,10 @@ pub fn manage_tiles(
                                 pos.x as f32 * TILE_SIZE,
                                 pos.y as f32 * TILE_SIZE,
                                 5.0,
-                            ),
+                            ) + footprint_offset(placer.tile_type).extend(0.0),
                             scale: Vec3::splat(TILE_SIZE / IMAGE_SIZE),
-                            rotation: match placer.direction {
+                            rotation: match placement_direction(placer.tile_type, placer.direction)
+                            {
                                 Direction::Up => Quat::IDENTITY,
                                 Direction::Down => Quat::from_rotation_z(PI),
                                 Direction::Left => Quat::from_rotation_z(FRAC_PI_2),
@@ -145,426 +197,112 @@ pub fn manage_tiles(
     }
 
     if mouse_button_input.pressed(MouseButton::Left)
-        && inventory_query.is_empty()
-        && core_menu_query.is_empty()
+        && !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+        && !keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+        && !tool_modifier_held(&keyboard_input)
+        && upgrade_planner.selection_start.is_none()
+        && !placer.planning
+        && !ui_state.blocks_gameplay()
+        && library.active.is_none()
     {
         if let Ok(window) = windows.single() {
-            if let Some(screen_pos) = window.cursor_position() {
-                if let Ok((camera, camera_transform)) = camera_query.single() {
-                    let window_size = Vec2::new(window.width(), window.height());
-
-                    let mut ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
-                    ndc.y *= -1.0;
-                    let ndc_to_world =
-                        camera_transform.compute_matrix() * camera.clip_from_view().inverse();
-                    let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
-                    let world_pos: Vec2 = world_pos.truncate();
-
-                    let grid_x = (world_pos.x / TILE_SIZE).round() as i32;
-                    let grid_y = (world_pos.y / TILE_SIZE).round() as i32;
-                    let pos = Position::new(grid_x, grid_y);
+            if let Ok((camera, camera_transform)) = camera_query.single() {
+                if let Some(cursor_pos) = cursor_grid_position(window, camera, camera_transform) {
+                    let pos = world.anchor_of(cursor_pos);
                     let tile_type = placer.tile_type;
                     let direction = placer.direction;
-                    if pos != Position::new(0, 0) {
-                        if world.tiles.contains_key(&pos) {
+                    let clicked = mouse_button_input.just_pressed(MouseButton::Left);
+                    let second_click = clicked && placer.pending_replace == Some(pos);
+                    if clicked {
+                        placer.pending_replace = None;
+                    }
+                    // Holding the button only places again once the cursor reaches another cell
+                    // or the repeat delay has passed.
+                    let repeat = clicked
+                        || placer.last_placed != Some(pos)
+                        || placer.repeat_timer >= PLACE_REPEAT_DELAY;
+                    if repeat {
+                        placer.last_placed = Some(pos);
+                        placer.repeat_timer = 0.0;
+                    } else {
+                        placer.repeat_timer += time.delta_secs();
+                    }
+                    if repeat && world.is_protected(pos) {
+                        if ui_state.can_open(UiPanel::Core)
+                            && let Some((tile, _)) = world.tiles.get(&pos)
+                            && let Some(core) = tile.as_any().downcast_ref::<Core>()
+                        {
+                            spawn_core_menu(&mut commands, pos, core);
+                        }
+                    } else if repeat
+                        && (tile_type == (0, 1)
+                            || is_valid_placement(&world, tile_type, pos, direction))
+                    {
+                        if tile_type == (0, 1) {
+                            if mouse_button_input.just_pressed(MouseButton::Left)
+                                && let Some((tile, id)) = world.tiles.get(&pos)
+                                && let Some(factory) = tile.as_any().downcast_ref::<Factory>()
+                            {
+                                spawn_factory_menu(&mut commands, &asset_server, pos, factory, *id);
+                            }
+                            if mouse_button_input.just_pressed(MouseButton::Left)
+                                && let Some((tile, id)) = world.tiles.get(&pos)
+                                && let Some(router) = tile.as_any().downcast_ref::<Router>()
+                            {
+                                spawn_router_menu(&mut commands, pos, router, *id);
+                            }
+                            if mouse_button_input.just_pressed(MouseButton::Left)
+                                && let Some((tile, _)) = world.tiles.get_mut(&pos)
+                                && let Some(display) =
+                                    tile.as_any_mut().downcast_mut::<ItemDisplay>()
+                            {
+                                display.count = 0;
+                                world.mark_chunk_dirty(pos);
+                            }
+                        } else if world.tiles.contains_key(&pos) {
                             let current_tile_id =
                                 world.tiles.get(&pos).map(|(_, id)| *id).unwrap_or((0, 1));
 
-                            if *world.resources.get(&tile_type).unwrap_or(&0) >= 1
+                            // A different tile is only replaced by clicking it twice, so dragging
+                            // or misclicking can't wipe out a configured machine.
+                            if current_tile_id != tile_type && !second_click {
+                                if clicked {
+                                    placer.pending_replace = Some(pos);
+                                }
+                            } else if *world.resources.get(&tile_type).unwrap_or(&0) >= 1
                                 || placer.tile_type == current_tile_id
                             {
                                 *world.resources.entry(current_tile_id).or_insert(0) += 1;
                                 *world.resources.entry(tile_type).or_insert(0) -= 1;
+                                if current_tile_id != tile_type {
+                                    world.lifetime.tiles_removed += 1;
+                                    world.lifetime.tiles_placed += 1;
+                                }
 
                                 let new_tile = get_new_tile(tile_type, pos, direction);
 
+                                world.vacate(pos, current_tile_id);
+                                world.occupy(pos, tile_type);
+                                if current_tile_id != tile_type {
+                                    world.tile_removed(pos, current_tile_id);
+                                    world.tile_placed(pos, tile_type);
+                                }
                                 if let Some(entry) = world.tiles.get_mut(&pos) {
                                     *entry = new_tile;
-                                    let new = world
-                                        .actions
-                                        .clone()
-                                        .into_iter()
-                                        .filter(|action| match action {
-                                            Action::Move(position, _, _) => *position != pos,
-                                            Action::Produce(position) => *position != pos,
-                                            Action::MoveRouter(position, _, _, _) => {
-                                                *position != pos
-                                            }
-                                            Action::Teleport(position, _) => *position != pos,
-                                            Action::IncreaseTicks(position) => *position != pos,
-                                        })
-                                        .collect();
-
-                                    world.actions = new;
+                                    world.mark_chunk_dirty(pos);
+                                    world.forget_actions(pos);
                                 }
                             }
                         } else {
                             if *world.resources.get(&tile_type).unwrap_or(&0) >= 1 {
                                 *world.resources.entry(tile_type).or_insert(0) -= 1;
+                                world.lifetime.tiles_placed += 1;
 
                                 let new_tile = get_new_tile(tile_type, pos, direction);
 
                                 world.tiles.insert(pos, new_tile);
-
-                                let new = world
-                                    .actions
-                                    .clone()
-                                    .into_iter()
-                                    .filter(|action| match action {
-                                        Action::Move(position, _, _) => *position != pos,
-                                        Action::Produce(position) => *position != pos,
-                                        Action::MoveRouter(position, _, _, _) => *position != pos,
-                                        Action::Teleport(position, _) => *position != pos,
-                                        Action::IncreaseTicks(position) => *position != pos,
-                                    })
-                                    .collect();
-
-                                world.actions = new;
-
-                                commands
-                                    .spawn((
-                                        Sprite::from_image(
-                                            asset_server.load(get_tile_texture(tile_type)),
-                                        ),
-                                        Transform {
-                                            translation: Vec3::new(
-                                                pos.x as f32 * TILE_SIZE,
-                                                pos.y as f32 * TILE_SIZE,
-                                                0.0,
-                                            ),
-                                            scale: Vec3::splat(TILE_SIZE / IMAGE_SIZE),
-                                            ..Default::default()
-                                        },
-                                        TileSprite { pos },
-                                    ))
-                                    .with_children(|parent| {
-                                        parent.spawn((
-                                            Sprite::from_image(
-                                                asset_server
-                                                    .load("embedded://textures/items/none.png"),
-                                            ),
-                                            Transform::from_scale(Vec3::splat(0.5)),
-                                        ));
-                                    });
-                            }
-                        }
-                    } else {
-                        if core_menu_query.is_empty() {
-                            if let Some(tile) = world.tiles.get(&pos) {
-                                if let Some(core) = tile.0.as_any().downcast_ref::<Core>() {
-                                    commands.spawn((
-                                        Node {
-                                            width: Val::Vw(80.0),
-                                            height: Val::Vh(80.0),
-                                            position_type: PositionType::Absolute,
-                                            left: Val::Vw(10.0),
-                                            top: Val::Vh(10.0),
-                                            display: Display::Flex,
-                                            flex_direction: FlexDirection::Column,
-                                            padding: UiRect::all(Val::Px(20.0)),
-                                            ..default()
-                                        },
-                                        BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
-                                        BorderRadius::all(Val::Px(10.0)),
-                                        CoreMenu {
-                                            position: pos,
-                                            selected_category: 1,
-                                        },
-                                        children![
-                                            (
-                                                Node {
-                                                    width: Val::Percent(100.0),
-                                                    height: Val::Px(40.0),
-                                                    margin: UiRect::bottom(Val::Px(20.0)),
-                                                    align_items: AlignItems::Center,
-                                                    justify_content: JustifyContent::Center,
-                                                    ..default()
-                                                },
-                                                children![(
-                                                    Text::new("Core Configuration"),
-                                                    TextFont {
-                                                        font_size: 24.0,
-                                                        ..Default::default()
-                                                    },
-                                                    TextColor(Color::WHITE)
-                                                )],
-                                            ),
-                                            (
-                                                Node {
-                                                    width: Val::Percent(100.0),
-                                                    height: Val::Px(60.0),
-                                                    margin: UiRect::bottom(Val::Px(20.0)),
-                                                    display: Display::Flex,
-                                                    flex_direction: FlexDirection::Column,
-                                                    ..default()
-                                                },
-                                                children![
-                                                    (
-                                                        Text::new(format!(
-                                                            "Current production: {} ({})",
-                                                            get_tile_name(core.tile_id),
-                                                            format_tile_id(core.tile_id)
-                                                        )),
-                                                        TextFont {
-                                                            font_size: 16.0,
-                                                            ..Default::default()
-                                                        },
-                                                        TextColor(Color::WHITE),
-                                                        Node {
-                                                            margin: UiRect::bottom(Val::Px(10.0)),
-                                                            ..Default::default()
-                                                        }
-                                                    ),
-                                                    (
-                                                        Text::new(format!(
-                                                            "Progress: {}/{} seconds",
-                                                            core.ticks, core.interval
-                                                        )),
-                                                        TextFont {
-                                                            font_size: 16.0,
-                                                            ..Default::default()
-                                                        },
-                                                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
-                                                        Name::new("core_progress")
-                                                    )
-                                                ],
-                                            ),
-                                            (
-                                                Node {
-                                                    width: Val::Percent(100.0),
-                                                    height: Val::Percent(100.0),
-                                                    display: Display::Flex,
-                                                    flex_direction: FlexDirection::Row,
-                                                    ..Default::default()
-                                                },
-                                                children![
-                                                    (
-                                                        Node {
-                                                            width: Val::Percent(25.0),
-                                                            height: Val::Percent(100.0),
-                                                            display: Display::Flex,
-                                                            flex_direction: FlexDirection::Column,
-                                                            padding: UiRect::all(Val::Px(10.0)),
-                                                            row_gap: Val::Px(10.0),
-                                                            ..Default::default()
-                                                        },
-                                                        BackgroundColor(Color::srgb(
-                                                            0.14, 0.16, 0.19
-                                                        )),
-                                                        BorderRadius::all(Val::Px(10.0)),
-                                                        children![
-                                                            (
-                                                                Button,
-                                                                Node {
-                                                                    width: Val::Percent(100.0),
-                                                                    height: Val::Px(50.0),
-                                                                    align_items: AlignItems::Center,
-                                                                    justify_content:
-                                                                        JustifyContent::Center,
-                                                                    ..Default::default()
-                                                                },
-                                                                BackgroundColor(Color::srgb(
-                                                                    0.3, 0.5, 0.7
-                                                                )),
-                                                                CoreCategory { category: 1 },
-                                                                Interaction::default(),
-                                                                BorderRadius::all(Val::Px(10.0)),
-                                                                children![(
-                                                                    Text::new("1: Portals"),
-                                                                    TextFont {
-                                                                        font_size: 18.0,
-                                                                        ..Default::default()
-                                                                    },
-                                                                    TextColor(Color::WHITE),
-                                                                    TextLayout {
-                                                                        justify:
-                                                                            JustifyText::Center,
-                                                                        ..Default::default()
-                                                                    }
-                                                                )],
-                                                            ),
-                                                            (
-                                                                Button,
-                                                                Node {
-                                                                    width: Val::Percent(100.0),
-                                                                    height: Val::Px(50.0),
-                                                                    align_items: AlignItems::Center,
-                                                                    justify_content:
-                                                                        JustifyContent::Center,
-                                                                    ..Default::default()
-                                                                },
-                                                                BackgroundColor(Color::srgb(
-                                                                    0.2, 0.22, 0.25
-                                                                )),
-                                                                CoreCategory { category: 2 },
-                                                                Interaction::default(),
-                                                                BorderRadius::all(Val::Px(10.0)),
-                                                                children![(
-                                                                    Text::new("2: Conveyors"),
-                                                                    TextFont {
-                                                                        font_size: 18.0,
-                                                                        ..Default::default()
-                                                                    },
-                                                                    TextColor(Color::WHITE),
-                                                                    TextLayout {
-                                                                        justify:
-                                                                            JustifyText::Center,
-                                                                        ..Default::default()
-                                                                    }
-                                                                )],
-                                                            ),
-                                                            (
-                                                                Button,
-                                                                Node {
-                                                                    width: Val::Percent(100.0),
-                                                                    height: Val::Px(50.0),
-                                                                    align_items: AlignItems::Center,
-                                                                    justify_content:
-                                                                        JustifyContent::Center,
-                                                                    ..Default::default()
-                                                                },
-                                                                BackgroundColor(Color::srgb(
-                                                                    0.2, 0.22, 0.25
-                                                                )),
-                                                                CoreCategory { category: 3 },
-                                                                Interaction::default(),
-                                                                BorderRadius::all(Val::Px(10.0)),
-                                                                children![(
-                                                                    Text::new("3: Extractors"),
-                                                                    TextFont {
-                                                                        font_size: 18.0,
-                                                                        ..Default::default()
-                                                                    },
-                                                                    TextColor(Color::WHITE),
-                                                                    TextLayout {
-                                                                        justify:
-                                                                            JustifyText::Center,
-                                                                        ..Default::default()
-                                                                    }
-                                                                )],
-                                                            ),
-                                                            (
-                                                                Button,
-                                                                Node {
-                                                                    width: Val::Percent(100.0),
-                                                                    height: Val::Px(50.0),
-                                                                    align_items: AlignItems::Center,
-                                                                    justify_content:
-                                                                        JustifyContent::Center,
-                                                                    ..Default::default()
-                                                                },
-                                                                BackgroundColor(Color::srgb(
-                                                                    0.2, 0.22, 0.25
-                                                                )),
-                                                                CoreCategory { category: 4 },
-                                                                Interaction::default(),
-                                                                BorderRadius::all(Val::Px(10.0)),
-                                                                children![(
-                                                                    Text::new("4: Factories"),
-                                                                    TextFont {
-                                                                        font_size: 18.0,
-                                                                        ..Default::default()
-                                                                    },
-                                                                    TextColor(Color::WHITE),
-                                                                    TextLayout {
-                                                                        justify:
-                                                                            JustifyText::Center,
-                                                                        ..Default::default()
-                                                                    }
-                                                                )],
-                                                            ),
-                                                            (
-                                                                Button,
-                                                                Node {
-                                                                    width: Val::Percent(100.0),
-                                                                    height: Val::Px(50.0),
-                                                                    align_items: AlignItems::Center,
-                                                                    justify_content:
-                                                                        JustifyContent::Center,
-                                                                    ..Default::default()
-                                                                },
-                                                                BackgroundColor(Color::srgb(
-                                                                    0.2, 0.22, 0.25
-                                                                )),
-                                                                CoreCategory { category: 5 },
-                                                                Interaction::default(),
-                                                                BorderRadius::all(Val::Px(10.0)),
-                                                                children![(
-                                                                    Text::new("5: Storage"),
-                                                                    TextFont {
-                                                                        font_size: 18.0,
-                                                                        ..Default::default()
-                                                                    },
-                                                                    TextColor(Color::WHITE),
-                                                                    TextLayout {
-                                                                        justify:
-                                                                            JustifyText::Center,
-                                                                        ..Default::default()
-                                                                    }
-                                                                )],
-                                                            ),
-                                                        ],
-                                                    ),
-                                                    (
-                                                        Node {
-                                                            width: Val::Percent(75.0),
-                                                            height: Val::Percent(100.0),
-                                                            display: Display::Flex,
-                                                            flex_direction: FlexDirection::Row,
-                                                            flex_wrap: FlexWrap::Wrap,
-                                                            align_content: AlignContent::FlexStart,
-                                                            padding: UiRect::all(Val::Px(15.0)),
-                                                            row_gap: Val::Px(15.0),
-                                                            column_gap: Val::Px(15.0),
-                                                            ..Default::default()
-                                                        },
-                                                        BackgroundColor(Color::srgb(
-                                                            0.18, 0.2, 0.23
-                                                        )),
-                                                        CoreItemsPanel,
-                                                    ),
-                                                ],
-                                            ),
-                                            (
-                                                Node {
-                                                    width: Val::Percent(100.0),
-                                                    height: Val::Px(40.0),
-                                                    display: Display::Flex,
-                                                    justify_content: JustifyContent::Center,
-                                                    margin: UiRect::top(Val::Px(20.0)),
-                                                    ..default()
-                                                },
-                                                children![(
-                                                    Button,
-                                                    Node {
-                                                        width: Val::Px(120.0),
-                                                        height: Val::Px(40.0),
-                                                        align_content: AlignContent::Center,
-                                                        justify_content: JustifyContent::Center,
-                                                        display: Display::Grid,
-                                                        ..default()
-                                                    },
-                                                    BackgroundColor(Color::srgb(0.6, 0.3, 0.3)),
-                                                    BorderRadius::all(Val::Px(5.0)),
-                                                    Interaction::default(),
-                                                    Name::new("close_button"),
-                                                    children![(
-                                                        Text::new("Close"),
-                                                        TextFont {
-                                                            font_size: 16.0,
-                                                            ..default()
-                                                        },
-                                                        TextColor(Color::WHITE),
-                                                    )]
-                                                )],
-                                            )
-                                        ],
-                                    ));
-                                } else {
-                                    commands.spawn((Node::default(), Text::default()));
-                                }
-                            } else {
-                                commands.spawn((Node::default(), Text::default()));
+                                world.occupy(pos, tile_type);
+                                world.tile_placed(pos, tile_type);
                             }
                         }
                     }
@@ -573,29 +311,18 @@ pub fn manage_tiles(
         }
     }
     if mouse_button_input.pressed(MouseButton::Right)
-        && inventory_query.is_empty()
-        && core_menu_query.is_empty()
+        && !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+        && !tool_modifier_held(&keyboard_input)
+        && !ui_state.blocks_gameplay()
+        && library.active.is_none()
     {
         placer.tile_type = (0, 1);
         if let Ok(window) = windows.single() {
-            if let Some(screen_pos) = window.cursor_position() {
-                if let Ok((camera, camera_transform)) = camera_query.single() {
-                    let window_size = Vec2::new(window.width(), window.height());
-
-                    let mut ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
-                    ndc.y *= -1.0;
-                    let ndc_to_world =
-                        camera_transform.compute_matrix() * camera.clip_from_view().inverse();
-                    let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
-                    let world_pos: Vec2 = world_pos.truncate();
-
-                    let grid_x = (world_pos.x / TILE_SIZE).round() as i32;
-                    let grid_y = (world_pos.y / TILE_SIZE).round() as i32;
-                    let pos = Position::new(grid_x, grid_y);
-                    if pos != Position::new(0, 0) {
-                        if let Some(entry) = world.tiles.remove_entry(&pos) {
-                            *world.resources.entry(entry.1.1).or_insert(0) += 1;
-                        }
+            if let Ok((camera, camera_transform)) = camera_query.single() {
+                if let Some(cursor_pos) = cursor_grid_position(window, camera, camera_transform) {
+                    let pos = world.anchor_of(cursor_pos);
+                    if !world.is_protected(pos) {
+                        world.remove_tile(pos);
                     }
                 }
             }
@@ -603,32 +330,99 @@ pub fn manage_tiles(
     }
 }
 
+// A tile's own sprite, as opposed to the item and overlay sprites it parents.
+type TileSpriteData = (
+    Entity,
+    Ref<'static, TileSprite>,
+    &'static mut Transform,
+    &'static mut Sprite,
+    Ref<'static, Visibility>,
+);
+
+// The tile sprites, their item sprites, and the ghost sprites of planned tiles.
+#[derive(SystemParam)]
+pub struct TileSprites<'w, 's> {
+    parent_query: Query<'w, 's, TileSpriteData>,
+    children_query: Query<'w, 's, Ref<'static, Children>, With<TileSprite>>,
+    child_sprite_query:
+        Query<'w, 's, (&'static mut Sprite, &'static mut Transform), Without<TileSprite>>,
+    ghost_query: Query<'w, 's, (Entity, &'static GhostSprite)>,
+}
+
+// What has changed since the tile sprites were last drawn.
+#[derive(SystemParam)]
+pub struct TileRedraws<'w, 's> {
+    tile_changes: EventReader<'w, 's, TileChanged>,
+    item_animation_query: Query<'w, 's, &'static ItemAnimation>,
+    last_animated: Local<'s, HashSet<Position>>,
+    was_lod: Local<'s, bool>,
+}
+
+impl TileRedraws<'_, '_> {
+    // Leaving LOD restores every texture; otherwise only changed tiles, their neighbours (for
+    // conveyor shapes) and tiles with items starting or finishing an animation are redrawn.
+    // Returns whether to redraw everything, the tiles to redraw, and the tiles being animated.
+    fn collect(&mut self, lod: bool) -> (bool, HashSet<Position>, HashSet<Position>) {
+        let full_refresh = *self.was_lod && !lod;
+        *self.was_lod = lod;
+        let mut changed = HashSet::new();
+        for change in self.tile_changes.read() {
+            changed.insert(change.pos);
+            changed.extend(Direction::ALL.map(|direction| change.pos.shift(direction)));
+        }
+
+        let mut animated_positions = HashSet::new();
+        for animation in self.item_animation_query.iter() {
+            let start_pos = Position::new(
+                (animation.start_pos.x / TILE_SIZE).round() as i32,
+                (animation.start_pos.y / TILE_SIZE).round() as i32,
+            );
+            let end_pos = Position::new(
+                (animation.end_pos.x / TILE_SIZE).round() as i32,
+                (animation.end_pos.y / TILE_SIZE).round() as i32,
+            );
+            animated_positions.insert(start_pos);
+            animated_positions.insert(end_pos);
+        }
+        changed.extend(self.last_animated.iter().copied());
+        changed.extend(animated_positions.iter().copied());
+        *self.last_animated = animated_positions.clone();
+        (full_refresh, changed, animated_positions)
+    }
+}
+
 pub fn update_tile_visuals(
     world: Res<WorldRes>,
-    mut parent_query: Query<(Entity, &TileSprite, &mut Transform, &mut Sprite)>,
-    children_query: Query<&Children, With<TileSprite>>,
-    mut child_sprite_query: Query<(&mut Sprite, &mut Transform), Without<TileSprite>>,
+    placer: Res<Placer>,
+    mut redraws: TileRedraws,
+    sprites: TileSprites,
     asset_server: Res<AssetServer>,
     mut commands: Commands,
-    item_animation_query: Query<&ItemAnimation>,
+    mut diagnostics: Diagnostics,
 ) {
+    let start = Instant::now();
     let mut existing_positions = HashSet::new();
-    let mut animated_positions = HashSet::new();
+    let (full_refresh, changed, animated_positions) =
+        redraws.collect(placer.zoom_level < LOD_ZOOM_THRESHOLD);
+    let TileSprites {
+        mut parent_query,
+        children_query,
+        mut child_sprite_query,
+        ghost_query,
+    } = sprites;
 
-    for animation in item_animation_query.iter() {
-        let start_pos = Position::new(
-            (animation.start_pos.x / TILE_SIZE).round() as i32,
-            (animation.start_pos.y / TILE_SIZE).round() as i32,
-        );
-        let end_pos = Position::new(
-            (animation.end_pos.x / TILE_SIZE).round() as i32,
-            (animation.end_pos.y / TILE_SIZE).round() as i32,
-        );
-        animated_positions.insert(start_pos);
-        animated_positions.insert(end_pos);
-    }
+    for (entity, tile_sprite, mut transform, mut sprite, visibility) in parent_query.iter_mut() {
+        if !full_refresh
+            && !tile_sprite.is_added()
+            && !visibility.is_changed()
+            && !changed.contains(&tile_sprite.pos)
+            && !children_query
+                .get(entity)
+                .is_ok_and(|children| children.is_changed())
+        {
+            continue;
+        }
 
-    for (entity, tile_sprite, mut transform, mut sprite) in parent_query.iter_mut() {
         transform.translation = Vec3::new(
             tile_sprite.pos.x as f32 * TILE_SIZE,
             tile_sprite.pos.y as f32 * TILE_SIZE,
@@ -637,6 +431,9 @@ pub fn update_tile_visuals(
 
         if let Some(tile) = world.tiles.get(&tile_sprite.pos) {
             existing_positions.insert(tile_sprite.pos);
+            if *visibility == Visibility::Hidden {
+                continue;
+            }
             if let Some(conveyor) = tile.0.as_any().downcast_ref::<Conveyor>() {
                 transform.translation = Vec3::new(
                     tile_sprite.pos.x as f32 * TILE_SIZE,
@@ -655,7 +452,7 @@ pub fn update_tile_visuals(
                 };
 
                 if let Ok(children) = children_query.get(entity) {
-                    for child in children.iter() {
+                    for (child, lane) in children.iter().zip(Lane::ALL) {
                         if let Ok((mut child_sprite, mut child_transform)) =
                             child_sprite_query.get_mut(child)
                         {
@@ -664,7 +461,9 @@ pub fn update_tile_visuals(
                             } else {
                                 child_sprite.color = Color::WHITE;
                             }
-                            child_transform.translation = Vec3::new(0.0, 0.0, 1.0);
+                            child_transform.translation =
+                                (get_lane_offset(Direction::Up, lane) * IMAGE_SIZE / TILE_SIZE)
+                                    .extend(1.0);
                             child_transform.rotation = match conveyor.direction {
                                 Direction::Up => Quat::IDENTITY,
                                 Direction::Down => Quat::from_rotation_z(PI),
@@ -672,13 +471,26 @@ pub fn update_tile_visuals(
                                 Direction::Right => Quat::from_rotation_z(FRAC_PI_2),
                             };
 
-                            child_sprite.image = if let Some(unwraped_item) = conveyor.item {
+                            // Fuller stacks are drawn slightly larger.
+                            let count = conveyor.stack(lane).map_or(1, |stack| stack.count);
+                            child_transform.scale = Vec3::splat(0.5 + 0.05 * (count - 1) as f32);
+
+                            child_sprite.image = if let Some(unwraped_item) = conveyor.lane(lane) {
                                 asset_server.load(unwraped_item.sprite())
                             } else {
                                 asset_server.load("embedded://textures/items/none.png")
                             }
                         }
                     }
+
+                    if children.len() < Lane::ALL.len() {
+                        commands.entity(entity).with_child((
+                            Sprite::from_image(
+                                asset_server.load("embedded://textures/items/none.png"),
+                            ),
+                            Transform::from_scale(Vec3::splat(0.5)),
+                        ));
+                    }
                 }
             } else if let Some(router) = tile.0.as_any().downcast_ref::<Router>() {
                 transform.translation = Vec3::new(
@@ -687,7 +499,7 @@ pub fn update_tile_visuals(
                     2.0,
                 );
 
-                sprite.image = asset_server.load("embedded://textures/tiles/conveyors/router.png");
+                sprite.image = asset_server.load(get_tile_texture(tile.1));
 
                 transform.rotation = match router.direction {
                     Direction::Up => Quat::IDENTITY,
@@ -745,6 +557,28 @@ pub fn update_tile_visuals(
                     Direction::Left => Quat::from_rotation_z(FRAC_PI_2),
                     Direction::Right => Quat::from_rotation_z(-FRAC_PI_2),
                 };
+            } else if let Some(quarry) = tile.0.as_any().downcast_ref::<Quarry>() {
+                transform.translation = Vec3::new(
+                    tile_sprite.pos.x as f32 * TILE_SIZE,
+                    tile_sprite.pos.y as f32 * TILE_SIZE,
+                    2.0,
+                );
+                sprite.image = asset_server.load("embedded://textures/tiles/quarry.png");
+
+                transform.rotation = match quarry.direction {
+                    Direction::Up => Quat::IDENTITY,
+                    Direction::Down => Quat::from_rotation_z(PI),
+                    Direction::Left => Quat::from_rotation_z(FRAC_PI_2),
+                    Direction::Right => Quat::from_rotation_z(-FRAC_PI_2),
+                };
+
+                if let Ok(children) = children_query.get(entity) {
+                    for child in children.iter() {
+                        if let Ok((mut child_sprite, _)) = child_sprite_query.get_mut(child) {
+                            child_sprite.color = Color::NONE;
+                        }
+                    }
+                }
             } else if tile.0.as_any().is::<Portal>() {
                 transform.translation = Vec3::new(
                     tile_sprite.pos.x as f32 * TILE_SIZE,
@@ -752,6 +586,23 @@ pub fn update_tile_visuals(
                     2.0,
                 );
                 sprite.image = asset_server.load("embedded://textures/tiles/portal.png");
+            } else if tile.0.as_any().is::<Beacon>() {
+                transform.translation = Vec3::new(
+                    tile_sprite.pos.x as f32 * TILE_SIZE,
+                    tile_sprite.pos.y as f32 * TILE_SIZE,
+                    2.0,
+                );
+                sprite.image = asset_server.load("embedded://textures/tiles/beacon.png");
+
+                transform.rotation = Quat::IDENTITY;
+
+                if let Ok(children) = children_query.get(entity) {
+                    for child in children.iter() {
+                        if let Ok((mut child_sprite, _)) = child_sprite_query.get_mut(child) {
+                            child_sprite.color = Color::NONE;
+                        }
+                    }
+                }
             } else if tile.0.as_any().is::<Junction>() {
                 transform.translation = Vec3::new(
                     tile_sprite.pos.x as f32 * TILE_SIZE,
@@ -763,6 +614,32 @@ pub fn update_tile_visuals(
 
                 transform.rotation = Quat::IDENTITY;
 
+                if let Ok(children) = children_query.get(entity) {
+                    for child in children.iter() {
+                        if let Ok((mut child_sprite, _)) = child_sprite_query.get_mut(child) {
+                            child_sprite.color = Color::NONE;
+                        }
+                    }
+                }
+            } else if tile.0.as_any().is::<Wire>()
+                || tile.0.as_any().is::<Sensor>()
+                || tile.0.as_any().is::<ItemDisplay>()
+                || tile.0.as_any().is::<Inserter>()
+            {
+                transform.translation = Vec3::new(
+                    tile_sprite.pos.x as f32 * TILE_SIZE,
+                    tile_sprite.pos.y as f32 * TILE_SIZE,
+                    2.0,
+                );
+                sprite.image = asset_server.load(get_tile_texture(tile.1));
+
+                transform.rotation = match get_tile_direction(tile.0.as_ref()) {
+                    Direction::Up => Quat::IDENTITY,
+                    Direction::Down => Quat::from_rotation_z(PI),
+                    Direction::Left => Quat::from_rotation_z(FRAC_PI_2),
+                    Direction::Right => Quat::from_rotation_z(-FRAC_PI_2),
+                };
+
                 if let Ok(children) = children_query.get(entity) {
                     for child in children.iter() {
                         if let Ok((mut child_sprite, _)) = child_sprite_query.get_mut(child) {
@@ -790,8 +667,1185 @@ pub fn update_tile_visuals(
             } else {
                 sprite.color = css::GRAY.into();
             }
+
+            if let Some(size) = footprint_sprite_size(tile.1) {
+                transform.translation += footprint_offset(tile.1).extend(0.0);
+                sprite.custom_size = Some(size);
+            }
         } else {
             commands.entity(entity).despawn();
         }
     }
+
+    let mut ghost_positions = HashSet::new();
+    for (entity, ghost_sprite) in ghost_query.iter() {
+        match world.ghosts.get(&ghost_sprite.pos) {
+            Some(ghost) if ghost.queued == ghost_sprite.queued => {
+                ghost_positions.insert(ghost_sprite.pos);
+            }
+            _ => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (pos, ghost) in world.ghosts.iter() {
+        if ghost_positions.contains(pos) {
+            continue;
+        }
+        commands.spawn((
+            Sprite {
+                image: asset_server.load(get_tile_texture(ghost.tile_type)),
+                color: if ghost.queued {
+                    Color::srgba(0.5, 1.0, 0.6, 0.45)
+                } else {
+                    Color::srgba(0.5, 0.7, 1.0, 0.35)
+                },
+                ..Default::default()
+            },
+            Transform {
+                translation: Vec3::new(pos.x as f32 * TILE_SIZE, pos.y as f32 * TILE_SIZE, 0.5),
+                scale: Vec3::splat(TILE_SIZE / IMAGE_SIZE),
+                rotation: match ghost.direction {
+                    Direction::Up => Quat::IDENTITY,
+                    Direction::Down => Quat::from_rotation_z(PI),
+                    Direction::Left => Quat::from_rotation_z(FRAC_PI_2),
+                    Direction::Right => Quat::from_rotation_z(-FRAC_PI_2),
+                },
+            },
+            GhostSprite {
+                pos: *pos,
+                queued: ghost.queued,
+            },
+        ));
+    }
+
+    diagnostics.add_measurement(&TILE_VISUALS_TIME, || {
+        start.elapsed().as_secs_f64() * 1000.0
+    });
+}
+
+pub fn copy_paste_tile_settings(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &Transform), Without<PhotoCamera>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut world: ResMut<WorldRes>,
+    mut copied: ResMut<CopiedSettings>,
+    ui_state: Res<UiState>,
+) {
+    if !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+        || ui_state.blocks_gameplay()
+    {
+        return;
+    }
+
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Some(pos) = cursor_grid_position(window, camera, camera_transform) else {
+        return;
+    };
+    let pos = world.anchor_of(pos);
+
+    if mouse_button_input.just_pressed(MouseButton::Right) {
+        if let Some((tile, tile_type)) = world.tiles.get(&pos) {
+            copied.settings = TileSettings::copy_from(tile.as_ref(), *tile_type);
+        }
+    } else if mouse_button_input.pressed(MouseButton::Left)
+        && let Some(settings) = copied.settings
+    {
+        let difficulty = world.difficulty;
+        if let Some((tile, tile_type)) = world.tiles.get_mut(&pos)
+            && settings.apply(tile.as_mut(), *tile_type, difficulty)
+        {
+            world.mark_dirty(pos);
+        }
+    }
+}
+
+pub fn upgrade_planner(
+    mut commands: Commands,
+    cursor: CursorInput,
+    placer: Res<Placer>,
+    mut world: ResMut<WorldRes>,
+    mut planner: ResMut<UpgradePlanner>,
+    mut gizmos: Gizmos,
+    ui_state: Res<UiState>,
+) {
+    if ui_state.blocks_gameplay() {
+        planner.selection_start = None;
+        return;
+    }
+
+    let Some(pos) = cursor.grid_position() else {
+        return;
+    };
+
+    if cursor.mouse_button_input.just_pressed(MouseButton::Left)
+        && cursor.keyboard_input.pressed(KeyCode::KeyU)
+    {
+        planner.selection_start = Some(pos);
+    }
+
+    let Some(start) = planner.selection_start else {
+        return;
+    };
+
+    let region = GridRegion::new(start, pos);
+    draw_grid_region(&mut gizmos, region, Color::srgb(0.4, 0.8, 0.4));
+
+    if !cursor.mouse_button_input.just_released(MouseButton::Left) {
+        return;
+    }
+    planner.selection_start = None;
+
+    // With a tile selected, tiles of the kind the drag started on become that tile; otherwise
+    // every tile goes up a tier.
+    let selected_mapping = if placer.tile_type != (0, 1) {
+        world
+            .tiles
+            .get(&world.anchor_of(start))
+            .map(|(_, tile_type)| (*tile_type, placer.tile_type))
+    } else {
+        None
+    };
+
+    let mut missing = 0;
+    let mut full = 0;
+    for pos in region.cells() {
+        let Some((tile, current_type)) = world.tiles.get(&pos) else {
+            continue;
+        };
+        let current_type = *current_type;
+        let target_type = match selected_mapping {
+            Some((from, to)) if from == current_type => to,
+            Some(_) => continue,
+            None => match get_tile_upgrade(current_type) {
+                Some(to) => to,
+                None => continue,
+            },
+        };
+        if target_type == current_type
+            || is_protected_tile(current_type)
+            || get_tile_footprint(target_type) != get_tile_footprint(current_type)
+        {
+            continue;
+        }
+        if *world.resources.get(&target_type).unwrap_or(&0) < 1 {
+            missing += 1;
+            continue;
+        }
+
+        let direction = get_tile_direction(tile.as_ref());
+        let mut new_tile = get_new_tile(target_type, pos, direction);
+        if !move_tile_contents(tile.as_ref(), new_tile.0.as_mut()) {
+            full += 1;
+            continue;
+        }
+
+        *world.resources.entry(target_type).or_insert(0) -= 1;
+        *world.resources.entry(current_type).or_insert(0) += 1;
+        world.tiles.insert(pos, new_tile);
+        world.lifetime.tiles_removed += 1;
+        world.lifetime.tiles_placed += 1;
+        world.mark_dirty(pos);
+        world.tile_removed(pos, current_type);
+        world.tile_placed(pos, target_type);
+    }
+
+    if missing > 0 {
+        spawn_dialog(
+            &mut commands,
+            "Not enough tiles",
+            &[format!("{} tiles could not be upgraded.", missing)],
+        );
+    } else if full > 0 {
+        spawn_dialog(
+            &mut commands,
+            "Tiles still in use",
+            &[format!(
+                "{} tiles hold items that wouldn't fit their replacement.",
+                full
+            )],
+        );
+    }
+}
+
+pub fn upgrade_tile_in_place(
+    mut commands: Commands,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &Transform), Without<PhotoCamera>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut world: ResMut<WorldRes>,
+    ui_state: Res<UiState>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyG) || ui_state.blocks_gameplay() {
+        return;
+    }
+
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Some(pos) = cursor_grid_position(window, camera, camera_transform) else {
+        return;
+    };
+    let pos = world.anchor_of(pos);
+    let Some((tile, current_type)) = world.tiles.get(&pos) else {
+        return;
+    };
+    let current_type = *current_type;
+    let Some(target_type) = get_tile_upgrade(current_type)
+        .filter(|target| get_tile_footprint(*target) == get_tile_footprint(current_type))
+    else {
+        return;
+    };
+    if *world.resources.get(&target_type).unwrap_or(&0) < 1 {
+        spawn_dialog(
+            &mut commands,
+            "Not enough tiles",
+            &[format!("Upgrading needs a {}.", get_tile_name(target_type))],
+        );
+        return;
+    }
+
+    let direction = get_tile_direction(tile.as_ref());
+    let mut new_tile = get_new_tile(target_type, pos, direction);
+    if !move_tile_contents(tile.as_ref(), new_tile.0.as_mut()) {
+        return;
+    }
+
+    *world.resources.entry(target_type).or_insert(0) -= 1;
+    *world.resources.entry(current_type).or_insert(0) += 1;
+    world.tiles.insert(pos, new_tile);
+    world.mark_dirty(pos);
+    world.tile_removed(pos, current_type);
+    world.tile_placed(pos, target_type);
+}
+
+pub fn deconstruction_planner(
+    cursor: CursorInput,
+    mut world: ResMut<WorldRes>,
+    mut planner: ResMut<DeconstructionPlanner>,
+    mut gizmos: Gizmos,
+    ui_state: Res<UiState>,
+) {
+    if ui_state.blocks_gameplay() {
+        planner.selection_start = None;
+        return;
+    }
+
+    let Some(pos) = cursor.grid_position() else {
+        return;
+    };
+
+    if cursor.mouse_button_input.just_pressed(MouseButton::Left)
+        && cursor.keyboard_input.pressed(KeyCode::KeyX)
+    {
+        planner.selection_start = Some(pos);
+    }
+
+    let Some(start) = planner.selection_start else {
+        return;
+    };
+
+    let cancel = cursor
+        .keyboard_input
+        .any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let region = GridRegion::new(start, pos);
+    draw_grid_region(
+        &mut gizmos,
+        region,
+        if cancel {
+            Color::srgb(0.7, 0.7, 0.7)
+        } else {
+            Color::srgb(0.9, 0.3, 0.2)
+        },
+    );
+
+    if !cursor.mouse_button_input.just_released(MouseButton::Left) {
+        return;
+    }
+    planner.selection_start = None;
+
+    let in_area = |pos: &Position| region.contains(*pos);
+    if cancel {
+        world.deconstruction.retain(|pos| !in_area(pos));
+        return;
+    }
+
+    let mut marked: Vec<Position> = world
+        .tiles
+        .iter()
+        .filter(|(pos, (_, tile_type))| in_area(pos) && !is_protected_tile(*tile_type))
+        .map(|(pos, _)| *pos)
+        .filter(|pos| !world.deconstruction.contains(pos))
+        .collect();
+    marked.sort_by_key(|pos| (pos.y, pos.x));
+    world.deconstruction.extend(marked);
+}
+
+pub fn clear_items_tool(
+    cursor: CursorInput,
+    mut world: ResMut<WorldRes>,
+    mut tool: ResMut<ClearItemsTool>,
+    mut gizmos: Gizmos,
+    ui_state: Res<UiState>,
+) {
+    if ui_state.blocks_gameplay() {
+        tool.selection_start = None;
+        return;
+    }
+
+    let Some(pos) = cursor.grid_position() else {
+        return;
+    };
+
+    if cursor.mouse_button_input.just_pressed(MouseButton::Left)
+        && cursor.keyboard_input.pressed(KeyCode::KeyK)
+    {
+        tool.selection_start = Some(pos);
+    }
+
+    let Some(start) = tool.selection_start else {
+        return;
+    };
+
+    let region = GridRegion::new(start, pos);
+    draw_grid_region(&mut gizmos, region, Color::srgb(0.9, 0.8, 0.3));
+
+    if !cursor.mouse_button_input.just_released(MouseButton::Left) {
+        return;
+    }
+    tool.selection_start = None;
+
+    let mut cleared = Vec::new();
+    for (pos, (tile, _)) in world.tiles.iter_mut() {
+        if !region.contains(*pos) {
+            continue;
+        }
+        if (tile.as_any().is::<Conveyor>()
+            || tile.as_any().is::<Router>()
+            || tile.as_any().is::<Junction>())
+            && tile.clear_items() > 0
+        {
+            cleared.push(*pos);
+        }
+    }
+    for pos in cleared {
+        world.forget_actions(pos);
+        world.mark_dirty(pos);
+    }
+}
+
+pub fn draw_deconstruction_marks(
+    world: Res<WorldRes>,
+    tile_sprite_query: Query<(&TileSprite, &Visibility)>,
+    mut gizmos: Gizmos,
+) {
+    if world.deconstruction.is_empty() {
+        return;
+    }
+
+    let marked: HashSet<Position> = world.deconstruction.iter().copied().collect();
+    let half = TILE_SIZE * 0.35;
+    for (tile_sprite, visibility) in tile_sprite_query.iter() {
+        if *visibility == Visibility::Hidden || !marked.contains(&tile_sprite.pos) {
+            continue;
+        }
+        let center = Vec2::new(
+            tile_sprite.pos.x as f32 * TILE_SIZE,
+            tile_sprite.pos.y as f32 * TILE_SIZE,
+        );
+        let color = Color::srgb(0.9, 0.3, 0.2);
+        gizmos.line_2d(
+            center - Vec2::splat(half),
+            center + Vec2::splat(half),
+            color,
+        );
+        gizmos.line_2d(
+            center + Vec2::new(-half, half),
+            center + Vec2::new(half, -half),
+            color,
+        );
+    }
+}
+
+pub fn draw_router_priorities(
+    world: Res<WorldRes>,
+    tile_sprite_query: Query<(&TileSprite, &Visibility)>,
+    mut gizmos: Gizmos,
+) {
+    for (tile_sprite, visibility) in tile_sprite_query.iter() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        if let Some((tile, _)) = world.tiles.get(&tile_sprite.pos)
+            && let Some(router) = tile.as_any().downcast_ref::<Router>()
+            && let Some(priority) = router.priority
+        {
+            let center = Vec2::new(
+                tile_sprite.pos.x as f32 * TILE_SIZE,
+                tile_sprite.pos.y as f32 * TILE_SIZE,
+            );
+            let offset = match priority.to_direction(router.direction) {
+                Direction::Up => Vec2::Y,
+                Direction::Down => Vec2::NEG_Y,
+                Direction::Left => Vec2::NEG_X,
+                Direction::Right => Vec2::X,
+            };
+            gizmos.arrow_2d(
+                center,
+                center + offset * TILE_SIZE * 0.45,
+                Color::srgb(1.0, 0.8, 0.2),
+            );
+        }
+    }
+}
+
+pub fn draw_disabled_tiles(
+    world: Res<WorldRes>,
+    tile_sprite_query: Query<(&TileSprite, &Visibility)>,
+    mut gizmos: Gizmos,
+) {
+    if world.disabled.is_empty() {
+        return;
+    }
+
+    for (tile_sprite, visibility) in tile_sprite_query.iter() {
+        if *visibility != Visibility::Hidden && world.disabled.contains(&tile_sprite.pos) {
+            gizmos.circle_2d(
+                Vec2::new(
+                    tile_sprite.pos.x as f32 * TILE_SIZE,
+                    tile_sprite.pos.y as f32 * TILE_SIZE,
+                ),
+                TILE_SIZE * 0.2,
+                Color::srgb(0.9, 0.2, 0.2),
+            );
+        }
+    }
+}
+
+pub fn update_item_display_labels(
+    mut commands: Commands,
+    world: Res<WorldRes>,
+    tile_sprite_query: Query<(&TileSprite, &Visibility)>,
+    mut label_query: Query<(Entity, &ItemDisplayLabel, &mut Text2d)>,
+) {
+    let mut labelled = HashSet::new();
+
+    for (entity, label, mut text) in label_query.iter_mut() {
+        if let Some((tile, _)) = world.tiles.get(&label.pos)
+            && let Some(display) = tile.as_any().downcast_ref::<ItemDisplay>()
+            && world.sprite_chunks.contains(&ChunkPosition::of(label.pos))
+        {
+            let count = display.count.to_string();
+            if text.0 != count {
+                text.0 = count;
+            }
+            labelled.insert(label.pos);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for (tile_sprite, visibility) in tile_sprite_query.iter() {
+        if *visibility == Visibility::Hidden || labelled.contains(&tile_sprite.pos) {
+            continue;
+        }
+        if let Some((tile, _)) = world.tiles.get(&tile_sprite.pos)
+            && let Some(display) = tile.as_any().downcast_ref::<ItemDisplay>()
+        {
+            commands.spawn((
+                Text2d::new(display.count.to_string()),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Transform::from_xyz(
+                    tile_sprite.pos.x as f32 * TILE_SIZE,
+                    tile_sprite.pos.y as f32 * TILE_SIZE + TILE_SIZE * 0.65,
+                    10.0,
+                ),
+                ItemDisplayLabel {
+                    pos: tile_sprite.pos,
+                },
+            ));
+        }
+    }
+}
+
+pub fn cull_tile_sprites(
+    camera_query: Query<&Transform, (With<Camera2d>, Without<PhotoCamera>)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut tile_sprite_query: Query<(&TileSprite, &mut Visibility)>,
+    mut item_animation_query: Query<(&ItemAnimation, &mut Visibility), Without<TileSprite>>,
+) {
+    let (Ok(camera_transform), Ok(window)) = (camera_query.single(), windows.single()) else {
+        return;
+    };
+
+    let center = camera_transform.translation.truncate();
+    let half_size = Vec2::new(window.width(), window.height()) * camera_transform.scale.x / 2.0
+        + Vec2::splat(CULL_MARGIN);
+    let view = Rect::from_center_half_size(center, half_size);
+
+    for (tile_sprite, mut visibility) in tile_sprite_query.iter_mut() {
+        let pos = Vec2::new(
+            tile_sprite.pos.x as f32 * TILE_SIZE,
+            tile_sprite.pos.y as f32 * TILE_SIZE,
+        );
+        visibility.set_if_neq(if view.contains(pos) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+
+    for (animation, mut visibility) in item_animation_query.iter_mut() {
+        visibility.set_if_neq(
+            if view.contains(animation.start_pos.truncate())
+                || view.contains(animation.end_pos.truncate())
+            {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+        );
+    }
+}
+
+// Only sprites that were just spawned or redrawn need LOD applied, unless LOD itself was switched.
+pub fn apply_tile_lod(
+    world: Res<WorldRes>,
+    placer: Res<Placer>,
+    mut parent_query: Query<(Entity, Ref<TileSprite>, &mut Sprite)>,
+    children_query: Query<Ref<Children>, With<TileSprite>>,
+    mut visibility_query: Query<&mut Visibility, Without<TileSprite>>,
+    item_animation_query: Query<(Entity, Ref<ItemAnimation>)>,
+    mut was_lod: Local<bool>,
+) {
+    let lod = placer.zoom_level < LOD_ZOOM_THRESHOLD;
+    let switched = lod != *was_lod;
+    *was_lod = lod;
+    if !lod && !switched {
+        return;
+    }
+
+    let item_visibility = if lod {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+
+    for (entity, tile_sprite, mut sprite) in parent_query.iter_mut() {
+        let children = children_query.get(entity).ok();
+        if !switched
+            && !tile_sprite.is_added()
+            && !sprite.is_changed()
+            && !children
+                .as_ref()
+                .is_some_and(|children| children.is_changed())
+        {
+            continue;
+        }
+
+        if lod {
+            if let Some((_, id)) = world.tiles.get(&tile_sprite.pos) {
+                sprite.image = Handle::default();
+                sprite.custom_size =
+                    Some(footprint_sprite_size(*id).unwrap_or(Vec2::splat(IMAGE_SIZE)));
+                // Keeps the deposit overlay's fading.
+                let alpha = sprite.color.alpha();
+                sprite.color = get_tile_lod_color(*id).with_alpha(alpha);
+            }
+        } else {
+            sprite.custom_size = world
+                .tiles
+                .get(&tile_sprite.pos)
+                .and_then(|(_, id)| footprint_sprite_size(*id));
+            sprite.color = Color::WHITE;
+        }
+
+        for child in children.iter().flat_map(|children| children.iter()) {
+            if let Ok(mut visibility) = visibility_query.get_mut(child) {
+                visibility.set_if_neq(item_visibility);
+            }
+        }
+    }
+
+    // Culling shows animations again as they come into view, so those are hidden again too.
+    for (entity, animation) in item_animation_query.iter() {
+        if let Ok(mut visibility) = visibility_query.get_mut(entity)
+            && (switched || animation.is_added() || visibility.is_changed())
+        {
+            visibility.set_if_neq(item_visibility);
+        }
+    }
+}
+
+pub fn toggle_planning_mode(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut placer: ResMut<Placer>,
+    mut world: ResMut<WorldRes>,
+    ui_state: Res<UiState>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyP) || ui_state.blocks_gameplay() {
+        return;
+    }
+
+    if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        for ghost in world.ghosts.values_mut() {
+            ghost.queued = true;
+        }
+    } else {
+        placer.planning = !placer.planning;
+    }
+}
+
+pub fn place_ghosts(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &Transform), Without<PhotoCamera>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    placer: Res<Placer>,
+    mut world: ResMut<WorldRes>,
+    ui_state: Res<UiState>,
+) {
+    if !placer.planning
+        || ui_state.blocks_gameplay()
+        || keyboard_input.any_pressed([
+            KeyCode::ShiftLeft,
+            KeyCode::ShiftRight,
+            KeyCode::AltLeft,
+            KeyCode::AltRight,
+        ])
+        || tool_modifier_held(&keyboard_input)
+    {
+        return;
+    }
+
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Some(pos) = cursor_grid_position(window, camera, camera_transform) else {
+        return;
+    };
+
+    if mouse_button_input.pressed(MouseButton::Left)
+        && placer.tile_type != (0, 1)
+        && !world.tiles.contains_key(&pos)
+        && is_valid_placement(&world, placer.tile_type, pos, placer.direction)
+    {
+        world.ghosts.insert(
+            pos,
+            Ghost {
+                tile_type: placer.tile_type,
+                direction: placement_direction(placer.tile_type, placer.direction),
+                queued: false,
+            },
+        );
+    } else if mouse_button_input.pressed(MouseButton::Right) {
+        world.ghosts.remove(&pos);
+    }
+}
+
+pub fn build_ghosts(mut world: ResMut<WorldRes>) {
+    let queued: Vec<(Position, Ghost)> = world
+        .ghosts
+        .iter()
+        .filter(|(_, ghost)| ghost.queued)
+        .map(|(pos, ghost)| (*pos, *ghost))
+        .collect();
+
+    for (pos, ghost) in queued {
+        if footprint_cells(ghost.tile_type, pos)
+            .into_iter()
+            .any(|cell| world.is_occupied(cell))
+            || !is_valid_placement(&world, ghost.tile_type, pos, ghost.direction)
+        {
+            world.ghosts.remove(&pos);
+            continue;
+        }
+        if *world.resources.get(&ghost.tile_type).unwrap_or(&0) < 1 {
+            continue;
+        }
+
+        *world.resources.entry(ghost.tile_type).or_insert(0) -= 1;
+        world
+            .tiles
+            .insert(pos, get_new_tile(ghost.tile_type, pos, ghost.direction));
+        world.ghosts.remove(&pos);
+        world.occupy(pos, ghost.tile_type);
+        world.tile_placed(pos, ghost.tile_type);
+        world.lifetime.tiles_placed += 1;
+    }
+}
+
+fn spawn_tile_sprite(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    pos: Position,
+    tile_type: (u8, u8),
+) {
+    commands
+        .spawn((
+            Sprite::from_image(asset_server.load(get_tile_texture(tile_type))),
+            Transform {
+                translation: Vec3::new(pos.x as f32 * TILE_SIZE, pos.y as f32 * TILE_SIZE, 0.0),
+                scale: Vec3::splat(TILE_SIZE / IMAGE_SIZE),
+                ..Default::default()
+            },
+            TileSprite { pos },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Sprite::from_image(asset_server.load("embedded://textures/items/none.png")),
+                Transform::from_scale(Vec3::splat(0.5)),
+            ));
+        });
+}
+
+// Tile sprites only exist for the chunks around the camera, so a large base costs sprites for
+// what is on screen rather than for every tile it has.
+pub fn stream_tile_sprites(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut world: ResMut<WorldRes>,
+    tile_sprite_query: Query<(Entity, &TileSprite)>,
+) {
+    if world.sprite_chunks == world.loaded_chunks {
+        return;
+    }
+
+    let mut existing = HashSet::new();
+    for (entity, tile_sprite) in tile_sprite_query.iter() {
+        if world
+            .loaded_chunks
+            .contains(&ChunkPosition::of(tile_sprite.pos))
+        {
+            existing.insert(tile_sprite.pos);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for chunk in world.loaded_chunks.difference(&world.sprite_chunks) {
+        for pos in chunk.cells() {
+            if let Some((_, tile_type)) = world.tiles.get(&pos)
+                && !existing.contains(&pos)
+            {
+                spawn_tile_sprite(&mut commands, &asset_server, pos, *tile_type);
+            }
+        }
+    }
+    world.sprite_chunks = world.loaded_chunks.clone();
+}
+
+pub fn spawn_tile_sprites(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    world: Res<WorldRes>,
+    mut tiles_placed: EventReader<TilePlaced>,
+    tile_sprite_query: Query<&TileSprite>,
+) {
+    if tiles_placed.is_empty() {
+        return;
+    }
+
+    let mut existing: HashSet<Position> =
+        tile_sprite_query.iter().map(|sprite| sprite.pos).collect();
+    for placed in tiles_placed.read() {
+        // Tiles placed out of view get their sprite when their chunk is streamed in.
+        if !world.sprite_chunks.contains(&ChunkPosition::of(placed.pos))
+            || !existing.insert(placed.pos)
+        {
+            continue;
+        }
+        spawn_tile_sprite(&mut commands, &asset_server, placed.pos, placed.tile_type);
+    }
+}
+
+// One short tick per frame at most, so dragging out a belt doesn't stack sounds.
+pub fn play_tile_sounds(
+    mut commands: Commands,
+    mut tiles_placed: EventReader<TilePlaced>,
+    mut tiles_removed: EventReader<TileRemoved>,
+    mut pitch_assets: ResMut<Assets<Pitch>>,
+) {
+    let frequency = if tiles_placed.read().count() > 0 {
+        PLACE_FREQUENCY
+    } else if tiles_removed.read().count() > 0 {
+        REMOVE_FREQUENCY
+    } else {
+        return;
+    };
+    tiles_removed.clear();
+
+    commands.spawn((
+        AudioPlayer(pitch_assets.add(Pitch::new(
+            frequency,
+            Duration::from_millis(TILE_SOUND_DURATION_MS),
+        ))),
+        PlaybackSettings::DESPAWN.with_volume(Volume::Linear(0.3)),
+    ));
+}
+
+// The placer, and the tools that take its left click over while they're in use.
+#[derive(SystemParam)]
+pub struct PlacementTools<'w> {
+    placer: Res<'w, Placer>,
+    library: Res<'w, BlueprintLibrary>,
+    upgrade_planner: Res<'w, UpgradePlanner>,
+}
+
+impl PlacementTools<'_> {
+    fn other_tool_active(&self) -> bool {
+        self.upgrade_planner.selection_start.is_some() || self.library.active.is_some()
+    }
+}
+
+pub fn update_placement_feedback(
+    mut commands: Commands,
+    input: CursorInput,
+    tools: PlacementTools,
+    world: Res<WorldRes>,
+    mut pitch_assets: ResMut<Assets<Pitch>>,
+    mut label_query: Query<(&mut Text, &mut TextColor, &mut Node), With<PlacementLabel>>,
+    ui_state: Res<UiState>,
+) {
+    let placer = &tools.placer;
+    let Ok((mut text, mut color, mut node)) = label_query.single_mut() else {
+        return;
+    };
+    let Ok(window) = input.windows.single() else {
+        return;
+    };
+
+    let cursor = window.cursor_position();
+    let pos = input.grid_position();
+    let menus_closed = !ui_state.blocks_gameplay();
+
+    let (Some(pos), Some(cursor)) = (pos, cursor) else {
+        node.display = Display::None;
+        return;
+    };
+    if placer.tile_type == (0, 1) || !menus_closed {
+        node.display = Display::None;
+        return;
+    }
+
+    let count = *world.resources.get(&placer.tile_type).unwrap_or(&0);
+    let affordable = can_afford_placement(&world, placer.tile_type, pos);
+
+    node.display = Display::Flex;
+    node.left = Val::Px(cursor.x + 16.0);
+    node.top = Val::Px(cursor.y + 16.0);
+    text.0 = if is_rotatable_tile(placer.tile_type) {
+        format!(
+            "{} {} x{}",
+            get_tile_name(placer.tile_type),
+            placer.direction.arrow(),
+            count
+        )
+    } else {
+        format!("{} x{}", get_tile_name(placer.tile_type), count)
+    };
+    let anchor = world.anchor_of(pos);
+    if let Some((_, current)) = world.tiles.get(&anchor)
+        && *current != placer.tile_type
+        && !placer.planning
+    {
+        text.0 = format!(
+            "{}\n{} {}\nRefunds 1 {}, uses 1 {}",
+            text.0,
+            if placer.pending_replace == Some(anchor) {
+                "Click again to replace"
+            } else {
+                "Click twice to replace"
+            },
+            get_tile_name(*current),
+            get_tile_name(*current),
+            get_tile_name(placer.tile_type)
+        );
+    }
+    color.0 = if affordable {
+        Color::WHITE
+    } else {
+        Color::srgb(0.5, 0.5, 0.5)
+    };
+    if !affordable
+        && !placer.planning
+        && input.mouse_button_input.just_pressed(MouseButton::Left)
+        && !input.keyboard_input.any_pressed([
+            KeyCode::ShiftLeft,
+            KeyCode::ShiftRight,
+            KeyCode::AltLeft,
+            KeyCode::AltRight,
+        ])
+        && !tool_modifier_held(&input.keyboard_input)
+        && !tools.other_tool_active()
+        && is_valid_placement(&world, placer.tile_type, pos, placer.direction)
+    {
+        commands.spawn((
+            AudioPlayer(pitch_assets.add(Pitch::new(
+                BUZZ_FREQUENCY,
+                Duration::from_millis(BUZZ_DURATION_MS),
+            ))),
+            PlaybackSettings::DESPAWN,
+        ));
+    }
+}
+
+pub fn toggle_deposit_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DepositOverlay>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyO) {
+        overlay.active = !overlay.active;
+    }
+}
+
+pub fn draw_deposit_overlay(
+    world: Res<WorldRes>,
+    overlay: Res<DepositOverlay>,
+    camera_query: Query<&Transform, (With<Camera2d>, Without<PhotoCamera>)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut tile_sprite_query: Query<&mut Sprite, With<TileSprite>>,
+    mut was_active: Local<bool>,
+    mut gizmos: Gizmos,
+) {
+    if overlay.active || *was_active {
+        let alpha = if overlay.active { 0.25 } else { 1.0 };
+        // Left alone when already faded, so LOD doesn't see every sprite as redrawn.
+        for mut sprite in tile_sprite_query.iter_mut() {
+            if sprite.color.alpha() != alpha {
+                sprite.color.set_alpha(alpha);
+            }
+        }
+    }
+    *was_active = overlay.active;
+
+    if !overlay.active {
+        return;
+    }
+    let (Ok(camera_transform), Ok(window)) = (camera_query.single(), windows.single()) else {
+        return;
+    };
+
+    let center = camera_transform.translation.truncate();
+    let half_size = Vec2::new(window.width(), window.height()) * camera_transform.scale.x / 2.0;
+    let view = Rect::from_center_half_size(center, half_size + Vec2::splat(TILE_SIZE));
+
+    for (pos, terrain) in world.terrain.iter() {
+        let center = Vec2::new(pos.x as f32 * TILE_SIZE, pos.y as f32 * TILE_SIZE);
+        if !view.contains(center) {
+            continue;
+        }
+        let Some(color) = get_deposit_color(*terrain) else {
+            continue;
+        };
+        let color = if world.tiles.contains_key(pos) {
+            color.with_alpha(0.35)
+        } else {
+            color
+        };
+        gizmos.rect_2d(center, Vec2::splat(TILE_SIZE * 0.9), color);
+    }
+}
+
+// Shows what a beacon would reach while one is being placed, along with the beacons already built.
+pub fn draw_beacon_ranges(
+    world: Res<WorldRes>,
+    placer: Res<Placer>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &Transform), Without<PhotoCamera>>,
+    tile_sprite_query: Query<(&TileSprite, &Visibility)>,
+    mut gizmos: Gizmos,
+) {
+    if placer.tile_type != (1, 3) {
+        return;
+    }
+
+    let size = Vec2::splat((BEACON_RADIUS * 2 + 1) as f32 * TILE_SIZE);
+    for (tile_sprite, visibility) in tile_sprite_query.iter() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        let Some(beacon) = world
+            .tiles
+            .get(&tile_sprite.pos)
+            .and_then(|(tile, _)| tile.as_any().downcast_ref::<Beacon>())
+        else {
+            continue;
+        };
+
+        let color = if beacon.powered() {
+            Color::srgba(0.4, 0.8, 1.0, 0.6)
+        } else {
+            Color::srgba(0.5, 0.5, 0.5, 0.4)
+        };
+        let center = Vec2::new(
+            beacon.position.x as f32 * TILE_SIZE,
+            beacon.position.y as f32 * TILE_SIZE,
+        );
+        gizmos.rect_2d(center, size, color);
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.single(), camera_query.single())
+    else {
+        return;
+    };
+    if let Some(pos) = cursor_grid_position(window, camera, camera_transform) {
+        let center = Vec2::new(pos.x as f32 * TILE_SIZE, pos.y as f32 * TILE_SIZE);
+        gizmos.rect_2d(center, size, Color::srgb(0.4, 0.8, 1.0));
+    }
+}
+
+pub fn draw_belt_motion(
+    world: Res<WorldRes>,
+    placer: Res<Placer>,
+    tile_sprite_query: Query<(&TileSprite, &Visibility)>,
+    mut gizmos: Gizmos,
+) {
+    if placer.zoom_level < LOD_ZOOM_THRESHOLD {
+        return;
+    }
+
+    // The surface advances one tile per tick, the same speed as the items riding on it.
+    let phase = world.tick_timer.fraction();
+    let spacing = 1.0 / BELT_STRIPE_COUNT as f32;
+
+    for (tile_sprite, visibility) in tile_sprite_query.iter() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        let Some((tile, _)) = world.tiles.get(&tile_sprite.pos) else {
+            continue;
+        };
+        let Some(conveyor) = tile.as_any().downcast_ref::<Conveyor>() else {
+            continue;
+        };
+
+        let forward = match conveyor.direction {
+            Direction::Up => Vec2::Y,
+            Direction::Down => Vec2::NEG_Y,
+            Direction::Left => Vec2::NEG_X,
+            Direction::Right => Vec2::X,
+        };
+        let side = forward.perp();
+        let center = Vec2::new(
+            tile_sprite.pos.x as f32 * TILE_SIZE,
+            tile_sprite.pos.y as f32 * TILE_SIZE,
+        );
+
+        for i in 0..BELT_STRIPE_COUNT {
+            let offset = ((phase + i as f32 * spacing).fract() - 0.5) * TILE_SIZE * 0.8;
+            let tip = center + forward * (offset + TILE_SIZE * 0.08);
+            let back = center + forward * (offset - TILE_SIZE * 0.08);
+            gizmos.line_2d(back + side * TILE_SIZE * 0.2, tip, BELT_STRIPE_COLOR);
+            gizmos.line_2d(back - side * TILE_SIZE * 0.2, tip, BELT_STRIPE_COLOR);
+        }
+    }
+}
+
+pub fn toggle_alt_mode(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    mut alt_mode: ResMut<AltMode>,
+    mut clicked: Local<bool>,
+) {
+    if keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+        && mouse_button_input.just_pressed(MouseButton::Left)
+    {
+        *clicked = true;
+    }
+
+    if keyboard_input.any_just_released([KeyCode::AltLeft, KeyCode::AltRight]) {
+        if !*clicked {
+            alt_mode.active = !alt_mode.active;
+        }
+        *clicked = false;
+    }
+}
+
+pub fn update_alt_mode_icons(
+    mut commands: Commands,
+    world: Res<WorldRes>,
+    alt_mode: Res<AltMode>,
+    asset_server: Res<AssetServer>,
+    tile_sprite_query: Query<(&TileSprite, &Visibility)>,
+    icon_query: Query<(Entity, &AltModeIcon)>,
+    mut gizmos: Gizmos,
+) {
+    let mut wanted = HashMap::new();
+    if alt_mode.active {
+        for (tile_sprite, visibility) in tile_sprite_query.iter() {
+            if *visibility == Visibility::Hidden {
+                continue;
+            }
+            let Some((tile, _)) = world.tiles.get(&tile_sprite.pos) else {
+                continue;
+            };
+
+            let center = Vec2::new(
+                tile_sprite.pos.x as f32 * TILE_SIZE,
+                tile_sprite.pos.y as f32 * TILE_SIZE,
+            );
+            if let Some(factory) = tile.as_any().downcast_ref::<Factory>() {
+                wanted.insert(tile_sprite.pos, factory.recipe().output);
+            } else if let Some(extractor) = tile.as_any().downcast_ref::<Extractor>() {
+                wanted.insert(tile_sprite.pos, extractor.extractor_type.spawn_item());
+            } else if let Some(storage) = tile.as_any().downcast_ref::<Storage>() {
+                wanted.insert(tile_sprite.pos, storage.storage_type.stored_item());
+
+                // Clustered storages all show the fill of the whole cluster.
+                let (stored, capacity) = world.storage_fill(tile_sprite.pos);
+                let fill = stored as f32 / capacity.max(1) as f32;
+                let left = center + Vec2::new(-TILE_SIZE * 0.4, -TILE_SIZE * 0.4);
+                gizmos.line_2d(
+                    left,
+                    left + Vec2::new(TILE_SIZE * 0.8, 0.0),
+                    Color::srgb(0.2, 0.2, 0.2),
+                );
+                gizmos.line_2d(
+                    left,
+                    left + Vec2::new(TILE_SIZE * 0.8 * fill.min(1.0), 0.0),
+                    Color::srgb(0.3, 0.9, 0.4),
+                );
+            }
+        }
+    }
+
+    for (entity, icon) in icon_query.iter() {
+        if wanted.get(&icon.pos) == Some(&icon.item) {
+            wanted.remove(&icon.pos);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for (pos, item) in wanted {
+        commands.spawn((
+            Sprite {
+                image: asset_server.load(item.sprite()),
+                custom_size: Some(Vec2::splat(TILE_SIZE * 0.45)),
+                ..Default::default()
+            },
+            Transform::from_xyz(
+                pos.x as f32 * TILE_SIZE,
+                pos.y as f32 * TILE_SIZE + TILE_SIZE * 0.1,
+                10.0,
+            ),
+            AltModeIcon { pos, item },
+        ));
+    }
 }
@@ -1,11 +1,39 @@
+pub mod bankruptcy;
+pub mod blueprint_menu;
 pub mod common;
+pub mod condition_menu;
 pub mod core_menu;
 pub mod core_menu_context;
+pub mod dialog;
+pub mod difficulty_menu;
+pub mod factory_menu;
+pub mod goal_widget;
 pub mod inventory;
 pub mod inventory_context;
+pub mod ledger;
+pub mod router_menu;
+pub mod scenario_menu;
+pub mod shop;
+pub mod stats_screen;
+pub mod tile_bar;
+pub mod victory;
 
+pub use bankruptcy::*;
+pub use blueprint_menu::*;
 pub use common::*;
+pub use condition_menu::*;
 pub use core_menu::*;
 pub use core_menu_context::*;
+pub use dialog::*;
+pub use difficulty_menu::*;
+pub use factory_menu::*;
+pub use goal_widget::*;
 pub use inventory::*;
 pub use inventory_context::*;
+pub use ledger::*;
+pub use router_menu::*;
+pub use scenario_menu::*;
+pub use shop::*;
+pub use stats_screen::*;
+pub use tile_bar::*;
+pub use victory::*;
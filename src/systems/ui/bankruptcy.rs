@@ -0,0 +1,159 @@
+use crate::{
+    LOAN_AMOUNT, LOAN_INTEREST_PERCENT, LOAN_REPAYMENT_PERCENT, TICK_LENGTH, components::*,
+    resources::*, systems::spawn_difficulty_menu,
+};
+use bevy::prelude::*;
+
+fn spawn_bankruptcy_menu(commands: &mut Commands, title: &str, body: String) -> Entity {
+    commands
+        .spawn((
+            Node {
+                width: Val::Vw(30.0),
+                position_type: PositionType::Absolute,
+                left: Val::Vw(35.0),
+                top: Val::Vh(25.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            ZIndex(250),
+            BankruptcyMenu,
+            children![
+                (
+                    Text::new(title),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ),
+                (
+                    Text::new(body),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ),
+            ],
+        ))
+        .id()
+}
+
+fn bankruptcy_button(action: BankruptcyAction, label: String) -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::all(Val::Px(10.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+        BorderRadius::all(Val::Px(5.0)),
+        Interaction::default(),
+        BankruptcyButton { action },
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 18.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+fn spawn_run_summary(commands: &mut Commands, world: &WorldRes) {
+    let seconds = (world.tick_count.max(0) as f32 * TICK_LENGTH) as u64;
+    let lifetime = &world.lifetime;
+    let body = [
+        format!(
+            "Survived {} ticks ({}:{:02}:{:02})",
+            world.tick_count,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ),
+        format!("Tiles placed: {}", lifetime.tiles_placed),
+        format!(
+            "Items produced: {}",
+            lifetime.produced.values().sum::<u64>()
+        ),
+        format!("Money earned: ${}", lifetime.money_earned),
+        format!("Money spent: ${}", lifetime.money_spent),
+        format!("Unpaid loan: ${}", world.loan_owed),
+    ]
+    .join("\n");
+
+    let menu = spawn_bankruptcy_menu(commands, "Run failed", body);
+    commands.entity(menu).with_child(bankruptcy_button(
+        BankruptcyAction::NewWorld,
+        "New world".to_string(),
+    ));
+}
+
+pub fn check_bankruptcy(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    overlay_menu_query: Query<(), OverlayMenuFilter>,
+    world: Res<WorldRes>,
+) {
+    if !settings.bankruptcy || !overlay_menu_query.is_empty() || !world.is_bankrupt() {
+        return;
+    }
+
+    if world.loan_owed > 0 {
+        spawn_run_summary(&mut commands, &world);
+        return;
+    }
+
+    let repay = LOAN_AMOUNT * (100 + LOAN_INTEREST_PERCENT) / 100;
+    let menu = spawn_bankruptcy_menu(
+        &mut commands,
+        "Bankrupt",
+        format!(
+            "You are out of money and tiles, and nothing is left to produce more.\nThe bank offers ${} now. You repay ${} from {}% of every future sale.",
+            LOAN_AMOUNT, repay, LOAN_REPAYMENT_PERCENT
+        ),
+    );
+    commands.entity(menu).with_children(|parent| {
+        parent.spawn(bankruptcy_button(
+            BankruptcyAction::TakeLoan,
+            format!("Take the ${} loan", LOAN_AMOUNT),
+        ));
+        parent.spawn(bankruptcy_button(
+            BankruptcyAction::GiveUp,
+            "Declare bankruptcy".to_string(),
+        ));
+    });
+}
+
+pub fn handle_bankruptcy_interaction(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &BankruptcyButton), Changed<Interaction>>,
+    menu_query: Query<Entity, With<BankruptcyMenu>>,
+    mut world: ResMut<WorldRes>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(action) = button_query
+        .iter()
+        .find(|(interaction, _)| matches!(interaction, Interaction::Pressed))
+        .map(|(_, button)| button.action)
+    else {
+        return;
+    };
+
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    match action {
+        BankruptcyAction::TakeLoan => world.take_loan(),
+        BankruptcyAction::GiveUp => spawn_run_summary(&mut commands, &world),
+        BankruptcyAction::NewWorld => spawn_difficulty_menu(&mut commands, &mut images),
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{components::*, resources::*, systems::ui::spawn_dialog};
+#[cfg(not(target_arch = "wasm32"))]
+use arboard::Clipboard;
+use bevy::prelude::*;
+
+// The web build has no clipboard, so blueprints go through the `blueprint` console command.
+const HAS_CLIPBOARD: bool = cfg!(not(target_arch = "wasm32"));
+
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_to_clipboard(text: String) -> Result<(), String> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn paste_from_clipboard() -> Result<String, String> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn copy_to_clipboard(_text: String) -> Result<(), String> {
+    Err("there is no clipboard in the browser".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn paste_from_clipboard() -> Result<String, String> {
+    Err("there is no clipboard in the browser".to_string())
+}
+
+fn blueprint_button(label: &str, color: Color, action: BlueprintAction) -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(color),
+        BorderRadius::all(Val::Px(5.0)),
+        Interaction::default(),
+        BlueprintButton { action },
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+fn spawn_blueprint_menu(commands: &mut Commands, library: &BlueprintLibrary) {
+    let menu_entity = commands
+        .spawn((
+            Node {
+                width: Val::Vw(40.0),
+                max_height: Val::Vh(70.0),
+                position_type: PositionType::Absolute,
+                left: Val::Vw(30.0),
+                top: Val::Vh(15.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                overflow: Overflow::scroll_y(),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            BlueprintMenu,
+            children![(
+                Text::new("Blueprints"),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            )],
+        ))
+        .id();
+
+    commands.entity(menu_entity).with_children(|parent| {
+        if HAS_CLIPBOARD {
+            parent.spawn(blueprint_button(
+                "Import from clipboard",
+                Color::srgb(0.3, 0.5, 0.7),
+                BlueprintAction::Import,
+            ));
+        } else {
+            parent.spawn((
+                Text::new(
+                    "Open the console with ~ and use `blueprint export <number>` or \
+                     `blueprint import <text>` to share blueprints.",
+                ),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+        }
+
+        if library.blueprints.is_empty() {
+            parent.spawn((
+                Text::new("Hold Ctrl and drag with the pointer to capture a blueprint."),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+        }
+
+        for (index, blueprint) in library.blueprints.iter().enumerate() {
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Percent(100.0),
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(10.0),
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.14, 0.16, 0.19)),
+                    BorderRadius::all(Val::Px(10.0)),
+                    children![
+                        (
+                            Node {
+                                flex_grow: 1.0,
+                                ..default()
+                            },
+                            Text::new(format!(
+                                "{}. {} ({} tiles)",
+                                index + 1,
+                                blueprint.name,
+                                blueprint.tiles.len()
+                            )),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ),
+                        blueprint_button(
+                            "Place",
+                            Color::srgb(0.3, 0.6, 0.3),
+                            BlueprintAction::Place(index),
+                        ),
+                    ],
+                ))
+                .with_children(|row| {
+                    if HAS_CLIPBOARD {
+                        row.spawn(blueprint_button(
+                            "Export",
+                            Color::srgb(0.3, 0.5, 0.7),
+                            BlueprintAction::Export(index),
+                        ));
+                    }
+                    row.spawn(blueprint_button(
+                        "Delete",
+                        Color::srgb(0.6, 0.3, 0.3),
+                        BlueprintAction::Delete(index),
+                    ));
+                });
+        }
+    });
+}
+
+pub fn toggle_blueprint_menu(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    blueprint_menu_query: Query<Entity, With<BlueprintMenu>>,
+    ui_state: Res<UiState>,
+    library: Res<BlueprintLibrary>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyL) || !ui_state.can_open_overlay() {
+        return;
+    }
+
+    if let Ok(entity) = blueprint_menu_query.single() {
+        commands.entity(entity).despawn();
+    } else {
+        spawn_blueprint_menu(&mut commands, &library);
+    }
+}
+
+pub fn handle_blueprint_menu_interaction(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &BlueprintButton), Changed<Interaction>>,
+    blueprint_menu_query: Query<Entity, With<BlueprintMenu>>,
+    mut library: ResMut<BlueprintLibrary>,
+    mut placer: ResMut<Placer>,
+) {
+    let Some(action) = button_query.iter().find_map(|(interaction, button)| {
+        matches!(interaction, Interaction::Pressed).then_some(button.action)
+    }) else {
+        return;
+    };
+
+    match action {
+        BlueprintAction::Place(index) => {
+            library.active = Some(index);
+            placer.tile_type = (0, 1);
+            for entity in blueprint_menu_query.iter() {
+                commands.entity(entity).despawn();
+            }
+            return;
+        }
+        BlueprintAction::Export(index) => {
+            let result = library.blueprints[index]
+                .export()
+                .map_err(|err| err.to_string())
+                .and_then(copy_to_clipboard);
+
+            match result {
+                Ok(()) => spawn_dialog(
+                    &mut commands,
+                    "Blueprint exported",
+                    &[format!(
+                        "\"{}\" was copied to the clipboard.",
+                        library.blueprints[index].name
+                    )],
+                ),
+                Err(err) => spawn_dialog(
+                    &mut commands,
+                    "Export failed",
+                    &[format!("Could not copy the blueprint: {}", err)],
+                ),
+            }
+            return;
+        }
+        BlueprintAction::Delete(index) => {
+            library.blueprints.remove(index);
+            library.active = None;
+        }
+        BlueprintAction::Import => {
+            let result = paste_from_clipboard()
+                .and_then(|string| Blueprint::import(&string).map_err(|err| err.to_string()));
+
+            match result {
+                Ok(blueprint) => library.blueprints.push(blueprint),
+                Err(err) => {
+                    spawn_dialog(
+                        &mut commands,
+                        "Import failed",
+                        &[format!("Could not import the blueprint: {}", err)],
+                    );
+                    return;
+                }
+            }
+        }
+    }
+
+    if let Err(err) = library.save("blueprints.ffs") {
+        eprintln!("Error saving blueprints: {}", err);
+    }
+    for entity in blueprint_menu_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_blueprint_menu(&mut commands, &library);
+}
@@ -1,35 +1,115 @@
-use crate::{WorldRes, components::*};
+use crate::{GameSpeed, MenuStack, Placer, TICK_LENGTH, UiPanel, UiState, WorldRes, components::*};
 use bevy::prelude::*;
 
 pub fn exit_menu(
     mut commands: Commands,
-    inventory_query: Query<Entity, With<Inventory>>,
-    inventory_context_query: Query<Entity, With<InventoryContextMenu>>,
-    core_menu_query: Query<Entity, With<CoreMenu>>,
-    core_context_query: Query<Entity, With<CoreContextMenu>>,
+    mut menu_stack: ResMut<MenuStack>,
+    opened_query: Query<Entity, (ClosableMenuFilter, Added<Node>)>,
+    open_query: Query<(), ClosableMenuFilter>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
-        for entity in inventory_context_query.iter() {
-            commands.entity(entity).despawn();
-        }
-        for entity in core_context_query.iter() {
-            commands.entity(entity).despawn();
-        }
-        for entity in core_menu_query.iter() {
-            commands.entity(entity).despawn();
-        }
-        for entity in inventory_query.iter() {
-            commands.entity(entity).despawn();
-        }
+    menu_stack
+        .menus
+        .retain(|entity| open_query.contains(*entity));
+    menu_stack.menus.extend(opened_query.iter());
+
+    if keyboard_input.just_pressed(KeyCode::Escape)
+        && let Some(entity) = menu_stack.menus.pop()
+    {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn update_ui_state(
+    mut ui_state: ResMut<UiState>,
+    panel_query: Query<(Has<Inventory>, Has<CoreMenu>), PanelMenuFilter>,
+    overlay_query: Query<(), OverlayMenuFilter>,
+) {
+    let panel = panel_query.iter().next().map(|panel| match panel {
+        (true, _) => UiPanel::Inventory,
+        (_, true) => UiPanel::Core,
+        _ => UiPanel::Factory,
+    });
+    let overlay = !overlay_query.is_empty();
+    if ui_state.panel != panel || ui_state.overlay != overlay {
+        *ui_state = UiState { panel, overlay };
     }
 }
 
 pub fn update_money_widget(
     mut money_widget_query: Query<&mut Text, With<MoneyWidget>>,
     world: Res<WorldRes>,
+    game_speed: Res<GameSpeed>,
+    placer: Res<Placer>,
 ) {
     if let Ok(mut text) = money_widget_query.single_mut() {
-        text.0 = format!("${}", world.money);
+        text.0 = if placer.planning {
+            format!("${} (Planning)", world.money)
+        } else if game_speed.paused {
+            format!("${} (Paused)", world.money)
+        } else if game_speed.multiplier > 1 {
+            format!("${} ({}x)", world.money, game_speed.multiplier)
+        } else {
+            format!("${}", world.money)
+        };
+    }
+}
+
+pub fn update_world_event_banner(
+    mut banner_query: Query<(&mut Text, &mut Node), With<WorldEventBanner>>,
+    world: Res<WorldRes>,
+) {
+    let Ok((mut text, mut node)) = banner_query.single_mut() else {
+        return;
+    };
+
+    if let Some(event) = world.world_event {
+        let remaining = (event.ends_at - world.tick_count).max(0) as f32 * TICK_LENGTH;
+        text.0 = format!(
+            "{}: {} ({:.0}s left)",
+            event.kind.name(),
+            event.kind.description(),
+            remaining
+        );
+        node.display = Display::Flex;
+    } else {
+        node.display = Display::None;
+    }
+}
+
+pub fn trade_option(label: String, enabled: bool, option: impl Component) -> impl Bundle {
+    (
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Px(30.0),
+            display: Display::Flex,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            margin: UiRect::bottom(Val::Px(5.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.25, 0.25, 0.25)),
+        BorderRadius::all(Val::Px(3.0)),
+        option,
+        Interaction::default(),
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 16.0,
+                ..Default::default()
+            },
+            TextColor(if enabled {
+                Color::WHITE
+            } else {
+                Color::srgb(0.5, 0.5, 0.5)
+            }),
+        )],
+    )
+}
+
+pub fn auto_sell_label(keep: Option<u32>) -> String {
+    match keep {
+        Some(keep) => format!("Auto-sell: keep {}", keep),
+        None => "Auto-sell: off".to_string(),
     }
 }
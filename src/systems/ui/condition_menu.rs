@@ -0,0 +1,299 @@
+use crate::{SIGNAL_CHANNELS, components::*, resources::*, tiles::*, types::*, utils::*};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+fn condition_label(condition: Option<&SignalCondition>) -> String {
+    match condition {
+        Some(condition) => format!(
+            "Enabled when {} > {}",
+            condition.item.name(),
+            condition.threshold
+        ),
+        None => "No condition (always enabled)".to_string(),
+    }
+}
+
+fn channel_label(channel: Option<&u8>) -> String {
+    match channel {
+        Some(channel) => format!("Channel {}", channel),
+        None => "No channel".to_string(),
+    }
+}
+
+fn condition_button(label: &str, action: ConditionAction) -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+        BorderRadius::all(Val::Px(5.0)),
+        Interaction::default(),
+        ConditionButton { action },
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+pub fn open_condition_menu(
+    mut commands: Commands,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &Transform), Without<PhotoCamera>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    world: Res<WorldRes>,
+    condition_menu_query: Query<Entity, With<ConditionMenu>>,
+) {
+    if !mouse_button_input.just_pressed(MouseButton::Left)
+        || !keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+        || !condition_menu_query.is_empty()
+    {
+        return;
+    }
+
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Some(pos) = cursor_grid_position(window, camera, camera_transform) else {
+        return;
+    };
+    let pos = world.anchor_of(pos);
+    let Some((tile, tile_type)) = world.tiles.get(&pos) else {
+        return;
+    };
+    if tile.as_any().is::<Wire>() || tile.as_any().is::<Core>() {
+        return;
+    }
+    if can_use_channel(tile.as_ref()) {
+        spawn_channel_menu(&mut commands, &world, pos, *tile_type);
+        return;
+    }
+
+    commands.spawn((
+        Node {
+            width: Val::Vw(30.0),
+            position_type: PositionType::Absolute,
+            left: Val::Vw(35.0),
+            top: Val::Vh(25.0),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(20.0)),
+            row_gap: Val::Px(10.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+        BorderRadius::all(Val::Px(10.0)),
+        ConditionMenu { position: pos },
+        children![
+            (
+                Text::new(format!("{} signal condition", get_tile_name(*tile_type))),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ),
+            (
+                Text::new(condition_label(world.conditions.get(&pos))),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                ConditionText,
+            ),
+            (
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                children![
+                    condition_button("< Item", ConditionAction::PreviousItem),
+                    condition_button("Item >", ConditionAction::NextItem),
+                ],
+            ),
+            (
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                children![
+                    condition_button("-10", ConditionAction::Adjust(-10)),
+                    condition_button("-1", ConditionAction::Adjust(-1)),
+                    condition_button("+1", ConditionAction::Adjust(1)),
+                    condition_button("+10", ConditionAction::Adjust(10)),
+                ],
+            ),
+            (
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                children![
+                    condition_button("Remove", ConditionAction::Remove),
+                    condition_button("Close", ConditionAction::Close),
+                ],
+            ),
+        ],
+    ));
+}
+
+// Sensors, portals and displays get a wireless channel picker instead of a condition.
+fn spawn_channel_menu(
+    commands: &mut Commands,
+    world: &WorldRes,
+    pos: Position,
+    tile_type: (u8, u8),
+) {
+    commands.spawn((
+        Node {
+            width: Val::Vw(30.0),
+            position_type: PositionType::Absolute,
+            left: Val::Vw(35.0),
+            top: Val::Vh(25.0),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(20.0)),
+            row_gap: Val::Px(10.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+        BorderRadius::all(Val::Px(10.0)),
+        ConditionMenu { position: pos },
+        children![
+            (
+                Text::new(format!("{} wireless channel", get_tile_name(tile_type))),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ),
+            (
+                Text::new(channel_label(world.channels.get(&pos))),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                ConditionText,
+            ),
+            (
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                children![
+                    condition_button("< Channel", ConditionAction::Channel(-1)),
+                    condition_button("Channel >", ConditionAction::Channel(1)),
+                ],
+            ),
+            (
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                children![
+                    condition_button("Remove", ConditionAction::Remove),
+                    condition_button("Close", ConditionAction::Close),
+                ],
+            ),
+        ],
+    ));
+}
+
+pub fn handle_condition_menu_interaction(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &ConditionButton), Changed<Interaction>>,
+    condition_menu_query: Query<(Entity, &ConditionMenu)>,
+    mut text_query: Query<&mut Text, With<ConditionText>>,
+    mut world: ResMut<WorldRes>,
+) {
+    let Ok((menu_entity, condition_menu)) = condition_menu_query.single() else {
+        return;
+    };
+    let position = condition_menu.position;
+
+    for (interaction, button) in button_query.iter() {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+
+        let item_index = |item: Item| Item::ALL.iter().position(|&i| i == item).unwrap_or(0);
+
+        match button.action {
+            ConditionAction::PreviousItem | ConditionAction::NextItem => {
+                let condition = world.conditions.entry(position).or_insert(SignalCondition {
+                    item: Item::ALL[0],
+                    threshold: 0,
+                });
+                let step = if matches!(button.action, ConditionAction::NextItem) {
+                    1
+                } else {
+                    Item::ALL.len() - 1
+                };
+                condition.item = Item::ALL[(item_index(condition.item) + step) % Item::ALL.len()];
+            }
+            ConditionAction::Adjust(delta) => {
+                let condition = world.conditions.entry(position).or_insert(SignalCondition {
+                    item: Item::ALL[0],
+                    threshold: 0,
+                });
+                condition.threshold = condition.threshold.saturating_add_signed(delta);
+            }
+            ConditionAction::Channel(delta) => {
+                // Stepping past the last channel wraps around through "no channel".
+                let current = world.channels.get(&position).copied().unwrap_or(0) as i32;
+                let channel = (current + delta).rem_euclid(SIGNAL_CHANNELS as i32 + 1) as u8;
+                if channel == 0 {
+                    world.channels.remove(&position);
+                } else {
+                    world.channels.insert(position, channel);
+                }
+                world.mark_dirty(position);
+            }
+            ConditionAction::Remove => {
+                world.conditions.remove(&position);
+                world.channels.remove(&position);
+                world.disabled.remove(&position);
+            }
+            ConditionAction::Close => {
+                commands.entity(menu_entity).despawn();
+                return;
+            }
+        }
+
+        if let Ok(mut text) = text_query.single_mut() {
+            text.0 = if world
+                .tiles
+                .get(&position)
+                .is_some_and(|(tile, _)| can_use_channel(tile.as_ref()))
+            {
+                channel_label(world.channels.get(&position))
+            } else {
+                condition_label(world.conditions.get(&position))
+            };
+        }
+    }
+}
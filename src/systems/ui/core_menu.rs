@@ -1,4 +1,11 @@
-use crate::{components::*, resources::*, tiles::Core, utils::*};
+use crate::{
+    Position, TILE_SIZE,
+    components::*,
+    resources::*,
+    systems::ui::{buy_amount_label, trade_option},
+    tiles::Core,
+    utils::*,
+};
 use bevy::prelude::*;
 
 pub fn handle_core_menu_interaction(
@@ -52,24 +59,27 @@ pub fn handle_core_menu_interaction(
     }
 
     for (interaction, item) in item_query.iter() {
-        if matches!(interaction, Interaction::Pressed) {
-            if let Ok((_, core_menu)) = core_menu_query.single() {
-                if let Some((tile, _)) = world.tiles.get_mut(&core_menu.position) {
-                    if let Some(core) = tile.as_any_mut().downcast_mut::<Core>() {
-                        core.tile_id = item.tile_type;
-                        core.interval = get_tile_core_interval(item.tile_type);
-                        core.ticks = 0;
+        if matches!(interaction, Interaction::Pressed)
+            && !world.is_tile_locked(item.tile_type)
+            && let Ok((_, core_menu)) = core_menu_query.single()
+        {
+            let difficulty = world.difficulty;
+            if let Some((tile, _)) = world.tiles.get_mut(&core_menu.position) {
+                if let Some(core) = tile.as_any_mut().downcast_mut::<Core>() {
+                    core.tile_id = item.tile_type;
+                    core.interval = get_tile_core_interval(item.tile_type, difficulty);
+                    core.ticks = 0;
 
-                        for (mut bg_color, option) in item_bg_query.iter_mut() {
-                            *bg_color = if option.tile_type == item.tile_type {
-                                BackgroundColor(Color::srgb(0.45, 0.67, 0.9))
-                            } else {
-                                BackgroundColor(Color::srgb(0.2, 0.22, 0.25))
-                            };
-                        }
+                    for (mut bg_color, option) in item_bg_query.iter_mut() {
+                        *bg_color = if option.tile_type == item.tile_type {
+                            BackgroundColor(Color::srgb(0.45, 0.67, 0.9))
+                        } else {
+                            BackgroundColor(Color::srgb(0.2, 0.22, 0.25))
+                        };
                     }
                 }
             }
+            world.mark_dirty(core_menu.position);
         }
     }
 
@@ -78,58 +88,72 @@ pub fn handle_core_menu_interaction(
             if let Ok((entity, _)) = core_menu_query.single() {
                 commands.entity(entity).despawn();
             }
+        } else if matches!(interaction, Interaction::Pressed) && name.as_str() == "core_output" {
+            if let Ok((_, core_menu)) = core_menu_query.single() {
+                if let Some((tile, _)) = world.tiles.get_mut(&core_menu.position) {
+                    if let Some(core) = tile.as_any_mut().downcast_mut::<Core>() {
+                        core.eject = !core.eject;
+                    }
+                }
+                world.mark_dirty(core_menu.position);
+            }
         }
     }
 
     if mouse_button_input.just_pressed(MouseButton::Right) {
         for (interaction, item) in item_query.iter() {
-            if matches!(interaction, Interaction::Hovered) {
+            if matches!(interaction, Interaction::Hovered) && !world.is_tile_locked(item.tile_type)
+            {
                 for entity in context_menu_query.iter() {
                     commands.entity(entity).despawn();
                 }
 
-                commands.spawn((
-                    Node {
-                        width: Val::Px(150.0),
-                        height: Val::Auto,
-                        position_type: PositionType::Absolute,
-                        right: Val::Px(10.0),
-                        top: Val::Px(10.0),
-                        display: Display::Flex,
-                        flex_direction: FlexDirection::Column,
-                        padding: UiRect::all(Val::Px(5.0)),
-                        ..default()
-                    },
-                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
-                    BorderRadius::all(Val::Px(5.0)),
-                    CoreContextMenu,
-                    ZIndex(100),
-                    children![(
+                commands
+                    .spawn((
                         Node {
-                            width: Val::Percent(100.0),
-                            height: Val::Px(30.0),
+                            width: Val::Px(180.0),
+                            height: Val::Auto,
+                            position_type: PositionType::Absolute,
+                            right: Val::Px(10.0),
+                            top: Val::Px(10.0),
                             display: Display::Flex,
-                            align_items: AlignItems::Center,
-                            justify_content: JustifyContent::Center,
-                            margin: UiRect::bottom(Val::Px(5.0)),
+                            flex_direction: FlexDirection::Column,
+                            padding: UiRect::all(Val::Px(5.0)),
                             ..default()
                         },
-                        BackgroundColor(Color::srgb(0.25, 0.25, 0.25)),
-                        BorderRadius::all(Val::Px(3.0)),
-                        BuyOption {
-                            tile_type: item.tile_type
-                        },
-                        Interaction::default(),
-                        children![(
-                            Text::new(format!("Buy (${})", get_tile_price(item.tile_type))),
-                            TextFont {
-                                font_size: 16.0,
-                                ..Default::default()
+                        BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                        BorderRadius::all(Val::Px(5.0)),
+                        CoreContextMenu,
+                        ZIndex(100),
+                    ))
+                    .with_children(|parent| {
+                        let price = get_tile_price(item.tile_type, world.difficulty);
+                        let affordable = world.money / price.max(1);
+                        for quantity in TradeQuantity::ALL {
+                            let amount = quantity.amount(affordable);
+                            let label = if quantity == TradeQuantity::Max {
+                                format!("Buy max ({}, ${})", amount, amount * price)
+                            } else {
+                                format!("Buy x{} (${})", amount, amount * price)
+                            };
+                            parent.spawn(trade_option(
+                                label,
+                                amount > 0 && amount <= affordable,
+                                BuyOption {
+                                    tile_type: item.tile_type,
+                                    quantity,
+                                },
+                            ));
+                        }
+                        parent.spawn(trade_option(
+                            buy_amount_label(0, price),
+                            false,
+                            BuyAmountField {
+                                tile_type: item.tile_type,
+                                amount: 0,
                             },
-                            TextColor(Color::WHITE),
-                        )]
-                    )],
-                ));
+                        ));
+                    });
                 break;
             }
         }
@@ -168,18 +192,11 @@ fn spawn_category_items(
     panel_entity: Entity,
     selected_tile_id: (u8, u8),
 ) {
-    let tile_types = match category {
-        1 => vec![(1, 2)],
-        2 => vec![(2, 1), (2, 2), (2, 3)],
-        3 => vec![(3, 1), (3, 2), (3, 3)],
-        4 => vec![(4, 1), (4, 2), (4, 3), (4, 4), (4, 5)],
-        5 => vec![(5, 1), (5, 2), (5, 3)],
-        _ => vec![],
-    };
+    let tile_types = get_category_tile_types(category);
 
     for tile_type in tile_types {
         let count = *world.resources.get(&tile_type).unwrap_or(&0);
-        let interval = get_tile_core_interval(tile_type);
+        let interval = get_tile_core_interval(tile_type, world.difficulty);
         let is_selected = tile_type == selected_tile_id;
 
         let tile_entity = commands
@@ -240,6 +257,18 @@ fn spawn_category_items(
                     },
                 ));
 
+                if world.is_tile_locked(tile_type) {
+                    parent.spawn((
+                        Text::new("Locked"),
+                        TextFont {
+                            font_size: 12.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::srgb(1.0, 0.5, 0.5)),
+                    ));
+                    return;
+                }
+
                 parent.spawn((
                     Text::new(format!("Available: {}", count)),
                     TextFont {
@@ -263,6 +292,45 @@ fn spawn_category_items(
     }
 }
 
+pub fn toggle_core_menu(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    camera: Query<&Transform, (With<Camera>, Without<PhotoCamera>)>,
+    core_menu_query: Query<Entity, With<CoreMenu>>,
+    ui_state: Res<UiState>,
+    world: Res<WorldRes>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyC) {
+        return;
+    }
+    if !core_menu_query.is_empty() {
+        for entity in core_menu_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    if !ui_state.can_open(UiPanel::Core) {
+        return;
+    }
+
+    // Opens the Core closest to the middle of the screen.
+    let center = camera.single().map_or(Vec2::ZERO, |transform| {
+        transform.translation.truncate() / TILE_SIZE
+    });
+    let nearest = world
+        .tiles
+        .iter()
+        .filter_map(|(pos, (tile, _))| Some((*pos, tile.as_any().downcast_ref::<Core>()?)))
+        .min_by(|(a, _), (b, _)| {
+            let distance =
+                |pos: &Position| Vec2::new(pos.x as f32, pos.y as f32).distance_squared(center);
+            distance(a).total_cmp(&distance(b))
+        });
+    if let Some((pos, core)) = nearest {
+        spawn_core_menu(&mut commands, pos, core);
+    }
+}
+
 pub fn update_core_menu(
     core_menu_query: Query<&CoreMenu>,
     mut category_query: Query<(&CoreCategory, &mut BackgroundColor)>,
@@ -284,9 +352,329 @@ pub fn update_core_menu(
                 for (mut text, name) in text_query.iter_mut() {
                     if name.as_str() == "core_progress" {
                         text.0 = format!("Progress: {}/{} seconds", core.ticks, core.interval);
+                    } else if name.as_str() == "core_output_label" {
+                        text.0 = core_output_label(core.eject).to_string();
                     }
                 }
             }
         }
     }
 }
+
+pub fn core_output_label(eject: bool) -> &'static str {
+    if eject {
+        "Output: Adjacent belt"
+    } else {
+        "Output: Inventory"
+    }
+}
+
+pub fn spawn_core_menu(commands: &mut Commands, position: Position, core: &Core) {
+    commands.spawn((
+        Node {
+            width: Val::Vw(80.0),
+            height: Val::Vh(80.0),
+            position_type: PositionType::Absolute,
+            left: Val::Vw(10.0),
+            top: Val::Vh(10.0),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(20.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+        BorderRadius::all(Val::Px(10.0)),
+        CoreMenu {
+            position,
+            selected_category: 1,
+        },
+        children![
+            (
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Px(40.0),
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(
+                    Text::new("Core Configuration"),
+                    TextFont {
+                        font_size: 24.0,
+                        ..Default::default()
+                    },
+                    TextColor(Color::WHITE)
+                )],
+            ),
+            (
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Px(100.0),
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                children![
+                    (
+                        Text::new(format!(
+                            "Current production: {} ({})",
+                            get_tile_name(core.tile_id),
+                            format_tile_id(core.tile_id)
+                        )),
+                        TextFont {
+                            font_size: 16.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::WHITE),
+                        Node {
+                            margin: UiRect::bottom(Val::Px(10.0)),
+                            ..Default::default()
+                        }
+                    ),
+                    (
+                        Text::new(format!(
+                            "Progress: {}/{} seconds",
+                            core.ticks, core.interval
+                        )),
+                        TextFont {
+                            font_size: 16.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                        Name::new("core_progress")
+                    ),
+                    (
+                        Button,
+                        Node {
+                            width: Val::Px(220.0),
+                            height: Val::Px(30.0),
+                            margin: UiRect::top(Val::Px(10.0)),
+                            align_items: AlignItems::Center,
+                            justify_content: JustifyContent::Center,
+                            ..Default::default()
+                        },
+                        BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+                        BorderRadius::all(Val::Px(5.0)),
+                        Name::new("core_output"),
+                        children![(
+                            Text::new(core_output_label(core.eject)),
+                            TextFont {
+                                font_size: 14.0,
+                                ..Default::default()
+                            },
+                            TextColor(Color::WHITE),
+                            Name::new("core_output_label")
+                        )],
+                    )
+                ],
+            ),
+            (
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    ..Default::default()
+                },
+                children![
+                    (
+                        Node {
+                            width: Val::Percent(25.0),
+                            height: Val::Percent(100.0),
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Column,
+                            padding: UiRect::all(Val::Px(10.0)),
+                            row_gap: Val::Px(10.0),
+                            ..Default::default()
+                        },
+                        BackgroundColor(Color::srgb(0.14, 0.16, 0.19)),
+                        BorderRadius::all(Val::Px(10.0)),
+                        children![
+                            (
+                                Button,
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Px(50.0),
+                                    align_items: AlignItems::Center,
+                                    justify_content: JustifyContent::Center,
+                                    ..Default::default()
+                                },
+                                BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+                                CoreCategory { category: 1 },
+                                Interaction::default(),
+                                BorderRadius::all(Val::Px(10.0)),
+                                children![(
+                                    Text::new("1: Portals"),
+                                    TextFont {
+                                        font_size: 18.0,
+                                        ..Default::default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                    TextLayout {
+                                        justify: JustifyText::Center,
+                                        ..Default::default()
+                                    }
+                                )],
+                            ),
+                            (
+                                Button,
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Px(50.0),
+                                    align_items: AlignItems::Center,
+                                    justify_content: JustifyContent::Center,
+                                    ..Default::default()
+                                },
+                                BackgroundColor(Color::srgb(0.2, 0.22, 0.25)),
+                                CoreCategory { category: 2 },
+                                Interaction::default(),
+                                BorderRadius::all(Val::Px(10.0)),
+                                children![(
+                                    Text::new("2: Conveyors"),
+                                    TextFont {
+                                        font_size: 18.0,
+                                        ..Default::default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                    TextLayout {
+                                        justify: JustifyText::Center,
+                                        ..Default::default()
+                                    }
+                                )],
+                            ),
+                            (
+                                Button,
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Px(50.0),
+                                    align_items: AlignItems::Center,
+                                    justify_content: JustifyContent::Center,
+                                    ..Default::default()
+                                },
+                                BackgroundColor(Color::srgb(0.2, 0.22, 0.25)),
+                                CoreCategory { category: 3 },
+                                Interaction::default(),
+                                BorderRadius::all(Val::Px(10.0)),
+                                children![(
+                                    Text::new("3: Extractors"),
+                                    TextFont {
+                                        font_size: 18.0,
+                                        ..Default::default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                    TextLayout {
+                                        justify: JustifyText::Center,
+                                        ..Default::default()
+                                    }
+                                )],
+                            ),
+                            (
+                                Button,
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Px(50.0),
+                                    align_items: AlignItems::Center,
+                                    justify_content: JustifyContent::Center,
+                                    ..Default::default()
+                                },
+                                BackgroundColor(Color::srgb(0.2, 0.22, 0.25)),
+                                CoreCategory { category: 4 },
+                                Interaction::default(),
+                                BorderRadius::all(Val::Px(10.0)),
+                                children![(
+                                    Text::new("4: Factories"),
+                                    TextFont {
+                                        font_size: 18.0,
+                                        ..Default::default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                    TextLayout {
+                                        justify: JustifyText::Center,
+                                        ..Default::default()
+                                    }
+                                )],
+                            ),
+                            (
+                                Button,
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Px(50.0),
+                                    align_items: AlignItems::Center,
+                                    justify_content: JustifyContent::Center,
+                                    ..Default::default()
+                                },
+                                BackgroundColor(Color::srgb(0.2, 0.22, 0.25)),
+                                CoreCategory { category: 5 },
+                                Interaction::default(),
+                                BorderRadius::all(Val::Px(10.0)),
+                                children![(
+                                    Text::new("5: Storage"),
+                                    TextFont {
+                                        font_size: 18.0,
+                                        ..Default::default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                    TextLayout {
+                                        justify: JustifyText::Center,
+                                        ..Default::default()
+                                    }
+                                )],
+                            ),
+                        ],
+                    ),
+                    (
+                        Node {
+                            width: Val::Percent(75.0),
+                            height: Val::Percent(100.0),
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Row,
+                            flex_wrap: FlexWrap::Wrap,
+                            align_content: AlignContent::FlexStart,
+                            padding: UiRect::all(Val::Px(15.0)),
+                            row_gap: Val::Px(15.0),
+                            column_gap: Val::Px(15.0),
+                            ..Default::default()
+                        },
+                        BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+                        CoreItemsPanel,
+                    ),
+                ],
+            ),
+            (
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Px(40.0),
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    margin: UiRect::top(Val::Px(20.0)),
+                    ..default()
+                },
+                children![(
+                    Button,
+                    Node {
+                        width: Val::Px(120.0),
+                        height: Val::Px(40.0),
+                        align_content: AlignContent::Center,
+                        justify_content: JustifyContent::Center,
+                        display: Display::Grid,
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.6, 0.3, 0.3)),
+                    BorderRadius::all(Val::Px(5.0)),
+                    Interaction::default(),
+                    Name::new("close_button"),
+                    children![(
+                        Text::new("Close"),
+                        TextFont {
+                            font_size: 16.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    )]
+                )],
+            )
+        ],
+    ));
+}
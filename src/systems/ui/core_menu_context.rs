@@ -1,5 +1,33 @@
-use crate::{components::*, get_tile_price, resources::*};
-use bevy::prelude::*;
+use crate::{BUY_AMOUNT_MAX, components::*, get_tile_name, get_tile_price, resources::*};
+use bevy::{
+    ecs::system::SystemParam,
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
+
+pub fn buy_amount_label(amount: u32, price: u32) -> String {
+    if amount == 0 {
+        "Type an amount to buy".to_string()
+    } else {
+        format!("Buy x{} (${})", amount, amount as u64 * price as u64)
+    }
+}
+
+// The typed quantity field and the keys typed into it.
+#[derive(SystemParam)]
+pub struct BuyAmountInput<'w, 's> {
+    key_events: EventReader<'w, 's, KeyboardInput>,
+    field_query: Query<
+        'w,
+        's,
+        (
+            Ref<'static, Interaction>,
+            &'static mut BuyAmountField,
+            &'static Children,
+        ),
+    >,
+    text_query: Query<'w, 's, (&'static mut Text, &'static mut TextColor)>,
+}
 
 pub fn handle_core_context_menu(
     mut commands: Commands,
@@ -7,22 +35,82 @@ pub fn handle_core_context_menu(
     context_menu_query: Query<Entity, With<CoreContextMenu>>,
     mut world: ResMut<WorldRes>,
     mouse_input: Res<ButtonInput<MouseButton>>,
+    mut amount_input: BuyAmountInput,
 ) {
-    for (interaction, sell_option) in buy_interaction_query.iter() {
+    for (interaction, buy_option) in buy_interaction_query.iter() {
         if matches!(interaction, Interaction::Pressed) {
             if let Ok(entity) = context_menu_query.single() {
-                if world.money >= get_tile_price(sell_option.tile_type) {
-                    world.money -= get_tile_price(sell_option.tile_type);
-                    *world
-                        .resources
-                        .entry(sell_option.tile_type)
-                        .or_insert(0_u32) += 1;
+                let price = get_tile_price(buy_option.tile_type, world.difficulty);
+                let amount = buy_option.quantity.amount(world.money / price.max(1));
+                let total = price as u64 * amount as u64;
+                if amount > 0 && world.money as u64 >= total {
+                    world.spend(
+                        total as u32,
+                        LedgerReason::Purchase,
+                        format!("{} x{}", get_tile_name(buy_option.tile_type), amount),
+                    );
+                    *world.resources.entry(buy_option.tile_type).or_insert(0_u32) += amount;
                     commands.entity(entity).despawn();
                 }
             }
         }
     }
-    if mouse_input.just_pressed(MouseButton::Left) && buy_interaction_query.is_empty() {
+    let typed: Vec<Key> = amount_input
+        .key_events
+        .read()
+        .filter(|event| event.state.is_pressed())
+        .map(|event| event.logical_key.clone())
+        .collect();
+    let mut field_pressed = false;
+    if let Ok((interaction, mut field, children)) = amount_input.field_query.single_mut() {
+        let before = field.amount;
+        for key in typed {
+            match key {
+                Key::Character(text) => {
+                    for digit in text.chars().filter_map(|c| c.to_digit(10)) {
+                        field.amount = (field.amount * 10 + digit).min(BUY_AMOUNT_MAX);
+                    }
+                }
+                Key::Backspace => field.amount /= 10,
+                _ => {}
+            }
+        }
+
+        let price = get_tile_price(field.tile_type, world.difficulty);
+        let total = field.amount as u64 * price as u64;
+        let affordable = field.amount > 0 && total <= world.money as u64;
+        if field.amount != before {
+            for child in children.iter() {
+                if let Ok((mut text, mut color)) = amount_input.text_query.get_mut(child) {
+                    text.0 = buy_amount_label(field.amount, price);
+                    color.0 = if affordable {
+                        Color::WHITE
+                    } else {
+                        Color::srgb(0.5, 0.5, 0.5)
+                    };
+                }
+            }
+        }
+
+        field_pressed = interaction.is_changed() && matches!(*interaction, Interaction::Pressed);
+        if field_pressed
+            && affordable
+            && let Ok(entity) = context_menu_query.single()
+        {
+            world.spend(
+                total as u32,
+                LedgerReason::Purchase,
+                format!("{} x{}", get_tile_name(field.tile_type), field.amount),
+            );
+            *world.resources.entry(field.tile_type).or_insert(0_u32) += field.amount;
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if mouse_input.just_pressed(MouseButton::Left)
+        && buy_interaction_query.is_empty()
+        && !field_pressed
+    {
         if let Ok(entity) = context_menu_query.single() {
             commands.entity(entity).despawn();
         }
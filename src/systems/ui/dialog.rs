@@ -0,0 +1,95 @@
+use crate::components::*;
+use bevy::prelude::*;
+
+pub fn spawn_dialog(commands: &mut Commands, title: &str, lines: &[String]) {
+    let dialog_entity = commands
+        .spawn((
+            Node {
+                width: Val::Vw(40.0),
+                height: Val::Auto,
+                position_type: PositionType::Absolute,
+                left: Val::Vw(30.0),
+                top: Val::Vh(20.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            Dialog,
+            children![(
+                Text::new(title),
+                TextFont {
+                    font_size: 24.0,
+                    ..Default::default()
+                },
+                TextColor(Color::WHITE),
+            )],
+        ))
+        .id();
+
+    commands.entity(dialog_entity).with_children(|parent| {
+        for line in lines.iter() {
+            parent.spawn((
+                Text::new(line),
+                TextFont {
+                    font_size: 16.0,
+                    ..Default::default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        }
+
+        parent.spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(40.0),
+                display: Display::Flex,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            children![(
+                Button,
+                Node {
+                    width: Val::Px(120.0),
+                    height: Val::Px(40.0),
+                    align_content: AlignContent::Center,
+                    justify_content: JustifyContent::Center,
+                    display: Display::Grid,
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.6, 0.3, 0.3)),
+                BorderRadius::all(Val::Px(5.0)),
+                Interaction::default(),
+                Name::new("close_button"),
+                children![(
+                    Text::new("Close"),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                )]
+            )],
+        ));
+    });
+}
+
+pub fn handle_dialogs(
+    mut commands: Commands,
+    dialog_query: Query<Entity, With<Dialog>>,
+    close_button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    let close_pressed = close_button_query.iter().any(|(interaction, name)| {
+        matches!(interaction, Interaction::Pressed) && name.as_str() == "close_button"
+    });
+
+    if close_pressed || keyboard_input.just_pressed(KeyCode::Escape) {
+        for entity in dialog_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use crate::{SAVE_THUMBNAIL_SIZE, components::*, resources::*, types::*};
+use bevy::{
+    asset::RenderAssetUsages,
+    image::ImageSampler,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+fn thumbnail_image(header: &SaveHeader) -> Image {
+    let data = header
+        .thumbnail
+        .chunks_exact(3)
+        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+        .collect();
+    let mut image = Image::new(
+        Extent3d {
+            width: SAVE_THUMBNAIL_SIZE as u32,
+            height: SAVE_THUMBNAIL_SIZE as u32,
+            ..default()
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+pub fn spawn_difficulty_menu(commands: &mut Commands, images: &mut Assets<Image>) {
+    // Read from the header alone, so it shows even when the rest of the save can't be loaded.
+    let saved = WorldRes::read_save_header("savegame.ffs")
+        .ok()
+        .filter(|header| {
+            header.thumbnail.len() == (SAVE_THUMBNAIL_SIZE * SAVE_THUMBNAIL_SIZE * 3) as usize
+        });
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Vw(30.0),
+                position_type: PositionType::Absolute,
+                left: Val::Vw(35.0),
+                top: Val::Vh(25.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            ZIndex(200),
+            DifficultyMenu,
+            children![(
+                Text::new("Choose a difficulty"),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            )],
+        ))
+        .with_children(|parent| {
+            if let Some(header) = saved {
+                parent.spawn((
+                    Node {
+                        display: Display::Flex,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    children![
+                        (
+                            ImageNode::new(images.add(thumbnail_image(&header))),
+                            Node {
+                                width: Val::Px(64.0),
+                                height: Val::Px(64.0),
+                                ..default()
+                            },
+                        ),
+                        (
+                            Text::new(format!(
+                                "Saved world: ${} and {} tiles after {}:{:02} played",
+                                header.money,
+                                header.tile_count,
+                                header.playtime / 3600,
+                                header.playtime / 60 % 60
+                            )),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.85, 0.85, 0.85)),
+                        ),
+                    ],
+                ));
+            }
+
+            for difficulty in Difficulty::ALL {
+                parent.spawn((
+                    Button,
+                    Node {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+                    BorderRadius::all(Val::Px(5.0)),
+                    Interaction::default(),
+                    DifficultyButton { difficulty },
+                    children![
+                        (
+                            Text::new(difficulty.name()),
+                            TextFont {
+                                font_size: 18.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ),
+                        (
+                            Text::new(difficulty.description()),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.85, 0.85, 0.85)),
+                        ),
+                    ],
+                ));
+            }
+
+            parent.spawn((
+                Button,
+                Node {
+                    padding: UiRect::all(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+                BorderRadius::all(Val::Px(5.0)),
+                Interaction::default(),
+                OpenScenarioMenuButton,
+                children![(
+                    Text::new("Scenarios..."),
+                    TextFont {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                )],
+            ));
+        });
+}
+
+// Second step of starting a new world, shown once a difficulty is picked.
+pub fn spawn_starter_kit_menu(
+    commands: &mut Commands,
+    difficulty: Difficulty,
+    world_size: WorldSize,
+) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Vw(30.0),
+                position_type: PositionType::Absolute,
+                left: Val::Vw(35.0),
+                top: Val::Vh(25.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            ZIndex(200),
+            DifficultyMenu,
+            children![(
+                Text::new(format!("Choose a starter kit ({})", difficulty.name())),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            )],
+        ))
+        .with_children(|parent| {
+            for kit in StarterKit::ALL {
+                parent.spawn((
+                    Button,
+                    Node {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+                    BorderRadius::all(Val::Px(5.0)),
+                    Interaction::default(),
+                    StarterKitButton {
+                        difficulty,
+                        kit,
+                        world_size,
+                    },
+                    children![
+                        (
+                            Text::new(kit.name()),
+                            TextFont {
+                                font_size: 18.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ),
+                        (
+                            Text::new(kit.description()),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.85, 0.85, 0.85)),
+                        ),
+                    ],
+                ));
+            }
+
+            let size_label = match world_size.radius() {
+                Some(radius) => format!(
+                    "World size: {} ({1}x{1} tiles)",
+                    world_size.name(),
+                    radius * 2 + 1
+                ),
+                None => format!("World size: {}", world_size.name()),
+            };
+            parent.spawn((
+                Button,
+                Node {
+                    padding: UiRect::all(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+                BorderRadius::all(Val::Px(5.0)),
+                Interaction::default(),
+                WorldSizeButton {
+                    difficulty,
+                    world_size,
+                },
+                children![(
+                    Text::new(size_label),
+                    TextFont {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                )],
+            ));
+        });
+}
+
+pub fn handle_difficulty_menu_interaction(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &DifficultyButton), Changed<Interaction>>,
+    kit_button_query: Query<(&Interaction, &StarterKitButton), Changed<Interaction>>,
+    menu_query: Query<Entity, With<DifficultyMenu>>,
+    chunk_query: Query<Entity, With<TerrainChunk>>,
+    tile_sprite_query: Query<&TileSprite>,
+    mut world: ResMut<WorldRes>,
+) {
+    if let Some(difficulty) = button_query
+        .iter()
+        .find(|(interaction, _)| matches!(interaction, Interaction::Pressed))
+        .map(|(_, button)| button.difficulty)
+    {
+        for entity in menu_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        spawn_starter_kit_menu(&mut commands, difficulty, WorldSize::default());
+        return;
+    }
+
+    let Some((difficulty, kit, world_size)) = kit_button_query
+        .iter()
+        .find(|(interaction, _)| matches!(interaction, Interaction::Pressed))
+        .map(|(_, button)| (button.difficulty, button.kit, button.world_size))
+    else {
+        return;
+    };
+
+    let mut new_world = WorldRes::with_starter_kit(difficulty, kit);
+    new_world.world_radius = world_size.radius();
+    start_new_world(
+        &mut commands,
+        &mut world,
+        new_world,
+        &tile_sprite_query,
+        &chunk_query,
+    );
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Replaces the world with a new one and clears out what the old one left behind.
+pub fn start_new_world(
+    commands: &mut Commands,
+    world: &mut WorldRes,
+    new_world: WorldRes,
+    tile_sprite_query: &Query<&TileSprite>,
+    chunk_query: &Query<Entity, With<TerrainChunk>>,
+) {
+    // A new revision makes the zone overlay drop the old world's zones.
+    let zone_revision = world.zone_revision + 1;
+    *world = new_world;
+    world.zone_revision = zone_revision;
+    // The old world's production doesn't count towards the new one's stats.
+    commands.insert_resource(ProductionStats::starting_at(world.tick_count));
+    // Hotkeys saved with the old world don't carry over to the new one.
+    commands.queue(|ecs: &mut World| {
+        let hotkeys = Hotkeys::new(HotkeyMap::new(), ecs.resource::<Settings>());
+        ecs.insert_resource(hotkeys);
+    });
+    // Old tile sprites are redrawn or despawned against the new world.
+    for tile_sprite in tile_sprite_query.iter() {
+        world.mark_dirty(tile_sprite.pos);
+    }
+
+    for entity in chunk_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn handle_world_size_interaction(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &WorldSizeButton), Changed<Interaction>>,
+    menu_query: Query<Entity, With<DifficultyMenu>>,
+) {
+    let Some((difficulty, world_size)) = button_query
+        .iter()
+        .find(|(interaction, _)| matches!(interaction, Interaction::Pressed))
+        .map(|(_, button)| (button.difficulty, button.world_size))
+    else {
+        return;
+    };
+
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_starter_kit_menu(&mut commands, difficulty, world_size.next());
+}
@@ -0,0 +1,527 @@
+use crate::{Factory, Item, Position, components::*, factory::FactorySide, resources::*, utils::*};
+use bevy::prelude::*;
+
+pub fn spawn_factory_menu(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    position: Position,
+    factory: &Factory,
+    tile_id: (u8, u8),
+) {
+    let recipe = factory.recipe();
+    let mut inputs: Vec<(Item, u32)> = recipe
+        .inputs
+        .iter()
+        .map(|(item, qty)| (*item, *qty))
+        .collect();
+    inputs.sort_by_key(|(item, _)| item.name());
+
+    let mut capacity: Vec<(Item, u32)> = factory
+        .capacity()
+        .iter()
+        .map(|(item, max)| (*item, *max))
+        .collect();
+    capacity.sort_by_key(|(item, _)| item.name());
+
+    let menu_entity = commands
+        .spawn((
+            Node {
+                width: Val::Vw(50.0),
+                height: Val::Auto,
+                position_type: PositionType::Absolute,
+                left: Val::Vw(25.0),
+                top: Val::Vh(15.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(15.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            FactoryMenu { position },
+            children![(
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Px(40.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(
+                    Text::new(get_tile_name(tile_id)),
+                    TextFont {
+                        font_size: 24.0,
+                        ..Default::default()
+                    },
+                    TextColor(Color::WHITE)
+                )],
+            )],
+        ))
+        .id();
+
+    let recipes = factory.factory_type.recipes();
+
+    commands.entity(menu_entity).with_children(|parent| {
+        if recipes.len() > 1 {
+            parent
+                .spawn(Node {
+                    width: Val::Percent(100.0),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::Center,
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(10.0),
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (recipe_index, option) in recipes.iter().enumerate() {
+                        // Recipes sharing an output, like the Recycler's, are told apart by input.
+                        let label = if recipes
+                            .iter()
+                            .filter(|other| other.output == option.output)
+                            .count()
+                            > 1
+                        {
+                            let inputs: Vec<&str> =
+                                option.inputs.keys().map(|item| item.name()).collect();
+                            format!("{} from {}", option.output.name(), inputs.join(", "))
+                        } else {
+                            option.output.name().to_string()
+                        };
+                        let label = option
+                            .byproducts
+                            .iter()
+                            .fold(label, |label, item| format!("{} + {}", label, item.name()));
+                        // Recipes the inventory doesn't fit can't be picked until it's emptied.
+                        let selectable = recipe_index == factory.recipe_index
+                            || factory.can_select_recipe(recipe_index);
+                        row.spawn((
+                            Button,
+                            Node {
+                                width: Val::Px(110.0),
+                                display: Display::Flex,
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::all(Val::Px(5.0)),
+                                ..default()
+                            },
+                            BackgroundColor(if recipe_index == factory.recipe_index {
+                                Color::srgb(0.45, 0.67, 0.9)
+                            } else {
+                                Color::srgb(0.2, 0.22, 0.25)
+                            }),
+                            BorderRadius::all(Val::Px(5.0)),
+                            FactoryRecipeOption { recipe_index },
+                            Interaction::default(),
+                            children![
+                                (
+                                    Node {
+                                        width: Val::Px(32.0),
+                                        height: Val::Px(32.0),
+                                        ..default()
+                                    },
+                                    ImageNode::new(asset_server.load(option.output.sprite())),
+                                ),
+                                (
+                                    Text::new(label),
+                                    TextFont {
+                                        font_size: 12.0,
+                                        ..Default::default()
+                                    },
+                                    TextColor(if selectable {
+                                        Color::WHITE
+                                    } else {
+                                        Color::srgb(0.5, 0.5, 0.5)
+                                    }),
+                                    TextLayout {
+                                        justify: JustifyText::Center,
+                                        ..Default::default()
+                                    },
+                                )
+                            ],
+                        ));
+                    }
+                });
+        }
+
+        parent
+            .spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.14, 0.16, 0.19)),
+                BorderRadius::all(Val::Px(10.0)),
+            ))
+            .with_children(|row| {
+                for (item, qty) in inputs.iter() {
+                    row.spawn((
+                        Node {
+                            width: Val::Px(32.0),
+                            height: Val::Px(32.0),
+                            ..default()
+                        },
+                        ImageNode::new(asset_server.load(item.sprite())),
+                    ));
+                    row.spawn((
+                        Text::new(format!("x{}", qty)),
+                        TextFont {
+                            font_size: 16.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                }
+                row.spawn((
+                    Text::new("->"),
+                    TextFont {
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                    TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                ));
+                row.spawn((
+                    Node {
+                        width: Val::Px(32.0),
+                        height: Val::Px(32.0),
+                        ..default()
+                    },
+                    ImageNode::new(asset_server.load(recipe.output.sprite())),
+                ));
+                for byproduct in recipe.byproducts.iter() {
+                    row.spawn((
+                        Text::new("+"),
+                        TextFont {
+                            font_size: 20.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                    ));
+                    row.spawn((
+                        Node {
+                            width: Val::Px(32.0),
+                            height: Val::Px(32.0),
+                            ..default()
+                        },
+                        ImageNode::new(asset_server.load(byproduct.sprite())),
+                    ));
+                }
+            });
+
+        parent
+            .spawn(Node {
+                width: Val::Percent(100.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(5.0),
+                ..default()
+            })
+            .with_children(|column| {
+                for (item, max) in capacity.iter() {
+                    column.spawn((
+                        Node {
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.0),
+                            ..default()
+                        },
+                        children![
+                            (
+                                Node {
+                                    width: Val::Px(24.0),
+                                    height: Val::Px(24.0),
+                                    ..default()
+                                },
+                                ImageNode::new(asset_server.load(item.sprite())),
+                            ),
+                            (
+                                Text::new(format!(
+                                    "{}: {}/{}",
+                                    item.name(),
+                                    factory.inventory.get(item).unwrap_or(&0),
+                                    max
+                                )),
+                                TextFont {
+                                    font_size: 16.0,
+                                    ..Default::default()
+                                },
+                                TextColor(Color::WHITE),
+                                FactoryInventoryText { item: *item },
+                            )
+                        ],
+                    ));
+                }
+            });
+
+        parent
+            .spawn(Node {
+                width: Val::Percent(100.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(10.0),
+                ..default()
+            })
+            .with_children(|row| {
+                for side in FactorySide::ALL {
+                    row.spawn((
+                        Node {
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            row_gap: Val::Px(4.0),
+                            ..default()
+                        },
+                        children![
+                            (
+                                Text::new(side.name()),
+                                TextFont {
+                                    font_size: 14.0,
+                                    ..Default::default()
+                                },
+                                TextColor(Color::WHITE),
+                            ),
+                            (
+                                Node {
+                                    display: Display::Flex,
+                                    column_gap: Val::Px(4.0),
+                                    ..default()
+                                },
+                                children![
+                                    factory_side_button(side, true, factory.outputs[side as usize]),
+                                    factory_side_button(side, false, factory.inputs[side as usize]),
+                                ],
+                            )
+                        ],
+                    ));
+                }
+            });
+
+        parent.spawn((
+            Text::new(format!(
+                "Progress: {}/{} ticks",
+                factory.ticks, factory.interval
+            )),
+            TextFont {
+                font_size: 16.0,
+                ..Default::default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            Name::new("factory_progress"),
+        ));
+
+        parent.spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(20.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.1, 0.1, 0.12)),
+            BorderRadius::all(Val::Px(5.0)),
+            children![(
+                Node {
+                    width: Val::Percent(factory_progress(factory, 0) * 100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.3, 0.7, 0.4)),
+                BorderRadius::all(Val::Px(5.0)),
+                FactoryProgressBar,
+            )],
+        ));
+
+        parent.spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(40.0),
+                display: Display::Flex,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            children![(
+                Button,
+                Node {
+                    width: Val::Px(120.0),
+                    height: Val::Px(40.0),
+                    align_content: AlignContent::Center,
+                    justify_content: JustifyContent::Center,
+                    display: Display::Grid,
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.6, 0.3, 0.3)),
+                BorderRadius::all(Val::Px(5.0)),
+                Interaction::default(),
+                Name::new("close_button"),
+                children![(
+                    Text::new("Close"),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                )]
+            )],
+        ));
+    });
+}
+
+fn factory_side_button(side: FactorySide, output: bool, active: bool) -> impl Bundle {
+    (
+        Button,
+        Node {
+            width: Val::Px(40.0),
+            padding: UiRect::all(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(if active {
+            Color::srgb(0.45, 0.67, 0.9)
+        } else {
+            Color::srgb(0.2, 0.22, 0.25)
+        }),
+        BorderRadius::all(Val::Px(5.0)),
+        FactorySideButton { side, output },
+        Interaction::default(),
+        children![(
+            Text::new(if output { "Out" } else { "In" }),
+            TextFont {
+                font_size: 12.0,
+                ..Default::default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+fn factory_progress(factory: &Factory, boost_percent: u32) -> f32 {
+    let interval = boosted_interval(factory.interval, boost_percent);
+    if interval == 0 {
+        return 1.0;
+    }
+    (factory.ticks as f32 / interval as f32).clamp(0.0, 1.0)
+}
+
+pub fn update_factory_menu(
+    factory_menu_query: Query<&FactoryMenu>,
+    world: Res<WorldRes>,
+    mut inventory_text_query: Query<(&mut Text, &FactoryInventoryText)>,
+    mut progress_bar_query: Query<&mut Node, With<FactoryProgressBar>>,
+    mut text_query: Query<(&mut Text, &Name), Without<FactoryInventoryText>>,
+) {
+    if let Ok(factory_menu) = factory_menu_query.single()
+        && let Some((tile, _)) = world.tiles.get(&factory_menu.position)
+        && let Some(factory) = tile.as_any().downcast_ref::<Factory>()
+    {
+        let capacity = factory.capacity();
+        let boost = beacon_boost_percent(&world, factory.position);
+        for (mut text, inventory_text) in inventory_text_query.iter_mut() {
+            text.0 = format!(
+                "{}: {}/{}",
+                inventory_text.item.name(),
+                factory.inventory.get(&inventory_text.item).unwrap_or(&0),
+                capacity.get(&inventory_text.item).unwrap_or(&0)
+            );
+        }
+
+        for mut node in progress_bar_query.iter_mut() {
+            node.width = Val::Percent(factory_progress(factory, boost) * 100.0);
+        }
+
+        for (mut text, name) in text_query.iter_mut() {
+            if name.as_str() == "factory_progress" {
+                text.0 = format!(
+                    "Progress: {}/{} ticks",
+                    factory.ticks,
+                    boosted_interval(factory.interval, boost)
+                );
+                if boost > 0 {
+                    text.0.push_str(&format!(" (beacons -{}%)", boost));
+                }
+            }
+        }
+    }
+}
+
+pub fn handle_factory_menu_interaction(
+    mut commands: Commands,
+    factory_menu_query: Query<(Entity, &FactoryMenu)>,
+    close_button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
+    recipe_option_query: Query<(&Interaction, &FactoryRecipeOption), Changed<Interaction>>,
+    side_button_query: Query<(&Interaction, &FactorySideButton), Changed<Interaction>>,
+    mut world: ResMut<WorldRes>,
+    asset_server: Res<AssetServer>,
+) {
+    let close_pressed = close_button_query.iter().any(|(interaction, name)| {
+        matches!(interaction, Interaction::Pressed) && name.as_str() == "close_button"
+    });
+
+    for (interaction, option) in recipe_option_query.iter() {
+        if matches!(interaction, Interaction::Pressed)
+            && let Ok((entity, factory_menu)) = factory_menu_query.single()
+            && let Some((tile, id)) = world.tiles.get_mut(&factory_menu.position)
+            && let Some(factory) = tile.as_any_mut().downcast_mut::<Factory>()
+        {
+            if !factory.select_recipe(option.recipe_index) {
+                return;
+            }
+            commands.entity(entity).despawn();
+            spawn_factory_menu(
+                &mut commands,
+                &asset_server,
+                factory_menu.position,
+                factory,
+                *id,
+            );
+            world.mark_dirty(factory_menu.position);
+            return;
+        }
+    }
+
+    for (interaction, button) in side_button_query.iter() {
+        if matches!(interaction, Interaction::Pressed)
+            && let Ok((entity, factory_menu)) = factory_menu_query.single()
+            && let Some((tile, id)) = world.tiles.get_mut(&factory_menu.position)
+            && let Some(factory) = tile.as_any_mut().downcast_mut::<Factory>()
+        {
+            let sides = if button.output {
+                &mut factory.outputs
+            } else {
+                &mut factory.inputs
+            };
+            sides[button.side as usize] = !sides[button.side as usize];
+            // A factory always keeps somewhere to put its output.
+            if !factory.outputs.contains(&true) {
+                factory.outputs[button.side as usize] = true;
+            }
+            commands.entity(entity).despawn();
+            spawn_factory_menu(
+                &mut commands,
+                &asset_server,
+                factory_menu.position,
+                factory,
+                *id,
+            );
+            world.mark_dirty(factory_menu.position);
+            return;
+        }
+    }
+
+    for (entity, factory_menu) in factory_menu_query.iter() {
+        let is_factory = world
+            .tiles
+            .get(&factory_menu.position)
+            .is_some_and(|(tile, _)| tile.as_any().is::<Factory>());
+        if close_pressed || !is_factory {
+            commands.entity(entity).despawn();
+        }
+    }
+}
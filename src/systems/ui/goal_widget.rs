@@ -0,0 +1,162 @@
+use crate::{GOAL_TARGET_STEP, components::*, resources::*, types::*};
+use bevy::prelude::*;
+
+fn goal_button(label: &str, action: GoalAction) -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+        BorderRadius::all(Val::Px(5.0)),
+        Interaction::default(),
+        GoalButton { action },
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+pub fn spawn_goal_widget(commands: &mut Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(5.0),
+            top: Val::Vh(6.0),
+            min_width: Val::Vw(15.0),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(8.0)),
+            row_gap: Val::Px(5.0),
+            ..default()
+        },
+        BorderRadius::all(Val::Px(10.0)),
+        BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+        children![
+            (
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                GoalText,
+            ),
+            (
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Px(8.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.1, 0.1, 0.1)),
+                BorderRadius::all(Val::Px(4.0)),
+                children![(
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.3, 0.7, 0.3)),
+                    BorderRadius::all(Val::Px(4.0)),
+                    GoalProgressBar,
+                )],
+            ),
+            (
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(5.0),
+                    ..default()
+                },
+                children![
+                    goal_button("<", GoalAction::PreviousItem),
+                    goal_button(">", GoalAction::NextItem),
+                    goal_button("-", GoalAction::DecreaseTarget),
+                    goal_button("+", GoalAction::IncreaseTarget),
+                    goal_button("Clear", GoalAction::Clear),
+                ],
+            ),
+        ],
+    ));
+}
+
+pub fn handle_goal_interaction(
+    button_query: Query<(&Interaction, &GoalButton), Changed<Interaction>>,
+    mut goal: ResMut<ProductionGoal>,
+    stats: Res<ProductionStats>,
+) {
+    for (interaction, button) in button_query.iter() {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+
+        match button.action {
+            GoalAction::PreviousItem | GoalAction::NextItem => {
+                let step = if matches!(button.action, GoalAction::NextItem) {
+                    1
+                } else {
+                    Item::ALL.len() - 1
+                };
+                let item = match goal.item {
+                    Some(item) => {
+                        let index = Item::ALL.iter().position(|&i| i == item).unwrap_or(0);
+                        Item::ALL[(index + step) % Item::ALL.len()]
+                    }
+                    None => Item::ALL[0],
+                };
+                goal.item = Some(item);
+                goal.baseline = *stats.totals.get(&item).unwrap_or(&0);
+            }
+            GoalAction::DecreaseTarget => {
+                goal.target = goal
+                    .target
+                    .saturating_sub(GOAL_TARGET_STEP)
+                    .max(GOAL_TARGET_STEP);
+            }
+            GoalAction::IncreaseTarget => {
+                goal.target = goal.target.saturating_add(GOAL_TARGET_STEP);
+            }
+            GoalAction::Clear => {
+                goal.item = None;
+            }
+        }
+    }
+}
+
+pub fn update_goal_widget(
+    goal: Res<ProductionGoal>,
+    stats: Res<ProductionStats>,
+    mut text_query: Query<&mut Text, With<GoalText>>,
+    mut bar_query: Query<&mut Node, With<GoalProgressBar>>,
+) {
+    let progress = goal.progress(&stats).min(goal.target as u64);
+
+    if let Ok(mut text) = text_query.single_mut() {
+        text.0 = match goal.item {
+            Some(item) => format!(
+                "Produce {} {}: {}/{}",
+                goal.target,
+                item.name(),
+                progress,
+                goal.target
+            ),
+            None => "No goal pinned".to_string(),
+        };
+    }
+
+    if let Ok(mut node) = bar_query.single_mut() {
+        node.width = Val::Percent(if goal.item.is_some() {
+            progress as f32 / goal.target as f32 * 100.0
+        } else {
+            0.0
+        });
+    }
+}
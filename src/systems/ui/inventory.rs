@@ -1,16 +1,21 @@
-use crate::{components::*, resources::*, utils::*};
-use bevy::prelude::*;
+use crate::{
+    components::*,
+    resources::*,
+    systems::ui::{auto_sell_label, trade_option},
+    utils::*,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
 
 pub fn spawn_inventory(
     mut commands: Commands,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     inventory_query: Query<(Entity, &Inventory)>,
-    core_menu_query: Query<(), With<CoreMenu>>,
+    ui_state: Res<UiState>,
     asset_server: Res<AssetServer>,
     world: Res<WorldRes>,
     placer: Res<Placer>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::KeyE) && core_menu_query.is_empty() {
+    if keyboard_input.just_pressed(KeyCode::KeyE) && ui_state.can_open(UiPanel::Inventory) {
         if let Ok((entity, _)) = inventory_query.single() {
             commands.entity(entity).despawn();
         } else {
@@ -286,20 +291,38 @@ pub fn update_inventory(
     }
 }
 
+// The inventory panel, its category tabs and item buttons, and the context menu an item opens.
+#[derive(SystemParam)]
+pub struct InventoryWidgets<'w, 's> {
+    category_query:
+        Query<'w, 's, (&'static Interaction, &'static InventoryCategory), Changed<Interaction>>,
+    item_query: Query<'w, 's, (&'static Interaction, &'static InventoryItem)>,
+    inventory_query: Query<'w, 's, (Entity, &'static mut Inventory)>,
+    context_menu_query: Query<'w, 's, Entity, With<InventoryContextMenu>>,
+    bg_color_query: Query<'w, 's, (&'static mut BackgroundColor, &'static InventoryItem)>,
+    item_panel_query: Query<'w, 's, Entity, With<InventoryItemsPanel>>,
+    existing_items_query: Query<'w, 's, Entity, With<InventoryItem>>,
+}
+
 pub fn handle_inventory_interaction(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     world: Res<WorldRes>,
-    category_query: Query<(&Interaction, &InventoryCategory), Changed<Interaction>>,
-    item_query: Query<(&Interaction, &InventoryItem)>,
-    mut inventory_query: Query<(Entity, &mut Inventory)>,
+    widgets: InventoryWidgets,
     mut placer: ResMut<Placer>,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
-    context_menu_query: Query<Entity, With<InventoryContextMenu>>,
-    mut bg_color_query: Query<(&mut BackgroundColor, &InventoryItem)>,
-    item_panel_query: Query<Entity, With<InventoryItemsPanel>>,
-    existing_items_query: Query<Entity, With<InventoryItem>>,
+    settings: Res<Settings>,
 ) {
+    let InventoryWidgets {
+        category_query,
+        item_query,
+        mut inventory_query,
+        context_menu_query,
+        mut bg_color_query,
+        item_panel_query,
+        existing_items_query,
+    } = widgets;
+
     for (interaction, category) in category_query.iter() {
         if matches!(interaction, Interaction::Pressed) {
             if let Ok((_, mut inventory)) = inventory_query.single_mut() {
@@ -393,24 +416,24 @@ pub fn handle_inventory_interaction(
                     commands.entity(entity).despawn();
                 }
 
-                commands.spawn((
-                    Node {
-                        width: Val::Px(150.0),
-                        height: Val::Auto,
-                        position_type: PositionType::Absolute,
-                        right: Val::Px(10.0),
-                        top: Val::Px(10.0),
-                        display: Display::Flex,
-                        flex_direction: FlexDirection::Column,
-                        padding: UiRect::all(Val::Px(5.0)),
-                        ..default()
-                    },
-                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
-                    BorderRadius::all(Val::Px(5.0)),
-                    InventoryContextMenu,
-                    ZIndex(100),
-                    children![
-                        (
+                commands
+                    .spawn((
+                        Node {
+                            width: Val::Px(180.0),
+                            height: Val::Auto,
+                            position_type: PositionType::Absolute,
+                            right: Val::Px(10.0),
+                            top: Val::Px(10.0),
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Column,
+                            padding: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                        BorderRadius::all(Val::Px(5.0)),
+                        InventoryContextMenu,
+                        ZIndex(100),
+                        children![(
                             Node {
                                 width: Val::Percent(100.0),
                                 height: Val::Px(30.0),
@@ -434,34 +457,46 @@ pub fn handle_inventory_interaction(
                                 },
                                 TextColor(Color::WHITE),
                             )]
-                        ),
-                        (
-                            Node {
-                                width: Val::Percent(100.0),
-                                height: Val::Px(30.0),
-                                display: Display::Flex,
-                                align_items: AlignItems::Center,
-                                justify_content: JustifyContent::Center,
-                                margin: UiRect::bottom(Val::Px(5.0)),
-                                ..default()
+                        )],
+                    ))
+                    .with_children(|parent| {
+                        let price = world.sell_price(item.tile_type);
+                        let available = *world.resources.get(&item.tile_type).unwrap_or(&0);
+                        for quantity in TradeQuantity::ALL {
+                            let amount = quantity.amount(available);
+                            let label = if quantity == TradeQuantity::Max {
+                                format!("Sell all ({}, ${})", amount, amount * price)
+                            } else {
+                                format!("Sell x{} (${})", amount, amount * price)
+                            };
+                            parent.spawn(trade_option(
+                                label,
+                                amount > 0 && amount <= available,
+                                SellOption {
+                                    tile_type: item.tile_type,
+                                    quantity,
+                                },
+                            ));
+                        }
+                        parent.spawn(trade_option(
+                            auto_sell_label(world.auto_sell.get(&item.tile_type).copied()),
+                            true,
+                            AutoSellOption {
+                                tile_type: item.tile_type,
                             },
-                            BackgroundColor(Color::srgb(0.25, 0.25, 0.25)),
-                            BorderRadius::all(Val::Px(3.0)),
-                            SellOption {
+                        ));
+                        parent.spawn(trade_option(
+                            if settings.favorite_tiles.contains(&item.tile_type) {
+                                "Remove from favorites".to_string()
+                            } else {
+                                "Add to favorites".to_string()
+                            },
+                            true,
+                            FavoriteOption {
                                 tile_type: item.tile_type,
                             },
-                            Interaction::default(),
-                            children![(
-                                Text::new(format!("Sell (${})", get_tile_price(item.tile_type))),
-                                TextFont {
-                                    font_size: 16.0,
-                                    ..Default::default()
-                                },
-                                TextColor(Color::WHITE),
-                            )]
-                        )
-                    ],
-                ));
+                        ));
+                    });
 
                 break;
             }
@@ -1,16 +1,51 @@
-use crate::{components::*, get_tile_price, resources::*};
-use bevy::prelude::*;
+use crate::{
+    AUTO_SELL_THRESHOLDS, components::*, get_tile_name, resources::*, systems::ui::auto_sell_label,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+// Clicks on the key buttons or the mode toggle must not close the hotkey menu.
+type HotkeyButtonFilter = (
+    Changed<Interaction>,
+    Or<(With<HotkeyButton>, With<HotkeyModeButton>)>,
+);
+
+// The buttons of the inventory menu and of the hotkey menu it opens.
+#[derive(SystemParam)]
+pub struct InventoryMenuButtons<'w, 's> {
+    hotkey: Query<'w, 's, (&'static Interaction, &'static HotkeyOption), Changed<Interaction>>,
+    hotkey_keys: Query<'w, 's, (), HotkeyButtonFilter>,
+    sell: Query<'w, 's, (&'static Interaction, &'static SellOption), Changed<Interaction>>,
+    auto_sell: Query<
+        'w,
+        's,
+        (
+            &'static Interaction,
+            &'static AutoSellOption,
+            &'static Children,
+        ),
+        Changed<Interaction>,
+    >,
+}
+
+impl InventoryMenuButtons<'_, '_> {
+    fn any_changed(&self) -> bool {
+        !(self.hotkey.is_empty()
+            && self.hotkey_keys.is_empty()
+            && self.sell.is_empty()
+            && self.auto_sell.is_empty())
+    }
+}
 
 pub fn handle_inventory_context_menu(
     mut commands: Commands,
-    hotkey_interaction_query: Query<(&Interaction, &HotkeyOption), Changed<Interaction>>,
-    hotkey_button_interaction_query: Query<(), (Changed<Interaction>, With<HotkeyButton>)>,
-    sell_interaction_query: Query<(&Interaction, &SellOption), Changed<Interaction>>,
+    buttons: InventoryMenuButtons,
+    mut text_query: Query<&mut Text>,
     context_menu_query: Query<Entity, With<InventoryContextMenu>>,
     mut world: ResMut<WorldRes>,
+    settings: Res<Settings>,
     mouse_input: Res<ButtonInput<MouseButton>>,
 ) {
-    for (interaction, hotkey_option) in hotkey_interaction_query.iter() {
+    for (interaction, hotkey_option) in buttons.hotkey.iter() {
         if matches!(interaction, Interaction::Pressed) {
             if let Ok(entity) = context_menu_query.single() {
                 commands.entity(entity).despawn();
@@ -140,46 +175,119 @@ pub fn handle_inventory_context_menu(
                                 });
                             }
                         });
+
+                    parent
+                        .spawn((
+                            Node {
+                                width: Val::Percent(100.0),
+                                height: Val::Px(25.0),
+                                margin: UiRect::top(Val::Px(10.0)),
+                                align_items: AlignItems::Center,
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+                            BorderRadius::all(Val::Px(3.0)),
+                            HotkeyModeButton,
+                            Button,
+                        ))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new(hotkey_mode_label(settings.global_hotkeys)),
+                                TextFont {
+                                    font_size: 14.0,
+                                    ..Default::default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                        });
                 });
             }
         }
     }
-    for (interaction, sell_option) in sell_interaction_query.iter() {
+    for (interaction, sell_option) in buttons.sell.iter() {
         if matches!(interaction, Interaction::Pressed) {
             if let Ok(entity) = context_menu_query.single() {
-                if world.resources.get(&sell_option.tile_type) >= Some(&1) {
-                    world.money += get_tile_price(sell_option.tile_type);
+                let available = *world.resources.get(&sell_option.tile_type).unwrap_or(&0);
+                let amount = sell_option.quantity.amount(available);
+                if amount > 0 && available >= amount {
+                    let total = world
+                        .sell_price(sell_option.tile_type)
+                        .saturating_mul(amount);
+                    world.earn(
+                        total,
+                        LedgerReason::Sale,
+                        format!("{} x{}", get_tile_name(sell_option.tile_type), amount),
+                    );
                     *world
                         .resources
                         .entry(sell_option.tile_type)
-                        .or_insert(0_u32) -= 1;
+                        .or_insert(0_u32) -= amount;
                     commands.entity(entity).despawn();
                 }
             }
         }
     }
-    if mouse_input.just_pressed(MouseButton::Left)
-        && hotkey_interaction_query.is_empty()
-        && sell_interaction_query.is_empty()
-        && hotkey_button_interaction_query.is_empty()
-    {
+    for (interaction, option, children) in buttons.auto_sell.iter() {
+        if matches!(interaction, Interaction::Pressed) {
+            // Steps through the thresholds, then switches auto-selling off.
+            let next = match world.auto_sell.get(&option.tile_type) {
+                Some(keep) => AUTO_SELL_THRESHOLDS
+                    .iter()
+                    .position(|threshold| threshold == keep)
+                    .and_then(|index| AUTO_SELL_THRESHOLDS.get(index + 1))
+                    .copied(),
+                None => Some(AUTO_SELL_THRESHOLDS[0]),
+            };
+            match next {
+                Some(keep) => world.auto_sell.insert(option.tile_type, keep),
+                None => world.auto_sell.remove(&option.tile_type),
+            };
+            world.apply_auto_sell(option.tile_type);
+
+            for child in children.iter() {
+                if let Ok(mut text) = text_query.get_mut(child) {
+                    text.0 = auto_sell_label(next);
+                }
+            }
+        }
+    }
+    if mouse_input.just_pressed(MouseButton::Left) && !buttons.any_changed() {
         if let Ok(entity) = context_menu_query.single() {
             commands.entity(entity).despawn();
         }
     }
 }
 
+fn hotkey_mode_label(global: bool) -> String {
+    if global {
+        "Hotkeys: All worlds".to_string()
+    } else {
+        "Hotkeys: This world".to_string()
+    }
+}
+
 pub fn handle_hotkey_assignment(
     mut commands: Commands,
     interaction_query: Query<(&Interaction, &HotkeyButton), Changed<Interaction>>,
     context_menu_query: Query<Entity, With<InventoryContextMenu>>,
     mut hotkeys: ResMut<Hotkeys>,
+    mut settings: ResMut<Settings>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
 ) {
     for (interaction, hotkey_button) in interaction_query.iter() {
         if matches!(interaction, Interaction::Pressed) {
-            hotkeys
-                .mappings
-                .insert(hotkey_button.key, hotkey_button.tile_type);
+            // Shift keeps the mapping to this world when hotkeys are global.
+            let world_only = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+            if hotkeys.assign(
+                hotkey_button.key,
+                hotkey_button.tile_type,
+                &mut settings,
+                world_only,
+            ) && let Err(err) = settings.save("settings.ron")
+            {
+                eprintln!("Error saving settings: {}", err);
+            }
 
             for entity in context_menu_query.iter() {
                 commands.entity(entity).despawn();
@@ -187,3 +295,26 @@ pub fn handle_hotkey_assignment(
         }
     }
 }
+
+pub fn toggle_hotkey_mode(
+    interaction_query: Query<(&Interaction, &HotkeyModeButton, &Children), Changed<Interaction>>,
+    mut text_query: Query<&mut Text>,
+    mut hotkeys: ResMut<Hotkeys>,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, _, children) in interaction_query.iter() {
+        if matches!(interaction, Interaction::Pressed) {
+            let global = !settings.global_hotkeys;
+            hotkeys.set_global(&mut settings, global);
+            if let Err(err) = settings.save("settings.ron") {
+                eprintln!("Error saving settings: {}", err);
+            }
+
+            for child in children.iter() {
+                if let Ok(mut text) = text_query.get_mut(child) {
+                    text.0 = hotkey_mode_label(global);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    LEDGER_BUCKET_TICKS, LEDGER_GRAPH_BUCKETS, LEDGER_VISIBLE_ENTRIES, components::*, resources::*,
+};
+use bevy::prelude::*;
+
+fn ledger_lines(world: &WorldRes) -> String {
+    if world.ledger.is_empty() {
+        return "No transactions yet".to_string();
+    }
+
+    world
+        .ledger
+        .iter()
+        .rev()
+        .take(LEDGER_VISIBLE_ENTRIES)
+        .map(|entry| {
+            format!(
+                "t{} {}${} {}: {}",
+                entry.tick,
+                if entry.amount < 0 { "-" } else { "+" },
+                entry.amount.unsigned_abs(),
+                entry.reason.name(),
+                entry.detail
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn net_income_buckets(world: &WorldRes) -> [i64; LEDGER_GRAPH_BUCKETS] {
+    let mut buckets = [0; LEDGER_GRAPH_BUCKETS];
+    let current = world.tick_count.max(0) / LEDGER_BUCKET_TICKS;
+    for entry in world.ledger.iter() {
+        let age = current - entry.tick.max(0) / LEDGER_BUCKET_TICKS;
+        if (0..LEDGER_GRAPH_BUCKETS as i32).contains(&age) {
+            buckets[LEDGER_GRAPH_BUCKETS - 1 - age as usize] += entry.amount;
+        }
+    }
+    buckets
+}
+
+pub fn toggle_ledger(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    money_button_query: Query<&Interaction, (Changed<Interaction>, With<MoneyWidgetButton>)>,
+    ledger_query: Query<Entity, With<LedgerPanel>>,
+    world: Res<WorldRes>,
+    ui_state: Res<UiState>,
+) {
+    let clicked = money_button_query
+        .iter()
+        .any(|interaction| matches!(interaction, Interaction::Pressed));
+    if !keyboard_input.just_pressed(KeyCode::F8) && !clicked {
+        return;
+    }
+
+    if !ledger_query.is_empty() {
+        for entity in ledger_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    if !ui_state.can_open_overlay() {
+        return;
+    }
+
+    let panel = commands
+        .spawn((
+            Node {
+                width: Val::Px(320.0),
+                position_type: PositionType::Absolute,
+                left: Val::Px(5.0),
+                top: Val::Vh(7.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            ZIndex(150),
+            LedgerPanel,
+            children![(
+                Text::new("Ledger"),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            )],
+        ))
+        .id();
+
+    let graph = commands
+        .spawn((
+            Node {
+                height: Val::Px(60.0),
+                display: Display::Flex,
+                align_items: AlignItems::End,
+                column_gap: Val::Px(2.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.14, 0.15, 0.17)),
+        ))
+        .id();
+    commands.entity(panel).add_child(graph);
+
+    for index in 0..LEDGER_GRAPH_BUCKETS {
+        let bar = commands
+            .spawn((
+                Node {
+                    flex_grow: 1.0,
+                    height: Val::Percent(0.0),
+                    ..default()
+                },
+                BackgroundColor(Color::NONE),
+                LedgerBar { index },
+            ))
+            .id();
+        commands.entity(graph).add_child(bar);
+    }
+
+    let text = commands
+        .spawn((
+            Text::new(ledger_lines(&world)),
+            TextFont {
+                font_size: 13.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            LedgerText,
+        ))
+        .id();
+    commands.entity(panel).add_child(text);
+}
+
+pub fn update_ledger(
+    world: Res<WorldRes>,
+    mut text_query: Query<&mut Text, With<LedgerText>>,
+    mut bar_query: Query<(&mut Node, &mut BackgroundColor, &LedgerBar)>,
+) {
+    if let Ok(mut text) = text_query.single_mut() {
+        text.0 = ledger_lines(&world);
+    }
+
+    if bar_query.is_empty() {
+        return;
+    }
+
+    let buckets = net_income_buckets(&world);
+    let peak = buckets
+        .iter()
+        .map(|net| net.abs())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for (mut node, mut color, bar) in bar_query.iter_mut() {
+        let net = buckets[bar.index];
+        node.height = Val::Percent(net.abs() as f32 / peak as f32 * 100.0);
+        color.0 = if net >= 0 {
+            Color::srgb(0.3, 0.8, 0.4)
+        } else {
+            Color::srgb(0.9, 0.35, 0.35)
+        };
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{components::*, resources::*, router::RouterOutputIndex, tiles::*, types::*, utils::*};
+use bevy::prelude::*;
+
+fn filter_label(router: &Router, output: RouterOutputIndex) -> String {
+    format!(
+        "{}: {}",
+        output.name(),
+        router.filters[output as usize].map_or("Everything else", |item| item.name())
+    )
+}
+
+fn priority_label(priority: RouterOutputIndex) -> String {
+    format!("Priority: {}", priority.name())
+}
+
+fn router_button(label: &str, action: RouterAction) -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+        BorderRadius::all(Val::Px(5.0)),
+        Interaction::default(),
+        RouterButton { action },
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+pub fn spawn_router_menu(
+    commands: &mut Commands,
+    position: Position,
+    router: &Router,
+    tile_type: (u8, u8),
+) {
+    let menu = commands
+        .spawn((
+            Node {
+                width: Val::Vw(30.0),
+                position_type: PositionType::Absolute,
+                left: Val::Vw(35.0),
+                top: Val::Vh(25.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            RouterMenu { position },
+            children![(
+                Text::new(format!("{} filters", get_tile_name(tile_type))),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            )],
+        ))
+        .id();
+
+    for output in RouterOutputIndex::ALL {
+        let row = commands
+            .spawn((
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                children![
+                    router_button("<", RouterAction::PreviousFilter(output)),
+                    router_button(">", RouterAction::NextFilter(output)),
+                    (
+                        Text::new(filter_label(router, output)),
+                        TextFont {
+                            font_size: 16.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        RouterFilterText { output },
+                    ),
+                ],
+            ))
+            .id();
+        commands.entity(menu).add_child(row);
+    }
+
+    if let Some(priority) = router.priority {
+        let row = commands
+            .spawn((
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                children![
+                    router_button("Cycle", RouterAction::CyclePriority),
+                    (
+                        Text::new(priority_label(priority)),
+                        TextFont {
+                            font_size: 16.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        RouterPriorityText,
+                    ),
+                ],
+            ))
+            .id();
+        commands.entity(menu).add_child(row);
+    }
+
+    commands
+        .entity(menu)
+        .with_child(router_button("Close", RouterAction::Close));
+}
+
+pub fn handle_router_menu_interaction(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &RouterButton), Changed<Interaction>>,
+    router_menu_query: Query<(Entity, &RouterMenu)>,
+    mut filter_text_query: Query<(&mut Text, &RouterFilterText), Without<RouterPriorityText>>,
+    mut priority_text_query: Query<&mut Text, With<RouterPriorityText>>,
+    mut world: ResMut<WorldRes>,
+) {
+    let Ok((menu_entity, router_menu)) = router_menu_query.single() else {
+        return;
+    };
+
+    for (interaction, button) in button_query.iter() {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+
+        world.mark_dirty(router_menu.position);
+        let Some((tile, _)) = world.tiles.get_mut(&router_menu.position) else {
+            commands.entity(menu_entity).despawn();
+            return;
+        };
+        let Some(router) = tile.as_any_mut().downcast_mut::<Router>() else {
+            commands.entity(menu_entity).despawn();
+            return;
+        };
+
+        match button.action {
+            RouterAction::PreviousFilter(output) | RouterAction::NextFilter(output) => {
+                // Index 0 is "no filter", followed by every item.
+                let count = Item::ALL.len() + 1;
+                let current = router.filters[output as usize]
+                    .and_then(|item| Item::ALL.iter().position(|&i| i == item))
+                    .map_or(0, |index| index + 1);
+                let step = if matches!(button.action, RouterAction::NextFilter(_)) {
+                    1
+                } else {
+                    count - 1
+                };
+                let next = (current + step) % count;
+                router.filters[output as usize] = next.checked_sub(1).map(|index| Item::ALL[index]);
+            }
+            RouterAction::CyclePriority => {
+                if let Some(priority) = router.priority {
+                    router.priority = Some(priority.next());
+                }
+            }
+            RouterAction::Close => {
+                commands.entity(menu_entity).despawn();
+                return;
+            }
+        }
+
+        for (mut text, filter_text) in filter_text_query.iter_mut() {
+            text.0 = filter_label(router, filter_text.output);
+        }
+        if let (Ok(mut text), Some(priority)) = (priority_text_query.single_mut(), router.priority)
+        {
+            text.0 = priority_label(priority);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    TIME_TRIAL_AMOUNT, TIME_TRIAL_ITEM, components::*, resources::*, systems::ui::start_new_world,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::{Rng, rng};
+
+fn challenge_button(
+    label: String,
+    description: String,
+    color: Color,
+    button: ChallengeButton,
+) -> impl Bundle {
+    (
+        Button,
+        Node {
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        BackgroundColor(color),
+        BorderRadius::all(Val::Px(5.0)),
+        Interaction::default(),
+        button,
+        children![
+            (
+                Text::new(label),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ),
+            (
+                Text::new(description),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.85, 0.85, 0.85)),
+            ),
+        ],
+    )
+}
+
+pub fn spawn_scenario_menu(
+    commands: &mut Commands,
+    settings: &Settings,
+    world: &WorldRes,
+    leaderboard: &Leaderboard,
+) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Vw(36.0),
+                position_type: PositionType::Absolute,
+                left: Val::Vw(32.0),
+                top: Val::Vh(20.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            ZIndex(300),
+            ScenarioMenu,
+            children![
+                (
+                    Text::new("Scenarios"),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ),
+                (
+                    Text::new("Starting a scenario replaces the current world."),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(1.0, 0.6, 0.6)),
+                ),
+            ],
+        ))
+        .with_children(|parent| {
+            let daily = DailyChallenge::today();
+            let best = settings
+                .daily_results
+                .iter()
+                .find(|(day, _)| *day == daily.day)
+                .map(|(_, ticks)| *ticks);
+            parent.spawn(challenge_button(
+                match best {
+                    Some(ticks) => format!("Daily challenge {} (best: {} ticks)", daily.date(), ticks),
+                    None => format!("Daily challenge {}", daily.date()),
+                },
+                format!(
+                    "Everyone gets the same map and contracts today: {}. Console cheats and offline progress are off.",
+                    daily
+                        .contracts
+                        .iter()
+                        .map(|(item, amount)| format!("{} {}", amount, item.name()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                if best.is_some() {
+                    Color::srgb(0.3, 0.6, 0.4)
+                } else {
+                    Color::srgb(0.45, 0.3, 0.6)
+                },
+                ChallengeButton::Daily,
+            ));
+
+            let goal = format!(
+                "Deliver {} {} to Portals as fast as you can. Best times are kept per seed.",
+                TIME_TRIAL_AMOUNT,
+                TIME_TRIAL_ITEM.name()
+            );
+            if world.time_trial.is_some() {
+                let best = leaderboard.times(world.world_seed).first().map(|entry| entry.ticks);
+                parent.spawn(challenge_button(
+                    match best {
+                        Some(ticks) => format!(
+                            "Retry time trial on seed {} (best: {} ticks)",
+                            world.world_seed, ticks
+                        ),
+                        None => format!("Retry time trial on seed {}", world.world_seed),
+                    },
+                    goal.clone(),
+                    Color::srgb(0.45, 0.3, 0.6),
+                    ChallengeButton::TimeTrial {
+                        seed: world.world_seed,
+                    },
+                ));
+            }
+            parent.spawn(challenge_button(
+                "Time trial on a new map".to_string(),
+                goal,
+                Color::srgb(0.45, 0.3, 0.6),
+                // A new map's seed, like any world seed, is saved with the run so it replays.
+                ChallengeButton::TimeTrial {
+                    seed: rng().random_range(u32::MIN..u32::MAX),
+                },
+            ));
+
+            for (index, scenario) in SCENARIOS.iter().enumerate() {
+                let completed = settings.completed_scenarios.contains(&scenario.id);
+                parent.spawn((
+                    Button,
+                    Node {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BackgroundColor(if completed {
+                        Color::srgb(0.3, 0.6, 0.4)
+                    } else {
+                        Color::srgb(0.3, 0.5, 0.7)
+                    }),
+                    BorderRadius::all(Val::Px(5.0)),
+                    Interaction::default(),
+                    ScenarioButton { index },
+                    children![
+                        (
+                            Text::new(if completed {
+                                format!(
+                                    "{} ({}, completed)",
+                                    scenario.name,
+                                    scenario.difficulty.name()
+                                )
+                            } else {
+                                format!("{} ({})", scenario.name, scenario.difficulty.name())
+                            }),
+                            TextFont {
+                                font_size: 18.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ),
+                        (
+                            Text::new(scenario.description.clone()),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.85, 0.85, 0.85)),
+                        ),
+                    ],
+                ));
+            }
+        });
+}
+
+// What the scenario menu shows completions and best times from.
+#[derive(SystemParam)]
+pub struct ScenarioRecords<'w> {
+    settings: Res<'w, Settings>,
+    world: Res<'w, WorldRes>,
+    leaderboard: Res<'w, Leaderboard>,
+}
+
+impl ScenarioRecords<'_> {
+    fn spawn_menu(&self, commands: &mut Commands) {
+        spawn_scenario_menu(commands, &self.settings, &self.world, &self.leaderboard);
+    }
+}
+
+pub fn toggle_scenario_menu(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    open_button_query: Query<&Interaction, (Changed<Interaction>, With<OpenScenarioMenuButton>)>,
+    menu_query: Query<Entity, With<ScenarioMenu>>,
+    records: ScenarioRecords,
+    ui_state: Res<UiState>,
+) {
+    let open_pressed = open_button_query
+        .iter()
+        .any(|interaction| matches!(interaction, Interaction::Pressed));
+
+    if keyboard_input.just_pressed(KeyCode::F7) {
+        if menu_query.is_empty() && ui_state.can_open_overlay() {
+            records.spawn_menu(&mut commands);
+        } else {
+            for entity in menu_query.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+    } else if open_pressed && menu_query.is_empty() && ui_state.can_open_overlay() {
+        records.spawn_menu(&mut commands);
+    }
+}
+
+// Starting a world closes the scenario menu and the difficulty menu it may have come from.
+type NewWorldMenuFilter = Or<(With<ScenarioMenu>, With<DifficultyMenu>)>;
+
+pub fn handle_scenario_menu_interaction(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &ScenarioButton), Changed<Interaction>>,
+    challenge_button_query: Query<(&Interaction, &ChallengeButton), Changed<Interaction>>,
+    menu_query: Query<Entity, NewWorldMenuFilter>,
+    chunk_query: Query<Entity, With<TerrainChunk>>,
+    tile_sprite_query: Query<&TileSprite>,
+    mut world: ResMut<WorldRes>,
+) {
+    let challenge = challenge_button_query
+        .iter()
+        .find(|(interaction, _)| matches!(interaction, Interaction::Pressed))
+        .map(|(_, button)| button);
+    let scenario = button_query
+        .iter()
+        .find(|(interaction, _)| matches!(interaction, Interaction::Pressed))
+        .and_then(|(_, button)| SCENARIOS.get(button.index));
+
+    let new_world = match (challenge, scenario) {
+        (Some(ChallengeButton::Daily), _) => DailyChallenge::today().create_world(),
+        (Some(ChallengeButton::TimeTrial { seed }), _) => TimeTrial::create_world(*seed),
+        (None, Some(scenario)) => scenario.create_world(),
+        (None, None) => return,
+    };
+    start_new_world(
+        &mut commands,
+        &mut world,
+        new_world,
+        &tile_sprite_query,
+        &chunk_query,
+    );
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
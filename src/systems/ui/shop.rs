@@ -0,0 +1,297 @@
+use crate::{components::*, resources::*, utils::*};
+use bevy::prelude::*;
+
+fn basket_total(world: &WorldRes, shop: &ShopMenu) -> (u32, u32) {
+    shop.basket
+        .iter()
+        .fold((0, 0), |(count, price), (tile_type, quantity)| {
+            (
+                count + quantity,
+                price + get_tile_price(*tile_type, world.difficulty) * quantity,
+            )
+        })
+}
+
+pub fn toggle_shop(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    shop_query: Query<Entity, With<ShopMenu>>,
+    world: Res<WorldRes>,
+    ui_state: Res<UiState>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    if !shop_query.is_empty() {
+        for entity in shop_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    if !ui_state.can_open_overlay() {
+        return;
+    }
+
+    let shop_entity = commands
+        .spawn((
+            Node {
+                width: Val::Vw(60.0),
+                position_type: PositionType::Absolute,
+                left: Val::Vw(20.0),
+                top: Val::Vh(10.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+            BorderRadius::all(Val::Px(10.0)),
+            ZIndex(200),
+            ShopMenu::default(),
+            children![(
+                Text::new("Shop"),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            )],
+        ))
+        .id();
+
+    let list_entity = commands
+        .spawn(Node {
+            display: Display::Flex,
+            flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::Wrap,
+            column_gap: Val::Px(10.0),
+            row_gap: Val::Px(6.0),
+            ..default()
+        })
+        .id();
+    commands.entity(shop_entity).add_child(list_entity);
+
+    for tile_type in (1..=5).flat_map(get_category_tile_types) {
+        if world.is_tile_locked(tile_type) {
+            continue;
+        }
+
+        let row = commands
+            .spawn((
+                Node {
+                    width: Val::Percent(48.0),
+                    display: Display::Flex,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(8.0),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.2, 0.22, 0.25)),
+                BorderRadius::all(Val::Px(5.0)),
+                children![
+                    (
+                        Node {
+                            flex_grow: 1.0,
+                            ..default()
+                        },
+                        Text::new(format!(
+                            "{} (${})",
+                            get_tile_name(tile_type),
+                            get_tile_price(tile_type, world.difficulty)
+                        )),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ),
+                    shop_step_button(tile_type, -1, "-"),
+                    (
+                        Node {
+                            width: Val::Px(30.0),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        Text::new("0"),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        TextLayout {
+                            justify: JustifyText::Center,
+                            ..default()
+                        },
+                        ShopQuantityText { tile_type },
+                    ),
+                    shop_step_button(tile_type, 1, "+"),
+                ],
+            ))
+            .id();
+        commands.entity(list_entity).add_child(row);
+    }
+
+    let footer = commands
+        .spawn((
+            Node {
+                display: Display::Flex,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.0),
+                ..default()
+            },
+            children![
+                (
+                    Node {
+                        flex_grow: 1.0,
+                        ..default()
+                    },
+                    Text::new("Basket is empty"),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    ShopBasketText,
+                ),
+                shop_action_button(ShopAction::Clear, "Clear"),
+                shop_action_button(ShopAction::Checkout, "Buy"),
+            ],
+        ))
+        .id();
+    commands.entity(shop_entity).add_child(footer);
+}
+
+fn shop_step_button(tile_type: (u8, u8), delta: i32, label: &str) -> impl Bundle {
+    (
+        Button,
+        Node {
+            width: Val::Px(24.0),
+            height: Val::Px(24.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+        BorderRadius::all(Val::Px(3.0)),
+        Interaction::default(),
+        ShopStepButton { tile_type, delta },
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+fn shop_action_button(action: ShopAction, label: &str) -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(14.0), Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+        BorderRadius::all(Val::Px(5.0)),
+        Interaction::default(),
+        ShopButton { action },
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+pub fn handle_shop_interaction(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    step_query: Query<(&Interaction, &ShopStepButton), Changed<Interaction>>,
+    button_query: Query<(&Interaction, &ShopButton), Changed<Interaction>>,
+    mut shop_query: Query<&mut ShopMenu>,
+    mut world: ResMut<WorldRes>,
+) {
+    let Ok(mut shop) = shop_query.single_mut() else {
+        return;
+    };
+
+    let step = if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        10
+    } else {
+        1
+    };
+    for (interaction, button) in step_query.iter() {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        let quantity = shop.basket.entry(button.tile_type).or_insert(0);
+        *quantity = quantity.saturating_add_signed(button.delta * step);
+        if *quantity == 0 {
+            shop.basket.remove(&button.tile_type);
+        }
+    }
+
+    for (interaction, button) in button_query.iter() {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        match button.action {
+            ShopAction::Clear => shop.basket.clear(),
+            ShopAction::Checkout => {
+                let (count, total) = basket_total(&world, &shop);
+                if shop.basket.is_empty() || world.money < total {
+                    continue;
+                }
+                world.spend(
+                    total,
+                    LedgerReason::Purchase,
+                    format!("Shop basket x{}", count),
+                );
+                for (tile_type, quantity) in shop.basket.drain() {
+                    *world.resources.entry(tile_type).or_insert(0) += quantity;
+                }
+            }
+        }
+    }
+}
+
+pub fn update_shop(
+    world: Res<WorldRes>,
+    shop_query: Query<&ShopMenu>,
+    mut quantity_query: Query<(&mut Text, &ShopQuantityText), Without<ShopBasketText>>,
+    mut basket_query: Query<(&mut Text, &mut TextColor), With<ShopBasketText>>,
+) {
+    let Ok(shop) = shop_query.single() else {
+        return;
+    };
+
+    for (mut text, quantity) in quantity_query.iter_mut() {
+        text.0 = shop
+            .basket
+            .get(&quantity.tile_type)
+            .copied()
+            .unwrap_or(0)
+            .to_string();
+    }
+
+    if let Ok((mut text, mut color)) = basket_query.single_mut() {
+        let (count, total) = basket_total(&world, shop);
+        if count == 0 {
+            text.0 = "Basket is empty".to_string();
+            color.0 = Color::WHITE;
+        } else {
+            text.0 = format!("{} tiles, ${} (you have ${})", count, total, world.money);
+            color.0 = if world.money >= total {
+                Color::WHITE
+            } else {
+                Color::srgb(1.0, 0.5, 0.5)
+            };
+        }
+    }
+}
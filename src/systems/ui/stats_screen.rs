@@ -0,0 +1,119 @@
+use crate::{TICK_LENGTH, components::*, resources::*, types::*};
+use bevy::prelude::*;
+
+fn stats_lines(world: &WorldRes) -> String {
+    let seconds = (world.tick_count.max(0) as f32 * TICK_LENGTH) as u64;
+    let lifetime = &world.lifetime;
+    let mut lines = vec![
+        format!(
+            "Ticks played: {} ({}:{:02}:{:02})",
+            world.tick_count,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ),
+        format!(
+            "Playtime: {}:{:02}:{:02}",
+            lifetime.playtime as u64 / 3600,
+            lifetime.playtime as u64 / 60 % 60,
+            lifetime.playtime as u64 % 60
+        ),
+        format!("Tiles placed: {}", lifetime.tiles_placed),
+        format!("Tiles removed: {}", lifetime.tiles_removed),
+        format!("Money earned: ${}", lifetime.money_earned),
+        format!("Money spent: ${}", lifetime.money_spent),
+        format!(
+            "Victory goal: {} {}/{}{}",
+            world.victory.item.name(),
+            world.victory.progress(lifetime).min(world.victory.target),
+            world.victory.target,
+            if world.victory.won_at.is_some() {
+                " (won)"
+            } else {
+                ""
+            }
+        ),
+        String::new(),
+        "Items produced:".to_string(),
+    ];
+
+    let produced: Vec<(Item, u64)> = Item::ALL
+        .iter()
+        .filter_map(|item| lifetime.produced.get(item).map(|count| (*item, *count)))
+        .collect();
+    if produced.is_empty() {
+        lines.push("  Nothing yet".to_string());
+    }
+    for (item, count) in produced {
+        lines.push(format!("  {}: {}", item.name(), count));
+    }
+
+    lines.join("\n")
+}
+
+pub fn toggle_stats_screen(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    stats_screen_query: Query<Entity, With<StatsScreen>>,
+    world: Res<WorldRes>,
+    ui_state: Res<UiState>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F6) {
+        return;
+    }
+
+    if !stats_screen_query.is_empty() {
+        for entity in stats_screen_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    if !ui_state.can_open_overlay() {
+        return;
+    }
+
+    commands.spawn((
+        Node {
+            width: Val::Vw(30.0),
+            position_type: PositionType::Absolute,
+            left: Val::Vw(35.0),
+            top: Val::Vh(15.0),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(20.0)),
+            row_gap: Val::Px(10.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+        BorderRadius::all(Val::Px(10.0)),
+        StatsScreen,
+        children![
+            (
+                Text::new("Lifetime statistics"),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ),
+            (
+                Text::new(stats_lines(&world)),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                StatsScreenText,
+            ),
+        ],
+    ));
+}
+
+pub fn update_stats_screen(
+    world: Res<WorldRes>,
+    mut text_query: Query<&mut Text, With<StatsScreenText>>,
+) {
+    if let Ok(mut text) = text_query.single_mut() {
+        text.0 = stats_lines(&world);
+    }
+}
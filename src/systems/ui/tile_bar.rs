@@ -0,0 +1,149 @@
+use crate::{RECENT_TILES_MAX, components::*, get_tile_name, get_tile_texture, resources::*};
+use bevy::prelude::*;
+
+pub fn spawn_tile_bar(commands: &mut Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Vw(25.0),
+            bottom: Val::Px(5.0),
+            width: Val::Vw(50.0),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Row,
+            justify_content: JustifyContent::Center,
+            column_gap: Val::Px(4.0),
+            ..default()
+        },
+        TileBar,
+    ));
+}
+
+pub fn track_recent_tiles(placer: Res<Placer>, mut recent: ResMut<RecentTiles>) {
+    if !placer.is_changed() || placer.tile_type == (0, 1) {
+        return;
+    }
+    if recent.tiles.front() == Some(&placer.tile_type) {
+        return;
+    }
+
+    recent
+        .tiles
+        .retain(|tile_type| *tile_type != placer.tile_type);
+    recent.tiles.push_front(placer.tile_type);
+    recent.tiles.truncate(RECENT_TILES_MAX);
+}
+
+fn tile_bar_slot(
+    asset_server: &AssetServer,
+    tile_type: (u8, u8),
+    selected: bool,
+    favorite: bool,
+) -> impl Bundle {
+    (
+        Node {
+            width: Val::Px(40.0),
+            height: Val::Px(40.0),
+            padding: UiRect::all(Val::Px(4.0)),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BackgroundColor(if selected {
+            Color::srgb(0.45, 0.67, 0.9)
+        } else {
+            Color::srgb(0.18, 0.2, 0.23)
+        }),
+        BorderColor(if favorite {
+            Color::srgb(0.9, 0.75, 0.2)
+        } else {
+            Color::NONE
+        }),
+        BorderRadius::all(Val::Px(6.0)),
+        Interaction::default(),
+        TileBarSlot { tile_type },
+        children![(
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            ImageNode::new(asset_server.load(get_tile_texture(tile_type))),
+        )],
+    )
+}
+
+// Favorites come first with a gold border, followed by recent tiles that aren't favorites.
+pub fn update_tile_bar(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    placer: Res<Placer>,
+    recent: Res<RecentTiles>,
+    settings: Res<Settings>,
+    bar_query: Query<Entity, With<TileBar>>,
+) {
+    if !placer.is_changed() && !recent.is_changed() && !settings.is_changed() {
+        return;
+    }
+    let Ok(bar) = bar_query.single() else {
+        return;
+    };
+
+    let recent_tiles = recent
+        .tiles
+        .iter()
+        .filter(|tile_type| !settings.favorite_tiles.contains(tile_type));
+    commands.entity(bar).despawn_related::<Children>();
+    commands.entity(bar).with_children(|parent| {
+        for tile_type in settings.favorite_tiles.iter() {
+            parent.spawn(tile_bar_slot(
+                &asset_server,
+                *tile_type,
+                placer.tile_type == *tile_type,
+                true,
+            ));
+        }
+        for tile_type in recent_tiles {
+            parent.spawn(tile_bar_slot(
+                &asset_server,
+                *tile_type,
+                placer.tile_type == *tile_type,
+                false,
+            ));
+        }
+    });
+}
+
+pub fn handle_tile_bar_interaction(
+    slot_query: Query<(&Interaction, &TileBarSlot), Changed<Interaction>>,
+    mut placer: ResMut<Placer>,
+) {
+    for (interaction, slot) in slot_query.iter() {
+        if matches!(interaction, Interaction::Pressed) {
+            placer.tile_type = slot.tile_type;
+        }
+    }
+}
+
+pub fn handle_favorite_option(
+    option_query: Query<(&Interaction, &FavoriteOption), Changed<Interaction>>,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, option) in option_query.iter() {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        if settings.favorite_tiles.contains(&option.tile_type) {
+            settings
+                .favorite_tiles
+                .retain(|tile_type| *tile_type != option.tile_type);
+        } else {
+            settings.favorite_tiles.push(option.tile_type);
+        }
+        if let Err(err) = settings.save("settings.ron") {
+            eprintln!(
+                "Error saving favorite {}: {}",
+                get_tile_name(option.tile_type),
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{TICK_LENGTH, components::*, resources::*, systems::spawn_difficulty_menu};
+use bevy::prelude::*;
+
+fn victory_button(action: VictoryAction, label: &str) -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::all(Val::Px(10.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.5, 0.7)),
+        BorderRadius::all(Val::Px(5.0)),
+        Interaction::default(),
+        VictoryButton { action },
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 18.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+pub fn check_victory(mut commands: Commands, mut world: ResMut<WorldRes>) {
+    if world.victory.won_at.is_some()
+        || world.victory.progress(&world.lifetime) < world.victory.target
+    {
+        return;
+    }
+
+    let tick = world.tick_count;
+    world.victory.won_at = Some(tick);
+
+    let seconds = (tick.max(0) as f32 * TICK_LENGTH) as u64;
+    let lifetime = &world.lifetime;
+    let body = [
+        format!(
+            "{} {} launched after {} ticks ({}:{:02}:{:02})",
+            world.victory.target,
+            world.victory.item.name(),
+            tick,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ),
+        format!("Difficulty: {}", world.difficulty.name()),
+        format!("Tiles placed: {}", lifetime.tiles_placed),
+        format!(
+            "Items produced: {}",
+            lifetime.produced.values().sum::<u64>()
+        ),
+        format!("Money earned: ${}", lifetime.money_earned),
+        format!("Money spent: ${}", lifetime.money_spent),
+    ]
+    .join("\n");
+
+    commands.spawn((
+        Node {
+            width: Val::Vw(30.0),
+            position_type: PositionType::Absolute,
+            left: Val::Vw(35.0),
+            top: Val::Vh(20.0),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(20.0)),
+            row_gap: Val::Px(10.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.18, 0.2, 0.23)),
+        BorderRadius::all(Val::Px(10.0)),
+        ZIndex(250),
+        VictoryScreen,
+        children![
+            (
+                Text::new("Victory!"),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.9, 0.5)),
+            ),
+            (
+                Text::new(body),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ),
+            victory_button(VictoryAction::Freeplay, "Continue in freeplay"),
+            victory_button(VictoryAction::NewWorld, "New world"),
+        ],
+    ));
+}
+
+pub fn handle_victory_interaction(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &VictoryButton), Changed<Interaction>>,
+    screen_query: Query<Entity, With<VictoryScreen>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(action) = button_query
+        .iter()
+        .find(|(interaction, _)| matches!(interaction, Interaction::Pressed))
+        .map(|(_, button)| button.action)
+    else {
+        return;
+    };
+
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if let VictoryAction::NewWorld = action {
+        spawn_difficulty_menu(&mut commands, &mut images);
+    }
+}
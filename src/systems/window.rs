@@ -0,0 +1,49 @@
+use crate::Settings;
+use bevy::{
+    prelude::*,
+    window::{MonitorSelection, PrimaryWindow, WindowCloseRequested, WindowMode, WindowPosition},
+};
+
+pub fn toggle_fullscreen(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F11) {
+        return;
+    }
+
+    if let Ok(mut window) = windows.single_mut() {
+        window.mode = match window.mode {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+            _ => WindowMode::Windowed,
+        };
+    }
+}
+
+// Mirrors the window into the settings as it changes and writes them out when the game closes.
+pub fn remember_window(
+    windows: Query<Ref<Window>, With<PrimaryWindow>>,
+    mut close_requests: EventReader<WindowCloseRequested>,
+    mut app_exits: EventReader<AppExit>,
+    mut settings: ResMut<Settings>,
+) {
+    if let Ok(window) = windows.single()
+        && window.is_changed()
+    {
+        settings.fullscreen = window.mode != WindowMode::Windowed;
+        if !settings.fullscreen {
+            settings.window_size = (window.resolution.width(), window.resolution.height());
+            if let WindowPosition::At(position) = window.position {
+                settings.window_position = Some((position.x, position.y));
+            }
+        }
+    }
+
+    let closing = close_requests.read().count() > 0;
+    let exiting = app_exits.read().count() > 0;
+    if (closing || exiting)
+        && let Err(err) = settings.save("settings.ron")
+    {
+        eprintln!("Error saving settings: {}", err);
+    }
+}
@@ -1,23 +1,45 @@
-use crate::{Hotkeys, tiles::*, utils::*};
-use bevy::prelude::*;
-use noise::*;
-use std::collections::HashSet;
+use crate::{
+    Hotkeys, TICK_DURATION, TICK_TILES_TIME, systems::ui::spawn_dialog, tiles::*, utils::*,
+};
+use bevy::{
+    asset::RenderAssetUsages,
+    diagnostic::Diagnostics,
+    ecs::system::SystemParam,
+    platform::time::Instant,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use std::{collections::HashSet, time::Duration};
+use web_time::{SystemTime, UNIX_EPOCH};
 
 use crate::{
-    Action, CHUNK_SIZE, ChunkPosition, Direction, ELECTRINE_DENSITY, ELECTRINE_NOISE_SCALE,
-    FLEXTORIUM_DENSITY, FLEXTORIUM_NOISE_SCALE, IMAGE_SIZE, Placer, Position, RIGTORIUM_DENSITY,
-    RIGTORIUM_NOISE_SCALE, TERRAIN_BASE_THRESHOLD, TILE_SIZE, TerrainChunk, TerrainTileType,
-    WorldRes,
+    Action, ActionSchedule, BEACON_CHARGE_PER_ELECTRINE, CHUNK_SIZE, ChunkPosition, Direction,
+    GameSpeed, Item, ItemMoved, ItemProduced, Lane, OFFLINE_MAX_TICKS, OfflineProgress,
+    PhotoCamera, Placer, Position, ProductionEventKind, ProductionStats,
+    SAVE_BACKUP_INTERVAL_TICKS, Settings, TERRAIN_BAKE_TEXELS, TICK_BUDGET_ACTIONS, TICK_LENGTH,
+    TILE_SIZE, TerrainChunk, TerrainTextures, TickEvents, TickFinished, TickProgress, TileChanged,
+    TilePlaced, TileRemoved, UiState, WorldEventKind, WorldRes,
 };
 
 pub fn manage_terrain_chunks(
     mut commands: Commands,
     mut world: ResMut<WorldRes>,
     placer: Res<Placer>,
-    camera_query: Query<&Transform, With<Camera2d>>,
-    chunk_query: Query<(Entity, &TerrainChunk)>,
-    asset_server: Res<AssetServer>,
+    camera_query: Query<&Transform, (With<Camera2d>, Without<PhotoCamera>)>,
+    mut chunk_query: Query<(
+        Entity,
+        &mut TerrainChunk,
+        &mut Sprite,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    mut images: ResMut<Assets<Image>>,
+    mut terrain_textures: ResMut<TerrainTextures>,
 ) {
+    if !terrain_textures.is_ready(&images) {
+        return;
+    }
+
     if let Ok(camera_transform) = camera_query.single() {
         let camera_pos = camera_transform.translation.truncate();
 
@@ -31,192 +53,684 @@ pub fn manage_terrain_chunks(
         let mut visible_chunks = HashSet::new();
         for x in (camera_chunk.x - chunks_radius)..(camera_chunk.x + chunks_radius + 1) {
             for y in (camera_chunk.y - chunks_radius)..(camera_chunk.y + chunks_radius + 1) {
-                visible_chunks.insert(ChunkPosition::new(x, y));
+                let chunk = ChunkPosition::new(x, y);
+                if world.chunk_in_bounds(chunk) {
+                    visible_chunks.insert(chunk);
+                }
             }
         }
 
-        let mut chunks_to_unload = HashSet::new();
-        for &loaded_chunk in &world.loaded_chunks {
-            if !visible_chunks.contains(&loaded_chunk) {
-                chunks_to_unload.insert(loaded_chunk);
+        // Chunks leaving the view are hidden and reused for the next chunks to load.
+        let mut pool = Vec::new();
+        for (entity, chunk, _, _, mut visibility) in chunk_query.iter_mut() {
+            if *visibility == Visibility::Hidden {
+                pool.push(entity);
+            } else if !visible_chunks.contains(&chunk.position) {
+                *visibility = Visibility::Hidden;
+                world.loaded_chunks.remove(&chunk.position);
+                pool.push(entity);
             }
         }
 
-        for chunk_pos in &chunks_to_unload {
-            for (entity, chunk) in &chunk_query {
-                if chunk.position == *chunk_pos {
-                    commands.entity(entity).despawn();
-                    break;
-                }
+        for chunk_pos in &visible_chunks {
+            if world.loaded_chunks.contains(chunk_pos) {
+                continue;
             }
-            world.loaded_chunks.remove(chunk_pos);
-        }
 
-        for chunk_pos in &visible_chunks {
-            if !world.loaded_chunks.contains(chunk_pos) {
-                generate_chunk(&mut commands, &mut world, *chunk_pos, &asset_server);
-                world.loaded_chunks.insert(*chunk_pos);
+            let image =
+                terrain_chunk_image(&mut world, *chunk_pos, &mut images, &mut terrain_textures);
+            let chunk_world_size = CHUNK_SIZE as f32 * TILE_SIZE;
+            let translation = terrain_chunk_translation(*chunk_pos);
+
+            if let Some(entity) = pool.pop()
+                && let Ok((_, mut chunk, mut sprite, mut transform, mut visibility)) =
+                    chunk_query.get_mut(entity)
+            {
+                chunk.position = *chunk_pos;
+                sprite.image = image;
+                transform.translation = translation;
+                *visibility = Visibility::Inherited;
+            } else {
+                commands.spawn((
+                    TerrainChunk {
+                        position: *chunk_pos,
+                    },
+                    Sprite {
+                        image,
+                        custom_size: Some(Vec2::splat(chunk_world_size)),
+                        ..default()
+                    },
+                    Transform::from_translation(translation),
+                ));
             }
+            world.loaded_chunks.insert(*chunk_pos);
+        }
+
+        // Keep at most one view's worth of spare chunks after zooming back in.
+        for entity in pool.into_iter().skip(visible_chunks.len()) {
+            commands.entity(entity).despawn();
         }
     }
 }
 
+// The baked terrain of a chunk, generating the chunk's terrain first if it hasn't been yet.
+pub fn terrain_chunk_image(
+    world: &mut WorldRes,
+    chunk_pos: ChunkPosition,
+    images: &mut Assets<Image>,
+    terrain_textures: &mut TerrainTextures,
+) -> Handle<Image> {
+    match terrain_textures.cached_chunk(world, chunk_pos) {
+        Some(image) => image,
+        None => {
+            let image = generate_chunk(world, chunk_pos, images, terrain_textures);
+            terrain_textures.cache_chunk(chunk_pos, image.clone());
+            image
+        }
+    }
+}
+
+pub fn terrain_chunk_translation(chunk_pos: ChunkPosition) -> Vec3 {
+    let chunk_world_size = CHUNK_SIZE as f32 * TILE_SIZE;
+    Vec3::new(
+        chunk_pos.x as f32 * chunk_world_size + (chunk_world_size - TILE_SIZE) / 2.0,
+        chunk_pos.y as f32 * chunk_world_size + (chunk_world_size - TILE_SIZE) / 2.0,
+        -1.0,
+    )
+}
+
 fn generate_chunk(
-    commands: &mut Commands,
     world: &mut WorldRes,
     chunk_pos: ChunkPosition,
-    asset_server: &AssetServer,
+    images: &mut Assets<Image>,
+    terrain_textures: &TerrainTextures,
+) -> Handle<Image> {
+    let noise = TerrainNoise::new(world.world_seed);
+
+    let texels = TERRAIN_BAKE_TEXELS as usize;
+    let chunk_texels = CHUNK_SIZE as usize * texels;
+    let mut data = vec![0; chunk_texels * chunk_texels * 4];
+
+    for x in 0..CHUNK_SIZE {
+        for y in 0..CHUNK_SIZE {
+            let world_x = chunk_pos.x * CHUNK_SIZE + x;
+            let world_y = chunk_pos.y * CHUNK_SIZE + y;
+            let pos = Position::new(world_x, world_y);
+
+            let terrain_type = noise.terrain_at(world.difficulty, pos);
+            world.terrain.insert(pos, terrain_type);
+
+            if let Some(pixels) = terrain_textures.pixels.get(&terrain_type) {
+                let origin_x = x as usize * texels;
+                let origin_y = (CHUNK_SIZE - 1 - y) as usize * texels;
+                for row in 0..texels {
+                    let start = ((origin_y + row) * chunk_texels + origin_x) * 4;
+                    for (i, pixel) in pixels[row * texels..(row + 1) * texels].iter().enumerate() {
+                        data[start + i * 4..start + i * 4 + 4].copy_from_slice(pixel);
+                    }
+                }
+            }
+        }
+    }
+
+    images.add(Image::new(
+        Extent3d {
+            width: chunk_texels as u32,
+            height: chunk_texels as u32,
+            ..default()
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    ))
+}
+
+pub fn manage_game_speed(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut game_speed: ResMut<GameSpeed>,
+    mut world: ResMut<WorldRes>,
+    ui_state: Res<UiState>,
 ) {
-    let chunk_entity = commands
-        .spawn((
-            TerrainChunk {
-                position: chunk_pos,
-            },
-            Visibility::Visible,
-            Transform::from_translation(Vec3::new(
-                chunk_pos.x as f32 * CHUNK_SIZE as f32 * TILE_SIZE,
-                chunk_pos.y as f32 * CHUNK_SIZE as f32 * TILE_SIZE,
-                0.0,
-            )),
-        ))
-        .id();
-
-    let seed = world.world_seed;
-    let rigtorium_noise = Perlin::new(seed);
-    let flextorium_noise = Perlin::new(seed.wrapping_add(1));
-    let electrine_noise = Perlin::new(seed.wrapping_add(2));
-
-    commands.entity(chunk_entity).with_children(|parent| {
-        for x in 0..CHUNK_SIZE {
-            for y in 0..CHUNK_SIZE {
-                let world_x = chunk_pos.x * CHUNK_SIZE + x;
-                let world_y = chunk_pos.y * CHUNK_SIZE + y;
-                let pos = Position::new(world_x, world_y);
-
-                let rigtorium_val = rigtorium_noise.get([
-                    world_x as f64 * RIGTORIUM_NOISE_SCALE,
-                    world_y as f64 * RIGTORIUM_NOISE_SCALE,
-                ]) + RIGTORIUM_DENSITY;
-
-                let flextorium_val = flextorium_noise.get([
-                    world_x as f64 * FLEXTORIUM_NOISE_SCALE,
-                    world_y as f64 * FLEXTORIUM_NOISE_SCALE,
-                ]) + FLEXTORIUM_DENSITY;
-
-                let electrine_val = electrine_noise.get([
-                    world_x as f64 * ELECTRINE_NOISE_SCALE,
-                    world_y as f64 * ELECTRINE_NOISE_SCALE,
-                ]) + ELECTRINE_DENSITY;
-
-                let terrain_type = if rigtorium_val > TERRAIN_BASE_THRESHOLD
-                    && rigtorium_val > flextorium_val
-                    && rigtorium_val > electrine_val
-                {
-                    TerrainTileType::RawRigtoriumDeposit
-                } else if flextorium_val > TERRAIN_BASE_THRESHOLD
-                    && flextorium_val > rigtorium_val
-                    && flextorium_val > electrine_val
-                {
-                    TerrainTileType::RawFlextoriumDeposit
-                } else if electrine_val > TERRAIN_BASE_THRESHOLD
-                    && electrine_val > rigtorium_val
-                    && electrine_val > flextorium_val
-                {
-                    TerrainTileType::ElectrineDeposit
-                } else {
-                    TerrainTileType::Stone
-                };
+    if ui_state.blocks_gameplay() {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        game_speed.paused = !game_speed.paused;
+        if game_speed.paused {
+            world.tick_timer.pause();
+        } else {
+            world.tick_timer.unpause();
+        }
+    }
+
+    let multiplier = if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        (game_speed.multiplier * 2).min(4)
+    } else if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        (game_speed.multiplier / 2).max(1)
+    } else {
+        game_speed.multiplier
+    };
+
+    if multiplier != game_speed.multiplier {
+        game_speed.multiplier = multiplier;
+        world
+            .tick_timer
+            .set_duration(Duration::from_secs_f32(TICK_LENGTH / multiplier as f32));
+    }
+}
+
+pub fn simulate_tick(world: &mut WorldRes) -> TickEvents {
+    // A tick left resolving by tick_tiles is completed first so none of its actions are lost.
+    let mut events = TickEvents::default();
+    if world.resolving.is_some() {
+        resolve_actions(world, usize::MAX);
+        events = finish_tick(world);
+    }
+
+    begin_tick(world);
+    resolve_actions(world, usize::MAX);
+    let finished = finish_tick(world);
+    events.moved.extend(finished.moved);
+    events.produced.extend(finished.produced);
+    events
+}
 
-                world.terrain.insert(pos, terrain_type);
+fn begin_tick(world: &mut WorldRes) {
+    world.tick_count += 1;
+    world.advance_world_event();
+    world.advance_deconstruction();
+    if world.storage_clusters.stale {
+        world.rebuild_storage_clusters();
+    }
+
+    let (actions, winners) = arbitrate_inputs(world.actions.clone(), world);
+    world.last_input.extend(winners);
+
+    for action in actions.iter() {
+        world.mark_dirty(action.position());
+        if let Some((_, end)) = action_route(action, world) {
+            world.mark_dirty(end);
+        }
+    }
+
+    world.resolving = Some(TickProgress {
+        actions: actions.into(),
+        ..Default::default()
+    });
+}
+
+// Applies up to `limit` of the resolving tick's actions in order. Returns whether any are left.
+fn resolve_actions(world: &mut WorldRes, limit: usize) -> bool {
+    let Some(mut progress) = world.resolving.take() else {
+        return false;
+    };
+    let count = limit.min(progress.actions.len());
+    let batch: Vec<Action> = progress.actions.drain(..count).collect();
+    for action in batch {
+        apply_action(world, action, &mut progress);
+    }
+    let remaining = !progress.actions.is_empty();
+    world.resolving = Some(progress);
+    remaining
+}
+
+fn finish_tick(world: &mut WorldRes) -> TickEvents {
+    let progress = world.resolving.take().unwrap_or_default();
+    // A display sits next to the tile it watches, so only the neighbours of tiles that gave up an
+    // item can be counting.
+    let mut counted = Vec::new();
+    for from in progress.moved_from.iter() {
+        for direction in Direction::ALL {
+            let pos = from.shift(direction);
+            if let Some((tile, _)) = world.tiles.get_mut(&pos)
+                && let Some(display) = tile.as_any_mut().downcast_mut::<ItemDisplay>()
+                && display.watched_position() == *from
+                && !world.channels.contains_key(&pos)
+            {
+                display.count += 1;
+                counted.push(pos);
+            }
+        }
+    }
+    for pos in counted {
+        world.mark_chunk_dirty(pos);
+    }
+    update_channel_displays(world);
+
+    let disabled = compute_disabled_tiles(world);
+    let toggled: Vec<Position> = disabled
+        .symmetric_difference(&world.disabled)
+        .copied()
+        .collect();
+    world.disabled = disabled;
+    for pos in toggled {
+        world.mark_dirty(pos);
+    }
+
+    // A lane's flow only depends on the lanes downstream of it, so only lanes upstream of a
+    // change need working out again.
+    let flowing = if world.schedule.stale {
+        compute_flowing_lanes(world)
+    } else {
+        let changed: HashSet<Position> = world
+            .schedule
+            .dirty
+            .iter()
+            .flat_map(|pos| world.changed_cells(*pos))
+            .collect();
+        update_flowing_lanes(world, &changed)
+    };
+    let changed: Vec<Position> = flowing
+        .symmetric_difference(&world.flowing)
+        .map(|(pos, _)| *pos)
+        .collect();
+    world.flowing = flowing;
+    for pos in changed {
+        world.mark_dirty(pos);
+    }
+
+    let next = schedule_actions(world);
+    world.actions = sort_moves_topologically(next, world);
+    world.actions.reverse();
+
+    progress.events
+}
+
+fn apply_action(world: &mut WorldRes, action: Action, progress: &mut TickProgress) {
+    let Action::Move(start, end, _, _) = action else {
+        perform_action(world, action, progress);
+        return;
+    };
+
+    // Clustered storages share their contents, so an empty one borrows the item it sends and a
+    // full one makes room for the item it takes. Both are undone if the move doesn't happen.
+    let borrowed = world.fill_storage_from_cluster(start);
+    let spilled = world.spill_storage_into_cluster(end);
+    if borrowed.is_none() && spilled.is_none() {
+        perform_action(world, action, progress);
+        return;
+    }
 
-                let texture_path = match terrain_type {
-                    TerrainTileType::Stone => "embedded://textures/terrain/stone.png",
-                    TerrainTileType::RawFlextoriumDeposit => {
-                        "embedded://textures/terrain/flextorium.png"
+    let held_at = |world: &WorldRes| {
+        world
+            .tiles
+            .get(&start)
+            .map_or(0, |tile| held_items(tile.0.as_ref()))
+    };
+    let held = held_at(world);
+    perform_action(world, action, progress);
+    let moved = held_at(world) < held;
+    for shift in [spilled, borrowed].into_iter().flatten() {
+        world.settle_storage_shift(shift, moved);
+    }
+}
+
+fn perform_action(world: &mut WorldRes, action: Action, progress: &mut TickProgress) {
+    let source = match action {
+        Action::Move(start, _, _, _) | Action::MoveRouter(start, _, _, _) => world
+            .tiles
+            .get(&start)
+            .is_some_and(|tile| tile.0.get_item().is_some())
+            .then_some(start),
+        _ => None,
+    };
+    // The destination lane is read before the move fills it.
+    let hop = match action {
+        Action::Move(start, end, item, lane) => Some((start, end, item, Some(lane))),
+        Action::MoveRouter(start, end, item, _) => Some((start, end, item, None)),
+        _ => None,
+    }
+    .map(|(from, to, item, from_lane)| {
+        let held = world
+            .tiles
+            .get(&from)
+            .map_or(0, |tile| held_items(tile.0.as_ref()));
+        let moved = ItemMoved {
+            from,
+            to,
+            item,
+            from_lane,
+            to_lane: conveyor_entry_lane(world, from, to, item, from_lane),
+        };
+        (moved, held)
+    });
+
+    match action {
+        Action::Move(start, end, item, lane) => {
+            // An inserter and the tile it picks from can both try to move the same item.
+            if !world
+                .tiles
+                .get(&start)
+                .is_some_and(|tile| holds_item(tile.0.as_ref(), item, lane))
+            {
+                return;
+            }
+
+            let batch = conveyor_batch(world, start, end, item, lane);
+            let entry = entry_position(world, start, end);
+            let mut empty = false;
+            let mut special = true;
+            if let Some(tile) = world.tiles.get_mut(&end) {
+                empty = tile.0.can_accept_lane(start, item, lane);
+                if empty {
+                    special = tile.0.as_any().is::<Factory>()
+                        || tile.0.as_any().is::<Junction>()
+                        || tile.0.as_any().is::<Extractor>()
+                        || tile.0.as_any().is::<Storage>();
+                    if !special {
+                        for _ in 0..batch {
+                            tile.0.insert_lane(start, item, lane);
+                        }
+                    } else if let Some(factory) = tile.0.as_any_mut().downcast_mut::<Factory>() {
+                        if factory.accepts_from(entry)
+                            && factory.capacity().get(&item).unwrap_or(&0_u32)
+                                > factory.inventory.get(&item).unwrap_or(&0_u32)
+                        {
+                            *factory.inventory.entry(item).or_insert(0) += 1;
+                            if let Some(start_tile) = world.tiles.get_mut(&start) {
+                                start_tile.0.clear_lane(lane);
+                                if let Some(start_junction) =
+                                    start_tile.0.as_any_mut().downcast_mut::<Junction>()
+                                {
+                                    if start.x != end.x {
+                                        start_junction.horizontal_item = None;
+                                    } else if start.y != end.y {
+                                        start_junction.vertical_item = None;
+                                    }
+                                } else if let Some(start_storage) =
+                                    start_tile.0.as_any_mut().downcast_mut::<Storage>()
+                                {
+                                    start_storage.inventory -= 1;
+                                }
+                            }
+                        }
+                    } else if let Some(end_junction) =
+                        tile.0.as_any_mut().downcast_mut::<Junction>()
+                    {
+                        if end.y == start.y {
+                            let input_direction = if end.x > start.x {
+                                Direction::Left
+                            } else {
+                                Direction::Right
+                            };
+                            if end_junction.horizontal_item.is_none() {
+                                end_junction.horizontal_item = Some((item, input_direction, lane));
+                                if let Some(tile) = world.tiles.get_mut(&start) {
+                                    tile.0.clear_lane(lane);
+                                    if let Some(start_junction) =
+                                        tile.0.as_any_mut().downcast_mut::<Junction>()
+                                    {
+                                        if start.x != end.x {
+                                            start_junction.horizontal_item = None;
+                                        } else if start.y != end.y {
+                                            start_junction.vertical_item = None;
+                                        }
+                                    } else if let Some(start_storage) =
+                                        tile.0.as_any_mut().downcast_mut::<Storage>()
+                                    {
+                                        start_storage.inventory -= 1;
+                                    }
+                                }
+                            }
+                        } else {
+                            let input_direction = if end.y > start.y {
+                                Direction::Down
+                            } else {
+                                Direction::Up
+                            };
+                            if end_junction.vertical_item.is_none() {
+                                end_junction.vertical_item = Some((item, input_direction, lane));
+                                if let Some(tile) = world.tiles.get_mut(&start) {
+                                    tile.0.clear_lane(lane);
+                                    if let Some(start_junction) =
+                                        tile.0.as_any_mut().downcast_mut::<Junction>()
+                                    {
+                                        if start.x != end.x {
+                                            start_junction.horizontal_item = None;
+                                        } else if start.y != end.y {
+                                            start_junction.vertical_item = None;
+                                        }
+                                    } else if let Some(start_storage) =
+                                        tile.0.as_any_mut().downcast_mut::<Storage>()
+                                    {
+                                        start_storage.inventory -= 1;
+                                    }
+                                }
+                            }
+                        }
+                    } else if let Some(end_storage) = tile.0.as_any_mut().downcast_mut::<Storage>()
+                    {
+                        if end_storage.storage_type.stored_item() == item
+                            && end_storage.storage_type.capacity() > end_storage.inventory
+                        {
+                            end_storage.inventory += 1;
+                            if let Some(start_tile) = world.tiles.get_mut(&start) {
+                                start_tile.0.clear_lane(lane);
+                                if let Some(start_junction) =
+                                    start_tile.0.as_any_mut().downcast_mut::<Junction>()
+                                {
+                                    if start.x != end.x {
+                                        start_junction.horizontal_item = None;
+                                    } else if start.y != end.y {
+                                        start_junction.vertical_item = None;
+                                    }
+                                } else if let Some(start_storage) =
+                                    start_tile.0.as_any_mut().downcast_mut::<Storage>()
+                                {
+                                    start_storage.inventory -= 1;
+                                }
+                            }
+                        }
                     }
-                    TerrainTileType::RawRigtoriumDeposit => {
-                        "embedded://textures/terrain/rigtorium.png"
+                }
+            }
+            if let Some(start_tile) = world.tiles.get_mut(&start) {
+                if empty && !special {
+                    for _ in 0..batch {
+                        start_tile.0.clear_lane(lane);
                     }
-                    TerrainTileType::ElectrineDeposit => {
-                        "embedded://textures/terrain/electrine.png"
+
+                    if let Some(start_junction) =
+                        start_tile.0.as_any_mut().downcast_mut::<Junction>()
+                    {
+                        if start.x != end.x {
+                            start_junction.horizontal_item = None;
+                        } else if start.y != end.y {
+                            start_junction.vertical_item = None;
+                        }
+                    } else if let Some(start_storage) =
+                        start_tile.0.as_any_mut().downcast_mut::<Storage>()
+                    {
+                        start_storage.inventory -= 1;
                     }
-                };
+                }
+            }
+        }
+        Action::MoveRouter(start, end, item, output) => {
+            // The router may have been emptied or replaced since the move was scheduled.
+            if !world.tiles.get(&start).is_some_and(|tile| {
+                tile.0
+                    .as_any()
+                    .downcast_ref::<Router>()
+                    .is_some_and(|router| router.item == Some(item))
+            }) {
+                return;
+            }
 
-                parent.spawn((
-                    Sprite::from_image(asset_server.load(texture_path)),
-                    Transform {
-                        translation: Vec3::new(x as f32 * TILE_SIZE, y as f32 * TILE_SIZE, -1.0),
-                        scale: Vec3::splat(TILE_SIZE / IMAGE_SIZE),
-                        ..Default::default()
-                    },
-                ));
+            let mut empty = false;
+            let mut special = true;
+            if let Some(tile) = world.tiles.get_mut(&end) {
+                empty = tile.0.can_accept_lane(start, item, Lane::Left);
+                if empty {
+                    special = tile.0.as_any().is::<Factory>()
+                        || tile.0.as_any().is::<Junction>()
+                        || tile.0.as_any().is::<Extractor>();
+                    if !special {
+                        tile.0.insert_lane(start, item, Lane::Left);
+                    } else if let Some(factory) = tile.0.as_any_mut().downcast_mut::<Factory>() {
+                        if factory.accepts_from(start)
+                            && factory.capacity().get(&item).unwrap_or(&0)
+                                > factory.inventory.get(&item).unwrap_or(&0)
+                        {
+                            *factory.inventory.entry(item).or_insert(0) += 1;
+                        }
+                    } else if let Some(end_junction) =
+                        tile.0.as_any_mut().downcast_mut::<Junction>()
+                    {
+                        if end.y == start.y {
+                            let input_direction = if end.x > start.x {
+                                Direction::Left
+                            } else {
+                                Direction::Right
+                            };
+                            if end_junction.horizontal_item.is_none() {
+                                end_junction.horizontal_item =
+                                    Some((item, input_direction, Lane::Left));
+                            }
+                        } else {
+                            let input_direction = if end.y > start.y {
+                                Direction::Down
+                            } else {
+                                Direction::Up
+                            };
+                            if end_junction.vertical_item.is_none() {
+                                end_junction.vertical_item =
+                                    Some((item, input_direction, Lane::Left));
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(start_tile) = world.tiles.get_mut(&start) {
+                if empty && !special {
+                    if let Some(start_router) = start_tile.0.as_any_mut().downcast_mut::<Router>() {
+                        start_router.item = None;
+                        // Round-robin resumes after the output used, not after the last one, so outputs
+                        // skipped by a filter don't take an extra turn.
+                        start_router.last_output = output;
+                    }
+                }
             }
         }
-    });
-}
+        Action::Produce(position) => {
+            let mined = world
+                .tiles
+                .get(&position)
+                .and_then(|(tile, _)| tile.as_any().downcast_ref::<Quarry>())
+                .and_then(|quarry| quarry.next_item(world));
+            let new_item = if let Some(tile) = world.tiles.get_mut(&position) {
+                if let Some(factory) = tile.0.as_any_mut().downcast_mut::<Factory>() {
+                    Some(factory.recipe().output)
+                } else if let Some(extractor) = tile.0.as_any_mut().downcast_mut::<Extractor>() {
+                    Some(extractor.extractor_type.spawn_item())
+                } else if tile.0.as_any().is::<Quarry>() {
+                    mined
+                } else {
+                    return;
+                }
+            } else {
+                None
+            };
+            let destination = get_produce_destination(position, world);
+            let boost = beacon_boost_percent(world, position);
 
-pub fn tick_tiles(time: Res<Time>, mut world: ResMut<WorldRes>, hotkeys: Res<Hotkeys>) {
-    world.tick_timer.tick(time.delta());
-    if world.tick_timer.finished() {
-        world.tick_count += 1;
+            if let Some(unwraped_item) = new_item {
+                let move_item;
+                if let Some(tile) = world.tiles.get_mut(&position) {
+                    if let Some(factory) = tile.0.as_any_mut().downcast_mut::<Factory>() {
+                        if factory.ticks >= boosted_interval(factory.interval, boost) {
+                            if let Some(output) = factory.produce() {
+                                progress.events.produced.push(ItemProduced {
+                                    pos: position,
+                                    kind: ProductionEventKind::Factory(factory.recipe()),
+                                });
+                                *world.lifetime.produced.entry(output).or_insert(0) += 1;
+                                world.last_produced.insert(position, world.tick_count);
+                                for byproduct in factory.recipe().byproducts.iter() {
+                                    *world.lifetime.produced.entry(*byproduct).or_insert(0) += 1;
+                                }
+                            }
+                            factory.ticks = 0;
+                            factory.item = Some(unwraped_item);
+                            move_item = true;
+                        } else {
+                            factory.ticks += 1;
+                            move_item = false;
+                        }
+                    } else if let Some(extractor) = tile.0.as_any_mut().downcast_mut::<Extractor>()
+                    {
+                        extractor.item = Some(unwraped_item);
+                        progress.events.produced.push(ItemProduced {
+                            pos: position,
+                            kind: ProductionEventKind::Extractor(unwraped_item),
+                        });
+                        *world.lifetime.produced.entry(unwraped_item).or_insert(0) += 1;
+                        move_item = true;
+                    } else if let Some(quarry) = tile.0.as_any_mut().downcast_mut::<Quarry>() {
+                        // Held until the quarry's next tick moves it out of the output side.
+                        quarry.item = Some(unwraped_item);
+                        quarry.mined += 1;
+                        progress.events.produced.push(ItemProduced {
+                            pos: position,
+                            kind: ProductionEventKind::Extractor(unwraped_item),
+                        });
+                        *world.lifetime.produced.entry(unwraped_item).or_insert(0) += 1;
+                        move_item = false;
+                    } else {
+                        move_item = false;
+                    }
+                } else {
+                    move_item = false;
+                }
+                if move_item {
+                    let dest_pos = destination.map_or(position, |(_, end)| end);
+                    let to_lane =
+                        conveyor_entry_lane(world, position, dest_pos, unwraped_item, None);
 
-        for action in world.actions.clone() {
-            match action {
-                Action::Move(start, end, item) => {
                     let mut empty = false;
                     let mut special = true;
-                    if let Some(tile) = world.tiles.get_mut(&end) {
-                        empty = tile.0.get_item().is_none();
+                    if let Some(tile) = world.tiles.get_mut(&dest_pos) {
+                        empty = tile.0.can_accept_lane(position, unwraped_item, Lane::Left);
                         if empty {
                             special = tile.0.as_any().is::<Factory>()
                                 || tile.0.as_any().is::<Junction>()
                                 || tile.0.as_any().is::<Extractor>();
                             if !special {
-                                tile.0.set_item(Some(item));
+                                tile.0.insert_lane(position, unwraped_item, Lane::Left);
                             } else if let Some(factory) =
                                 tile.0.as_any_mut().downcast_mut::<Factory>()
                             {
-                                if factory.factory_type.capacity().get(&item).unwrap_or(&0_u32)
-                                    > factory.inventory.get(&item).unwrap_or(&0_u32)
+                                if factory.accepts_from(position)
+                                    && factory.capacity().get(&unwraped_item).unwrap_or(&0_u32)
+                                        > factory.inventory.get(&unwraped_item).unwrap_or(&0_u32)
                                 {
-                                    *factory.inventory.entry(item).or_insert(0) += 1;
-                                    if let Some(start_tile) = world.tiles.get_mut(&start) {
+                                    *factory.inventory.entry(unwraped_item).or_insert(0) += 1;
+                                    if let Some(start_tile) = world.tiles.get_mut(&position) {
                                         start_tile.0.set_item(None);
-                                        if let Some(start_junction) =
-                                            start_tile.0.as_any_mut().downcast_mut::<Junction>()
-                                        {
-                                            if start.x != end.x {
-                                                start_junction.horizontal_item = None;
-                                            } else if start.y != end.y {
-                                                start_junction.vertical_item = None;
-                                            }
-                                        } else if let Some(start_storage) =
-                                            start_tile.0.as_any_mut().downcast_mut::<Storage>()
-                                        {
-                                            start_storage.inventory -= 1;
-                                        }
                                     }
                                 }
                             } else if let Some(end_junction) =
                                 tile.0.as_any_mut().downcast_mut::<Junction>()
                             {
-                                if end.y == start.y {
-                                    let input_direction = if end.x > start.x {
+                                if dest_pos.y == position.y {
+                                    let input_direction = if dest_pos.x > position.x {
                                         Direction::Left
                                     } else {
                                         Direction::Right
                                     };
                                     if end_junction.horizontal_item.is_none() {
                                         end_junction.horizontal_item =
-                                            Some((item, input_direction));
-                                        if let Some(tile) = world.tiles.get_mut(&start) {
+                                            Some((unwraped_item, input_direction, Lane::Left));
+                                        if let Some(tile) = world.tiles.get_mut(&position) {
                                             tile.0.set_item(None);
                                             if let Some(start_junction) =
                                                 tile.0.as_any_mut().downcast_mut::<Junction>()
                                             {
-                                                if start.x != end.x {
+                                                if position.x != dest_pos.x {
                                                     start_junction.horizontal_item = None;
-                                                } else if start.y != end.y {
+                                                } else if position.y != dest_pos.y {
                                                     start_junction.vertical_item = None;
                                                 }
                                             } else if let Some(start_storage) =
@@ -227,319 +741,889 @@ pub fn tick_tiles(time: Res<Time>, mut world: ResMut<WorldRes>, hotkeys: Res<Hot
                                         }
                                     }
                                 } else {
-                                    let input_direction = if end.y > start.y {
+                                    let input_direction = if dest_pos.y > position.y {
                                         Direction::Down
                                     } else {
                                         Direction::Up
                                     };
                                     if end_junction.vertical_item.is_none() {
-                                        end_junction.vertical_item = Some((item, input_direction));
-                                        if let Some(tile) = world.tiles.get_mut(&start) {
+                                        end_junction.vertical_item =
+                                            Some((unwraped_item, input_direction, Lane::Left));
+                                        if let Some(tile) = world.tiles.get_mut(&position) {
                                             tile.0.set_item(None);
-                                            if let Some(start_junction) =
-                                                tile.0.as_any_mut().downcast_mut::<Junction>()
-                                            {
-                                                if start.x != end.x {
-                                                    start_junction.horizontal_item = None;
-                                                } else if start.y != end.y {
-                                                    start_junction.vertical_item = None;
-                                                }
-                                            } else if let Some(start_storage) =
-                                                tile.0.as_any_mut().downcast_mut::<Storage>()
-                                            {
-                                                start_storage.inventory -= 1;
-                                            }
-                                        }
-                                    }
-                                }
-                            } else if let Some(end_storage) =
-                                tile.0.as_any_mut().downcast_mut::<Storage>()
-                            {
-                                if end_storage.storage_type.capacity() > end_storage.inventory {
-                                    end_storage.inventory += 1;
-                                    if let Some(start_tile) = world.tiles.get_mut(&start) {
-                                        start_tile.0.set_item(None);
-                                        if let Some(start_junction) =
-                                            start_tile.0.as_any_mut().downcast_mut::<Junction>()
-                                        {
-                                            if start.x != end.x {
-                                                start_junction.horizontal_item = None;
-                                            } else if start.y != end.y {
-                                                start_junction.vertical_item = None;
-                                            }
-                                        } else if let Some(start_storage) =
-                                            start_tile.0.as_any_mut().downcast_mut::<Storage>()
-                                        {
-                                            start_storage.inventory -= 1;
                                         }
                                     }
                                 }
                             }
                         }
                     }
-                    if let Some(start_tile) = world.tiles.get_mut(&start) {
+                    if let Some(start_tile) = world.tiles.get_mut(&position) {
                         if empty && !special {
                             start_tile.0.set_item(None);
-
-                            if let Some(start_junction) =
-                                start_tile.0.as_any_mut().downcast_mut::<Junction>()
-                            {
-                                if start.x != end.x {
-                                    start_junction.horizontal_item = None;
-                                } else if start.y != end.y {
-                                    start_junction.vertical_item = None;
-                                }
-                            } else if let Some(start_storage) =
-                                start_tile.0.as_any_mut().downcast_mut::<Storage>()
-                            {
-                                start_storage.inventory -= 1;
-                            }
-                        }
-                    }
-                }
-                Action::MoveRouter(start, end, item, _last_output) => {
-                    let mut empty = false;
-                    let mut special = true;
-                    if let Some(tile) = world.tiles.get_mut(&end) {
-                        empty = tile.0.get_item().is_none();
-                        if empty {
-                            special = tile.0.as_any().is::<Factory>()
-                                || tile.0.as_any().is::<Junction>()
-                                || tile.0.as_any().is::<Extractor>();
-                            if !special {
-                                tile.0.set_item(Some(item));
-                            } else if let Some(factory) =
-                                tile.0.as_any_mut().downcast_mut::<Factory>()
-                            {
-                                if factory.factory_type.capacity().get(&item).unwrap_or(&0)
-                                    > factory.inventory.get(&item).unwrap_or(&0)
-                                {
-                                    *factory.inventory.entry(item).or_insert(0) += 1;
-                                }
-                            } else if let Some(end_junction) =
-                                tile.0.as_any_mut().downcast_mut::<Junction>()
-                            {
-                                if end.y == start.y {
-                                    let input_direction = if end.x > start.x {
-                                        Direction::Left
-                                    } else {
-                                        Direction::Right
-                                    };
-                                    if end_junction.horizontal_item.is_none() {
-                                        end_junction.horizontal_item =
-                                            Some((item, input_direction));
-                                    }
-                                } else {
-                                    let input_direction = if end.y > start.y {
-                                        Direction::Down
-                                    } else {
-                                        Direction::Up
-                                    };
-                                    if end_junction.vertical_item.is_none() {
-                                        end_junction.vertical_item = Some((item, input_direction));
-                                    }
-                                }
-                            }
                         }
                     }
-                    if let Some(start_tile) = world.tiles.get_mut(&start) {
-                        if empty && !special {
-                            if let Some(start_router) =
-                                start_tile.0.as_any_mut().downcast_mut::<Router>()
-                            {
-                                start_router.item = None;
-                                start_router.last_output = start_router.last_output.next();
-                            }
-                        }
+                    if dest_pos != position
+                        && world
+                            .tiles
+                            .get(&position)
+                            .is_some_and(|tile| tile.0.get_item().is_none())
+                    {
+                        progress.events.moved.push(ItemMoved {
+                            from: position,
+                            to: dest_pos,
+                            item: unwraped_item,
+                            from_lane: None,
+                            to_lane,
+                        });
                     }
                 }
-                Action::Produce(position) => {
-                    let new_item = if let Some(tile) = world.tiles.get_mut(&position) {
-                        if let Some(factory) = tile.0.as_any_mut().downcast_mut::<Factory>() {
-                            Some(factory.factory_type.recipe().output)
-                        } else if let Some(extractor) =
-                            tile.0.as_any_mut().downcast_mut::<Extractor>()
-                        {
-                            Some(extractor.extractor_type.spawn_item())
-                        } else {
-                            return;
-                        }
-                    } else {
-                        None
-                    };
-                    let direction = if let Some(tile) = world.tiles.get_mut(&position) {
-                        if let Some(factory) = tile.0.as_any_mut().downcast_mut::<Factory>() {
-                            Some(factory.direction)
-                        } else if let Some(extractor) =
-                            tile.0.as_any_mut().downcast_mut::<Extractor>()
-                        {
-                            Some(extractor.direction)
-                        } else {
-                            return;
-                        }
-                    } else {
-                        None
-                    };
-
-                    if let Some(unwraped_item) = new_item {
-                        let move_item;
-                        if let Some(tile) = world.tiles.get_mut(&position) {
-                            if let Some(factory) = tile.0.as_any_mut().downcast_mut::<Factory>() {
-                                if factory.ticks >= factory.interval {
-                                    factory.produce();
-                                    factory.ticks = 0;
-                                    factory.item = Some(unwraped_item);
-                                    move_item = true;
-                                } else {
-                                    factory.ticks += 1;
-                                    move_item = false;
-                                }
-                            } else if let Some(extractor) =
-                                tile.0.as_any_mut().downcast_mut::<Extractor>()
-                            {
-                                extractor.item = Some(unwraped_item);
-                                move_item = true;
-                            } else {
-                                move_item = false;
-                            }
-                        } else {
-                            move_item = false;
-                        }
-                        if move_item {
-                            let mut dest_pos = position;
-                            if let Some(unwraped_direction) = direction {
-                                match unwraped_direction {
-                                    Direction::Up => dest_pos.y += 1,
-                                    Direction::Down => dest_pos.y -= 1,
-                                    Direction::Left => dest_pos.x -= 1,
-                                    Direction::Right => dest_pos.x += 1,
-                                }
-                            }
-
-                            let mut empty = false;
-                            let mut special = true;
-                            if let Some(tile) = world.tiles.get_mut(&dest_pos) {
-                                empty = tile.0.get_item().is_none();
-                                if empty {
-                                    special = tile.0.as_any().is::<Factory>()
-                                        || tile.0.as_any().is::<Junction>()
-                                        || tile.0.as_any().is::<Extractor>();
-                                    if !special {
-                                        tile.0.set_item(Some(unwraped_item));
-                                    } else if let Some(factory) =
-                                        tile.0.as_any_mut().downcast_mut::<Factory>()
-                                    {
-                                        if factory
-                                            .factory_type
-                                            .capacity()
-                                            .get(&unwraped_item)
-                                            .unwrap_or(&0_u32)
-                                            > factory
-                                                .inventory
-                                                .get(&unwraped_item)
-                                                .unwrap_or(&0_u32)
-                                        {
-                                            *factory.inventory.entry(unwraped_item).or_insert(0) +=
-                                                1;
-                                            if let Some(start_tile) = world.tiles.get_mut(&position)
-                                            {
-                                                start_tile.0.set_item(None);
-                                            }
-                                        }
-                                    } else if let Some(end_junction) =
-                                        tile.0.as_any_mut().downcast_mut::<Junction>()
-                                    {
-                                        if dest_pos.y == position.y {
-                                            let input_direction = if dest_pos.x > position.x {
-                                                Direction::Left
-                                            } else {
-                                                Direction::Right
-                                            };
-                                            if end_junction.horizontal_item.is_none() {
-                                                end_junction.horizontal_item =
-                                                    Some((unwraped_item, input_direction));
-                                                if let Some(tile) = world.tiles.get_mut(&position) {
-                                                    tile.0.set_item(None);
-                                                    if let Some(start_junction) = tile
-                                                        .0
-                                                        .as_any_mut()
-                                                        .downcast_mut::<Junction>()
-                                                    {
-                                                        if position.x != dest_pos.x {
-                                                            start_junction.horizontal_item = None;
-                                                        } else if position.y != dest_pos.y {
-                                                            start_junction.vertical_item = None;
-                                                        }
-                                                    } else if let Some(start_storage) = tile
-                                                        .0
-                                                        .as_any_mut()
-                                                        .downcast_mut::<Storage>()
-                                                    {
-                                                        start_storage.inventory -= 1;
-                                                    }
-                                                }
-                                            }
-                                        } else {
-                                            let input_direction = if dest_pos.y > position.y {
-                                                Direction::Down
-                                            } else {
-                                                Direction::Up
-                                            };
-                                            if end_junction.vertical_item.is_none() {
-                                                end_junction.vertical_item =
-                                                    Some((unwraped_item, input_direction));
-                                                if let Some(tile) = world.tiles.get_mut(&position) {
-                                                    tile.0.set_item(None);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            if let Some(start_tile) = world.tiles.get_mut(&position) {
-                                if empty && !special {
-                                    start_tile.0.set_item(None);
-                                }
-                            }
-                        }
+            }
+        }
+        Action::Teleport(position, tile) => {
+            if let Some(tiles) = world.tiles.get_mut(&position) {
+                if let Some(portal) = tiles.0.as_any_mut().downcast_mut::<Portal>() {
+                    let delivered = portal.item.take();
+                    if let Some(item) = delivered {
+                        *portal.teleported.entry(item).or_insert(0) += 1;
                     }
-                }
-                Action::Teleport(position, tile) => {
-                    if let Some(tiles) = world.tiles.get_mut(&position) {
-                        if let Some(portal) = tiles.0.as_any_mut().downcast_mut::<Portal>() {
-                            portal.item = None;
 
-                            *world.resources.entry(tile).or_insert(0) += 1;
-                        } else if let Some(core) = tiles.0.as_any_mut().downcast_mut::<Core>() {
-                            core.ticks = 0;
+                    *world.resources.entry(tile).or_insert(0) += 1;
+                    if let Some(trial) = world.time_trial.as_mut()
+                        && delivered == Some(trial.item)
+                    {
+                        trial.delivered += 1;
+                    }
+                } else if let Some(core) = tiles.0.as_any_mut().downcast_mut::<Core>() {
+                    core.ticks = 0;
+                    progress.events.produced.push(ItemProduced {
+                        pos: position,
+                        kind: ProductionEventKind::Core(tile),
+                    });
 
+                    match Item::from_tile(tile).filter(|_| core.eject) {
+                        Some(item) => core.item = Some(item),
+                        None => {
                             *world.resources.entry(tile).or_insert(0) += 1;
+                            world.apply_auto_sell(tile);
                         }
                     }
                 }
-                Action::IncreaseTicks(position) => {
-                    if let Some(tiles) = world.tiles.get_mut(&position) {
-                        if let Some(core) = tiles.0.as_any_mut().downcast_mut::<Core>() {
-                            core.ticks += 1;
-                        }
+            }
+        }
+        Action::IncreaseTicks(position) => {
+            if let Some(tiles) = world.tiles.get_mut(&position) {
+                if let Some(core) = tiles.0.as_any_mut().downcast_mut::<Core>() {
+                    core.ticks += 1;
+                } else if let Some(beacon) = tiles.0.as_any_mut().downcast_mut::<Beacon>() {
+                    beacon.charge = beacon.charge.saturating_sub(1);
+                    if beacon.item.is_some() && beacon.can_absorb() {
+                        beacon.item = None;
+                        beacon.charge += BEACON_CHARGE_PER_ELECTRINE;
                     }
                 }
             }
         }
+    }
 
-        let mut next = Vec::new();
+    if let Some((moved, held)) = hop
+        && world
+            .tiles
+            .get(&moved.from)
+            .map_or(0, |tile| held_items(tile.0.as_ref()))
+            < held
+    {
+        progress.events.moved.push(moved);
+    }
+
+    if let Some(start) = source
+        && world
+            .tiles
+            .get(&start)
+            .is_some_and(|tile| tile.0.get_item().is_none())
+    {
+        progress.moved_from.push(start);
+    }
+}
 
-        for tile in world.tiles.values() {
-            if let Some(action) = tile.0.tick(&world) {
-                next.push(action);
+// Ticks only the tiles the schedule says can act, so tick cost follows activity, not base size.
+fn schedule_actions(world: &mut WorldRes) -> Vec<Action> {
+    let schedule = std::mem::take(&mut world.schedule);
+    let refresh: HashSet<Position> = if schedule.stale {
+        world.tiles.keys().copied().collect()
+    } else {
+        let mut refresh: HashSet<Position> =
+            schedule.active.union(&schedule.clocked).copied().collect();
+        for pos in schedule.dirty.iter() {
+            // Everything bordering a changed cell may feed into it or take from it.
+            for cell in world.changed_cells(*pos) {
+                refresh.insert(cell);
+                refresh.extend(Direction::ALL.map(|direction| cell.shift(direction)));
             }
         }
+        refresh
+    };
 
-        world.actions = sort_moves_topologically(next, &world);
-        world.actions.reverse();
+    let flicker = world.has_world_event(WorldEventKind::PowerFlicker) && world.tick_count % 2 == 1;
+    let mut next = Vec::new();
+    let mut active = HashSet::new();
+    let mut clocked = HashSet::new();
+    for pos in refresh {
+        let Some((tile, _)) = world.tiles.get(&pos) else {
+            continue;
+        };
+        if tile.as_any().is::<Extractor>() || tile.as_any().is::<Quarry>() {
+            clocked.insert(pos);
+        }
+        if world.disabled.contains(&pos) {
+            continue;
+        }
+        if flicker && tile.as_any().is::<Factory>() {
+            active.insert(pos);
+            continue;
+        }
+
+        let emitted = next.len();
+        next.extend(tile.tick(world));
+        next.extend(tile.secondary_tick(world));
+        if next.len() > emitted {
+            active.insert(pos);
+        }
+    }
+
+    world.schedule = ActionSchedule {
+        active,
+        clocked,
+        dirty: HashSet::new(),
+        stale: false,
+    };
+    next
+}
+
+// Displays on a wireless channel show the total of everything reported to it.
+fn update_channel_displays(world: &mut WorldRes) {
+    if world.channels.is_empty() {
+        return;
+    }
+
+    let signals = channel_signals(world);
+    let mut updated = Vec::new();
+    for (pos, channel) in world.channels.iter() {
+        if let Some((tile, _)) = world.tiles.get_mut(pos)
+            && let Some(display) = tile.as_any_mut().downcast_mut::<ItemDisplay>()
+        {
+            let count = signals.get(channel).map_or(0, |items| items.values().sum());
+            if display.count != count {
+                display.count = count;
+                updated.push(*pos);
+            }
+        }
+    }
+    for pos in updated {
+        world.mark_chunk_dirty(pos);
+    }
+}
+
+// How many items a tile holds, to tell whether a move actually took one out of it.
+fn held_items(tile: &dyn Tile) -> u32 {
+    if let Some(conveyor) = tile.as_any().downcast_ref::<Conveyor>() {
+        Lane::ALL
+            .into_iter()
+            .filter_map(|lane| conveyor.stack(lane))
+            .map(|stack| stack.count as u32)
+            .sum()
+    } else if let Some(storage) = tile.as_any().downcast_ref::<Storage>() {
+        storage.inventory
+    } else if let Some(junction) = tile.as_any().downcast_ref::<Junction>() {
+        junction.horizontal_item.is_some() as u32 + junction.vertical_item.is_some() as u32
+    } else {
+        tile.get_item().is_some() as u32
+    }
+}
+
+fn conveyor_entry_lane(
+    world: &WorldRes,
+    from: Position,
+    to: Position,
+    item: Item,
+    lane: Option<Lane>,
+) -> Option<Lane> {
+    world
+        .tiles
+        .get(&to)
+        .and_then(|tile| tile.0.as_any().downcast_ref::<Conveyor>())
+        .and_then(|conveyor| conveyor.entry_lane(from, item, lane.unwrap_or(Lane::Left)))
+}
+
+// Belt to belt moves carry as much of the stack as the next belt has room for; anything else
+// takes items one at a time.
+fn conveyor_batch(world: &WorldRes, from: Position, to: Position, item: Item, lane: Lane) -> u8 {
+    let conveyor = |pos: Position| {
+        world
+            .tiles
+            .get(&pos)
+            .and_then(|(tile, _)| tile.as_any().downcast_ref::<Conveyor>())
+    };
+    match (
+        conveyor(from).and_then(|start| start.stack(lane)),
+        conveyor(to),
+    ) {
+        (Some(stack), Some(next)) => stack.count.min(next.entry_room(from, item, lane)).max(1),
+        _ => 1,
+    }
+}
+
+fn holds_item(tile: &dyn Tile, item: Item, lane: Lane) -> bool {
+    if let Some(conveyor) = tile.as_any().downcast_ref::<Conveyor>() {
+        conveyor.lane(lane) == Some(item)
+    } else if let Some(storage) = tile.as_any().downcast_ref::<Storage>() {
+        storage.inventory > 0
+    } else if tile.as_any().is::<Junction>() {
+        true
+    } else {
+        tile.get_item() == Some(item)
+    }
+}
+
+pub fn simulate_offline_progress(
+    world: Res<WorldRes>,
+    mut offline: ResMut<OfflineProgress>,
+    settings: Res<Settings>,
+) {
+    if !settings.offline_progress
+        || world.saved_at == 0
+        || world.daily.is_some()
+        || world.time_trial.is_some()
+    {
+        return;
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let elapsed = now.saturating_sub(world.saved_at);
+    let ticks = ((elapsed as f32 / TICK_LENGTH) as u32).min(OFFLINE_MAX_TICKS);
+    if ticks == 0 {
+        return;
+    }
+
+    *offline = OfflineProgress {
+        remaining: ticks,
+        ticks,
+        elapsed,
+        resources_before: world.resources.clone(),
+        money_before: world.money,
+    };
+}
+
+// Run condition for `tick_tiles`: offline ticks are caught up on first, in
+// `advance_offline_progress`.
+pub fn offline_progress_caught_up(offline: Res<OfflineProgress>) -> bool {
+    offline.remaining == 0
+}
+
+// Resolves offline ticks within the same per-frame action budget as `tick_tiles`, then shows what
+// was gained once they are all done.
+pub fn advance_offline_progress(
+    mut commands: Commands,
+    mut world: ResMut<WorldRes>,
+    mut offline: ResMut<OfflineProgress>,
+    mut stats: ResMut<ProductionStats>,
+    settings: Res<Settings>,
+) {
+    if offline.remaining == 0 {
+        return;
+    }
+
+    let budget = match settings.tick_budget {
+        0 => TICK_BUDGET_ACTIONS,
+        budget => budget,
+    } as usize;
+    let mut spent = 0;
+    while offline.remaining > 0 && spent < budget {
+        if world.resolving.is_none() {
+            begin_tick(&mut world);
+        }
+        let pending = world
+            .resolving
+            .as_ref()
+            .map_or(0, |progress| progress.actions.len());
+        let limit = budget - spent;
+        // Ticks with nothing to resolve still count, so an idle base can't loop forever.
+        spent += pending.min(limit).max(1);
+        if !resolve_actions(&mut world, limit) {
+            let events = finish_tick(&mut world);
+            stats.advance(world.tick_count);
+            for event in events.produced {
+                stats.record(event.kind);
+            }
+            offline.remaining -= 1;
+        }
+    }
+    if offline.remaining > 0 {
+        return;
+    }
+
+    let mut gained: Vec<((u8, u8), u32)> = world
+        .resources
+        .iter()
+        .filter_map(|(tile_type, &count)| {
+            let before = offline
+                .resources_before
+                .get(tile_type)
+                .copied()
+                .unwrap_or(0);
+            (count > before).then_some((*tile_type, count - before))
+        })
+        .collect();
+    gained.sort();
+
+    let elapsed = offline.elapsed;
+    let mut lines = vec![format!(
+        "{}h {}m {}s offline, {} ticks simulated",
+        elapsed / 3600,
+        elapsed / 60 % 60,
+        elapsed % 60,
+        offline.ticks
+    )];
+    let money = world.money.saturating_sub(offline.money_before);
+    if money > 0 {
+        lines.push(format!("Money: +${}", money));
+    }
+    for (tile_type, count) in gained {
+        lines.push(format!("{}: +{}", get_tile_name(tile_type), count));
+    }
+    if lines.len() == 1 {
+        lines.push("Nothing was produced.".to_string());
+    }
+
+    spawn_dialog(&mut commands, "While you were away", &lines);
+    *offline = OfflineProgress::default();
+}
+
+pub fn emit_tile_events(
+    mut world: ResMut<WorldRes>,
+    mut tile_changes: EventWriter<TileChanged>,
+    mut tiles_placed: EventWriter<TilePlaced>,
+    mut tiles_removed: EventWriter<TileRemoved>,
+) {
+    let mutations = std::mem::take(&mut world.mutations);
+    tiles_removed.write_batch(mutations.removed);
+    tiles_placed.write_batch(mutations.placed);
+    if !world.changed.is_empty() {
+        tile_changes.write_batch(world.changed.drain().map(|pos| TileChanged { pos }));
+    }
+}
+
+// Where a finished tick's events go.
+#[derive(SystemParam)]
+pub struct TickEventWriters<'w> {
+    item_moves: EventWriter<'w, ItemMoved>,
+    items_produced: EventWriter<'w, ItemProduced>,
+    ticks_finished: EventWriter<'w, TickFinished>,
+}
+
+impl TickEventWriters<'_> {
+    fn write(&mut self, tick: i32, events: TickEvents) {
+        self.item_moves.write_batch(events.moved);
+        self.items_produced.write_batch(events.produced);
+        self.ticks_finished.write(TickFinished { tick });
+    }
+}
+
+pub fn tick_tiles(
+    time: Res<Time>,
+    mut world: ResMut<WorldRes>,
+    hotkeys: Res<Hotkeys>,
+    settings: Res<Settings>,
+    mut tick_events: TickEventWriters,
+    mut diagnostics: Diagnostics,
+) {
+    let start = Instant::now();
+    world.tick_timer.tick(time.delta());
+    world.lifetime.playtime += time.delta_secs_f64();
+    let budget = match settings.tick_budget {
+        0 => usize::MAX,
+        budget => budget as usize,
+    };
+
+    let mut finished = Vec::new();
+    if world.tick_timer.finished() {
+        // A tick still resolving when the next one is due is completed first.
+        if world.resolving.is_some() {
+            resolve_actions(&mut world, usize::MAX);
+            finished.push((world.tick_count, finish_tick(&mut world)));
+        }
+        begin_tick(&mut world);
+    }
+    if world.resolving.is_some() && !resolve_actions(&mut world, budget) {
+        finished.push((world.tick_count, finish_tick(&mut world)));
+    }
+
+    if !finished.is_empty() {
+        for (tick, events) in finished {
+            tick_events.write(tick, events);
+
+            if tick % SAVE_BACKUP_INTERVAL_TICKS == 0
+                && let Err(err) = WorldRes::rotate_backups("savegame.ffs")
+            {
+                eprintln!("Error rotating save backups: {}", err);
+            }
+        }
 
         if let Err(err) = world.save("savegame.ffs", &hotkeys) {
             eprintln!("Error saving game: {}", err);
         }
+
+        diagnostics.add_measurement(&TICK_DURATION, || start.elapsed().as_secs_f64() * 1000.0);
+    }
+
+    diagnostics.add_measurement(&TICK_TILES_TIME, || start.elapsed().as_secs_f64() * 1000.0);
+}
+
+pub fn record_production_stats(
+    mut ticks_finished: EventReader<TickFinished>,
+    mut items_produced: EventReader<ItemProduced>,
+    mut stats: ResMut<ProductionStats>,
+) {
+    for finished in ticks_finished.read() {
+        stats.advance(finished.tick);
+    }
+    for event in items_produced.read() {
+        stats.record(event.kind);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Difficulty, ItemStack, router::RouterOutputIndex};
+
+    // An empty world holding only the given (x, y, tile type, direction) tiles, each marked dirty so
+    // the first tick sees it.
+    fn build_world(layout: &[(i32, i32, (u8, u8), Direction)]) -> WorldRes {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        world.tiles.clear();
+        for &(x, y, tile_type, direction) in layout {
+            let pos = Position::new(x, y);
+            world
+                .tiles
+                .insert(pos, get_new_tile(tile_type, pos, direction));
+            world.mark_dirty(pos);
+        }
+        world
+    }
+
+    fn load_belt(world: &mut WorldRes, x: i32, y: i32, lanes: [Option<ItemStack>; 2]) {
+        let (tile, _) = world.tiles.get_mut(&Position::new(x, y)).unwrap();
+        tile.as_any_mut().downcast_mut::<Conveyor>().unwrap().lanes = lanes;
+    }
+
+    fn run(world: &mut WorldRes, ticks: usize) {
+        for _ in 0..ticks {
+            simulate_tick(world);
+        }
+    }
+
+    fn stack_label(stack: Option<ItemStack>) -> String {
+        match stack {
+            Some(stack) => format!("{:?}x{}", stack.item, stack.count),
+            None => "-".to_string(),
+        }
+    }
+
+    // One line per tile in position order describing what it holds, so a whole layout can be
+    // compared against the expected state at once.
+    fn snapshot(world: &WorldRes) -> Vec<String> {
+        let mut positions: Vec<_> = world.tiles.keys().copied().collect();
+        positions.sort();
+        positions
+            .into_iter()
+            .map(|pos| {
+                let tile = world.tiles[&pos].0.as_any();
+                let state = if let Some(conveyor) = tile.downcast_ref::<Conveyor>() {
+                    format!(
+                        "belt {} {}",
+                        stack_label(conveyor.lanes[0]),
+                        stack_label(conveyor.lanes[1])
+                    )
+                } else if let Some(router) = tile.downcast_ref::<Router>() {
+                    format!("router {:?} last {:?}", router.item, router.last_output)
+                } else if let Some(junction) = tile.downcast_ref::<Junction>() {
+                    format!(
+                        "junction h {:?} v {:?}",
+                        junction.horizontal_item.map(|(item, ..)| item),
+                        junction.vertical_item.map(|(item, ..)| item)
+                    )
+                } else if let Some(storage) = tile.downcast_ref::<Storage>() {
+                    format!("storage {}", storage.inventory)
+                } else {
+                    "other".to_string()
+                };
+                format!("{},{} {}", pos.x, pos.y, state)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_lone_item_laps_a_belt_loop_and_swaps_lanes_on_corners() {
+        let mut world = build_world(&[
+            (10, 10, (2, 1), Direction::Up),
+            (10, 11, (2, 1), Direction::Right),
+            (11, 11, (2, 1), Direction::Down),
+            (11, 10, (2, 1), Direction::Left),
+        ]);
+        load_belt(
+            &mut world,
+            10,
+            10,
+            [Some(ItemStack::new(Item::Rigtorium)), None],
+        );
+
+        // The first tick only schedules, then the item advances one belt per tick.
+        run(&mut world, 2);
+        assert_eq!(
+            snapshot(&world),
+            [
+                "10,10 belt - -",
+                "10,11 belt - Rigtoriumx1",
+                "11,10 belt - -",
+                "11,11 belt - -",
+            ]
+        );
+        run(&mut world, 3);
+        assert_eq!(
+            snapshot(&world),
+            [
+                "10,10 belt - Rigtoriumx1",
+                "10,11 belt - -",
+                "11,10 belt - -",
+                "11,11 belt - -",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_saturated_belt_advances_as_a_whole_into_its_first_gap() {
+        let mut world = build_world(&[
+            (0, 5, (2, 1), Direction::Right),
+            (1, 5, (2, 1), Direction::Right),
+            (2, 5, (2, 1), Direction::Right),
+            (3, 5, (2, 1), Direction::Right),
+        ]);
+        for (x, item) in [
+            (0, Item::Rigtorium),
+            (1, Item::Flextorium),
+            (2, Item::Electrine),
+        ] {
+            load_belt(&mut world, x, 5, [Some(ItemStack::full(item)); 2]);
+        }
+
+        run(&mut world, 2);
+        assert_eq!(
+            snapshot(&world),
+            [
+                "0,5 belt - -",
+                "1,5 belt Rigtoriumx4 Rigtoriumx4",
+                "2,5 belt Flextoriumx4 Flextoriumx4",
+                "3,5 belt Electrinex4 Electrinex4",
+            ]
+        );
+        run(&mut world, 3);
+        assert_eq!(snapshot(&world)[0], "0,5 belt - -");
+        assert_eq!(snapshot(&world)[3], "3,5 belt Electrinex4 Electrinex4");
+    }
+
+    #[test]
+    fn crossing_flows_pass_straight_through_a_junction_together() {
+        let mut world = build_world(&[
+            (-2, 0, (2, 1), Direction::Right),
+            (-1, 0, (2, 1), Direction::Right),
+            (0, 0, (2, 3), Direction::Up),
+            (1, 0, (2, 1), Direction::Right),
+            (2, 0, (2, 1), Direction::Right),
+            (0, -2, (2, 1), Direction::Up),
+            (0, -1, (2, 1), Direction::Up),
+            (0, 1, (2, 1), Direction::Up),
+            (0, 2, (2, 1), Direction::Up),
+        ]);
+        load_belt(
+            &mut world,
+            -2,
+            0,
+            [Some(ItemStack::new(Item::Rigtorium)), None],
+        );
+        load_belt(
+            &mut world,
+            0,
+            -2,
+            [Some(ItemStack::new(Item::Flextorium)), None],
+        );
+
+        // Both axes hold an item at once, but only one leaves the junction per tick.
+        run(&mut world, 3);
+        assert_eq!(
+            snapshot(&world)[4],
+            "0,0 junction h Some(Rigtorium) v Some(Flextorium)"
+        );
+        run(&mut world, 1);
+        assert_eq!(
+            snapshot(&world)[4],
+            "0,0 junction h None v Some(Flextorium)"
+        );
+
+        run(&mut world, 6);
+        assert_eq!(
+            snapshot(&world),
+            [
+                "-2,0 belt - -",
+                "-1,0 belt - -",
+                "0,-2 belt - -",
+                "0,-1 belt - -",
+                "0,0 junction h None v None",
+                "0,1 belt - -",
+                "0,2 belt Flextoriumx1 -",
+                "1,0 belt - -",
+                "2,0 belt Rigtoriumx1 -",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_filtered_router_alternates_between_its_open_outputs() {
+        let mut world = build_world(&[
+            (0, 0, (2, 2), Direction::Up),
+            (0, 1, (2, 1), Direction::Up),
+            (1, 0, (2, 1), Direction::Right),
+            (-1, 0, (2, 1), Direction::Left),
+        ]);
+        let router = Position::new(0, 0);
+        let (tile, _) = world.tiles.get_mut(&router).unwrap();
+        tile.as_any_mut().downcast_mut::<Router>().unwrap().filters
+            [RouterOutputIndex::Forward as usize] = Some(Item::Flextorium);
+
+        let router_at = |world: &WorldRes| {
+            let (tile, _) = &world.tiles[&router];
+            let router = tile.as_any().downcast_ref::<Router>().unwrap();
+            (router.item, router.last_output)
+        };
+        let mut outputs = Vec::new();
+        for _ in 0..4 {
+            world
+                .tiles
+                .get_mut(&router)
+                .unwrap()
+                .0
+                .set_item(Some(Item::Rigtorium));
+            world.mark_dirty(router);
+            while router_at(&world).0.is_some() {
+                run(&mut world, 1);
+            }
+            outputs.push(router_at(&world).1);
+        }
+        assert_eq!(
+            outputs,
+            [
+                RouterOutputIndex::Right,
+                RouterOutputIndex::Left,
+                RouterOutputIndex::Right,
+                RouterOutputIndex::Left,
+            ]
+        );
+    }
+
+    #[test]
+    fn a_router_deals_round_robin_into_three_sinks() {
+        let mut world = build_world(&[
+            (0, -1, (2, 1), Direction::Up),
+            (0, 0, (2, 2), Direction::Up),
+            (0, 1, (2, 1), Direction::Up),
+            (0, 2, (5, 1), Direction::Up),
+            (1, 0, (2, 1), Direction::Right),
+            (2, 0, (5, 1), Direction::Right),
+            (-1, 0, (2, 1), Direction::Left),
+            (-2, 0, (5, 1), Direction::Left),
+        ]);
+        let stack = ItemStack {
+            item: Item::Rigtorium,
+            count: 3,
+        };
+        load_belt(&mut world, 0, -1, [Some(stack); 2]);
+
+        // Starting after Forward, outputs are tried Right, Left, then Forward.
+        run(&mut world, 3);
+        assert_eq!(snapshot(&world)[3], "0,0 router Some(Rigtorium) last Right");
+        assert_eq!(snapshot(&world)[6], "1,0 belt Rigtoriumx1 -");
+        run(&mut world, 1);
+        assert_eq!(snapshot(&world)[3], "0,0 router Some(Rigtorium) last Left");
+        assert_eq!(snapshot(&world)[1], "-1,0 belt Rigtoriumx1 -");
+        run(&mut world, 1);
+        assert_eq!(
+            snapshot(&world)[3],
+            "0,0 router Some(Rigtorium) last Forward"
+        );
+        assert_eq!(snapshot(&world)[4], "0,1 belt Rigtoriumx1 -");
+
+        run(&mut world, 20);
+        assert_eq!(
+            snapshot(&world),
+            [
+                "-2,0 storage 2",
+                "-1,0 belt - -",
+                "0,-1 belt - -",
+                "0,0 router None last Forward",
+                "0,1 belt - -",
+                "0,2 storage 2",
+                "1,0 belt - -",
+                "2,0 storage 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn incremental_scheduling_matches_a_full_rescan_every_tick() {
+        let layout = || {
+            let mut layout = vec![
+                (0, 0, (2, 2), Direction::Up),
+                (0, 1, (2, 1), Direction::Up),
+                (0, 2, (5, 1), Direction::Up),
+                (1, 0, (2, 1), Direction::Right),
+                (2, 0, (5, 1), Direction::Right),
+                (-1, 0, (2, 1), Direction::Left),
+                (-2, 0, (5, 1), Direction::Left),
+                (1, -3, (2, 7), Direction::Right),
+                (10, 10, (2, 3), Direction::Up),
+            ];
+            layout.extend((-5..0).map(|y| (0, y, (2, 1), Direction::Up)));
+            layout.extend(
+                (8..13)
+                    .filter(|&x| x != 10)
+                    .map(|x| (x, 10, (2, 1), Direction::Right)),
+            );
+            layout.extend(
+                (8..13)
+                    .filter(|&y| y != 10)
+                    .map(|y| (10, y, (2, 1), Direction::Up)),
+            );
+            let mut world = build_world(&layout);
+            for y in -5..0 {
+                load_belt(
+                    &mut world,
+                    0,
+                    y,
+                    [Some(ItemStack::full(Item::Rigtorium)); 2],
+                );
+            }
+            load_belt(
+                &mut world,
+                8,
+                10,
+                [Some(ItemStack::full(Item::Flextorium)); 2],
+            );
+            load_belt(
+                &mut world,
+                10,
+                8,
+                [Some(ItemStack::full(Item::Electrine)); 2],
+            );
+            world
+        };
+        let state = |world: &WorldRes| {
+            let mut flowing: Vec<_> = world.flowing.iter().copied().collect();
+            flowing.sort_by_key(|(pos, lane)| (*pos, *lane as u8));
+            let (display, _) = &world.tiles[&Position::new(1, -3)];
+            let count = display
+                .as_any()
+                .downcast_ref::<ItemDisplay>()
+                .unwrap()
+                .count;
+            (snapshot(world), flowing, count)
+        };
+
+        let mut incremental = layout();
+        let mut rescanned = layout();
+        for tick in 0..60 {
+            simulate_tick(&mut incremental);
+            rescanned.schedule.stale = true;
+            simulate_tick(&mut rescanned);
+            assert_eq!(state(&incremental), state(&rescanned), "tick {}", tick);
+        }
+        // The run should have filled the vaults and backed the feed belt up.
+        assert_eq!(snapshot(&incremental)[0], "-2,0 storage 10");
+    }
+
+    #[test]
+    fn a_blocked_storage_leaves_its_cluster_untouched() {
+        let mut world = build_world(&[
+            (0, 0, (5, 1), Direction::Up),
+            (1, 0, (5, 1), Direction::Up),
+            (0, 1, (2, 1), Direction::Up),
+        ]);
+        load_belt(
+            &mut world,
+            0,
+            1,
+            [Some(ItemStack::full(Item::Rigtorium)); 2],
+        );
+        let (vault, _) = world.tiles.get_mut(&Position::new(1, 0)).unwrap();
+        vault
+            .as_any_mut()
+            .downcast_mut::<Storage>()
+            .unwrap()
+            .inventory = 5;
+
+        // The empty vault would borrow from its neighbour to feed the belt, but the belt is full.
+        run(&mut world, 3);
+        assert_eq!(snapshot(&world)[0], "0,0 storage 0");
+        assert_eq!(snapshot(&world)[2], "1,0 storage 5");
+    }
+
+    fn belt_and_router_world() -> WorldRes {
+        let mut world = build_world(&[
+            (0, 0, (2, 1), Direction::Up),
+            (0, 1, (2, 1), Direction::Up),
+            (5, 0, (2, 2), Direction::Up),
+            (5, 1, (2, 1), Direction::Up),
+        ]);
+        load_belt(
+            &mut world,
+            0,
+            0,
+            [Some(ItemStack::new(Item::Rigtorium)), None],
+        );
+        let (router, _) = world.tiles.get_mut(&Position::new(5, 0)).unwrap();
+        router.set_item(Some(Item::Flextorium));
+        simulate_tick(&mut world);
+        world
+    }
+
+    #[test]
+    fn removing_a_tile_between_resolve_batches_drops_its_pending_move() {
+        let mut world = belt_and_router_world();
+        begin_tick(&mut world);
+        assert!(resolve_actions(&mut world, 1));
+
+        // Whichever tile hasn't moved yet is removed before the next batch.
+        let pending = world.resolving.as_ref().unwrap().actions[0].position();
+        let (_, tile_type) = world.tiles.remove(&pending).unwrap();
+        world.tile_removed(pending, tile_type);
+        resolve_actions(&mut world, usize::MAX);
+        finish_tick(&mut world);
+
+        let (downstream, _) = &world.tiles[&pending.shift(Direction::Up)];
+        assert_eq!(downstream.get_item(), None);
+        assert_eq!(snapshot(&world).len(), 3);
+    }
+
+    #[test]
+    fn a_router_emptied_mid_tick_delivers_nothing() {
+        let mut world = belt_and_router_world();
+        begin_tick(&mut world);
+        let router = Position::new(5, 0);
+        world.tiles.get_mut(&router).unwrap().0.set_item(None);
+        resolve_actions(&mut world, usize::MAX);
+        finish_tick(&mut world);
+
+        assert_eq!(snapshot(&world)[3], "5,1 belt - -");
     }
 }
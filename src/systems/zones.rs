@@ -0,0 +1,151 @@
+use crate::{
+    GridRegion, TILE_SIZE, UiState, WorldRes, ZONE_COLORS, ZoneOverlay, ZonePainter, utils::*,
+};
+use bevy::{input::mouse::MouseWheel, prelude::*};
+use std::collections::HashMap;
+
+pub fn paint_zones(
+    cursor: CursorInput,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut world: ResMut<WorldRes>,
+    mut painter: ResMut<ZonePainter>,
+    mut gizmos: Gizmos,
+    ui_state: Res<UiState>,
+) {
+    if ui_state.blocks_gameplay() {
+        painter.selection_start = None;
+        mouse_wheel_events.clear();
+        return;
+    }
+
+    let Some(pos) = cursor.grid_position() else {
+        return;
+    };
+
+    if cursor.keyboard_input.pressed(KeyCode::KeyZ) {
+        for event in mouse_wheel_events.read() {
+            let step = if event.y < 0.0 {
+                1
+            } else {
+                ZONE_COLORS.len() - 1
+            };
+            painter.color = ((painter.color as usize + step) % ZONE_COLORS.len()) as u8;
+        }
+        if cursor
+            .mouse_button_input
+            .any_just_pressed([MouseButton::Left, MouseButton::Right])
+        {
+            painter.selection_start = Some(pos);
+        }
+        if painter.selection_start.is_none() {
+            draw_grid_region(
+                &mut gizmos,
+                GridRegion::new(pos, pos),
+                ZONE_COLORS[painter.color as usize].with_alpha(1.0),
+            );
+        }
+    } else {
+        mouse_wheel_events.clear();
+    }
+
+    let Some(start) = painter.selection_start else {
+        return;
+    };
+
+    let erase = cursor.mouse_button_input.pressed(MouseButton::Right)
+        || cursor.mouse_button_input.just_released(MouseButton::Right);
+    let region = GridRegion::new(start, pos);
+    draw_grid_region(
+        &mut gizmos,
+        region,
+        if erase {
+            Color::srgb(0.9, 0.9, 0.9)
+        } else {
+            ZONE_COLORS[painter.color as usize].with_alpha(1.0)
+        },
+    );
+
+    if !cursor
+        .mouse_button_input
+        .any_just_released([MouseButton::Left, MouseButton::Right])
+    {
+        return;
+    }
+    painter.selection_start = None;
+
+    world.zone_revision += 1;
+    for cell in region.cells() {
+        if erase {
+            world.zones.remove(&cell);
+        } else {
+            world.zones.insert(cell, painter.color);
+        }
+    }
+}
+
+// Zones and labels change rarely, so the overlay is rebuilt only when they do.
+pub fn update_zone_overlays(
+    mut commands: Commands,
+    world: Res<WorldRes>,
+    overlay_query: Query<Entity, With<ZoneOverlay>>,
+    mut drawn: Local<Option<u32>>,
+) {
+    if *drawn == Some(world.zone_revision) {
+        return;
+    }
+    *drawn = Some(world.zone_revision);
+
+    for entity in overlay_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let mut centers: HashMap<u8, (Vec2, f32)> = HashMap::new();
+    for (pos, zone) in world.zones.iter() {
+        let center = Vec2::new(pos.x as f32, pos.y as f32) * TILE_SIZE;
+        let entry = centers.entry(*zone).or_insert((Vec2::ZERO, 0.0));
+        entry.0 += center;
+        entry.1 += 1.0;
+        commands.spawn((
+            Sprite {
+                color: ZONE_COLORS[*zone as usize % ZONE_COLORS.len()],
+                custom_size: Some(Vec2::splat(TILE_SIZE)),
+                ..default()
+            },
+            Transform::from_translation(center.extend(4.0)),
+            ZoneOverlay,
+        ));
+    }
+
+    for (zone, (sum, count)) in centers {
+        let Some(name) = world.zone_names.get(&zone) else {
+            continue;
+        };
+        commands.spawn((
+            Text2d::new(name.clone()),
+            TextFont {
+                font_size: 24.0,
+                ..default()
+            },
+            TextColor(ZONE_COLORS[zone as usize % ZONE_COLORS.len()].with_alpha(0.9)),
+            Transform::from_translation((sum / count).extend(6.0)),
+            ZoneOverlay,
+        ));
+    }
+
+    for (pos, label) in world.labels.iter() {
+        commands.spawn((
+            Text2d::new(label.clone()),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Transform::from_translation(Vec3::new(
+                pos.x as f32 * TILE_SIZE,
+                pos.y as f32 * TILE_SIZE,
+                6.0,
+            )),
+            ZoneOverlay,
+        ));
+    }
+}
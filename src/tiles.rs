@@ -1,19 +1,31 @@
 pub mod base;
+pub mod beacon;
 pub mod conveyor;
 pub mod core;
 pub mod extractor;
 pub mod factory;
+pub mod inserter;
+pub mod item_display;
 pub mod junction;
 pub mod portal;
+pub mod quarry;
 pub mod router;
+pub mod sensor;
 pub mod storage;
+pub mod wire;
 
 pub use base::Tile;
+pub use beacon::Beacon;
 pub use conveyor::Conveyor;
 pub use core::Core;
 pub use extractor::Extractor;
 pub use factory::Factory;
+pub use inserter::Inserter;
+pub use item_display::ItemDisplay;
 pub use junction::Junction;
 pub use portal::Portal;
+pub use quarry::Quarry;
 pub use router::Router;
+pub use sensor::Sensor;
 pub use storage::Storage;
+pub use wire::Wire;
@@ -1,14 +1,36 @@
 use std::{any::Any, fmt::Debug};
 
-use crate::{Action, Item, WorldRes};
+use crate::{Action, Item, Lane, Position, WorldRes};
 
 pub trait Tile: Send + Sync + Debug {
     fn tick(&self, tiles: &WorldRes) -> Option<Action>;
 
+    fn secondary_tick(&self, _tiles: &WorldRes) -> Option<Action> {
+        None
+    }
+
     fn set_item(&mut self, item: Option<Item>);
 
     fn get_item(&self) -> Option<Item>;
 
+    fn can_accept_lane(&self, _from: Position, _item: Item, _lane: Lane) -> bool {
+        self.get_item().is_none()
+    }
+
+    fn insert_lane(&mut self, _from: Position, item: Item, _lane: Lane) {
+        self.set_item(Some(item));
+    }
+
+    fn clear_lane(&mut self, _lane: Lane) {
+        self.set_item(None);
+    }
+
+    fn clear_items(&mut self) -> u32 {
+        let count = self.get_item().is_some() as u32;
+        self.set_item(None);
+        count
+    }
+
     fn as_any(&self) -> &dyn Any;
 
     fn as_any_mut(&mut self) -> &mut dyn Any;
@@ -0,0 +1,60 @@
+use std::any::Any;
+
+use crate::{
+    Action, BEACON_CHARGE_PER_ELECTRINE, BEACON_MAX_CHARGE, BEACON_RADIUS, Item, Lane, Position,
+    WorldRes,
+};
+
+use super::Tile;
+
+// Burns Electrine to speed up factories and extractors around it. Each Electrine powers it for
+// `BEACON_CHARGE_PER_ELECTRINE` ticks.
+#[derive(Debug)]
+pub struct Beacon {
+    pub position: Position,
+    pub item: Option<Item>,
+    pub charge: u32,
+}
+
+impl Beacon {
+    pub fn powered(&self) -> bool {
+        self.charge > 0
+    }
+
+    pub fn can_absorb(&self) -> bool {
+        self.charge + BEACON_CHARGE_PER_ELECTRINE <= BEACON_MAX_CHARGE
+    }
+
+    pub fn in_range(&self, pos: Position) -> bool {
+        (pos.x - self.position.x).abs() <= BEACON_RADIUS
+            && (pos.y - self.position.y).abs() <= BEACON_RADIUS
+    }
+}
+
+impl Tile for Beacon {
+    fn tick(&self, _world: &WorldRes) -> Option<Action> {
+        (self.powered() || (self.item.is_some() && self.can_absorb()))
+            .then_some(Action::IncreaseTicks(self.position))
+    }
+
+    fn set_item(&mut self, item: Option<Item>) {
+        self.item = item;
+    }
+
+    // Like a portal, the slot only feeds the beacon, so inserters can't take the Electrine back.
+    fn get_item(&self) -> Option<Item> {
+        None
+    }
+
+    fn can_accept_lane(&self, _from: Position, _item: Item, _lane: Lane) -> bool {
+        self.item.is_none()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
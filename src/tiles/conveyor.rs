@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use crate::{Action, Direction, Item, Position, WorldRes};
+use crate::{
+    Action, CONVEYOR_STACK_SIZE, Direction, Item, ItemStack, Lane, Position, WorldRes,
+    rotate_direction_clockwise, rotate_direction_counterclockwise,
+};
 
 use super::Tile;
 
@@ -8,36 +11,156 @@ use super::Tile;
 pub struct Conveyor {
     pub position: Position,
     pub direction: Direction,
-    pub item: Option<Item>,
+    pub lanes: [Option<ItemStack>; 2],
+    pub tier: ConveyorTier,
 }
 
-impl Tile for Conveyor {
-    fn tick(&self, world: &WorldRes) -> Option<Action> {
-        let start_position = self.position;
-        let mut end_position = self.position;
+// Faster belts carry bigger stacks, since a lane moves its whole stack each tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConveyorTier {
+    Basic,
+    Fast,
+}
+
+impl ConveyorTier {
+    pub fn stack_size(&self) -> u8 {
+        match self {
+            ConveyorTier::Basic => CONVEYOR_STACK_SIZE,
+            ConveyorTier::Fast => CONVEYOR_STACK_SIZE * 2,
+        }
+    }
+}
+
+impl Conveyor {
+    pub fn lane(&self, lane: Lane) -> Option<Item> {
+        self.lanes[lane.index()].map(|stack| stack.item)
+    }
+
+    pub fn stack(&self, lane: Lane) -> Option<ItemStack> {
+        self.lanes[lane.index()]
+    }
+
+    /// How many more of `item` a lane can take; a lane only stacks one kind of item.
+    pub fn room(&self, lane: Lane, item: Item) -> u8 {
+        match self.stack(lane) {
+            None => self.tier.stack_size(),
+            Some(stack) if stack.item == item => self.tier.stack_size().saturating_sub(stack.count),
+            Some(_) => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lanes.iter().all(Option::is_none)
+    }
+
+    pub fn has_free_lane(&self) -> bool {
+        self.lanes.iter().any(Option::is_none)
+    }
 
-        match self.direction {
-            Direction::Up => end_position.y += 1,
-            Direction::Down => end_position.y -= 1,
-            Direction::Left => end_position.x -= 1,
-            Direction::Right => end_position.x += 1,
+    /// Side-loaded items always land on the near lane; items from behind keep their lane and
+    /// spill over to the other one when it is taken.
+    pub fn entry_lanes(&self, from: Position, lane: Lane) -> Vec<Lane> {
+        if from
+            == self
+                .position
+                .shift(rotate_direction_counterclockwise(self.direction))
+        {
+            vec![Lane::Left]
+        } else if from
+            == self
+                .position
+                .shift(rotate_direction_clockwise(self.direction))
+        {
+            vec![Lane::Right]
+        } else {
+            vec![lane, lane.other()]
         }
+    }
+
+    pub fn entry_lane(&self, from: Position, item: Item, lane: Lane) -> Option<Lane> {
+        self.entry_lanes(from, lane)
+            .into_iter()
+            .find(|lane| self.room(*lane, item) > 0)
+    }
+
+    pub fn entry_room(&self, from: Position, item: Item, lane: Lane) -> u8 {
+        self.entry_lanes(from, lane)
+            .into_iter()
+            .map(|lane| self.room(lane, item))
+            .sum()
+    }
+
+    fn lane_action(&self, world: &WorldRes, lane: Lane) -> Option<Action> {
+        let end_position = world.anchor_of(self.position.shift(self.direction));
 
         if world.tiles.contains_key(&end_position) {
-            if let Some(item) = self.item {
-                return Some(Action::Move(start_position, end_position, item));
+            if let Some(item) = self.lane(lane) {
+                return Some(Action::Move(self.position, end_position, item, lane));
             }
         }
 
         None
     }
+}
+
+impl Tile for Conveyor {
+    fn tick(&self, world: &WorldRes) -> Option<Action> {
+        self.lane_action(world, Lane::Left)
+    }
+
+    fn secondary_tick(&self, world: &WorldRes) -> Option<Action> {
+        self.lane_action(world, Lane::Right)
+    }
 
     fn set_item(&mut self, item: Option<Item>) {
-        self.item = item;
+        match item {
+            Some(item) => {
+                let lane = if self.lane(Lane::Left).is_none() {
+                    Lane::Left
+                } else {
+                    Lane::Right
+                };
+                self.lanes[lane.index()] = Some(ItemStack::new(item));
+            }
+            None => self.lanes = [None, None],
+        }
     }
 
     fn get_item(&self) -> Option<Item> {
-        self.item
+        self.lane(Lane::Left).or(self.lane(Lane::Right))
+    }
+
+    fn can_accept_lane(&self, from: Position, item: Item, lane: Lane) -> bool {
+        self.entry_lane(from, item, lane).is_some()
+    }
+
+    fn insert_lane(&mut self, from: Position, item: Item, lane: Lane) {
+        if let Some(lane) = self.entry_lane(from, item, lane) {
+            let stack = &mut self.lanes[lane.index()];
+            match stack {
+                Some(stack) => stack.count += 1,
+                None => *stack = Some(ItemStack::new(item)),
+            }
+        }
+    }
+
+    // Takes one item off the lane, so factories and junctions split stacks as they consume them.
+    fn clear_lane(&mut self, lane: Lane) {
+        let stack = &mut self.lanes[lane.index()];
+        if let Some(held) = stack {
+            held.count -= 1;
+            if held.count == 0 {
+                *stack = None;
+            }
+        }
+    }
+
+    fn clear_items(&mut self) -> u32 {
+        self.lanes
+            .iter_mut()
+            .filter_map(Option::take)
+            .map(|stack| stack.count as u32)
+            .sum()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -48,3 +171,129 @@ impl Tile for Conveyor {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Difficulty, Storage, compute_flowing_lanes, find_deadlocks, get_new_tile,
+        move_tile_contents, simulate_tick,
+    };
+
+    #[test]
+    fn items_stack_on_a_lane_and_move_as_a_whole_stack() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        let (start, end) = (Position::new(20, 20), Position::new(20, 21));
+        let behind = Position::new(20, 19);
+        let mut tile = get_new_tile((2, 1), start, Direction::Up);
+        for _ in 0..5 {
+            tile.0.insert_lane(behind, Item::Rigtorium, Lane::Left);
+        }
+        assert!(!tile.0.can_accept_lane(behind, Item::Flextorium, Lane::Left));
+        let conveyor = tile.0.as_any().downcast_ref::<Conveyor>().unwrap();
+        assert_eq!(
+            conveyor.stack(Lane::Left),
+            Some(ItemStack::full(Item::Rigtorium))
+        );
+        assert_eq!(
+            conveyor.stack(Lane::Right),
+            Some(ItemStack::new(Item::Rigtorium))
+        );
+
+        world.tiles.insert(start, tile);
+        world
+            .tiles
+            .insert(end, get_new_tile((2, 1), end, Direction::Up));
+        world.mark_dirty(start);
+        // The first tick schedules the move and the second applies it.
+        simulate_tick(&mut world);
+        simulate_tick(&mut world);
+
+        let (tile, _) = world.tiles.get_mut(&end).unwrap();
+        let conveyor = tile.as_any().downcast_ref::<Conveyor>().unwrap();
+        assert_eq!(
+            conveyor.stack(Lane::Left),
+            Some(ItemStack::full(Item::Rigtorium))
+        );
+        tile.clear_lane(Lane::Left);
+        assert_eq!(tile.clear_items(), 4);
+    }
+
+    #[test]
+    fn fast_belts_carry_bigger_stacks_through_an_upgrade() {
+        let pos = Position::new(0, 0);
+        let behind = Position::new(0, -1);
+        let (mut basic, _) = get_new_tile((2, 1), pos, Direction::Up);
+        let (mut fast, _) = get_new_tile((2, 9), pos, Direction::Up);
+        for _ in 0..CONVEYOR_STACK_SIZE {
+            basic.insert_lane(behind, Item::Rigtorium, Lane::Left);
+        }
+        basic.insert_lane(behind, Item::Flextorium, Lane::Right);
+
+        assert!(move_tile_contents(basic.as_ref(), fast.as_mut()));
+        let conveyor = fast.as_any().downcast_ref::<Conveyor>().unwrap();
+        assert_eq!(
+            conveyor.lanes,
+            [
+                Some(ItemStack::full(Item::Rigtorium)),
+                Some(ItemStack::new(Item::Flextorium)),
+            ]
+        );
+        assert_eq!(
+            conveyor.room(Lane::Left, Item::Rigtorium),
+            CONVEYOR_STACK_SIZE
+        );
+
+        for _ in 0..CONVEYOR_STACK_SIZE {
+            fast.insert_lane(behind, Item::Rigtorium, Lane::Left);
+        }
+        let (mut downgraded, _) = get_new_tile((2, 1), pos, Direction::Up);
+        assert!(!move_tile_contents(fast.as_ref(), downgraded.as_mut()));
+    }
+
+    #[test]
+    fn a_full_belt_loop_is_deadlocked_until_it_has_a_gap() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        let belt_loop = [
+            (Position::new(10, 10), Direction::Up),
+            (Position::new(10, 11), Direction::Right),
+            (Position::new(11, 11), Direction::Down),
+            (Position::new(11, 10), Direction::Left),
+        ];
+        for (pos, direction) in belt_loop {
+            let mut tile = get_new_tile((2, 1), pos, direction);
+            if let Some(conveyor) = tile.0.as_any_mut().downcast_mut::<Conveyor>() {
+                conveyor.lanes = [Some(ItemStack::full(Item::Rigtorium)); 2];
+            }
+            world.tiles.insert(pos, tile);
+        }
+        world.flowing = compute_flowing_lanes(&world);
+        assert_eq!(find_deadlocks(&world).len(), 4);
+
+        let (tile, _) = world.tiles.get_mut(&Position::new(11, 10)).unwrap();
+        tile.clear_items();
+        world.flowing = compute_flowing_lanes(&world);
+        assert!(find_deadlocks(&world).is_empty());
+    }
+
+    #[test]
+    fn a_belt_into_storage_flows_only_while_the_storage_has_room() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        let (belt, vault) = (Position::new(10, 10), Position::new(10, 11));
+        let mut tile = get_new_tile((2, 1), belt, Direction::Up);
+        if let Some(conveyor) = tile.0.as_any_mut().downcast_mut::<Conveyor>() {
+            conveyor.lanes = [Some(ItemStack::new(Item::Rigtorium)), None];
+        }
+        world.tiles.insert(belt, tile);
+        world
+            .tiles
+            .insert(vault, get_new_tile((5, 1), vault, Direction::Up));
+        world.rebuild_storage_clusters();
+        assert!(compute_flowing_lanes(&world).contains(&(belt, Lane::Left)));
+
+        let (tile, _) = world.tiles.get_mut(&vault).unwrap();
+        let storage = tile.as_any_mut().downcast_mut::<Storage>().unwrap();
+        storage.inventory = storage.storage_type.capacity();
+        assert!(!compute_flowing_lanes(&world).contains(&(belt, Lane::Left)));
+    }
+}
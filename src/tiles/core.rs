@@ -1,6 +1,6 @@
 use std::any::Any;
 
-use crate::{Action, Item, Position, WorldRes};
+use crate::{Action, Conveyor, Direction, Item, Lane, Position, WorldRes, will_tile_accept_item};
 
 use super::Tile;
 
@@ -10,20 +10,43 @@ pub struct Core {
     pub interval: u32,
     pub ticks: u32,
     pub tile_id: (u8, u8),
+    // Puts finished tiles that have an item form onto an adjacent belt instead of the inventory.
+    pub eject: bool,
+    pub item: Option<Item>,
 }
 
 impl Tile for Core {
-    fn tick(&self, _world: &WorldRes) -> Option<Action> {
+    fn tick(&self, world: &WorldRes) -> Option<Action> {
+        if let Some(item) = self.item {
+            return Direction::ALL
+                .into_iter()
+                .map(|direction| self.position.shift(direction))
+                .find(|pos| {
+                    world
+                        .tiles
+                        .get(pos)
+                        .is_some_and(|(tile, _)| tile.as_any().is::<Conveyor>())
+                        && will_tile_accept_item(world, self.position, *pos, item, Lane::Left)
+                })
+                .map(|pos| Action::Move(self.position, pos, item, Lane::Left));
+        }
+
         if self.ticks >= self.interval {
             return Some(Action::Teleport(self.position, self.tile_id));
         } else {
             return Some(Action::IncreaseTicks(self.position));
         }
     }
-    fn set_item(&mut self, _item: Option<Item>) {}
+    fn set_item(&mut self, item: Option<Item>) {
+        self.item = item;
+    }
 
     fn get_item(&self) -> Option<Item> {
-        return None;
+        self.item
+    }
+
+    fn can_accept_lane(&self, _from: Position, _item: Item, _lane: Lane) -> bool {
+        false
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -3,7 +3,10 @@ use std::any::Any;
 use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-use crate::{Action, Direction, Item, Position, WorldRes};
+use crate::{
+    Action, Direction, EXTRACTOR_FULL_COVERAGE, Item, Lane, Position, WorldEventKind, WorldRes,
+    beacon_boost_percent,
+};
 
 use super::Tile;
 
@@ -12,14 +15,49 @@ pub struct Extractor {
     pub position: Position,
     pub direction: Direction,
     pub extractor_type: ExtractorType,
+    pub tier: ExtractorTier,
     pub item: Option<Item>,
 }
 
+impl Extractor {
+    pub fn covered_deposits(&self, world: &WorldRes) -> i32 {
+        let terrain = self.extractor_type.terrain();
+        (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+            .filter(|(dx, dy)| {
+                world
+                    .terrain
+                    .get(&Position::new(self.position.x + dx, self.position.y + dy))
+                    == Some(&terrain)
+            })
+            .count() as i32
+    }
+
+    // Produces at a fractional rate so tiers and partial coverage still differ on fast deposits.
+    pub fn produces_this_tick(&self, world: &WorldRes) -> bool {
+        let covered = self.covered_deposits(world);
+        if covered == 0 {
+            return false;
+        }
+
+        let surge = if world.has_world_event(WorldEventKind::DepositSurge) {
+            2
+        } else {
+            1
+        };
+        let rate =
+            (self.tier.speed_percent() * covered.min(EXTRACTOR_FULL_COVERAGE) * surge) as i64;
+        let boost = beacon_boost_percent(world, self.position) as i32;
+        let period =
+            (self.extractor_type.interval() * EXTRACTOR_FULL_COVERAGE * (100 - boost)) as i64;
+        let tick = world.tick_count as i64;
+        tick * rate / period != (tick - 1) * rate / period
+    }
+}
+
 impl Tile for Extractor {
     fn tick(&self, world: &WorldRes) -> Option<Action> {
-        if world.tick_count % self.extractor_type.interval() == 0
-            && world.terrain.get(&self.position) == Some(&self.extractor_type.terrain())
-        {
+        if self.produces_this_tick(world) {
             return Some(Action::Produce(self.position));
         }
         let start_position = self.position;
@@ -34,7 +72,7 @@ impl Tile for Extractor {
 
         if world.tiles.contains_key(&end_position) {
             if let Some(item) = self.item {
-                return Some(Action::Move(start_position, end_position, item));
+                return Some(Action::Move(start_position, end_position, item, Lane::Left));
             }
         }
 
@@ -58,11 +96,29 @@ impl Tile for Extractor {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub enum ExtractorTier {
+    Mk1,
+    Mk2,
+    Mk3,
+}
+
+impl ExtractorTier {
+    pub fn speed_percent(&self) -> i32 {
+        match self {
+            ExtractorTier::Mk1 => 100,
+            ExtractorTier::Mk2 => 150,
+            ExtractorTier::Mk3 => 200,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode)]
 pub enum ExtractorType {
     RawFlextorium,
     RawRigtorium,
     Electrine,
+    CrudeOil,
 }
 
 impl ExtractorType {
@@ -71,6 +127,7 @@ impl ExtractorType {
             ExtractorType::RawRigtorium => 5,
             ExtractorType::RawFlextorium => 5,
             ExtractorType::Electrine => 2,
+            ExtractorType::CrudeOil => 8,
         }
     }
 
@@ -79,6 +136,7 @@ impl ExtractorType {
             ExtractorType::RawRigtorium => crate::types::TerrainTileType::RawRigtoriumDeposit,
             ExtractorType::RawFlextorium => crate::types::TerrainTileType::RawFlextoriumDeposit,
             ExtractorType::Electrine => crate::types::TerrainTileType::ElectrineDeposit,
+            ExtractorType::CrudeOil => crate::types::TerrainTileType::OilSeep,
         }
     }
 
@@ -87,6 +145,7 @@ impl ExtractorType {
             ExtractorType::RawRigtorium => Item::RawRigtorium,
             ExtractorType::RawFlextorium => Item::RawFlextorium,
             ExtractorType::Electrine => Item::Electrine,
+            ExtractorType::CrudeOil => Item::CrudeOil,
         }
     }
 
@@ -97,6 +156,7 @@ impl ExtractorType {
                 "embedded://textures/tiles/extractors/raw_flextorium.png"
             }
             ExtractorType::Electrine => "embedded://textures/tiles/extractors/electrine.png",
+            ExtractorType::CrudeOil => "embedded://textures/tiles/extractors/pumpjack.png",
         }
         .to_string()
     }
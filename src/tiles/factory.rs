@@ -1,9 +1,9 @@
-use std::{any::Any, collections::HashMap};
+use std::{any::Any, collections::HashMap, sync::LazyLock};
 
 use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-use crate::{Action, Direction, Item, Position, WorldRes};
+use crate::{Action, Direction, Item, Lane, Position, Recipe, WorldRes, will_tile_accept_item};
 
 use super::Tile;
 
@@ -14,28 +14,117 @@ pub struct Factory {
     pub factory_type: FactoryType,
     pub inventory: HashMap<Item, u32>,
     pub item: Option<Item>,
+    pub byproducts: Vec<Item>,
     pub interval: u32,
     pub ticks: u32,
+    pub recipe_index: usize,
+    pub outputs: [bool; 4],
+    pub inputs: [bool; 4],
 }
 
 impl Factory {
+    pub fn recipe(&self) -> &'static Recipe {
+        self.factory_type.recipe(self.recipe_index)
+    }
+
+    pub fn capacity(&self) -> &'static HashMap<Item, u32> {
+        self.factory_type.capacity(self.recipe_index)
+    }
+
+    // Whether everything in the inventory fits the recipe, so switching to it loses nothing.
+    pub fn can_select_recipe(&self, recipe_index: usize) -> bool {
+        if recipe_index >= self.factory_type.recipes().len() {
+            return false;
+        }
+        let capacity = self.factory_type.capacity(recipe_index);
+        self.inventory
+            .iter()
+            .all(|(item, &qty)| qty <= capacity.get(item).copied().unwrap_or(0))
+    }
+
+    // Leaves the recipe as it is, returning false, while the inventory holds items the new
+    // recipe has no room for.
+    pub fn select_recipe(&mut self, recipe_index: usize) -> bool {
+        if recipe_index == self.recipe_index {
+            return true;
+        }
+        if !self.can_select_recipe(recipe_index) {
+            return false;
+        }
+        self.recipe_index = recipe_index;
+        self.ticks = 0;
+
+        let capacity = self.capacity();
+        self.inventory.retain(|item, _| capacity.contains_key(item));
+        true
+    }
+
+    pub fn side_position(&self, side: FactorySide) -> Position {
+        self.position.shift(side.to_direction(self.direction))
+    }
+
+    pub fn accepts_from(&self, from: Position) -> bool {
+        FactorySide::ALL
+            .into_iter()
+            .any(|side| self.inputs[side as usize] && self.side_position(side) == from)
+    }
+
+    pub fn outputs_to(&self, direction: Direction) -> bool {
+        FactorySide::ALL.into_iter().any(|side| {
+            self.outputs[side as usize] && side.to_direction(self.direction) == direction
+        })
+    }
+
+    // The first output side that will take the item, falling back to the first one with a tile.
+    pub fn output_position(&self, world: &WorldRes) -> Option<Position> {
+        let item = self.item.unwrap_or(self.recipe().output);
+        let outputs: Vec<Position> = FactorySide::ALL
+            .into_iter()
+            .filter(|side| self.outputs[*side as usize])
+            .map(|side| world.anchor_of(self.side_position(side)))
+            .filter(|pos| world.tiles.contains_key(pos))
+            .collect();
+
+        outputs
+            .iter()
+            .find(|pos| will_tile_accept_item(world, self.position, **pos, item, Lane::Left))
+            .or(outputs.first())
+            .copied()
+    }
+
     pub fn can_produce(&self) -> bool {
-        let recipe = self.factory_type.recipe();
+        let recipe = self.recipe();
         recipe
             .inputs
             .iter()
             .all(|(item, &qty_required)| self.inventory.get(item).unwrap_or(&0) >= &qty_required)
             && self.item.is_none()
+            && self.byproducts.is_empty()
+    }
+
+    // The recipe input furthest from being met, as (item, held, required). Ties go to the item
+    // that sorts first so the answer doesn't flicker between frames.
+    pub fn limiting_input(&self) -> Option<(Item, u32, u32)> {
+        self.recipe()
+            .inputs
+            .iter()
+            .map(|(item, &required)| (*item, *self.inventory.get(item).unwrap_or(&0), required))
+            .min_by(|a, b| {
+                (a.1 as u64 * b.2 as u64)
+                    .cmp(&(b.1 as u64 * a.2 as u64))
+                    .then((a.0 as usize).cmp(&(b.0 as usize)))
+            })
     }
 
     pub fn produce(&mut self) -> Option<Item> {
-        let recipe = self.factory_type.recipe();
+        let recipe = self.recipe();
         if self.can_produce() {
             for (item, &qty_required) in recipe.inputs.iter() {
                 if let Some(qty) = self.inventory.get_mut(item) {
                     *qty = qty.saturating_sub(qty_required);
                 }
             }
+            self.byproducts.extend(recipe.byproducts.iter().rev());
 
             Some(recipe.output)
         } else {
@@ -50,26 +139,25 @@ impl Tile for Factory {
             return Some(Action::Produce(self.position));
         }
 
-        let mut end_position = self.position;
-
-        match self.direction {
-            Direction::Up => end_position.y += 1,
-            Direction::Down => end_position.y -= 1,
-            Direction::Left => end_position.x -= 1,
-            Direction::Right => end_position.x += 1,
-        }
-
-        if world.tiles.contains_key(&end_position) {
-            if let Some(item) = self.item {
-                return Some(Action::Move(self.position, end_position, item));
-            }
+        if let Some(item) = self.item
+            && let Some(end_position) = self.output_position(world)
+        {
+            return Some(Action::Move(self.position, end_position, item, Lane::Left));
         }
 
         None
     }
 
+    // Emptying the output slot lets the next byproduct out.
     fn set_item(&mut self, item: Option<Item>) {
-        self.item = item;
+        self.item = item.or_else(|| self.byproducts.pop());
+    }
+
+    fn clear_items(&mut self) -> u32 {
+        let count = self.item.is_some() as u32 + self.byproducts.len() as u32;
+        self.item = None;
+        self.byproducts.clear();
+        count
     }
 
     fn get_item(&self) -> Option<Item> {
@@ -85,6 +173,36 @@ impl Tile for Factory {
     }
 }
 
+// Sides are relative to the factory's facing, so they follow it when rotated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub enum FactorySide {
+    Front = 0,
+    Right = 1,
+    Back = 2,
+    Left = 3,
+}
+impl FactorySide {
+    pub const ALL: [FactorySide; 4] = [
+        FactorySide::Front,
+        FactorySide::Right,
+        FactorySide::Back,
+        FactorySide::Left,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FactorySide::Front => "Front",
+            FactorySide::Right => "Right",
+            FactorySide::Back => "Back",
+            FactorySide::Left => "Left",
+        }
+    }
+
+    pub fn to_direction(&self, base_direction: Direction) -> Direction {
+        base_direction.shift(*self as i32)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode)]
 pub enum FactoryType {
     RigtoriumSmelter,
@@ -92,96 +210,288 @@ pub enum FactoryType {
     RigtoriumRodMolder,
     ConveyorConstructor,
     RouterConstructor,
+    Assembler,
+    CircuitPrinter,
+    MotorAssembler,
+    ExtractorConstructor,
+    FactoryConstructor,
+    BeaconAssembler,
+    Recycler,
+    Refinery,
 }
 
-impl FactoryType {
-    pub fn capacity(&self) -> HashMap<Item, u32> {
-        match self {
-            FactoryType::RigtoriumSmelter => {
-                let mut hashmap = HashMap::new();
-                hashmap.insert(Item::RawRigtorium, 2);
-                hashmap.insert(Item::Electrine, 2);
-                hashmap
-            }
-            FactoryType::FlextoriumFabricator => {
-                let mut hashmap = HashMap::new();
-                hashmap.insert(Item::RawFlextorium, 2);
-                hashmap.insert(Item::Electrine, 2);
-                hashmap
-            }
-            FactoryType::RigtoriumRodMolder => {
-                let mut hashmap = HashMap::new();
-                hashmap.insert(Item::Rigtorium, 4);
-                hashmap.insert(Item::Electrine, 2);
-                hashmap
-            }
-            FactoryType::ConveyorConstructor => {
-                let mut hashmap = HashMap::new();
-                hashmap.insert(Item::Flextorium, 8);
-                hashmap.insert(Item::RigtoriumRod, 4);
-                hashmap.insert(Item::Electrine, 2);
-                hashmap
-            }
-            FactoryType::RouterConstructor => {
-                let mut hashmap = HashMap::new();
-                hashmap.insert(Item::Flextorium, 4);
-                hashmap.insert(Item::Conveyor, 2);
-                hashmap
+struct RecipeBook {
+    recipes: Vec<Recipe>,
+    capacities: Vec<HashMap<Item, u32>>,
+}
+
+// Indexed by `FactoryType as usize`; recipes and capacities are queried many times per tick, so
+// they are built once instead of on every call.
+static RECIPE_BOOKS: LazyLock<Vec<RecipeBook>> = LazyLock::new(|| {
+    FactoryType::ALL
+        .iter()
+        .map(|factory_type| {
+            let recipes = factory_type.build_recipes();
+            let capacities = recipes
+                .iter()
+                .map(|recipe| {
+                    recipe
+                        .inputs
+                        .iter()
+                        .map(|(item, qty)| (*item, qty * 2))
+                        .collect()
+                })
+                .collect();
+            RecipeBook {
+                recipes,
+                capacities,
             }
-        }
-    }
+        })
+        .collect()
+});
 
-    pub fn recipe(&self) -> crate::types::Recipe {
+impl FactoryType {
+    pub const ALL: [FactoryType; 13] = [
+        FactoryType::RigtoriumSmelter,
+        FactoryType::FlextoriumFabricator,
+        FactoryType::RigtoriumRodMolder,
+        FactoryType::ConveyorConstructor,
+        FactoryType::RouterConstructor,
+        FactoryType::Assembler,
+        FactoryType::CircuitPrinter,
+        FactoryType::MotorAssembler,
+        FactoryType::ExtractorConstructor,
+        FactoryType::FactoryConstructor,
+        FactoryType::BeaconAssembler,
+        FactoryType::Recycler,
+        FactoryType::Refinery,
+    ];
+
+    fn build_recipes(&self) -> Vec<Recipe> {
         match self {
             FactoryType::RigtoriumSmelter => {
                 let mut inputs = HashMap::new();
                 inputs.insert(Item::RawRigtorium, 1);
                 inputs.insert(Item::Electrine, 1);
-                crate::types::Recipe {
+                vec![Recipe {
                     inputs,
                     output: Item::Rigtorium,
-                }
+                    byproducts: Vec::new(),
+                }]
             }
             FactoryType::FlextoriumFabricator => {
                 let mut inputs = HashMap::new();
                 inputs.insert(Item::RawFlextorium, 1);
                 inputs.insert(Item::Electrine, 1);
-                crate::types::Recipe {
+                vec![Recipe {
                     inputs,
                     output: Item::Flextorium,
-                }
+                    byproducts: Vec::new(),
+                }]
             }
             FactoryType::RigtoriumRodMolder => {
                 let mut inputs = HashMap::new();
                 inputs.insert(Item::Rigtorium, 2);
                 inputs.insert(Item::Electrine, 1);
-                crate::types::Recipe {
+                vec![Recipe {
                     inputs,
                     output: Item::RigtoriumRod,
-                }
+                    byproducts: Vec::new(),
+                }]
             }
             FactoryType::ConveyorConstructor => {
                 let mut inputs = HashMap::new();
                 inputs.insert(Item::Flextorium, 4);
                 inputs.insert(Item::RigtoriumRod, 2);
                 inputs.insert(Item::Electrine, 1);
-                crate::types::Recipe {
+                vec![Recipe {
                     inputs,
                     output: Item::Conveyor,
-                }
+                    byproducts: Vec::new(),
+                }]
             }
             FactoryType::RouterConstructor => {
                 let mut inputs = HashMap::new();
                 inputs.insert(Item::Flextorium, 2);
                 inputs.insert(Item::Conveyor, 1);
-                crate::types::Recipe {
+                vec![Recipe {
                     inputs,
                     output: Item::Router,
+                    byproducts: Vec::new(),
+                }]
+            }
+            FactoryType::Assembler => [
+                FactoryType::RigtoriumRodMolder,
+                FactoryType::ConveyorConstructor,
+                FactoryType::RouterConstructor,
+            ]
+            .iter()
+            .map(|factory_type| factory_type.build_recipes().swap_remove(0))
+            .collect(),
+            FactoryType::CircuitPrinter => {
+                let mut inputs = HashMap::new();
+                inputs.insert(Item::Flextorium, 2);
+                inputs.insert(Item::Electrine, 2);
+                inputs.insert(Item::RigtoriumRod, 1);
+
+                let mut plastic = HashMap::new();
+                plastic.insert(Item::Plastic, 1);
+                plastic.insert(Item::Electrine, 1);
+                plastic.insert(Item::RigtoriumRod, 1);
+
+                vec![
+                    Recipe {
+                        inputs,
+                        output: Item::Circuit,
+                        byproducts: Vec::new(),
+                    },
+                    Recipe {
+                        inputs: plastic,
+                        output: Item::Circuit,
+                        byproducts: Vec::new(),
+                    },
+                ]
+            }
+            FactoryType::MotorAssembler => {
+                let mut inputs = HashMap::new();
+                inputs.insert(Item::Rigtorium, 2);
+                inputs.insert(Item::RigtoriumRod, 2);
+                inputs.insert(Item::Circuit, 1);
+                vec![Recipe {
+                    inputs,
+                    output: Item::Motor,
+                    byproducts: Vec::new(),
+                }]
+            }
+            FactoryType::ExtractorConstructor => [
+                (Item::Rigtorium, Item::RawRigtoriumExtractor),
+                (Item::Flextorium, Item::RawFlextoriumExtractor),
+                (Item::Rigtorium, Item::ElectrineExtractor),
+            ]
+            .into_iter()
+            .map(|(material, output)| {
+                let mut inputs = HashMap::new();
+                inputs.insert(material, 4);
+                inputs.insert(Item::Motor, 1);
+                inputs.insert(Item::Circuit, 1);
+                Recipe {
+                    inputs,
+                    output,
+                    byproducts: Vec::new(),
                 }
+            })
+            .collect(),
+            FactoryType::FactoryConstructor => {
+                let mut smelter = HashMap::new();
+                smelter.insert(Item::Rigtorium, 4);
+                smelter.insert(Item::Circuit, 2);
+
+                let mut fabricator = HashMap::new();
+                fabricator.insert(Item::Flextorium, 4);
+                fabricator.insert(Item::Circuit, 2);
+
+                let mut molder = HashMap::new();
+                molder.insert(Item::RigtoriumRod, 4);
+                molder.insert(Item::Circuit, 2);
+                molder.insert(Item::Motor, 1);
+
+                vec![
+                    Recipe {
+                        inputs: smelter,
+                        output: Item::RigtoriumSmelter,
+                        byproducts: Vec::new(),
+                    },
+                    Recipe {
+                        inputs: fabricator,
+                        output: Item::FlextoriumFabricator,
+                        byproducts: Vec::new(),
+                    },
+                    Recipe {
+                        inputs: molder,
+                        output: Item::RigtoriumRodMolder,
+                        byproducts: Vec::new(),
+                    },
+                ]
+            }
+            FactoryType::BeaconAssembler => {
+                let mut inputs = HashMap::new();
+                inputs.insert(Item::Motor, 4);
+                inputs.insert(Item::Circuit, 6);
+                inputs.insert(Item::RigtoriumRod, 8);
+                inputs.insert(Item::Flextorium, 8);
+
+                let mut fueled = HashMap::new();
+                fueled.insert(Item::Motor, 4);
+                fueled.insert(Item::Circuit, 4);
+                fueled.insert(Item::Plastic, 6);
+                fueled.insert(Item::Fuel, 6);
+
+                vec![
+                    Recipe {
+                        inputs,
+                        output: Item::OrbitalBeacon,
+                        byproducts: Vec::new(),
+                    },
+                    Recipe {
+                        inputs: fueled,
+                        output: Item::OrbitalBeacon,
+                        byproducts: Vec::new(),
+                    },
+                ]
+            }
+            // One recipe per component that can be reclaimed. Each item returns a single unit of the
+            // chosen component, a fraction of what it took to make.
+            FactoryType::Recycler => [
+                FactoryType::ConveyorConstructor,
+                FactoryType::RouterConstructor,
+                FactoryType::RigtoriumRodMolder,
+            ]
+            .iter()
+            .flat_map(|factory_type| {
+                let recipe = factory_type.build_recipes().swap_remove(0);
+                Item::ALL
+                    .into_iter()
+                    .filter(|item| recipe.inputs.contains_key(item))
+                    .map(|component| {
+                        let mut inputs = HashMap::new();
+                        inputs.insert(recipe.output, 1);
+                        Recipe {
+                            inputs,
+                            output: component,
+                            byproducts: Vec::new(),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect(),
+            FactoryType::Refinery => {
+                let mut inputs = HashMap::new();
+                inputs.insert(Item::CrudeOil, 2);
+                inputs.insert(Item::Electrine, 1);
+                vec![Recipe {
+                    inputs,
+                    output: Item::Plastic,
+                    byproducts: vec![Item::Fuel],
+                }]
             }
         }
     }
 
+    pub fn recipes(&self) -> &'static [Recipe] {
+        &RECIPE_BOOKS[*self as usize].recipes
+    }
+
+    pub fn recipe(&self, recipe_index: usize) -> &'static Recipe {
+        self.recipes()
+            .get(recipe_index)
+            .unwrap_or(&self.recipes()[0])
+    }
+
+    pub fn capacity(&self, recipe_index: usize) -> &'static HashMap<Item, u32> {
+        let capacities = &RECIPE_BOOKS[*self as usize].capacities;
+        capacities.get(recipe_index).unwrap_or(&capacities[0])
+    }
+
     pub fn sprite(&self) -> &'static str {
         match self {
             FactoryType::RigtoriumSmelter => {
@@ -199,6 +509,142 @@ impl FactoryType {
             FactoryType::RouterConstructor => {
                 "embedded://textures/tiles/factories/router_constructor.png"
             }
+            FactoryType::Assembler => "embedded://textures/tiles/assembler.png",
+            FactoryType::CircuitPrinter => {
+                "embedded://textures/tiles/factories/circuit_printer.png"
+            }
+            FactoryType::MotorAssembler => {
+                "embedded://textures/tiles/factories/motor_assembler.png"
+            }
+            FactoryType::ExtractorConstructor => {
+                "embedded://textures/tiles/factories/extractor_constructor.png"
+            }
+            FactoryType::FactoryConstructor => {
+                "embedded://textures/tiles/factories/factory_constructor.png"
+            }
+            FactoryType::BeaconAssembler => {
+                "embedded://textures/tiles/factories/beacon_assembler.png"
+            }
+            FactoryType::Recycler => "embedded://textures/tiles/factories/recycler.png",
+            FactoryType::Refinery => "embedded://textures/tiles/factories/refinery.png",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factory_types_are_listed_in_declaration_order() {
+        for (index, factory_type) in FactoryType::ALL.iter().enumerate() {
+            assert_eq!(*factory_type as usize, index);
+        }
+    }
+
+    #[test]
+    fn recycler_returns_one_component_of_the_source_recipe() {
+        let recipes = FactoryType::Recycler.recipes();
+        assert_eq!(recipes.len(), 7);
+        for recipe in recipes {
+            let (&recycled, &count) = recipe.inputs.iter().next().unwrap();
+            assert_eq!(count, 1);
+            let source = FactoryType::ALL
+                .iter()
+                .flat_map(|factory_type| factory_type.recipes())
+                .find(|source| source.output == recycled)
+                .unwrap();
+            assert!(source.inputs[&recipe.output] >= 1);
         }
     }
+
+    #[test]
+    fn refinery_hands_out_byproducts_after_the_output() {
+        let mut factory = Factory {
+            position: Position::new(0, 0),
+            direction: Direction::Up,
+            factory_type: FactoryType::Refinery,
+            inventory: HashMap::from([(Item::CrudeOil, 2), (Item::Electrine, 1)]),
+            item: None,
+            byproducts: Vec::new(),
+            interval: 6,
+            ticks: 0,
+            recipe_index: 0,
+            outputs: [true, false, false, false],
+            inputs: [false, true, true, true],
+        };
+
+        let output = factory.produce();
+        factory.set_item(output);
+        assert_eq!(factory.get_item(), Some(Item::Plastic));
+        assert!(!factory.can_produce());
+
+        factory.set_item(None);
+        assert_eq!(factory.get_item(), Some(Item::Fuel));
+        factory.set_item(None);
+        assert_eq!(factory.get_item(), None);
+    }
+
+    #[test]
+    fn switching_recipes_keeps_the_inventory() {
+        let recipes = FactoryType::Recycler.recipes();
+        let (&first_input, _) = recipes[0].inputs.iter().next().unwrap();
+        let other = recipes
+            .iter()
+            .position(|recipe| !recipe.inputs.contains_key(&first_input))
+            .unwrap();
+        let (&other_input, _) = recipes[other].inputs.iter().next().unwrap();
+        let mut factory = Factory {
+            position: Position::new(0, 0),
+            direction: Direction::Up,
+            factory_type: FactoryType::Recycler,
+            inventory: HashMap::from([(first_input, 1)]),
+            item: None,
+            byproducts: Vec::new(),
+            interval: 4,
+            ticks: 2,
+            recipe_index: 0,
+            outputs: [true, false, false, false],
+            inputs: [false, true, true, true],
+        };
+
+        assert!(!factory.select_recipe(other));
+        assert_eq!(factory.recipe_index, 0);
+        assert_eq!(factory.ticks, 2);
+        assert_eq!(factory.inventory, HashMap::from([(first_input, 1)]));
+
+        factory.inventory.insert(first_input, 0);
+        assert!(factory.select_recipe(other));
+        assert_eq!(factory.recipe_index, other);
+        assert_eq!(factory.ticks, 0);
+        assert!(factory.inventory.is_empty());
+
+        factory
+            .inventory
+            .insert(other_input, factory.capacity()[&other_input]);
+        assert!(factory.can_select_recipe(other));
+        assert!(!factory.can_select_recipe(recipes.len()));
+    }
+
+    #[test]
+    fn limiting_input_is_the_lowest_fill_ratio() {
+        let mut factory = Factory {
+            position: Position::new(0, 0),
+            direction: Direction::Up,
+            factory_type: FactoryType::Refinery,
+            inventory: HashMap::from([(Item::CrudeOil, 1), (Item::Electrine, 1)]),
+            item: None,
+            byproducts: Vec::new(),
+            interval: 6,
+            ticks: 0,
+            recipe_index: 0,
+            outputs: [true, false, false, false],
+            inputs: [false, true, true, true],
+        };
+        assert_eq!(factory.limiting_input(), Some((Item::CrudeOil, 1, 2)));
+
+        factory.inventory.insert(Item::CrudeOil, 2);
+        factory.inventory.insert(Item::Electrine, 0);
+        assert_eq!(factory.limiting_input(), Some((Item::Electrine, 0, 1)));
+    }
 }
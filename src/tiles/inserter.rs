@@ -0,0 +1,170 @@
+use std::any::Any;
+
+use crate::{Action, Direction, Item, Lane, Position, WorldRes, will_tile_accept_item};
+
+use super::{Conveyor, Storage, Tile};
+
+#[derive(Debug)]
+pub struct Inserter {
+    pub position: Position,
+    pub direction: Direction,
+}
+
+impl Inserter {
+    pub fn pickup_position(&self) -> Position {
+        self.position.shift(self.direction.shift(2))
+    }
+
+    pub fn drop_position(&self) -> Position {
+        self.position.shift(self.direction)
+    }
+}
+
+impl Tile for Inserter {
+    fn tick(&self, world: &WorldRes) -> Option<Action> {
+        let pickup = world.anchor_of(self.pickup_position());
+        let drop = world.anchor_of(self.drop_position());
+        let (source, _) = world.tiles.get(&pickup)?;
+        let (item, lane) = if let Some(conveyor) = source.as_any().downcast_ref::<Conveyor>() {
+            Lane::ALL
+                .into_iter()
+                .find_map(|lane| conveyor.lane(lane).map(|item| (item, lane)))?
+        } else if let Some(storage) = source.as_any().downcast_ref::<Storage>() {
+            if world.storage_fill(pickup).0 == 0 {
+                return None;
+            }
+            (storage.storage_type.stored_item(), Lane::Left)
+        } else {
+            (source.get_item()?, Lane::Left)
+        };
+
+        let target = world.tiles.get(&drop)?;
+        let can_accept = if let Some(storage) = target.0.as_any().downcast_ref::<Storage>() {
+            let (stored, capacity) = world.storage_fill(drop);
+            storage.storage_type.stored_item() == item && stored < capacity
+        } else {
+            will_tile_accept_item(world, pickup, drop, item, lane)
+        };
+
+        can_accept.then_some(Action::Move(pickup, drop, item, lane))
+    }
+
+    // Items only pass through an inserter, so tiles pointing into it can't drop anything on it.
+    fn can_accept_lane(&self, _from: Position, _item: Item, _lane: Lane) -> bool {
+        false
+    }
+
+    fn set_item(&mut self, _item: Option<Item>) {}
+
+    fn get_item(&self) -> Option<Item> {
+        None
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Difficulty, Factory, ItemStack, Storage, get_new_tile, simulate_tick};
+
+    fn place(world: &mut WorldRes, pos: Position, tile_type: (u8, u8), direction: Direction) {
+        world
+            .tiles
+            .insert(pos, get_new_tile(tile_type, pos, direction));
+        world.mark_dirty(pos);
+    }
+
+    #[test]
+    fn an_inserter_feeds_a_belt_item_into_a_factory() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        world.tiles.clear();
+        let (belt, inserter, smelter) = (
+            Position::new(0, 0),
+            Position::new(0, 1),
+            Position::new(0, 2),
+        );
+        // The belt runs past the inserter, which picks the item off its side.
+        place(&mut world, belt, (2, 1), Direction::Right);
+        place(&mut world, inserter, (2, 8), Direction::Up);
+        place(&mut world, smelter, (4, 1), Direction::Up);
+        let (tile, _) = world.tiles.get_mut(&belt).unwrap();
+        tile.as_any_mut().downcast_mut::<Conveyor>().unwrap().lanes =
+            [Some(ItemStack::new(Item::RawRigtorium)), None];
+        let (tile, _) = world.tiles.get_mut(&smelter).unwrap();
+        tile.as_any_mut().downcast_mut::<Factory>().unwrap().inputs = [true; 4];
+
+        for _ in 0..4 {
+            simulate_tick(&mut world);
+        }
+
+        let (tile, _) = &world.tiles[&smelter];
+        let factory = tile.as_any().downcast_ref::<Factory>().unwrap();
+        assert_eq!(factory.inventory.get(&Item::RawRigtorium), Some(&1));
+        assert_eq!(world.tiles[&belt].0.get_item(), None);
+    }
+
+    #[test]
+    fn a_belt_ending_at_an_inserter_hands_its_items_to_the_factory_behind() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        world.tiles.clear();
+        let (belt, inserter, smelter) = (
+            Position::new(0, 0),
+            Position::new(0, 1),
+            Position::new(0, 2),
+        );
+        place(&mut world, belt, (2, 1), Direction::Up);
+        place(&mut world, inserter, (2, 8), Direction::Up);
+        place(&mut world, smelter, (4, 1), Direction::Up);
+        let (tile, _) = world.tiles.get_mut(&belt).unwrap();
+        tile.as_any_mut().downcast_mut::<Conveyor>().unwrap().lanes =
+            [Some(ItemStack::new(Item::RawRigtorium)), None];
+        let (tile, _) = world.tiles.get_mut(&smelter).unwrap();
+        tile.as_any_mut().downcast_mut::<Factory>().unwrap().inputs = [true; 4];
+
+        for _ in 0..6 {
+            simulate_tick(&mut world);
+        }
+
+        // The item isn't dropped onto the inserter itself on the way.
+        let (tile, _) = &world.tiles[&smelter];
+        let factory = tile.as_any().downcast_ref::<Factory>().unwrap();
+        assert_eq!(factory.inventory.get(&Item::RawRigtorium), Some(&1));
+        assert_eq!(world.tiles[&belt].0.get_item(), None);
+    }
+
+    #[test]
+    fn an_inserter_moves_items_between_storages() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        world.tiles.clear();
+        let (source, inserter, target) = (
+            Position::new(0, 0),
+            Position::new(0, 1),
+            Position::new(0, 2),
+        );
+        place(&mut world, source, (5, 1), Direction::Up);
+        place(&mut world, inserter, (2, 8), Direction::Up);
+        place(&mut world, target, (5, 1), Direction::Up);
+        let (tile, _) = world.tiles.get_mut(&source).unwrap();
+        tile.as_any_mut()
+            .downcast_mut::<Storage>()
+            .unwrap()
+            .inventory = 3;
+
+        for _ in 0..8 {
+            simulate_tick(&mut world);
+        }
+
+        let inventory = |pos: Position| {
+            let (tile, _) = &world.tiles[&pos];
+            tile.as_any().downcast_ref::<Storage>().unwrap().inventory
+        };
+        assert_eq!((inventory(source), inventory(target)), (0, 3));
+    }
+}
@@ -0,0 +1,38 @@
+use std::any::Any;
+
+use crate::{Action, Direction, Item, Position, WorldRes};
+
+use super::Tile;
+
+#[derive(Debug)]
+pub struct ItemDisplay {
+    pub position: Position,
+    pub direction: Direction,
+    pub count: u32,
+}
+
+impl ItemDisplay {
+    pub fn watched_position(&self) -> Position {
+        self.position.shift(self.direction.shift(2))
+    }
+}
+
+impl Tile for ItemDisplay {
+    fn tick(&self, _world: &WorldRes) -> Option<Action> {
+        None
+    }
+
+    fn set_item(&mut self, _item: Option<Item>) {}
+
+    fn get_item(&self) -> Option<Item> {
+        None
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -1,6 +1,6 @@
 use std::any::Any;
 
-use crate::{Action, Direction, Item, Position, WorldRes, can_tile_accept_item};
+use crate::{Action, Direction, Item, Lane, Position, WorldRes, will_tile_accept_item};
 
 use super::Tile;
 
@@ -8,37 +8,33 @@ use super::Tile;
 pub struct Junction {
     pub position: Position,
 
-    pub horizontal_item: Option<(Item, Direction)>,
+    pub horizontal_item: Option<(Item, Direction, Lane)>,
 
-    pub vertical_item: Option<(Item, Direction)>,
+    pub vertical_item: Option<(Item, Direction, Lane)>,
 }
 impl Tile for Junction {
     fn tick(&self, world: &WorldRes) -> Option<Action> {
-        if let Some((item, input_dir)) = self.horizontal_item {
+        if let Some((item, input_dir, lane)) = self.horizontal_item {
             let output = match input_dir {
                 Direction::Left => Direction::Right,
                 Direction::Right => Direction::Left,
                 _ => return None,
             };
-            let end_pos = self.position.shift(output);
-            if world.tiles.get(&end_pos).is_some()
-                && can_tile_accept_item(world.tiles.get(&end_pos).unwrap(), item)
-            {
-                return Some(Action::Move(self.position, end_pos, item));
+            let end_pos = world.anchor_of(self.position.shift(output));
+            if will_tile_accept_item(world, self.position, end_pos, item, lane) {
+                return Some(Action::Move(self.position, end_pos, item, lane));
             }
         }
 
-        if let Some((item, input_dir)) = self.vertical_item {
+        if let Some((item, input_dir, lane)) = self.vertical_item {
             let output = match input_dir {
                 Direction::Down => Direction::Up,
                 Direction::Up => Direction::Down,
                 _ => return None,
             };
-            let end_pos = self.position.shift(output);
-            if world.tiles.get(&end_pos).is_some()
-                && can_tile_accept_item(world.tiles.get(&end_pos).unwrap(), item)
-            {
-                return Some(Action::Move(self.position, end_pos, item));
+            let end_pos = world.anchor_of(self.position.shift(output));
+            if will_tile_accept_item(world, self.position, end_pos, item, lane) {
+                return Some(Action::Move(self.position, end_pos, item, lane));
             }
         }
         None
@@ -50,6 +46,10 @@ impl Tile for Junction {
         return None;
     }
 
+    fn clear_items(&mut self) -> u32 {
+        self.horizontal_item.take().is_some() as u32 + self.vertical_item.take().is_some() as u32
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -1,4 +1,4 @@
-use std::any::Any;
+use std::{any::Any, collections::HashMap};
 
 use crate::{Action, Item, Position, WorldRes};
 
@@ -8,6 +8,8 @@ use super::Tile;
 pub struct Portal {
     pub position: Position,
     pub item: Option<Item>,
+    // Items teleported so far, reported to the portal's wireless channel.
+    pub teleported: HashMap<Item, u32>,
 }
 impl Tile for Portal {
     fn tick(&self, _world: &WorldRes) -> Option<Action> {
@@ -0,0 +1,148 @@
+use std::any::Any;
+
+use crate::{
+    Action, Direction, Item, Lane, Position, QUARRY_INTERVAL, WorldEventKind, WorldRes,
+    beacon_boost_percent, extractor::ExtractorType, footprint_cells,
+};
+
+use super::Tile;
+
+// A 3x3 extractor that mines every deposit under it, cycling through them so the output mix
+// follows the ground. The position is the bottom-left cell and `direction` picks the output side.
+#[derive(Debug)]
+pub struct Quarry {
+    pub position: Position,
+    pub direction: Direction,
+    pub item: Option<Item>,
+    pub mined: u32,
+}
+
+impl Quarry {
+    pub fn deposits(&self, world: &WorldRes) -> Vec<Item> {
+        footprint_cells((3, 10), self.position)
+            .iter()
+            .filter_map(|cell| world.terrain.get(cell))
+            .filter_map(|terrain| {
+                [
+                    ExtractorType::RawRigtorium,
+                    ExtractorType::RawFlextorium,
+                    ExtractorType::Electrine,
+                ]
+                .into_iter()
+                .find(|extractor_type| extractor_type.terrain() == *terrain)
+                .map(|extractor_type| extractor_type.spawn_item())
+            })
+            .collect()
+    }
+
+    pub fn next_item(&self, world: &WorldRes) -> Option<Item> {
+        let deposits = self.deposits(world);
+        (!deposits.is_empty()).then(|| deposits[self.mined as usize % deposits.len()])
+    }
+
+    // The cell in front of the middle of the output side.
+    pub fn output_position(&self) -> Position {
+        Position::new(self.position.x + 1, self.position.y + 1)
+            .shift(self.direction)
+            .shift(self.direction)
+    }
+
+    // Mines one item every `QUARRY_INTERVAL` ticks when all nine cells are deposits, slower the
+    // fewer there are.
+    pub fn produces_this_tick(&self, world: &WorldRes) -> bool {
+        let covered = self.deposits(world).len() as i64;
+        if covered == 0 {
+            return false;
+        }
+
+        let surge = if world.has_world_event(WorldEventKind::DepositSurge) {
+            2
+        } else {
+            1
+        };
+        let rate = covered * surge * 100;
+        let boost = beacon_boost_percent(world, self.position) as i64;
+        let period = QUARRY_INTERVAL as i64 * 9 * (100 - boost);
+        let tick = world.tick_count as i64;
+        tick * rate / period != (tick - 1) * rate / period
+    }
+}
+
+impl Tile for Quarry {
+    fn tick(&self, world: &WorldRes) -> Option<Action> {
+        let Some(item) = self.item else {
+            return self
+                .produces_this_tick(world)
+                .then_some(Action::Produce(self.position));
+        };
+
+        let end_position = world.anchor_of(self.output_position());
+        world
+            .tiles
+            .contains_key(&end_position)
+            .then_some(Action::Move(self.position, end_position, item, Lane::Left))
+    }
+
+    fn set_item(&mut self, item: Option<Item>) {
+        self.item = item;
+    }
+
+    fn get_item(&self) -> Option<Item> {
+        self.item
+    }
+
+    fn can_accept_lane(&self, _from: Position, _item: Item, _lane: Lane) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Difficulty, types::TerrainTileType};
+
+    #[test]
+    fn quarry_cycles_through_the_deposits_under_it() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        world
+            .terrain
+            .insert(Position::new(0, 0), TerrainTileType::RawRigtoriumDeposit);
+        world
+            .terrain
+            .insert(Position::new(2, 2), TerrainTileType::ElectrineDeposit);
+        world
+            .terrain
+            .insert(Position::new(3, 3), TerrainTileType::RawFlextoriumDeposit);
+
+        let mut quarry = Quarry {
+            position: Position::new(0, 0),
+            direction: Direction::Up,
+            item: None,
+            mined: 0,
+        };
+        let mut mined = Vec::new();
+        for _ in 0..4 {
+            mined.push(quarry.next_item(&world).unwrap());
+            quarry.mined += 1;
+        }
+
+        assert_eq!(
+            mined,
+            [
+                Item::RawRigtorium,
+                Item::Electrine,
+                Item::RawRigtorium,
+                Item::Electrine
+            ]
+        );
+        assert_eq!(quarry.output_position(), Position::new(1, 3));
+    }
+}
@@ -4,8 +4,8 @@ use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    Action, Direction, Item, Position, WorldRes, rotate_direction_clockwise,
-    rotate_direction_counterclockwise,
+    Action, Direction, Item, Lane, Position, WorldRes, rotate_direction_clockwise,
+    rotate_direction_counterclockwise, will_tile_accept_item,
 };
 
 use super::{Conveyor, Factory, Tile};
@@ -16,15 +16,31 @@ pub struct Router {
     pub direction: Direction,
     pub item: Option<Item>,
     pub last_output: RouterOutputIndex,
+    pub priority: Option<RouterOutputIndex>,
+    pub filters: [Option<Item>; 3],
+}
+
+impl Router {
+    pub fn accepts(&self, output: RouterOutputIndex, item: Item) -> bool {
+        match self.filters[output as usize] {
+            Some(filter) => filter == item,
+            None => !self.filters.contains(&Some(item)),
+        }
+    }
 }
 
 impl Tile for Router {
     fn tick(&self, world: &WorldRes) -> Option<Action> {
         if let Some(item) = self.item {
-            let mut next_output = self.last_output.next();
+            let mut next_output = self.priority.unwrap_or(self.last_output.next());
             let start_position = self.position;
 
             for _ in 0..3 {
+                if !self.accepts(next_output, item) {
+                    next_output = next_output.next();
+                    continue;
+                }
+
                 let dir = next_output.to_direction(self.direction);
                 let mut end_pos = self.position;
 
@@ -34,19 +50,20 @@ impl Tile for Router {
                     Direction::Left => end_pos.x -= 1,
                     Direction::Right => end_pos.x += 1,
                 }
+                let end_pos = world.anchor_of(end_pos);
 
                 if let Some(tile) = world.tiles.get(&end_pos) {
-                    let can_accept =
-                        if let Some(conveyor) = tile.0.as_any().downcast_ref::<Conveyor>() {
-                            conveyor.item.is_none()
-                        } else if let Some(router) = tile.0.as_any().downcast_ref::<Router>() {
-                            router.item.is_none()
-                        } else if let Some(factory) = tile.0.as_any().downcast_ref::<Factory>() {
-                            factory.factory_type.capacity().get(&item).unwrap_or(&0)
+                    let can_accept = if tile.0.as_any().is::<Conveyor>() {
+                        will_tile_accept_item(world, start_position, end_pos, item, Lane::Left)
+                    } else if let Some(router) = tile.0.as_any().downcast_ref::<Router>() {
+                        router.item.is_none()
+                    } else if let Some(factory) = tile.0.as_any().downcast_ref::<Factory>() {
+                        factory.accepts_from(start_position)
+                            && factory.capacity().get(&item).unwrap_or(&0)
                                 > factory.inventory.get(&item).unwrap_or(&0)
-                        } else {
-                            false
-                        };
+                    } else {
+                        false
+                    };
 
                     if can_accept {
                         return Some(Action::MoveRouter(
@@ -80,13 +97,27 @@ impl Tile for Router {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
 pub enum RouterOutputIndex {
     Forward = 0,
     Right = 1,
     Left = 2,
 }
 impl RouterOutputIndex {
+    pub const ALL: [RouterOutputIndex; 3] = [
+        RouterOutputIndex::Forward,
+        RouterOutputIndex::Right,
+        RouterOutputIndex::Left,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RouterOutputIndex::Forward => "Forward",
+            RouterOutputIndex::Right => "Right",
+            RouterOutputIndex::Left => "Left",
+        }
+    }
+
     pub fn next(&self) -> Self {
         match self {
             RouterOutputIndex::Forward => RouterOutputIndex::Right,
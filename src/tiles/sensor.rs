@@ -0,0 +1,86 @@
+use std::{any::Any, collections::HashMap};
+
+use crate::{Action, Direction, Item, Position, WorldRes};
+
+use super::{Factory, Storage, Tile};
+
+#[derive(Debug)]
+pub struct Sensor {
+    pub position: Position,
+    pub direction: Direction,
+}
+
+impl Sensor {
+    pub fn read(&self, world: &WorldRes) -> HashMap<Item, u32> {
+        let mut signals = HashMap::new();
+
+        if let Some((tile, _)) = world.tiles.get(&self.position.shift(self.direction)) {
+            if let Some(storage) = tile.as_any().downcast_ref::<Storage>() {
+                signals.insert(storage.storage_type.stored_item(), storage.inventory);
+            } else if let Some(factory) = tile.as_any().downcast_ref::<Factory>() {
+                signals = factory.inventory.clone();
+                if let Some(item) = factory.item {
+                    *signals.entry(item).or_insert(0) += 1;
+                }
+            }
+        }
+
+        signals
+    }
+}
+
+impl Tile for Sensor {
+    fn tick(&self, _world: &WorldRes) -> Option<Action> {
+        None
+    }
+
+    fn set_item(&mut self, _item: Option<Item>) {}
+
+    fn get_item(&self) -> Option<Item> {
+        None
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Difficulty, ItemDisplay, get_new_tile, simulate_tick};
+
+    #[test]
+    fn a_display_on_a_sensors_channel_shows_the_remote_storage() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        let (storage, sensor, display) = (
+            Position::new(30, 30),
+            Position::new(30, 29),
+            Position::new(-30, -30),
+        );
+        let mut tile = get_new_tile((5, 1), storage, Direction::Up);
+        if let Some(storage) = tile.0.as_any_mut().downcast_mut::<Storage>() {
+            storage.inventory = 7;
+        }
+        world.tiles.insert(storage, tile);
+        world
+            .tiles
+            .insert(sensor, get_new_tile((2, 6), sensor, Direction::Up));
+        world
+            .tiles
+            .insert(display, get_new_tile((2, 7), display, Direction::Up));
+        world.channels.insert(sensor, 3);
+        world.channels.insert(display, 3);
+        simulate_tick(&mut world);
+
+        let (tile, _) = world.tiles.get(&display).unwrap();
+        assert_eq!(
+            tile.as_any().downcast_ref::<ItemDisplay>().unwrap().count,
+            7
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::any::Any;
 use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-use crate::{Action, Direction, Item, Position, WorldRes};
+use crate::{Action, Direction, Item, Lane, Position, WorldRes};
 
 use super::Tile;
 
@@ -27,11 +27,12 @@ impl Tile for Storage {
         }
 
         if world.tiles.contains_key(&end_position) {
-            if self.inventory >= 1 {
+            if world.storage_fill(self.position).0 >= 1 {
                 return Some(Action::Move(
                     self.position,
                     end_position,
                     self.storage_type.stored_item(),
+                    Lane::Left,
                 ));
             }
         }
@@ -69,7 +70,7 @@ impl StorageType {
             StorageType::SmallBattery => 10,
         }
     }
-    fn stored_item(&self) -> Item {
+    pub fn stored_item(&self) -> Item {
         match self {
             StorageType::SmallRigotriumVault => Item::Rigtorium,
             StorageType::SmallFlextoriumVault => Item::Flextorium,
@@ -77,3 +78,37 @@ impl StorageType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Difficulty, get_new_tile};
+
+    #[test]
+    fn adjacent_identical_storages_pool_their_capacity() {
+        let mut world = WorldRes::new(Difficulty::Normal);
+        world.tiles.clear();
+        for (x, tile_type) in [(0, (5, 1)), (1, (5, 1)), (2, (5, 1)), (3, (5, 2))] {
+            let pos = Position::new(x, 0);
+            world
+                .tiles
+                .insert(pos, get_new_tile(tile_type, pos, Direction::Up));
+        }
+        world.rebuild_storage_clusters();
+
+        let start = Position::new(0, 0);
+        assert_eq!(world.storage_cluster(start).map(<[_]>::len), Some(3));
+        assert_eq!(world.storage_cluster(Position::new(3, 0)), None);
+        assert_eq!(world.storage_fill(start), (0, 30));
+
+        for _ in 0..25 {
+            world.spill_storage_into_cluster(start);
+            let (tile, _) = world.tiles.get_mut(&start).unwrap();
+            let storage = tile.as_any_mut().downcast_mut::<Storage>().unwrap();
+            if storage.inventory < storage.storage_type.capacity() {
+                storage.inventory += 1;
+            }
+        }
+        assert_eq!(world.storage_fill(Position::new(2, 0)), (25, 30));
+    }
+}
@@ -0,0 +1,30 @@
+use std::any::Any;
+
+use crate::{Action, Item, Position, WorldRes};
+
+use super::Tile;
+
+#[derive(Debug)]
+pub struct Wire {
+    pub position: Position,
+}
+
+impl Tile for Wire {
+    fn tick(&self, _world: &WorldRes) -> Option<Action> {
+        None
+    }
+
+    fn set_item(&mut self, _item: Option<Item>) {}
+
+    fn get_item(&self) -> Option<Item> {
+        None
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
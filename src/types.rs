@@ -2,7 +2,7 @@ use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::router::RouterOutputIndex;
+use crate::{CONVEYOR_STACK_SIZE, router::RouterOutputIndex};
 
 #[derive(
     Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Serialize, Deserialize, Encode, Decode,
@@ -39,6 +39,32 @@ impl Position {
     }
 }
 
+// An inclusive rectangle of grid cells, such as the area dragged out by a selection tool.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GridRegion {
+    pub min: Position,
+    pub max: Position,
+}
+
+impl GridRegion {
+    pub fn new(a: Position, b: Position) -> Self {
+        Self {
+            min: Position::new(a.x.min(b.x), a.y.min(b.y)),
+            max: Position::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        (self.min.x..=self.max.x).contains(&pos.x) && (self.min.y..=self.max.y).contains(&pos.y)
+    }
+
+    // Cells column by column, from the bottom-left corner.
+    pub fn cells(&self) -> impl Iterator<Item = Position> + use<> {
+        let (min, max) = (self.min, self.max);
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| Position::new(x, y)))
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq, Eq)]
 pub enum Direction {
     Up,
@@ -48,6 +74,13 @@ pub enum Direction {
 }
 
 impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
     pub fn shift(&self, i: i32) -> Direction {
         let current_index = match self {
             Direction::Up => 0,
@@ -66,6 +99,59 @@ impl Direction {
             _ => unreachable!(),
         }
     }
+
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            Direction::Up => "^",
+            Direction::Right => ">",
+            Direction::Down => "v",
+            Direction::Left => "<",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Hash, Debug, Copy, Deserialize, Serialize, Encode, Decode)]
+pub enum Lane {
+    Left,
+    Right,
+}
+
+impl Lane {
+    pub const ALL: [Lane; 2] = [Lane::Left, Lane::Right];
+
+    pub fn index(&self) -> usize {
+        match self {
+            Lane::Left => 0,
+            Lane::Right => 1,
+        }
+    }
+
+    pub fn other(&self) -> Lane {
+        match self {
+            Lane::Left => Lane::Right,
+            Lane::Right => Lane::Left,
+        }
+    }
+}
+
+// Several items of one kind sharing a conveyor lane.
+#[derive(PartialEq, Eq, Clone, Hash, Debug, Copy, Deserialize, Serialize, Encode, Decode)]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: u8,
+}
+
+impl ItemStack {
+    pub fn new(item: Item) -> Self {
+        Self { item, count: 1 }
+    }
+
+    pub fn full(item: Item) -> Self {
+        Self {
+            item,
+            count: CONVEYOR_STACK_SIZE,
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Clone, Hash, Debug, Copy, Deserialize, Serialize, Encode, Decode)]
@@ -78,9 +164,44 @@ pub enum Item {
     RigtoriumRod,
     Conveyor,
     Router,
+    Circuit,
+    Motor,
+    RawRigtoriumExtractor,
+    RawFlextoriumExtractor,
+    ElectrineExtractor,
+    RigtoriumSmelter,
+    FlextoriumFabricator,
+    RigtoriumRodMolder,
+    OrbitalBeacon,
+    CrudeOil,
+    Plastic,
+    Fuel,
 }
 
 impl Item {
+    pub const ALL: [Item; 20] = [
+        Item::RawFlextorium,
+        Item::RawRigtorium,
+        Item::Flextorium,
+        Item::Rigtorium,
+        Item::Electrine,
+        Item::RigtoriumRod,
+        Item::Circuit,
+        Item::Motor,
+        Item::Conveyor,
+        Item::Router,
+        Item::RawRigtoriumExtractor,
+        Item::RawFlextoriumExtractor,
+        Item::ElectrineExtractor,
+        Item::RigtoriumSmelter,
+        Item::FlextoriumFabricator,
+        Item::RigtoriumRodMolder,
+        Item::OrbitalBeacon,
+        Item::CrudeOil,
+        Item::Plastic,
+        Item::Fuel,
+    ];
+
     pub fn sprite(&self) -> &'static str {
         match self {
             Item::RawFlextorium => "embedded://textures/items/raw_flextorium.png",
@@ -89,8 +210,51 @@ impl Item {
             Item::Rigtorium => "embedded://textures/items/rigtorium.png",
             Item::Electrine => "embedded://textures/items/electrine.png",
             Item::RigtoriumRod => "embedded://textures/items/rigtorium_rod.png",
+            Item::Circuit => "embedded://textures/items/circuit.png",
+            Item::Motor => "embedded://textures/items/motor.png",
             Item::Conveyor => "embedded://textures/items/conveyor.png",
             Item::Router => "embedded://textures/items/router.png",
+            Item::RawRigtoriumExtractor => "embedded://textures/tiles/extractors/raw_rigtorium.png",
+            Item::RawFlextoriumExtractor => {
+                "embedded://textures/tiles/extractors/raw_flextorium.png"
+            }
+            Item::ElectrineExtractor => "embedded://textures/tiles/extractors/electrine.png",
+            Item::RigtoriumSmelter => "embedded://textures/tiles/factories/rigtorium_smelter.png",
+            Item::FlextoriumFabricator => {
+                "embedded://textures/tiles/factories/flextorium_fabricator.png"
+            }
+            Item::RigtoriumRodMolder => {
+                "embedded://textures/tiles/factories/rigtorium_rod_molder.png"
+            }
+            Item::OrbitalBeacon => "embedded://textures/items/orbital_beacon.png",
+            Item::CrudeOil => "embedded://textures/items/crude_oil.png",
+            Item::Plastic => "embedded://textures/items/plastic.png",
+            Item::Fuel => "embedded://textures/items/fuel.png",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Item::RawFlextorium => "Raw Flextorium",
+            Item::RawRigtorium => "Raw Rigtorium",
+            Item::Flextorium => "Flextorium",
+            Item::Rigtorium => "Rigtorium",
+            Item::Electrine => "Electrine",
+            Item::RigtoriumRod => "Rigtorium Rod",
+            Item::Circuit => "Circuit",
+            Item::Motor => "Motor",
+            Item::Conveyor => "Conveyor",
+            Item::Router => "Router",
+            Item::RawRigtoriumExtractor => "Raw Rigtorium Extractor",
+            Item::RawFlextoriumExtractor => "Raw Flextorium Extractor",
+            Item::ElectrineExtractor => "Electrine Extractor",
+            Item::RigtoriumSmelter => "Rigtorium Smelter",
+            Item::FlextoriumFabricator => "Flextorium Fabricator",
+            Item::RigtoriumRodMolder => "Rigtorium Rod Molder",
+            Item::OrbitalBeacon => "Orbital Beacon",
+            Item::CrudeOil => "Crude Oil",
+            Item::Plastic => "Plastic",
+            Item::Fuel => "Fuel",
         }
     }
 
@@ -98,30 +262,275 @@ impl Item {
         match self {
             Item::Conveyor => Some((2, 1)),
             Item::Router => Some((2, 2)),
+            Item::RawRigtoriumExtractor => Some((3, 1)),
+            Item::RawFlextoriumExtractor => Some((3, 2)),
+            Item::ElectrineExtractor => Some((3, 3)),
+            Item::RigtoriumSmelter => Some((4, 1)),
+            Item::FlextoriumFabricator => Some((4, 2)),
+            Item::RigtoriumRodMolder => Some((4, 3)),
             _ => None,
         }
     }
+
+    pub fn from_tile(tile_type: (u8, u8)) -> Option<Item> {
+        Item::ALL
+            .into_iter()
+            .find(|item| item.to_tile() == Some(tile_type))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Recipe {
     pub inputs: HashMap<Item, u32>,
     pub output: Item,
+    // Extra items made alongside the output, handed out one at a time after it.
+    pub byproducts: Vec<Item>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode)]
+pub struct SignalCondition {
+    pub item: Item,
+    pub threshold: u32,
 }
 
 #[derive(Debug, Clone)]
 pub enum Action {
-    Move(Position, Position, Item),
+    Move(Position, Position, Item, Lane),
     MoveRouter(Position, Position, Item, RouterOutputIndex),
     Produce(Position),
     Teleport(Position, (u8, u8)),
     IncreaseTicks(Position),
 }
 
+impl Action {
+    pub fn position(&self) -> Position {
+        match self {
+            Action::Move(position, _, _, _) => *position,
+            Action::MoveRouter(position, _, _, _) => *position,
+            Action::Produce(position) => *position,
+            Action::Teleport(position, _) => *position,
+            Action::IncreaseTicks(position) => *position,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Encode, Decode)]
 pub enum TerrainTileType {
     RawFlextoriumDeposit,
     RawRigtoriumDeposit,
     ElectrineDeposit,
     Stone,
+    OilSeep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub enum WorldEventKind {
+    DepositSurge,
+    MarketCrash,
+    PowerFlicker,
+}
+
+impl WorldEventKind {
+    pub const ALL: [WorldEventKind; 3] = [
+        WorldEventKind::DepositSurge,
+        WorldEventKind::MarketCrash,
+        WorldEventKind::PowerFlicker,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WorldEventKind::DepositSurge => "Deposit surge",
+            WorldEventKind::MarketCrash => "Market crash",
+            WorldEventKind::PowerFlicker => "Power flicker",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            WorldEventKind::DepositSurge => "Extractors produce twice as fast",
+            WorldEventKind::MarketCrash => "Sell prices are halved",
+            WorldEventKind::PowerFlicker => "Factories only run every other tick",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Encode, Decode)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "More starting tiles, faster Cores, cheaper tiles, rich deposits",
+            Difficulty::Normal => "The standard experience",
+            Difficulty::Hard => {
+                "Fewer starting tiles, slower Cores, pricier tiles, sparse deposits"
+            }
+        }
+    }
+
+    pub fn scale_resources(&self, count: u32) -> u32 {
+        match self {
+            Difficulty::Easy => count * 2,
+            Difficulty::Normal => count,
+            Difficulty::Hard => count.div_ceil(2),
+        }
+    }
+
+    pub fn scale_core_interval(&self, interval: u32) -> u32 {
+        match self {
+            Difficulty::Easy => (interval * 3 / 4).max(1),
+            Difficulty::Normal => interval,
+            Difficulty::Hard => interval * 3 / 2,
+        }
+    }
+
+    pub fn scale_price(&self, price: u32) -> u32 {
+        match self {
+            Difficulty::Easy => (price * 3 / 4).max(1),
+            Difficulty::Normal => price,
+            Difficulty::Hard => price * 3 / 2,
+        }
+    }
+
+    pub fn victory_target(&self) -> u64 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 3,
+            Difficulty::Hard => 5,
+        }
+    }
+
+    pub fn deposit_density_offset(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.1,
+            Difficulty::Normal => 0.0,
+            Difficulty::Hard => -0.1,
+        }
+    }
+}
+
+// The tiles and money a new world starts with, picked after the difficulty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StarterKit {
+    Logistics,
+    Extraction,
+    #[default]
+    Balanced,
+}
+
+impl StarterKit {
+    pub const ALL: [StarterKit; 3] = [
+        StarterKit::Logistics,
+        StarterKit::Extraction,
+        StarterKit::Balanced,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StarterKit::Logistics => "Logistics",
+            StarterKit::Extraction => "Extraction",
+            StarterKit::Balanced => "Balanced",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            StarterKit::Logistics => "Plenty of belts, routers and junctions, less money",
+            StarterKit::Extraction => "Extra extractors, fewer belts",
+            StarterKit::Balanced => "A bit of everything",
+        }
+    }
+
+    pub fn resources(&self) -> &'static [((u8, u8), u32)] {
+        match self {
+            StarterKit::Logistics => &[
+                ((2, 1), 40),
+                ((2, 2), 10),
+                ((2, 3), 10),
+                ((3, 1), 1),
+                ((3, 3), 1),
+                ((4, 1), 1),
+            ],
+            StarterKit::Extraction => &[
+                ((2, 1), 15),
+                ((2, 2), 3),
+                ((2, 3), 2),
+                ((3, 1), 2),
+                ((3, 2), 1),
+                ((3, 3), 2),
+                ((4, 1), 1),
+            ],
+            StarterKit::Balanced => &[
+                ((2, 1), 20),
+                ((2, 2), 5),
+                ((2, 3), 5),
+                ((3, 1), 1),
+                ((3, 3), 1),
+                ((4, 1), 1),
+            ],
+        }
+    }
+
+    pub fn money(&self) -> u32 {
+        match self {
+            StarterKit::Logistics => 50,
+            StarterKit::Extraction => 75,
+            StarterKit::Balanced => 100,
+        }
+    }
+}
+
+// How far a new world extends. Smaller worlds keep fewer chunks around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorldSize {
+    Small,
+    Medium,
+    Large,
+    #[default]
+    Endless,
+}
+
+impl WorldSize {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WorldSize::Small => "Small",
+            WorldSize::Medium => "Medium",
+            WorldSize::Large => "Large",
+            WorldSize::Endless => "Endless",
+        }
+    }
+
+    // Half the side of the world's square, in tiles.
+    pub fn radius(&self) -> Option<i32> {
+        match self {
+            WorldSize::Small => Some(64),
+            WorldSize::Medium => Some(160),
+            WorldSize::Large => Some(400),
+            WorldSize::Endless => None,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            WorldSize::Small => WorldSize::Medium,
+            WorldSize::Medium => WorldSize::Large,
+            WorldSize::Large => WorldSize::Endless,
+            WorldSize::Endless => WorldSize::Small,
+        }
+    }
 }
@@ -1,26 +1,123 @@
 use crate::{
-    Action, Direction, Item, Position, WorldRes, extractor::ExtractorType, factory::FactoryType,
-    router::RouterOutputIndex, storage::StorageType, tiles::*,
+    Action, BEACON_BOOST_PERCENT, BEACON_MAX_BOOST_PERCENT, BEACON_RADIUS, Difficulty, Direction,
+    ELECTRINE_DENSITY, ELECTRINE_NOISE_SCALE, FLEXTORIUM_DENSITY, FLEXTORIUM_NOISE_SCALE,
+    FLOW_TRACE_MAX_STEPS, GridRegion, IMAGE_SIZE, Item, LANE_OFFSET, Lane, NON_ROTATABLE_TILES,
+    OIL_NOISE_SCALE, OIL_SEEP_THRESHOLD, PROTECTED_TILES, PhotoCamera, Position, RIGTORIUM_DENSITY,
+    RIGTORIUM_NOISE_SCALE, TERRAIN_BASE_THRESHOLD, TILE_FOOTPRINTS, TILE_SIZE, TerrainTileType,
+    WorldRes,
+    conveyor::ConveyorTier,
+    extractor::{ExtractorTier, ExtractorType},
+    factory::FactoryType,
+    router::RouterOutputIndex,
+    storage::StorageType,
+    tiles::*,
 };
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*, window::PrimaryWindow};
+use noise::{NoiseFn, Perlin};
 use std::collections::{HashMap, HashSet};
 
+// The point on the world plane under the cursor.
+pub fn cursor_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &Transform,
+) -> Option<Vec2> {
+    let screen_pos = window.cursor_position()?;
+    let window_size = Vec2::new(window.width(), window.height());
+
+    let mut ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
+    ndc.y *= -1.0;
+    let ndc_to_world = camera_transform.compute_matrix() * camera.clip_from_view().inverse();
+    Some(ndc_to_world.project_point3(ndc.extend(-1.0)).truncate())
+}
+
+pub fn world_to_grid(world_pos: Vec2) -> Position {
+    Position::new(
+        (world_pos.x / TILE_SIZE).round() as i32,
+        (world_pos.y / TILE_SIZE).round() as i32,
+    )
+}
+
+pub fn cursor_grid_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &Transform,
+) -> Option<Position> {
+    cursor_world_position(window, camera, camera_transform).map(world_to_grid)
+}
+
+// What tools read to act on the cell under the cursor.
+#[derive(SystemParam)]
+pub struct CursorInput<'w, 's> {
+    pub windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    pub camera_query: Query<'w, 's, (&'static Camera, &'static Transform), Without<PhotoCamera>>,
+    pub mouse_button_input: Res<'w, ButtonInput<MouseButton>>,
+    pub keyboard_input: Res<'w, ButtonInput<KeyCode>>,
+}
+
+impl CursorInput<'_, '_> {
+    pub fn grid_position(&self) -> Option<Position> {
+        let window = self.windows.single().ok()?;
+        let (camera, camera_transform) = self.camera_query.single().ok()?;
+        cursor_grid_position(window, camera, camera_transform)
+    }
+}
+
+// The cells between where a drag started and the cell under the cursor.
+pub fn cursor_grid_region(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &Transform,
+    start: Position,
+) -> Option<GridRegion> {
+    cursor_grid_position(window, camera, camera_transform).map(|pos| GridRegion::new(start, pos))
+}
+
+pub fn draw_grid_region(gizmos: &mut Gizmos, region: GridRegion, color: Color) {
+    let min = Vec2::new(region.min.x as f32, region.min.y as f32) * TILE_SIZE;
+    let max = Vec2::new(region.max.x as f32, region.max.y as f32) * TILE_SIZE;
+    gizmos.rect_2d((min + max) / 2.0, max - min + Vec2::splat(TILE_SIZE), color);
+}
+
 pub fn get_tile_texture(tile_type: (u8, u8)) -> &'static str {
     match tile_type {
         (0, 1) => "embedded://textures/tiles/none.png",
         (1, 1) => "embedded://textures/tiles/core.png",
         (1, 2) => "embedded://textures/tiles/portal.png",
+        (1, 3) => "embedded://textures/tiles/beacon.png",
         (2, 1) => "embedded://textures/tiles/conveyors/back.png",
         (2, 2) => "embedded://textures/tiles/conveyors/router.png",
         (2, 3) => "embedded://textures/tiles/conveyors/junction.png",
+        (2, 4) => "embedded://textures/tiles/conveyors/priority_router.png",
+        (2, 5) => "embedded://textures/tiles/conveyors/wire.png",
+        (2, 6) => "embedded://textures/tiles/conveyors/sensor.png",
+        (2, 7) => "embedded://textures/tiles/conveyors/display.png",
+        (2, 8) => "embedded://textures/tiles/conveyors/inserter.png",
+        (2, 9) => "embedded://textures/tiles/conveyors/back.png",
         (3, 1) => "embedded://textures/tiles/extractors/raw_rigtorium.png",
         (3, 2) => "embedded://textures/tiles/extractors/raw_flextorium.png",
         (3, 3) => "embedded://textures/tiles/extractors/electrine.png",
+        (3, 4) => "embedded://textures/tiles/extractors/raw_rigtorium_mk2.png",
+        (3, 5) => "embedded://textures/tiles/extractors/raw_flextorium_mk2.png",
+        (3, 6) => "embedded://textures/tiles/extractors/electrine_mk2.png",
+        (3, 7) => "embedded://textures/tiles/extractors/raw_rigtorium_mk3.png",
+        (3, 8) => "embedded://textures/tiles/extractors/raw_flextorium_mk3.png",
+        (3, 9) => "embedded://textures/tiles/extractors/electrine_mk3.png",
+        (3, 10) => "embedded://textures/tiles/quarry.png",
+        (3, 11) => "embedded://textures/tiles/extractors/pumpjack.png",
         (4, 1) => "embedded://textures/tiles/factories/rigtorium_smelter.png",
         (4, 2) => "embedded://textures/tiles/factories/flextorium_fabricator.png",
         (4, 3) => "embedded://textures/tiles/factories/rigtorium_rod_molder.png",
         (4, 4) => "embedded://textures/tiles/factories/conveyor_constructor.png",
         (4, 5) => "embedded://textures/tiles/factories/router_constructor.png",
+        (4, 6) => "embedded://textures/tiles/assembler.png",
+        (4, 7) => "embedded://textures/tiles/factories/circuit_printer.png",
+        (4, 8) => "embedded://textures/tiles/factories/motor_assembler.png",
+        (4, 9) => "embedded://textures/tiles/factories/extractor_constructor.png",
+        (4, 10) => "embedded://textures/tiles/factories/factory_constructor.png",
+        (4, 11) => "embedded://textures/tiles/factories/beacon_assembler.png",
+        (4, 12) => "embedded://textures/tiles/factories/recycler.png",
+        (4, 13) => "embedded://textures/tiles/factories/refinery.png",
         (5, 1) => "embedded://textures/tiles/small_rigtorium_vault.png",
         (5, 2) => "embedded://textures/tiles/small_flextorium_vault.png",
         (5, 3) => "embedded://textures/tiles/small_battery.png",
@@ -28,75 +125,483 @@ pub fn get_tile_texture(tile_type: (u8, u8)) -> &'static str {
     }
 }
 
+pub fn get_category_tile_types(category: u8) -> Vec<(u8, u8)> {
+    match category {
+        1 => vec![(1, 2), (1, 3)],
+        2 => vec![
+            (2, 1),
+            (2, 2),
+            (2, 3),
+            (2, 4),
+            (2, 5),
+            (2, 6),
+            (2, 7),
+            (2, 8),
+            (2, 9),
+        ],
+        3 => vec![
+            (3, 1),
+            (3, 2),
+            (3, 3),
+            (3, 4),
+            (3, 5),
+            (3, 6),
+            (3, 7),
+            (3, 8),
+            (3, 9),
+            (3, 10),
+            (3, 11),
+        ],
+        4 => vec![
+            (4, 1),
+            (4, 2),
+            (4, 3),
+            (4, 4),
+            (4, 5),
+            (4, 6),
+            (4, 7),
+            (4, 8),
+            (4, 9),
+            (4, 10),
+            (4, 11),
+            (4, 12),
+            (4, 13),
+        ],
+        5 => vec![(5, 1), (5, 2), (5, 3)],
+        _ => vec![],
+    }
+}
+
+pub fn get_tile_lod_color(tile_type: (u8, u8)) -> Color {
+    match tile_type.0 {
+        1 => Color::srgb(0.9, 0.75, 0.3),
+        2 => Color::srgb(0.55, 0.58, 0.62),
+        3 => Color::srgb(0.85, 0.5, 0.25),
+        4 => Color::srgb(0.35, 0.55, 0.85),
+        5 => Color::srgb(0.4, 0.75, 0.45),
+        _ => Color::srgb(0.7, 0.7, 0.7),
+    }
+}
+
+pub fn get_deposit_color(terrain_type: TerrainTileType) -> Option<Color> {
+    match terrain_type {
+        TerrainTileType::RawFlextoriumDeposit => Some(Color::srgb(0.3, 1.0, 0.5)),
+        TerrainTileType::RawRigtoriumDeposit => Some(Color::srgb(1.0, 0.55, 0.2)),
+        TerrainTileType::ElectrineDeposit => Some(Color::srgb(0.3, 0.8, 1.0)),
+        TerrainTileType::Stone => None,
+        TerrainTileType::OilSeep => Some(Color::srgb(0.7, 0.4, 1.0)),
+    }
+}
+
+pub fn get_terrain_texture(terrain_type: TerrainTileType) -> &'static str {
+    match terrain_type {
+        TerrainTileType::Stone => "embedded://textures/terrain/stone.png",
+        TerrainTileType::RawFlextoriumDeposit => "embedded://textures/terrain/flextorium.png",
+        TerrainTileType::RawRigtoriumDeposit => "embedded://textures/terrain/rigtorium.png",
+        TerrainTileType::ElectrineDeposit => "embedded://textures/terrain/electrine.png",
+        TerrainTileType::OilSeep => "embedded://textures/terrain/oil_seep.png",
+    }
+}
+
+// Built once per seed so terrain generation doesn't rebuild the noise for every cell.
+pub struct TerrainNoise {
+    rigtorium: Perlin,
+    flextorium: Perlin,
+    electrine: Perlin,
+    oil: Perlin,
+}
+
+impl TerrainNoise {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            rigtorium: Perlin::new(seed),
+            flextorium: Perlin::new(seed.wrapping_add(1)),
+            electrine: Perlin::new(seed.wrapping_add(2)),
+            oil: Perlin::new(seed.wrapping_add(3)),
+        }
+    }
+
+    pub fn terrain_at(&self, difficulty: Difficulty, pos: Position) -> TerrainTileType {
+        let (x, y) = (pos.x as f64, pos.y as f64);
+        let offset = difficulty.deposit_density_offset();
+
+        let rigtorium_val = self
+            .rigtorium
+            .get([x * RIGTORIUM_NOISE_SCALE, y * RIGTORIUM_NOISE_SCALE])
+            + RIGTORIUM_DENSITY
+            + offset;
+        let flextorium_val = self
+            .flextorium
+            .get([x * FLEXTORIUM_NOISE_SCALE, y * FLEXTORIUM_NOISE_SCALE])
+            + FLEXTORIUM_DENSITY
+            + offset;
+        let electrine_val = self
+            .electrine
+            .get([x * ELECTRINE_NOISE_SCALE, y * ELECTRINE_NOISE_SCALE])
+            + ELECTRINE_DENSITY
+            + offset;
+
+        if rigtorium_val > TERRAIN_BASE_THRESHOLD
+            && rigtorium_val > flextorium_val
+            && rigtorium_val > electrine_val
+        {
+            TerrainTileType::RawRigtoriumDeposit
+        } else if flextorium_val > TERRAIN_BASE_THRESHOLD
+            && flextorium_val > rigtorium_val
+            && flextorium_val > electrine_val
+        {
+            TerrainTileType::RawFlextoriumDeposit
+        } else if electrine_val > TERRAIN_BASE_THRESHOLD
+            && electrine_val > rigtorium_val
+            && electrine_val > flextorium_val
+        {
+            TerrainTileType::ElectrineDeposit
+        } else if self.oil.get([x * OIL_NOISE_SCALE, y * OIL_NOISE_SCALE]) + offset
+            > OIL_SEEP_THRESHOLD
+        {
+            TerrainTileType::OilSeep
+        } else {
+            TerrainTileType::Stone
+        }
+    }
+}
+
 pub fn format_tile_id(tile_type: (u8, u8)) -> String {
     format!("{}, {}", tile_type.0, tile_type.1)
 }
 
 pub fn get_tile_name(tile_type: (u8, u8)) -> String {
-    match tile_type {
+    tile_name(tile_type).unwrap_or("Unknown Tile").to_string()
+}
+
+// Whether a tile type exists, for ids read from outside the game like blueprints or the console.
+pub fn is_known_tile(tile_type: (u8, u8)) -> bool {
+    tile_name(tile_type).is_some()
+}
+
+fn tile_name(tile_type: (u8, u8)) -> Option<&'static str> {
+    let name = match tile_type {
         (1, 1) => "Core",
         (1, 2) => "Portal",
+        (1, 3) => "Beacon",
         (2, 1) => "Conveyor",
         (2, 2) => "Router",
         (2, 3) => "Junction",
+        (2, 4) => "Priority Router",
+        (2, 5) => "Wire",
+        (2, 6) => "Sensor",
+        (2, 7) => "Item Display",
+        (2, 8) => "Inserter",
+        (2, 9) => "Fast Conveyor",
         (3, 1) => "Raw Rigtorium Extractor",
         (3, 2) => "Raw Flextorium Extractor",
         (3, 3) => "Electrine Extractor",
+        (3, 4) => "Raw Rigtorium Extractor Mk2",
+        (3, 5) => "Raw Flextorium Extractor Mk2",
+        (3, 6) => "Electrine Extractor Mk2",
+        (3, 7) => "Raw Rigtorium Extractor Mk3",
+        (3, 8) => "Raw Flextorium Extractor Mk3",
+        (3, 9) => "Electrine Extractor Mk3",
+        (3, 10) => "Quarry",
+        (3, 11) => "Pumpjack",
         (4, 1) => "Rigtorium Smelter",
         (4, 2) => "Flextorium Fabricator",
         (4, 3) => "Rigtorium Rod Molder",
         (4, 4) => "Conveyor Constructor",
         (4, 5) => "Router Constructor",
+        (4, 6) => "Assembler",
+        (4, 7) => "Circuit Printer",
+        (4, 8) => "Motor Assembler",
+        (4, 9) => "Extractor Constructor",
+        (4, 10) => "Factory Constructor",
+        (4, 11) => "Beacon Assembler",
+        (4, 12) => "Recycler",
+        (4, 13) => "Refinery",
         (5, 1) => "Small Rigtorium Vault",
         (5, 2) => "Small Flextorium Vault",
         (5, 3) => "Small Battery",
-        _ => "Unknown Tile",
-    }
-    .to_string()
+        _ => return None,
+    };
+    Some(name)
 }
 
-pub fn get_tile_core_interval(tile_type: (u8, u8)) -> u32 {
-    match tile_type {
+pub fn get_tile_core_interval(tile_type: (u8, u8), difficulty: Difficulty) -> u32 {
+    let interval = match tile_type {
         (1, 2) => 100,
+        (1, 3) => 150,
         (2, 1) => 20,
         (2, 2) => 30,
         (2, 3) => 30,
+        (2, 4) => 40,
+        (2, 5) => 10,
+        (2, 6) => 40,
+        (2, 7) => 30,
+        (2, 8) => 30,
+        (2, 9) => 45,
 
         (3, 1) => 40,
         (3, 2) => 40,
         (3, 3) => 40,
+        (3, 4) => 90,
+        (3, 5) => 90,
+        (3, 6) => 90,
+        (3, 7) => 180,
+        (3, 8) => 180,
+        (3, 9) => 180,
+        (3, 10) => 400,
+        (3, 11) => 200,
         (4, 1) => 60,
         (4, 2) => 60,
         (4, 3) => 70,
         (4, 4) => 80,
         (4, 5) => 80,
+        (4, 6) => 120,
+        (4, 7) => 100,
+        (4, 8) => 100,
+        (4, 9) => 150,
+        (4, 10) => 150,
+        (4, 11) => 300,
+        (4, 12) => 100,
+        (4, 13) => 250,
         (5, 1) => 50,
         (5, 2) => 50,
         (5, 3) => 50,
         _ => 6942,
+    };
+    difficulty.scale_core_interval(interval)
+}
+
+pub fn get_tile_footprint(tile_type: (u8, u8)) -> (i32, i32) {
+    TILE_FOOTPRINTS
+        .iter()
+        .find(|(category, index, _, _)| (*category, *index) == tile_type)
+        .map_or((1, 1), |(_, _, width, height)| (*width, *height))
+}
+
+pub fn is_protected_tile(tile_type: (u8, u8)) -> bool {
+    PROTECTED_TILES.contains(&tile_type)
+}
+
+pub fn is_rotatable_tile(tile_type: (u8, u8)) -> bool {
+    !NON_ROTATABLE_TILES.contains(&tile_type)
+}
+
+// The direction a tile actually faces when placed with the given direction.
+pub fn placement_direction(tile_type: (u8, u8), direction: Direction) -> Direction {
+    if is_rotatable_tile(tile_type) {
+        direction
+    } else {
+        Direction::Up
     }
 }
 
-pub fn get_tile_price(tile_type: (u8, u8)) -> u32 {
-    match tile_type {
+// Multi-tile buildings are anchored at their bottom-left cell and extend up and to the right.
+pub fn footprint_cells(tile_type: (u8, u8), anchor: Position) -> Vec<Position> {
+    let (width, height) = get_tile_footprint(tile_type);
+    (0..width)
+        .flat_map(|dx| (0..height).map(move |dy| Position::new(anchor.x + dx, anchor.y + dy)))
+        .collect()
+}
+
+// Offset from the anchor to the middle of the footprint, in world units.
+pub fn footprint_offset(tile_type: (u8, u8)) -> Vec2 {
+    let (width, height) = get_tile_footprint(tile_type);
+    Vec2::new((width - 1) as f32, (height - 1) as f32) * TILE_SIZE / 2.0
+}
+
+// Sprite size for tiles that cover more than one cell, `None` for regular tiles.
+pub fn footprint_sprite_size(tile_type: (u8, u8)) -> Option<Vec2> {
+    let (width, height) = get_tile_footprint(tile_type);
+    (width > 1 || height > 1).then(|| Vec2::new(width as f32, height as f32) * IMAGE_SIZE)
+}
+
+pub fn get_tile_price(tile_type: (u8, u8), difficulty: Difficulty) -> u32 {
+    let price = match tile_type {
         (1, 2) => 50,
+        (1, 3) => 80,
         (2, 1) => 10,
         (2, 2) => 15,
         (2, 3) => 15,
+        (2, 4) => 25,
+        (2, 5) => 5,
+        (2, 6) => 30,
+        (2, 7) => 20,
+        (2, 8) => 20,
+        (2, 9) => 25,
 
         (3, 1) => 20,
         (3, 2) => 20,
         (3, 3) => 20,
+        (3, 4) => 50,
+        (3, 5) => 50,
+        (3, 6) => 50,
+        (3, 7) => 110,
+        (3, 8) => 110,
+        (3, 9) => 110,
+        (3, 10) => 300,
+        (3, 11) => 120,
         (4, 1) => 30,
         (4, 2) => 30,
         (4, 3) => 35,
         (4, 4) => 40,
         (4, 5) => 40,
+        (4, 6) => 70,
+        (4, 7) => 50,
+        (4, 8) => 50,
+        (4, 9) => 90,
+        (4, 10) => 90,
+        (4, 11) => 200,
+        (4, 12) => 60,
+        (4, 13) => 150,
         (5, 1) => 25,
         (5, 2) => 25,
         (5, 3) => 25,
         _ => 60,
+    };
+    difficulty.scale_price(price)
+}
+
+fn get_extractor_tier(tile_type: (u8, u8)) -> ExtractorTier {
+    match tile_type {
+        (3, 4..=6) => ExtractorTier::Mk2,
+        (3, 7..=9) => ExtractorTier::Mk3,
+        _ => ExtractorTier::Mk1,
+    }
+}
+
+// Conveyors don't save their tier, so it always follows from the tile type.
+pub fn get_conveyor_tier(tile_type: (u8, u8)) -> ConveyorTier {
+    match tile_type {
+        (2, 9) => ConveyorTier::Fast,
+        _ => ConveyorTier::Basic,
+    }
+}
+
+// The next tier of a tile, for upgrading it in place.
+pub fn get_tile_upgrade(tile_type: (u8, u8)) -> Option<(u8, u8)> {
+    match tile_type {
+        (2, 1) => Some((2, 9)),
+        (3, id @ 1..=6) => Some((3, id + 3)),
+        _ => None,
+    }
+}
+
+// Moves what `old` holds into `new`, the tile replacing it. Returns false if it wouldn't all fit,
+// in which case the tile shouldn't be replaced.
+pub fn move_tile_contents(old: &dyn Tile, new: &mut dyn Tile) -> bool {
+    if let Some(old) = old.as_any().downcast_ref::<Conveyor>() {
+        return match new.as_any_mut().downcast_mut::<Conveyor>() {
+            Some(new)
+                if old
+                    .lanes
+                    .iter()
+                    .flatten()
+                    .all(|stack| stack.count <= new.tier.stack_size()) =>
+            {
+                new.lanes = old.lanes;
+                true
+            }
+            _ => old.is_empty(),
+        };
+    }
+    if let Some(old) = old.as_any().downcast_ref::<Storage>() {
+        return match new.as_any_mut().downcast_mut::<Storage>() {
+            Some(new)
+                if new.storage_type.stored_item() == old.storage_type.stored_item()
+                    && new.storage_type.capacity() >= old.inventory =>
+            {
+                new.inventory = old.inventory;
+                true
+            }
+            _ => old.inventory == 0,
+        };
+    }
+    if let Some(old) = old.as_any().downcast_ref::<Factory>() {
+        return old.item.is_none()
+            && old.byproducts.is_empty()
+            && old.inventory.values().all(|&count| count == 0);
+    }
+    if let Some(old) = old.as_any().downcast_ref::<Junction>() {
+        return match new.as_any_mut().downcast_mut::<Junction>() {
+            Some(new) => {
+                new.horizontal_item = old.horizontal_item;
+                new.vertical_item = old.vertical_item;
+                true
+            }
+            None => old.horizontal_item.is_none() && old.vertical_item.is_none(),
+        };
+    }
+
+    let item = old.get_item();
+    new.set_item(item);
+    new.get_item() == item
+}
+
+pub fn get_tile_direction(tile: &dyn Tile) -> Direction {
+    if let Some(conveyor) = tile.as_any().downcast_ref::<Conveyor>() {
+        conveyor.direction
+    } else if let Some(router) = tile.as_any().downcast_ref::<Router>() {
+        router.direction
+    } else if let Some(extractor) = tile.as_any().downcast_ref::<Extractor>() {
+        extractor.direction
+    } else if let Some(factory) = tile.as_any().downcast_ref::<Factory>() {
+        factory.direction
+    } else if let Some(storage) = tile.as_any().downcast_ref::<Storage>() {
+        storage.direction
+    } else if let Some(sensor) = tile.as_any().downcast_ref::<Sensor>() {
+        sensor.direction
+    } else if let Some(display) = tile.as_any().downcast_ref::<ItemDisplay>() {
+        display.direction
+    } else if let Some(inserter) = tile.as_any().downcast_ref::<Inserter>() {
+        inserter.direction
+    } else if let Some(quarry) = tile.as_any().downcast_ref::<Quarry>() {
+        quarry.direction
+    } else {
+        Direction::Up
+    }
+}
+
+pub fn is_valid_placement(
+    world: &WorldRes,
+    tile_type: (u8, u8),
+    pos: Position,
+    direction: Direction,
+) -> bool {
+    if world.is_protected(pos) {
+        return false;
+    }
+
+    if let Some((_, current_tile_id)) = world.tiles.get(&pos)
+        && *current_tile_id != tile_type
+        && *world.resources.get(&tile_type).unwrap_or(&0) < 1
+    {
+        return false;
+    }
+
+    // Every cell of the footprint has to be free, or belong to the tile being replaced. That
+    // includes `pos` itself, which may be an extra cell of a bigger building.
+    if footprint_cells(tile_type, pos).into_iter().any(|cell| {
+        world.is_protected(cell)
+            || !world.in_bounds(cell)
+            || (world.anchor_of(cell) != pos && world.is_occupied(cell))
+    }) {
+        return false;
     }
+
+    let (tile, _) = get_new_tile(tile_type, pos, direction);
+    if let Some(extractor) = tile.as_any().downcast_ref::<Extractor>() {
+        return extractor.covered_deposits(world) > 0;
+    }
+    if let Some(quarry) = tile.as_any().downcast_ref::<Quarry>() {
+        return !quarry.deposits(world).is_empty();
+    }
+
+    true
+}
+
+pub fn can_afford_placement(world: &WorldRes, tile_type: (u8, u8), pos: Position) -> bool {
+    *world.resources.get(&tile_type).unwrap_or(&0) >= 1
+        || world.tiles.get(&pos).map(|(_, id)| *id) == Some(tile_type)
 }
 
 pub fn get_new_tile(
@@ -104,6 +609,7 @@ pub fn get_new_tile(
     position: Position,
     direction: Direction,
 ) -> (Box<dyn Tile>, (u8, u8)) {
+    let direction = placement_direction(tile_type, direction);
     match tile_type {
         (1, 1) => (
             Box::new(Core {
@@ -111,6 +617,8 @@ pub fn get_new_tile(
                 interval: 10,
                 ticks: 0,
                 tile_id: (1, 1),
+                eject: false,
+                item: None,
             }) as Box<dyn Tile>,
             tile_type,
         ),
@@ -118,14 +626,24 @@ pub fn get_new_tile(
             Box::new(Portal {
                 position,
                 item: None,
+                teleported: HashMap::new(),
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (1, 3) => (
+            Box::new(Beacon {
+                position,
+                item: None,
+                charge: 0,
             }) as Box<dyn Tile>,
             tile_type,
         ),
-        (2, 1) => (
+        (2, 1) | (2, 9) => (
             Box::new(Conveyor {
                 position,
                 direction,
-                item: None,
+                lanes: [None, None],
+                tier: get_conveyor_tier(tile_type),
             }) as Box<dyn Tile>,
             tile_type,
         ),
@@ -135,6 +653,42 @@ pub fn get_new_tile(
                 direction,
                 item: None,
                 last_output: RouterOutputIndex::Forward,
+                priority: None,
+                filters: [None; 3],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (2, 4) => (
+            Box::new(Router {
+                position,
+                direction,
+                item: None,
+                last_output: RouterOutputIndex::Forward,
+                priority: Some(RouterOutputIndex::Forward),
+                filters: [None; 3],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (2, 5) => (Box::new(Wire { position }) as Box<dyn Tile>, tile_type),
+        (2, 6) => (
+            Box::new(Sensor {
+                position,
+                direction,
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (2, 7) => (
+            Box::new(ItemDisplay {
+                position,
+                direction,
+                count: 0,
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (2, 8) => (
+            Box::new(Inserter {
+                position,
+                direction,
             }) as Box<dyn Tile>,
             tile_type,
         ),
@@ -147,33 +701,55 @@ pub fn get_new_tile(
             }) as Box<dyn Tile>,
             tile_type,
         ),
-        (3, 1) => (
+        (3, 1) | (3, 4) | (3, 7) => (
             Box::new(Extractor {
                 position,
                 direction,
                 extractor_type: ExtractorType::RawRigtorium,
+                tier: get_extractor_tier(tile_type),
                 item: None,
             }) as Box<dyn Tile>,
             tile_type,
         ),
-        (3, 2) => (
+        (3, 2) | (3, 5) | (3, 8) => (
             Box::new(Extractor {
                 position,
                 direction,
                 extractor_type: ExtractorType::RawFlextorium,
+                tier: get_extractor_tier(tile_type),
                 item: None,
             }) as Box<dyn Tile>,
             tile_type,
         ),
-        (3, 3) => (
+        (3, 3) | (3, 6) | (3, 9) => (
             Box::new(Extractor {
                 position,
                 direction,
                 extractor_type: ExtractorType::Electrine,
+                tier: get_extractor_tier(tile_type),
                 item: None,
             }) as Box<dyn Tile>,
             tile_type,
         ),
+        (3, 11) => (
+            Box::new(Extractor {
+                position,
+                direction,
+                extractor_type: ExtractorType::CrudeOil,
+                tier: ExtractorTier::Mk1,
+                item: None,
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (3, 10) => (
+            Box::new(Quarry {
+                position,
+                direction,
+                item: None,
+                mined: 0,
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
         (4, 1) => (
             Box::new(Factory {
                 factory_type: FactoryType::RigtoriumSmelter,
@@ -181,8 +757,12 @@ pub fn get_new_tile(
                 direction,
                 inventory: HashMap::new(),
                 item: None,
+                byproducts: Vec::new(),
                 interval: 2,
                 ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
             }) as Box<dyn Tile>,
             tile_type,
         ),
@@ -193,8 +773,12 @@ pub fn get_new_tile(
                 direction,
                 inventory: HashMap::new(),
                 item: None,
+                byproducts: Vec::new(),
                 interval: 2,
                 ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
             }) as Box<dyn Tile>,
             tile_type,
         ),
@@ -205,8 +789,12 @@ pub fn get_new_tile(
                 direction,
                 inventory: HashMap::new(),
                 item: None,
+                byproducts: Vec::new(),
                 interval: 2,
                 ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
             }) as Box<dyn Tile>,
             tile_type,
         ),
@@ -217,8 +805,12 @@ pub fn get_new_tile(
                 direction,
                 inventory: HashMap::new(),
                 item: None,
+                byproducts: Vec::new(),
                 interval: 5,
                 ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
             }) as Box<dyn Tile>,
             tile_type,
         ),
@@ -229,8 +821,140 @@ pub fn get_new_tile(
                 direction,
                 inventory: HashMap::new(),
                 item: None,
+                byproducts: Vec::new(),
                 interval: 5,
                 ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (4, 6) => (
+            Box::new(Factory {
+                factory_type: FactoryType::Assembler,
+                position,
+                direction,
+                inventory: HashMap::new(),
+                item: None,
+                byproducts: Vec::new(),
+                interval: 8,
+                ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (4, 7) => (
+            Box::new(Factory {
+                factory_type: FactoryType::CircuitPrinter,
+                position,
+                direction,
+                inventory: HashMap::new(),
+                item: None,
+                byproducts: Vec::new(),
+                interval: 6,
+                ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (4, 8) => (
+            Box::new(Factory {
+                factory_type: FactoryType::MotorAssembler,
+                position,
+                direction,
+                inventory: HashMap::new(),
+                item: None,
+                byproducts: Vec::new(),
+                interval: 6,
+                ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (4, 9) => (
+            Box::new(Factory {
+                factory_type: FactoryType::ExtractorConstructor,
+                position,
+                direction,
+                inventory: HashMap::new(),
+                item: None,
+                byproducts: Vec::new(),
+                interval: 10,
+                ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (4, 10) => (
+            Box::new(Factory {
+                factory_type: FactoryType::FactoryConstructor,
+                position,
+                direction,
+                inventory: HashMap::new(),
+                item: None,
+                byproducts: Vec::new(),
+                interval: 10,
+                ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (4, 11) => (
+            Box::new(Factory {
+                factory_type: FactoryType::BeaconAssembler,
+                position,
+                direction,
+                inventory: HashMap::new(),
+                item: None,
+                byproducts: Vec::new(),
+                interval: 20,
+                ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (4, 12) => (
+            Box::new(Factory {
+                factory_type: FactoryType::Recycler,
+                position,
+                direction,
+                inventory: HashMap::new(),
+                item: None,
+                byproducts: Vec::new(),
+                interval: 4,
+                ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
+            }) as Box<dyn Tile>,
+            tile_type,
+        ),
+        (4, 13) => (
+            Box::new(Factory {
+                factory_type: FactoryType::Refinery,
+                position,
+                direction,
+                inventory: HashMap::new(),
+                item: None,
+                byproducts: Vec::new(),
+                interval: 6,
+                ticks: 0,
+                recipe_index: 0,
+                outputs: [true, false, false, false],
+                inputs: [true; 4],
             }) as Box<dyn Tile>,
             tile_type,
         ),
@@ -267,13 +991,28 @@ pub fn get_new_tile(
             Box::new(Conveyor {
                 position,
                 direction,
-                item: None,
+                lanes: [None, None],
+                tier: ConveyorTier::Basic,
             }) as Box<dyn Tile>,
             tile_type,
         ),
     }
 }
 
+pub fn get_lane_offset(direction: Direction, lane: Lane) -> Vec2 {
+    let forward = match direction {
+        Direction::Up => Vec2::Y,
+        Direction::Down => Vec2::NEG_Y,
+        Direction::Left => Vec2::NEG_X,
+        Direction::Right => Vec2::X,
+    };
+    let side = match lane {
+        Lane::Left => forward.perp(),
+        Lane::Right => -forward.perp(),
+    };
+    side * TILE_SIZE * LANE_OFFSET
+}
+
 pub fn rotate_direction_clockwise(dir: Direction) -> Direction {
     match dir {
         Direction::Up => Direction::Right,
@@ -297,7 +1036,7 @@ pub fn is_conveyor_pointing_to(
     from_pos: Position,
     pointing_direction: Direction,
 ) -> bool {
-    if let Some(tile) = world.tiles.get(&from_pos) {
+    if let Some(tile) = world.tiles.get(&world.anchor_of(from_pos)) {
         if let Some(conveyor) = tile.0.as_any().downcast_ref::<Conveyor>() {
             return conveyor.direction == pointing_direction;
         } else if let Some(router) = tile.0.as_any().downcast_ref::<Router>() {
@@ -305,9 +1044,11 @@ pub fn is_conveyor_pointing_to(
                 || router.direction.shift(1) == pointing_direction
                 || router.direction.shift(-1) == pointing_direction;
         } else if let Some(factory) = tile.0.as_any().downcast_ref::<Factory>() {
-            return factory.direction == pointing_direction;
+            return factory.outputs_to(pointing_direction);
         } else if let Some(extractor) = tile.0.as_any().downcast_ref::<Extractor>() {
             return extractor.direction == pointing_direction;
+        } else if let Some(quarry) = tile.0.as_any().downcast_ref::<Quarry>() {
+            return quarry.output_position() == from_pos.shift(pointing_direction);
         } else if let Some(_junction) = tile.0.as_any().downcast_ref::<Junction>() {
             return pointing_direction == Direction::Up
                 || pointing_direction == Direction::Down
@@ -364,33 +1105,325 @@ pub fn determine_conveyor_texture(world: &WorldRes, conveyor: &Conveyor) -> &'st
 
 pub fn can_tile_accept_item(tile: &(Box<dyn Tile>, (u8, u8)), item: Item) -> bool {
     if let Some(conveyor) = tile.0.as_any().downcast_ref::<Conveyor>() {
-        conveyor.item.is_none()
+        conveyor.has_free_lane()
     } else if let Some(router) = tile.0.as_any().downcast_ref::<Router>() {
         router.item.is_none()
     } else if let Some(factory) = tile.0.as_any().downcast_ref::<Factory>() {
-        factory.factory_type.capacity().get(&item).unwrap_or(&0)
-            > factory.inventory.get(&item).unwrap_or(&0)
+        factory.capacity().get(&item).unwrap_or(&0) > factory.inventory.get(&item).unwrap_or(&0)
     } else if let Some(junction) = tile.0.as_any().downcast_ref::<Junction>() {
         junction.horizontal_item.is_none()
     } else if let Some(portal) = tile.0.as_any().downcast_ref::<Portal>() {
         portal.item.is_none()
+    } else if let Some(beacon) = tile.0.as_any().downcast_ref::<Beacon>() {
+        item == Item::Electrine && beacon.item.is_none()
     } else {
         false
     }
 }
 
+// Powered beacons in range each shorten a machine's interval by `BEACON_BOOST_PERCENT`. Overlapping
+// beacons add up, to at most `BEACON_MAX_BOOST_PERCENT`.
+pub fn beacon_boost_percent(world: &WorldRes, pos: Position) -> u32 {
+    let boost: u32 = (-BEACON_RADIUS..=BEACON_RADIUS)
+        .flat_map(|dx| (-BEACON_RADIUS..=BEACON_RADIUS).map(move |dy| (dx, dy)))
+        .filter_map(|(dx, dy)| world.tiles.get(&Position::new(pos.x + dx, pos.y + dy)))
+        .filter_map(|(tile, _)| tile.as_any().downcast_ref::<Beacon>())
+        .filter(|beacon| beacon.powered())
+        .map(|_| BEACON_BOOST_PERCENT)
+        .sum();
+    boost.min(BEACON_MAX_BOOST_PERCENT)
+}
+
+pub fn boosted_interval(interval: u32, boost_percent: u32) -> u32 {
+    interval * (100 - boost_percent) / 100
+}
+
+// Like `can_tile_accept_item`, but also counts conveyor lanes that will be vacated this tick.
+pub fn will_tile_accept_item(
+    world: &WorldRes,
+    from: Position,
+    to: Position,
+    item: Item,
+    lane: Lane,
+) -> bool {
+    let to = world.anchor_of(to);
+    let Some(tile) = world.tiles.get(&to) else {
+        return false;
+    };
+    if let Some(conveyor) = tile.0.as_any().downcast_ref::<Conveyor>() {
+        conveyor
+            .entry_lanes(from, lane)
+            .into_iter()
+            .any(|lane| conveyor.room(lane, item) > 0 || world.flowing.contains(&(to, lane)))
+    } else if let Some(factory) = tile.0.as_any().downcast_ref::<Factory>() {
+        factory.accepts_from(entry_position(world, from, to)) && can_tile_accept_item(tile, item)
+    } else {
+        can_tile_accept_item(tile, item)
+    }
+}
+
+// The cell an item moved from `from` enters `to` through. That is `from` itself unless an inserter
+// between them carries it across, in which case it enters from the inserter's own cell.
+pub fn entry_position(world: &WorldRes, from: Position, to: Position) -> Position {
+    let to = world.anchor_of(to);
+    let adjacent = world.tiles.get(&to).is_some_and(|(_, id)| {
+        footprint_cells(*id, to)
+            .into_iter()
+            .any(|cell| Direction::ALL.into_iter().any(|d| cell.shift(d) == from))
+    });
+    if adjacent {
+        return from;
+    }
+
+    Direction::ALL
+        .into_iter()
+        .map(|direction| from.shift(direction))
+        .find(|pos| {
+            world
+                .tiles
+                .get(pos)
+                .and_then(|(tile, _)| tile.as_any().downcast_ref::<Inserter>())
+                .is_some_and(|inserter| {
+                    inserter.pickup_position() == from
+                        && world.anchor_of(inserter.drop_position()) == to
+                })
+        })
+        .unwrap_or(from)
+}
+
+// Whether a `Move` from `from` lands on `to`, by the same rules `apply_action` applies it with.
+pub fn accepts_move(
+    world: &WorldRes,
+    from: Position,
+    to: Position,
+    item: Item,
+    lane: Lane,
+) -> bool {
+    let Some((tile, _)) = world.tiles.get(&to) else {
+        return false;
+    };
+    if !tile.can_accept_lane(from, item, lane) {
+        return false;
+    }
+    if let Some(factory) = tile.as_any().downcast_ref::<Factory>() {
+        factory.accepts_from(entry_position(world, from, to))
+            && factory.capacity().get(&item).unwrap_or(&0)
+                > factory.inventory.get(&item).unwrap_or(&0)
+    } else if let Some(junction) = tile.as_any().downcast_ref::<Junction>() {
+        if from.y == to.y {
+            junction.horizontal_item.is_none()
+        } else {
+            junction.vertical_item.is_none()
+        }
+    } else if let Some(storage) = tile.as_any().downcast_ref::<Storage>() {
+        let (stored, capacity) = world.storage_fill(to);
+        storage.storage_type.stored_item() == item && stored < capacity
+    } else {
+        !tile.as_any().is::<Extractor>()
+    }
+}
+
+enum LaneFlow {
+    Decided(bool),
+    Depends(Vec<(Position, Lane)>),
+}
+
+fn lane_flow(world: &WorldRes, (pos, lane): (Position, Lane)) -> LaneFlow {
+    let Some(conveyor) = world
+        .tiles
+        .get(&pos)
+        .and_then(|(tile, _)| tile.as_any().downcast_ref::<Conveyor>())
+    else {
+        return LaneFlow::Decided(false);
+    };
+    let Some(item) = conveyor.lane(lane) else {
+        return LaneFlow::Decided(true);
+    };
+    if world.disabled.contains(&pos) {
+        return LaneFlow::Decided(false);
+    }
+
+    let next = world.anchor_of(pos.shift(conveyor.direction));
+    match world.tiles.get(&next) {
+        Some(next_tile) => match next_tile.0.as_any().downcast_ref::<Conveyor>() {
+            Some(next_conveyor) if next_conveyor.entry_room(pos, item, lane) > 0 => {
+                LaneFlow::Decided(true)
+            }
+            Some(next_conveyor) => LaneFlow::Depends(
+                next_conveyor
+                    .entry_lanes(pos, lane)
+                    .into_iter()
+                    .map(|lane| (next, lane))
+                    .collect(),
+            ),
+            None => LaneFlow::Decided(accepts_move(world, pos, next, item, lane)),
+        },
+        None => LaneFlow::Decided(false),
+    }
+}
+
+// Works out the flow of every lane reachable downstream from `starts`. Lanes `known` has an
+// answer for are taken as given instead of being followed further.
+fn resolve_lane_flows(
+    world: &WorldRes,
+    starts: impl IntoIterator<Item = (Position, Lane)>,
+    known: impl Fn(&(Position, Lane)) -> Option<bool>,
+) -> HashMap<(Position, Lane), bool> {
+    let mut flows: HashMap<(Position, Lane), bool> = HashMap::new();
+    let mut visiting = HashSet::new();
+
+    for start in starts {
+        let mut stack = vec![(start, false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if flows.contains_key(&node) {
+                continue;
+            }
+            if let Some(flow) = known(&node) {
+                flows.insert(node, flow);
+                continue;
+            }
+            match lane_flow(world, node) {
+                LaneFlow::Decided(flow) => {
+                    flows.insert(node, flow);
+                }
+                LaneFlow::Depends(next) if expanded => {
+                    let flow = next.iter().any(|next| flows.get(next) == Some(&true));
+                    flows.insert(node, flow);
+                }
+                LaneFlow::Depends(next) => {
+                    visiting.insert(node);
+                    stack.push((node, true));
+                    for next in next {
+                        if !flows.contains_key(&next) && !visiting.contains(&next) {
+                            stack.push((next, false));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    flows
+}
+
+// Conveyor lanes whose item will move on next tick, following full belts down to their first free
+// slot. Moves resolve downstream first, so a saturated belt advances as a whole.
+pub fn compute_flowing_lanes(world: &WorldRes) -> HashSet<(Position, Lane)> {
+    let starts = world
+        .tiles
+        .iter()
+        .filter(|(_, (tile, _))| tile.as_any().is::<Conveyor>())
+        .flat_map(|(pos, _)| Lane::ALL.map(|lane| (*pos, lane)));
+
+    resolve_lane_flows(world, starts, |_| None)
+        .into_iter()
+        .filter_map(|(node, flow)| flow.then_some(node))
+        .collect()
+}
+
+// `compute_flowing_lanes` for a world where only the `changed` cells differ since `world.flowing`
+// was worked out. Only the belts leading into them can have started or stopped flowing.
+pub fn update_flowing_lanes(
+    world: &WorldRes,
+    changed: &HashSet<Position>,
+) -> HashSet<(Position, Lane)> {
+    let mut upstream = changed.clone();
+    let mut queue: Vec<Position> = changed.iter().copied().collect();
+    while let Some(cell) = queue.pop() {
+        for direction in Direction::ALL {
+            let from = cell.shift(direction);
+            let feeds_cell = world
+                .tiles
+                .get(&from)
+                .and_then(|(tile, _)| tile.as_any().downcast_ref::<Conveyor>())
+                .is_some_and(|conveyor| from.shift(conveyor.direction) == cell);
+            if feeds_cell && upstream.insert(from) {
+                queue.push(from);
+            }
+        }
+    }
+
+    let starts = upstream
+        .iter()
+        .flat_map(|pos| Lane::ALL.map(|lane| (*pos, lane)));
+    let flows = resolve_lane_flows(world, starts, |node| {
+        (!upstream.contains(&node.0)).then(|| world.flowing.contains(node))
+    });
+
+    world
+        .flowing
+        .iter()
+        .filter(|(pos, _)| !upstream.contains(pos))
+        .copied()
+        .chain(
+            flows
+                .into_iter()
+                .filter_map(|(node, flow)| flow.then_some(node)),
+        )
+        .collect()
+}
+
+// Conveyors and junctions caught in a loop of tiles that all wait on each other, such as a full
+// belt loop or two junctions feeding each other. Unlike an ordinary jam these never clear.
+pub fn find_deadlocks(world: &WorldRes) -> HashSet<Position> {
+    let mut waits_on: HashMap<Position, Vec<Position>> = HashMap::new();
+    for (pos, (tile, _)) in world.tiles.iter() {
+        let mut next = Vec::new();
+        if let Some(conveyor) = tile.as_any().downcast_ref::<Conveyor>() {
+            if Lane::ALL
+                .into_iter()
+                .any(|lane| conveyor.lane(lane).is_some() && !world.flowing.contains(&(*pos, lane)))
+            {
+                next.push(world.anchor_of(pos.shift(conveyor.direction)));
+            }
+        } else if let Some(junction) = tile.as_any().downcast_ref::<Junction>() {
+            for (item, input, lane) in [junction.horizontal_item, junction.vertical_item]
+                .into_iter()
+                .flatten()
+            {
+                let end = world.anchor_of(pos.shift(input.shift(2)));
+                if !will_tile_accept_item(world, *pos, end, item, lane) {
+                    next.push(end);
+                }
+            }
+        }
+        if !next.is_empty() {
+            waits_on.insert(*pos, next);
+        }
+    }
+
+    // Peels off stuck tiles that wait on nothing stuck or that nothing stuck waits on, until only
+    // the loops are left.
+    let mut stuck: HashSet<Position> = waits_on.keys().copied().collect();
+    loop {
+        let waited_on: HashSet<Position> = stuck
+            .iter()
+            .flat_map(|pos| waits_on[pos].iter())
+            .copied()
+            .collect();
+        let kept: HashSet<Position> = stuck
+            .iter()
+            .filter(|pos| {
+                waited_on.contains(pos) && waits_on[pos].iter().any(|next| stuck.contains(next))
+            })
+            .copied()
+            .collect();
+        if kept.len() == stuck.len() {
+            return kept;
+        }
+        stuck = kept;
+    }
+}
+
 pub fn get_produce_destination(pos: Position, world: &WorldRes) -> Option<(Position, Position)> {
     if let Some((tile, _)) = world.tiles.get(&pos) {
         let mut end_position = pos;
 
         if let Some(factory) = tile.as_any().downcast_ref::<Factory>() {
-            match factory.direction {
-                Direction::Up => end_position.y += 1,
-                Direction::Down => end_position.y -= 1,
-                Direction::Left => end_position.x -= 1,
-                Direction::Right => end_position.x += 1,
-            }
-            return Some((pos, end_position));
+            return factory
+                .output_position(world)
+                .or(Some(pos.shift(factory.direction)))
+                .map(|end_position| (pos, end_position));
         } else if let Some(extractor) = tile.as_any().downcast_ref::<Extractor>() {
             match extractor.direction {
                 Direction::Up => end_position.y += 1,
@@ -404,13 +1437,147 @@ pub fn get_produce_destination(pos: Position, world: &WorldRes) -> Option<(Posit
     None
 }
 
+// The cells an item leaving `start` passes through as the belts, routers and junctions are set up
+// right now. Stops at the first tile that keeps the item, or once the path starts looping.
+pub fn trace_item_flow(world: &WorldRes, start: Position, item: Option<Item>) -> Vec<Position> {
+    let mut path = vec![start];
+    let mut visited = HashSet::new();
+    let mut pos = start;
+    let mut travel: Option<Direction> = None;
+
+    while path.len() < FLOW_TRACE_MAX_STEPS {
+        let Some((tile, _)) = world.tiles.get(&pos) else {
+            break;
+        };
+        let next = if let Some(conveyor) = tile.as_any().downcast_ref::<Conveyor>() {
+            Some(world.anchor_of(pos.shift(conveyor.direction)))
+        } else if let Some(router) = tile.as_any().downcast_ref::<Router>() {
+            router_trace_output(world, router, item)
+        } else if tile.as_any().is::<Junction>() {
+            travel.map(|dir| world.anchor_of(pos.shift(dir)))
+        } else if pos == start {
+            get_produce_destination(pos, world).map(|(_, end)| world.anchor_of(end))
+        } else {
+            None
+        };
+        let Some(next) = next.filter(|next| world.tiles.contains_key(next)) else {
+            break;
+        };
+
+        let looped = !visited.insert((pos, next));
+        travel = direction_towards(pos, next);
+        path.push(next);
+        if looped {
+            break;
+        }
+        pos = next;
+    }
+    path
+}
+
+// The output a router would hand `item` to next, ignoring whether the target currently has room.
+fn router_trace_output(world: &WorldRes, router: &Router, item: Option<Item>) -> Option<Position> {
+    let mut output = router.priority.unwrap_or(router.last_output.next());
+    for _ in 0..3 {
+        let allowed = match item {
+            Some(item) => router.accepts(output, item),
+            None => router.filters[output as usize].is_none(),
+        };
+        let end_pos = world.anchor_of(router.position.shift(output.to_direction(router.direction)));
+        if allowed
+            && world.tiles.get(&end_pos).is_some_and(|(tile, _)| {
+                tile.as_any().is::<Conveyor>()
+                    || tile.as_any().is::<Router>()
+                    || tile.as_any().is::<Factory>()
+            })
+        {
+            return Some(end_pos);
+        }
+        output = output.next();
+    }
+    None
+}
+
+fn direction_towards(from: Position, to: Position) -> Option<Direction> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    if dx == 0 && dy == 0 {
+        None
+    } else if dx.abs() >= dy.abs() {
+        Some(if dx > 0 {
+            Direction::Right
+        } else {
+            Direction::Left
+        })
+    } else {
+        Some(if dy > 0 {
+            Direction::Up
+        } else {
+            Direction::Down
+        })
+    }
+}
+
+pub fn action_route(action: &Action, world: &WorldRes) -> Option<(Position, Position)> {
+    match action {
+        Action::Move(from, to, _, _) | Action::MoveRouter(from, to, _, _) => Some((*from, *to)),
+        Action::Produce(pos) => {
+            get_produce_destination(*pos, world).map(|(_, destination)| (*pos, destination))
+        }
+        Action::Teleport(_, _) | Action::IncreaseTicks(_) => None,
+    }
+}
+
+// Puts the round-robin winner for each contested destination ahead of its rivals.
+pub fn arbitrate_inputs(
+    actions: Vec<Action>,
+    world: &WorldRes,
+) -> (Vec<Action>, Vec<(Position, Position)>) {
+    let mut contenders: HashMap<Position, Vec<(usize, Position)>> = HashMap::new();
+    for (i, action) in actions.iter().enumerate() {
+        if let Some((from, to)) = action_route(action, world) {
+            contenders.entry(to).or_default().push((i, from));
+        }
+    }
+
+    let mut slots: Vec<(usize, u8)> = (0..actions.len()).map(|i| (i, 1)).collect();
+    let mut winners = Vec::new();
+    for (destination, entries) in contenders {
+        if entries.len() < 2 {
+            continue;
+        }
+
+        let last = world
+            .last_input
+            .get(&destination)
+            .map(|pos| pos.get_as_key());
+        let Some(&(winner_index, winner_source)) = entries
+            .iter()
+            .filter(|(_, from)| last.is_none_or(|last| from.get_as_key() > last))
+            .min_by_key(|(_, from)| from.get_as_key())
+            .or_else(|| entries.iter().min_by_key(|(_, from)| from.get_as_key()))
+        else {
+            continue;
+        };
+
+        slots[winner_index] = (entries[0].0, 0);
+        winners.push((destination, winner_source));
+    }
+
+    let mut order: Vec<usize> = (0..actions.len()).collect();
+    order.sort_by_key(|&i| slots[i]);
+    (
+        order.into_iter().map(|i| actions[i].clone()).collect(),
+        winners,
+    )
+}
+
 pub fn sort_moves_topologically(actions: Vec<Action>, world: &WorldRes) -> Vec<Action> {
     let mut position_to_output_action: HashMap<Position, Vec<usize>> = HashMap::new();
     let mut position_to_input_action: HashMap<Position, Vec<usize>> = HashMap::new();
 
     for (i, action) in actions.iter().enumerate() {
         match action {
-            Action::Move(from, to, _) => {
+            Action::Move(from, to, _, _) => {
                 position_to_output_action.entry(*from).or_default().push(i);
                 position_to_input_action.entry(*to).or_default().push(i);
             }
@@ -480,3 +1647,120 @@ pub fn sort_moves_topologically(actions: Vec<Action>, world: &WorldRes) -> Vec<A
 
     sorted
 }
+
+// Sensors, portals and displays can be tuned to a wireless channel.
+pub fn can_use_channel(tile: &dyn Tile) -> bool {
+    tile.as_any().is::<Sensor>()
+        || tile.as_any().is::<Portal>()
+        || tile.as_any().is::<ItemDisplay>()
+}
+
+// What a tile adds to its signal network or wireless channel.
+fn signal_reading(world: &WorldRes, tile: &dyn Tile) -> HashMap<Item, u32> {
+    if let Some(sensor) = tile.as_any().downcast_ref::<Sensor>() {
+        sensor.read(world)
+    } else if let Some(portal) = tile.as_any().downcast_ref::<Portal>() {
+        portal.teleported.clone()
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn channel_signals(world: &WorldRes) -> HashMap<u8, HashMap<Item, u32>> {
+    let mut channels: HashMap<u8, HashMap<Item, u32>> = HashMap::new();
+    for (pos, channel) in world.channels.iter() {
+        let signals = channels.entry(*channel).or_default();
+        if let Some((tile, _)) = world.tiles.get(pos) {
+            for (item, count) in signal_reading(world, tile.as_ref()) {
+                *signals.entry(item).or_insert(0) += count;
+            }
+        }
+    }
+    channels
+}
+
+pub fn compute_disabled_tiles(world: &WorldRes) -> HashSet<Position> {
+    let mut disabled = HashSet::new();
+    if world.conditions.is_empty() {
+        return disabled;
+    }
+
+    let is_signal_tile = |pos: &Position| {
+        world
+            .tiles
+            .get(pos)
+            .is_some_and(|(tile, _)| tile.as_any().is::<Wire>() || tile.as_any().is::<Sensor>())
+    };
+    let directions = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    let mut network_of: HashMap<Position, usize> = HashMap::new();
+    let mut networks: Vec<HashMap<Item, u32>> = Vec::new();
+
+    // Only networks touching a condition matter, so the search starts next to the conditions
+    // rather than at every tile.
+    let starts = world
+        .conditions
+        .keys()
+        .flat_map(|pos| directions.map(|direction| pos.shift(direction)));
+    for pos in starts {
+        if network_of.contains_key(&pos) || !is_signal_tile(&pos) {
+            continue;
+        }
+
+        let network = networks.len();
+        let mut signals = HashMap::new();
+        let mut queue = vec![pos];
+        network_of.insert(pos, network);
+
+        while let Some(current) = queue.pop() {
+            if let Some((tile, _)) = world.tiles.get(&current) {
+                for (item, count) in signal_reading(world, tile.as_ref()) {
+                    *signals.entry(item).or_insert(0) += count;
+                }
+            }
+
+            for direction in directions {
+                let next = current.shift(direction);
+                if !network_of.contains_key(&next) && is_signal_tile(&next) {
+                    network_of.insert(next, network);
+                    queue.push(next);
+                }
+            }
+
+            // Everything on the same wireless channel joins the network, however far away.
+            if let Some(channel) = world.channels.get(&current) {
+                for (next, _) in world.channels.iter().filter(|(_, c)| *c == channel) {
+                    if !network_of.contains_key(next) {
+                        network_of.insert(*next, network);
+                        queue.push(*next);
+                    }
+                }
+            }
+        }
+
+        networks.push(signals);
+    }
+
+    for (pos, condition) in world.conditions.iter() {
+        let mut connected = HashSet::new();
+        let mut value = 0;
+        for direction in directions {
+            if let Some(&network) = network_of.get(&pos.shift(direction))
+                && connected.insert(network)
+            {
+                value += networks[network].get(&condition.item).unwrap_or(&0);
+            }
+        }
+
+        if value <= condition.threshold {
+            disabled.insert(*pos);
+        }
+    }
+
+    disabled
+}
@@ -0,0 +1,51 @@
+// Lives in its own test binary because the counting allocator replaces the global allocator for
+// everything compiled alongside it.
+use factoryfactory::factory::FactoryType;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    hint::black_box,
+};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn lookup_every_recipe() {
+    for factory_type in FactoryType::ALL {
+        for recipe_index in 0..factory_type.recipes().len() {
+            black_box((
+                factory_type.recipe(recipe_index),
+                factory_type.capacity(recipe_index),
+            ));
+        }
+    }
+}
+
+#[test]
+fn recipe_lookups_do_not_allocate() {
+    // Warm up the cache so its one-time setup isn't counted.
+    lookup_every_recipe();
+
+    let before = ALLOCATIONS.with(Cell::get);
+    for _ in 0..1000 {
+        lookup_every_recipe();
+    }
+    assert_eq!(ALLOCATIONS.with(Cell::get) - before, 0);
+}